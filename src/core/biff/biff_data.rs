@@ -13,24 +13,33 @@ pub(crate) trait IntoBiffData: BiffDataCompatible {
         size_of::<Self>()
     }
 
-    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> std::io::Result<usize>;
+    #[allow(clippy::wrong_self_convention)]
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize>;
 }
 
 pub(crate) trait FromBiffData: BiffDataCompatible {
     fn from_biff_data(
-        data: &Box<[u8]>,
+        data: &[u8],
         offset: usize,
         out_data: &mut MaybeUninit<Self>,
-    ) -> std::io::Result<usize>;
+    ) -> crate::Result<usize>;
 
-    fn deserialize(data: &Box<[u8]>) -> std::io::Result<Self> {
-        if data.len() != Self::size_of_type() {
-            Err(std::io::Error::from(std::io::ErrorKind::InvalidData))
+    fn deserialize(data: &[u8]) -> crate::Result<Self> {
+        if data.len() < Self::size_of_type() {
+            Err(crate::Error::truncated(Self::size_of_type(), data.len()))
         } else {
             let mut ret = MaybeUninit::uninit();
-            match Self::from_biff_data(&data, 0, &mut ret) {
+            match Self::from_biff_data(data, 0, &mut ret) {
                 Err(e) => Err(e),
-                Ok(_) => Ok(unsafe { ret.assume_init() }),
+                // Variable sized types must consume the whole record
+                Ok(n) => {
+                    let value = unsafe { ret.assume_init() };
+                    if n == data.len() {
+                        Ok(value)
+                    } else {
+                        Err(crate::Error::trailing_data(n, data.len()))
+                    }
+                }
             }
         }
     }
@@ -39,9 +48,12 @@ pub(crate) trait FromBiffData: BiffDataCompatible {
 pub(crate) trait AlignedBiffData: BiffDataCompatible {}
 
 impl<T: AlignedBiffData> IntoBiffData for T {
-    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> std::io::Result<usize> {
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
         if out_data.len() < offset + self.size_of() {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            Err(crate::Error::truncated(
+                offset + self.size_of(),
+                out_data.len(),
+            ))
         } else {
             let slice = out_data[offset..offset + self.size_of()].as_mut_ptr();
             unsafe {
@@ -58,15 +70,18 @@ impl<T: AlignedBiffData> IntoBiffData for T {
 
 impl<T: AlignedBiffData + Copy> FromBiffData for T {
     fn from_biff_data(
-        data: &Box<[u8]>,
+        data: &[u8],
         offset: usize,
         out_data: &mut MaybeUninit<T>,
-    ) -> std::io::Result<usize> {
+    ) -> crate::Result<usize> {
         if data.len() < offset + Self::size_of_type() {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            Err(crate::Error::truncated(
+                offset + Self::size_of_type(),
+                data.len(),
+            ))
         } else {
             let slice = data[offset..offset + Self::size_of_type()].as_ptr();
-            out_data.write(unsafe { *(slice as *const T) });
+            out_data.write(unsafe { std::ptr::read_unaligned(slice as *const T) });
             Ok(Self::size_of_type())
         }
     }
@@ -153,7 +168,7 @@ pub(crate) mod macros {
                     &self,
                     offset: usize,
                     out_data: &mut Box<[u8]>,
-                ) -> std::io::Result<usize> {
+                ) -> crate::Result<usize> {
                     Ok(0)
                 }
             }
@@ -161,21 +176,21 @@ pub(crate) mod macros {
             impl crate::core::biff::biff_data::FromBiffData for $name {
                 #[allow(unused_variables)]
                 fn from_biff_data(
-                    data: &Box<[u8]>,
+                    data: &[u8],
                     offset: usize,
                     out_data: &mut MaybeUninit<Self>,
-                ) -> std::io::Result<usize> {
+                ) -> crate::Result<usize> {
                     out_data.write($name {});
                     Ok(0)
                 }
             }
 
             impl CheckBiff for $name {
-                fn validated<'a>(&'a self) -> std::io::Result<&'a Self> {
+                fn validated(&self) -> crate::Result<&Self> {
                     if $check_expr(self) {
                         Ok(self)
                     } else {
-                        Err(std::io::Error::from(std::io::ErrorKind::InvalidData))
+                        Err(crate::Error::invalid_value())
                     }
                 }
             }
@@ -186,7 +201,7 @@ pub(crate) mod macros {
 
             impl crate::core::biff::biff_data::IntoBiffData for $name {
                 fn size_of(&self) -> usize {
-                    0 $(+ <$type as crate::core::biff::biff_data::BiffDataCompatible>::size_of_type())*
+                    0 $(+ crate::core::biff::biff_data::IntoBiffData::size_of(&self.$field))*
                 }
                 #[inline]
                 #[allow(unused_variables)]
@@ -194,13 +209,14 @@ pub(crate) mod macros {
                     &self,
                     offset: usize,
                     out_data: &mut Box<[u8]>,
-                ) -> std::io::Result<usize> {
+                ) -> crate::Result<usize> {
                     if self.size_of() == 0 {
                         Ok(0)
                     } else {
                         if out_data.len() < offset + self.size_of() {
-                            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+                            return Err(crate::Error::truncated(offset + self.size_of(), out_data.len()));
                         }
+                        let start = offset;
                         let mut offset = offset;
                         $(
                             match crate::core::biff::biff_data::IntoBiffData::into_biff_data(&self.$field, offset, out_data) {
@@ -208,12 +224,12 @@ pub(crate) mod macros {
                                     offset += data_size;
                                 }
                                 Err(e) => {
-                                    return Err(e);
+                                    return Err(e.with_field(stringify!($field)));
                                 }
                             }
                         )+
 
-                        Ok(offset)
+                        Ok(offset - start)
                     }
                 }
             }
@@ -221,13 +237,14 @@ pub(crate) mod macros {
             impl crate::core::biff::biff_data::FromBiffData for $name {
                 #[allow(unused_variables)]
                 fn from_biff_data(
-                    data: &Box<[u8]>,
+                    data: &[u8],
                     offset: usize,
                     out_data: &mut std::mem::MaybeUninit<Self>,
-                ) -> std::io::Result<usize> {
+                ) -> crate::Result<usize> {
                     if data.len() < offset + Self::size_of_type() {
-                        return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+                        return Err(crate::Error::truncated(offset + Self::size_of_type(), data.len()));
                     } else {
+                        let start = offset;
                         let mut offset = offset;
                         out_data.write(Self {
                             $(
@@ -239,26 +256,28 @@ pub(crate) mod macros {
                                             unsafe { field_value.assume_init() }
                                         }
                                         Err(e) => {
-                                            return Err(e);
+                                            return Err(e.with_field(stringify!($field)));
                                         }
                                     }
                                 },
                             )+
                         });
-                        Ok(offset)
+                        Ok(offset - start)
                     }
                 }
             }
 
             impl CheckBiff for $name {
-                fn validated(&self) -> std::io::Result<&Self> {
+                fn validated(&self) -> crate::Result<&Self> {
                     $(
-                        self.$field.validated()?;
+                        self.$field
+                            .validated()
+                            .map_err(|e| e.with_field(stringify!($field)))?;
                     )+
                     if $check_expr(self) {
                         Ok(self)
                     } else {
-                        Err(std::io::Error::from(std::io::ErrorKind::InvalidData))
+                        Err(crate::Error::invalid_value())
                     }
                 }
             }
@@ -303,12 +322,13 @@ pub(crate) mod macros {
             use crate::core::biff::biff_data::*;
 
             #[derive(Default, PartialEq)]
-            #[cfg_attr(feature = "test", derive(Debug))]
+            #[cfg_attr(any(test, feature = "test"), derive(Debug))]
             pub(crate) struct $name;
 
             impl_packable_for!($name, $check_expr);
 
             impl $name {
+                #[allow(dead_code)]
                 pub(crate) fn new() -> Self {
                     Self {}
                 }
@@ -318,12 +338,13 @@ pub(crate) mod macros {
             use crate::core::biff::biff_data::*;
 
             #[derive(Default, PartialEq)]
-            #[cfg_attr(feature = "test", derive(Debug))]
+            #[cfg_attr(any(test, feature = "test"), derive(Debug))]
             pub(crate) struct $name;
 
             impl_packable_for!($name, $check_expr, $id);
 
             impl $name {
+                #[allow(dead_code)]
                 pub(crate) fn new() -> Self {
                     Self {}
                 }
@@ -333,7 +354,7 @@ pub(crate) mod macros {
             use crate::core::biff::biff_data::*;
 
             #[derive(Default, PartialEq)]
-            #[cfg_attr(feature = "test", derive(Debug))]
+            #[cfg_attr(any(test, feature = "test"), derive(Debug))]
             pub(crate) struct $name {
                 pub(crate) $field: $type
             }
@@ -341,6 +362,7 @@ pub(crate) mod macros {
             impl_packable_for!($name, $check_expr, $field, $type);
 
             impl $name {
+                #[allow(dead_code)]
                 pub(crate) fn new($field: $type) -> Self {
                     Self {
                         $field,
@@ -360,7 +382,7 @@ pub(crate) mod macros {
             use crate::core::biff::biff_data::*;
 
             #[derive(Default, PartialEq)]
-            #[cfg_attr(feature = "test", derive(Debug))]
+            #[cfg_attr(any(test, feature = "test"), derive(Debug))]
             pub(crate) struct $name {
                 pub(crate) $field: $type
             }
@@ -368,6 +390,7 @@ pub(crate) mod macros {
             impl_packable_for!($name, $check_expr, $id, $field, $type);
 
             impl $name {
+                #[allow(dead_code)]
                 pub(crate) fn new($field: $type) -> Self {
                     Self {
                         $field,
@@ -384,10 +407,10 @@ pub(crate) mod macros {
             }
         };
         ($name: ident, $check_expr: expr, $field: ident, $type: ty $(, $other: ident, $other_type: ty)+) => {
-            pub(crate) use crate::core::biff::biff_data::*;
+            use crate::core::biff::biff_data::*;
 
             #[derive(Default, PartialEq)]
-            #[cfg_attr(feature = "test", derive(Debug))]
+            #[cfg_attr(any(test, feature = "test"), derive(Debug))]
             pub(crate) struct $name {
                 pub(crate) $field: $type,
                 $(pub(crate) $other: $other_type,)+
@@ -396,6 +419,7 @@ pub(crate) mod macros {
             impl_packable_for!($name, $check_expr, $field, $type $(, $other, $other_type)+);
 
             impl $name {
+                #[allow(dead_code, clippy::too_many_arguments)]
                 pub(crate) fn new($field: $type $(,$other: $other_type)+) -> Self {
                     Self {
                         $field,
@@ -405,10 +429,10 @@ pub(crate) mod macros {
             }
         };
         ($name: ident, $check_expr: expr, $id: ident, $field: ident, $type: ty $(, $other: ident, $other_type: ty)+) => {
-            pub(crate) use crate::core::biff::biff_data::*;
+            use crate::core::biff::biff_data::*;
 
             #[derive(Default, PartialEq)]
-            #[cfg_attr(feature = "test", derive(Debug))]
+            #[cfg_attr(any(test, feature = "test"), derive(Debug))]
             pub(crate) struct $name {
                 pub(crate) $field: $type,
                 $(pub(crate) $other: $other_type,)+
@@ -417,6 +441,7 @@ pub(crate) mod macros {
             impl_packable_for!($name, $check_expr, $id, $field, $type $(, $other, $other_type)+);
 
             impl $name {
+                #[allow(dead_code, clippy::too_many_arguments)]
                 pub(crate) fn new($field: $type $(,$other: $other_type)+) -> Self {
                     Self {
                         $field,
@@ -440,11 +465,11 @@ pub(crate) use self::macros::{
     internal_impl_packable_for, pack_biff_data,
 };
 pub(crate) use super::biff_id::BiffId;
-pub(crate) use crate::core::biff::biff_record::{box_alloc, try_to_sized, BiffError, BiffRecord};
+pub(crate) use crate::core::biff::biff_record::{box_alloc, try_to_sized, BiffRecord};
 
 pub(crate) trait CheckBiff: FromBiffData + IntoBiffData {
-    fn validated<'a>(&'a self) -> std::io::Result<&'a Self> {
-        Err(std::io::Error::from(std::io::ErrorKind::InvalidData))
+    fn validated(&self) -> crate::Result<&Self> {
+        Err(crate::Error::invalid_value())
     }
 
     fn is_valid(&self) -> bool {
@@ -455,7 +480,7 @@ pub(crate) trait CheckBiff: FromBiffData + IntoBiffData {
 pub(crate) trait ValidBiff: FromBiffData + IntoBiffData {}
 
 impl<T: ValidBiff> CheckBiff for T {
-    fn validated<'a>(&'a self) -> std::io::Result<&'a Self> {
+    fn validated(&self) -> crate::Result<&Self> {
         Ok(self)
     }
 }
@@ -463,21 +488,25 @@ impl<T: ValidBiff> CheckBiff for T {
 pub(crate) trait BiffSerializable: CheckBiff {
     const ID: super::BiffId;
 
-    fn into_biff(&self) -> std::io::Result<super::BiffRecord> {
+    #[allow(clippy::wrong_self_convention)]
+    fn into_biff(&self) -> crate::Result<super::BiffRecord> {
         match self.validated() {
             Ok(me) => Ok(super::BiffRecord {
                 id: Self::ID,
-                data: pack_biff_data!(me)?,
+                data: pack_biff_data!(me).map_err(|e| e.with_record(Self::ID))?,
             }),
-            Err(e) => Err(e),
+            Err(e) => Err(e.with_record(Self::ID)),
         }
     }
 
-    fn from_biff(data: &super::BiffRecord) -> std::io::Result<Self> {
+    fn from_biff(data: &super::BiffRecord) -> crate::Result<Self> {
         if Self::ID == data.id {
-            FromBiffData::deserialize(&data.data)
+            FromBiffData::deserialize(&data.data).map_err(|e| e.with_record(Self::ID))
         } else {
-            Err(std::io::Error::from(std::io::ErrorKind::InvalidData))
+            Err(
+                crate::Error::malformed(format!("{} expected", <&'static str>::from(Self::ID)))
+                    .with_record(data.id),
+            )
         }
     }
 }
@@ -491,7 +520,7 @@ pub(crate) const fn checked<T: BiffDataCompatible>(_: &T) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::{self, assert_matches};
+    use std::assert_matches;
 
     use arrow::datatypes::ToByteSlice;
 
@@ -1,5 +1,6 @@
-#[cfg(feature = "test")]
+#[cfg(any(test, feature = "test"))]
 use strum_macros::Display as EnumDisplay;
+use strum_macros::{FromRepr, IntoStaticStr};
 
 use super::BiffRecord;
 
@@ -14,8 +15,8 @@ const fn as_biff_id(id: u16) -> u16 {
 
 #[repr(u16)]
 #[allow(non_camel_case_types, dead_code)]
-#[cfg_attr(feature = "test", derive(Debug, EnumDisplay))]
-#[derive(Copy, Clone, PartialEq)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug, EnumDisplay))]
+#[derive(Copy, Clone, PartialEq, FromRepr, IntoStaticStr)]
 /// Enumeration with all variants of XLSB BIFF IDs from section 2.3.2
 pub(crate) enum BiffId {
     BrtRowHdr = as_biff_id(0),
@@ -884,21 +885,21 @@ pub(crate) enum BiffId {
 impl From<u16> for BiffId {
     #[inline]
     fn from(id: u16) -> Self {
-        // unsafe, because the enumeration purposed for internal usage only
-        unsafe { std::mem::transmute(id) }
+        // the enumeration purposed for internal usage only, so the id must be known
+        Self::from_repr(id).expect("unknown BIFF record id")
     }
 }
 
-impl Into<u16> for BiffId {
+impl From<BiffId> for u16 {
     #[inline]
-    fn into(self) -> u16 {
-        self as u16
+    fn from(id: BiffId) -> Self {
+        id as u16
     }
 }
 
-impl Into<Box<[u8]>> for BiffId {
-    fn into(self) -> Box<[u8]> {
-        let id = self as u16;
+impl From<BiffId> for Box<[u8]> {
+    fn from(id: BiffId) -> Self {
+        let id = id as u16;
 
         if id & 0x80 != 0 {
             Box::from(id.to_le_bytes())
@@ -915,6 +916,8 @@ mod tests {
     #[test]
     fn test_known_id() {
         assert_eq!(BiffId::from(as_biff_id(0)), BiffId::BrtRowHdr);
+        assert_eq!(BiffId::from_repr(0x0183), Some(BiffId::BrtBeginBook));
+        assert_eq!(BiffId::from_repr(0x7F7F), None);
         // assert_eq!(as_biff_id(33), BiffId::BrtPCRRecord.into());
     }
 }
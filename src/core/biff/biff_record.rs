@@ -9,42 +9,28 @@ use std::{
     slice,
 };
 
-use super::{BiffDataCompatible, BiffId, BiffSerializable, BiffSize, CheckBiff, FromBiffData};
-
-/// The error type for the `BiffRecord` struct
-#[derive(Debug)]
-pub(crate) enum BiffError {
-    Empty,
-    SizeMismatch,
-    InvalidCast,
-}
+use super::{
+    BiffDataCompatible, BiffId, BiffScanner, BiffSerializable, BiffSize, CheckBiff, FromBiffData,
+};
 
+#[allow(dead_code)]
 pub(crate) struct DataSlice<'a>(&'a [u8]);
+#[allow(dead_code)]
 impl<'a> DataSlice<'a> {
     pub(crate) const fn new(data: &'a [u8]) -> Self {
         Self(data)
     }
-    pub(crate) const fn peek<T: Sized>(&self, offset: usize) -> Result<T, BiffError> {
-        if std::mem::size_of::<T>() <= (self.0.len() - offset) {
-            Ok(unsafe { self.0.as_ptr().offset(offset as isize).cast::<T>().read() })
-        } else {
-            Err(BiffError::SizeMismatch)
-        }
+    pub(crate) fn peek<T: Sized>(&self, offset: usize) -> crate::Result<T> {
+        try_to_sized(offset, self.0)
     }
-    pub(crate) const fn peek_many<T: Sized>(
-        &self,
-        offset: usize,
-        count: usize,
-    ) -> Result<&[T], BiffError> {
-        if count * std::mem::size_of::<T>() <= (self.0.len() - offset) {
+    pub(crate) fn peek_many<T: Sized>(&self, offset: usize, count: usize) -> crate::Result<&[T]> {
+        let expected = offset + count * std::mem::size_of::<T>();
+        if expected <= self.0.len() {
             Ok(unsafe {
-                std::slice::from_raw_parts(
-                    self.0.as_ptr().offset(offset as isize).cast::<T>(),
-                    count,
-                )
+                std::slice::from_raw_parts(self.0.as_ptr().add(offset).cast::<T>(), count)
             })
         } else {
-            Err(BiffError::SizeMismatch)
+            Err(crate::Error::truncated(expected, self.0.len()))
         }
     }
 }
@@ -61,18 +47,20 @@ pub(crate) struct BiffRecord {
 ///
 /// Function is implemented to avoid copying data on reading.
 #[inline]
-pub(crate) const fn try_to_sized<T: Sized>(offset: usize, data: &[u8]) -> Result<T, BiffError> {
-    if std::mem::size_of::<T>() <= (data.len() - offset) {
-        Ok(unsafe { data.as_ptr().offset(offset as isize).cast::<T>().read() })
+#[allow(dead_code)]
+pub(crate) fn try_to_sized<T: Sized>(offset: usize, data: &[u8]) -> crate::Result<T> {
+    let expected = offset + std::mem::size_of::<T>();
+    if expected <= data.len() {
+        Ok(unsafe { data.as_ptr().add(offset).cast::<T>().read_unaligned() })
     } else {
-        Err(BiffError::SizeMismatch)
+        Err(crate::Error::truncated(expected, data.len()))
     }
 }
 
 #[inline]
 pub(crate) fn box_alloc(size: usize) -> Box<[u8]> {
     match size {
-        0 => return Box::new([]),
+        0 => Box::new([]),
         n => unsafe {
             Box::from_raw(slice_from_raw_parts_mut(
                 alloc(Layout::from_size_align_unchecked(n, 1)),
@@ -82,14 +70,9 @@ pub(crate) fn box_alloc(size: usize) -> Box<[u8]> {
     }
 }
 
-enum ReadResult {
-    Accepted(BiffRecord),
-    Rejected,
-    Error(io::Error),
-}
-
 impl BiffRecord {
-    pub(crate) fn read<R: Read + ?Sized>(reader: &mut R) -> io::Result<Option<BiffRecord>> {
+    #[allow(dead_code)]
+    pub(crate) fn read<R: Read + ?Sized>(reader: &mut R) -> crate::Result<Option<BiffRecord>> {
         let mut id_buf = [0u8; 2];
         let mut sz = 0u32;
         let mut sz_idx = 0u32;
@@ -108,17 +91,14 @@ impl BiffRecord {
             }
             (id_part, _) if id_part & 0x80 != 0 => {
                 id = u16::from_le_bytes(id_buf);
-                for b in reader.bytes().take(4) {
-                    match b {
-                        Ok(b) => {
-                            sz |= ((b & 0x7f) as u32) << (sz_idx * 7);
-                            sz_idx += 1;
-
-                            if b & 0x80 == 0 {
-                                break;
-                            }
-                        }
-                        Err(e) => return Err(e),
+                let mut b = [0u8; 1];
+                while sz_idx < 4 {
+                    reader.read_exact(&mut b)?;
+                    sz |= ((b[0] & 0x7f) as u32) << (sz_idx * 7);
+                    sz_idx += 1;
+
+                    if b[0] & 0x80 == 0 {
+                        break;
                     }
                 }
             }
@@ -126,90 +106,52 @@ impl BiffRecord {
                 id = biff_id as u16;
                 sz = (sz_part & 0x7f) as u32;
                 sz_idx += 1;
-                if sz_part & 0x80 != 0 {
-                    for b in reader.bytes().take(3) {
-                        if let Ok(b) = b {
-                            sz |= ((b & 0x7f) as u32) << (sz_idx * 7);
-                            sz_idx += 1;
-
-                            if b & 0x80 == 0 {
-                                break;
-                            }
-                        }
-                    }
+                let mut b = [0u8; 1];
+                let mut more = sz_part & 0x80 != 0;
+                while more && sz_idx < 4 {
+                    reader.read_exact(&mut b)?;
+                    sz |= ((b[0] & 0x7f) as u32) << (sz_idx * 7);
+                    sz_idx += 1;
+                    more = b[0] & 0x80 != 0;
                 }
             }
         };
-        if sz > 0 {
-            let mut data = box_alloc(sz as usize);
-            reader.read_exact(data.as_mut())?;
-            Ok(Some(BiffRecord {
-                id: unsafe { transmute(id) },
-                data,
-            }))
-        } else {
-            Ok(Some(BiffRecord {
-                id: unsafe { transmute(id) },
-                data: box_alloc(0),
-            }))
+        let mut data = box_alloc(sz as usize);
+        reader.read_exact(data.as_mut())?;
+        match BiffId::from_repr(id) {
+            Some(id) => Ok(Some(BiffRecord { id, data })),
+            None => Err(crate::Error::UnknownRecord {
+                location: crate::Location {
+                    record: Some(id),
+                    ..Default::default()
+                },
+            }),
         }
     }
 
-    pub(crate) fn scan<'a, R: Read + ?Sized>(
-        reader: &'a mut R,
-        ids_only: Option<&[u16]>,
-        ids_skip: Option<&[u16]>,
-        break_id: Option<u16>,
+    /// Scan records of the stream, see [`BiffScanner`] for the meaning of filters
+    #[allow(dead_code)]
+    pub(crate) fn scan<R: Read>(
+        reader: R,
+        ids_only: Option<&[BiffId]>,
+        ids_skip: Option<&[BiffId]>,
+        break_id: Option<BiffId>,
         max_scan: Option<usize>,
-    ) -> impl Iterator<Item = io::Result<BiffRecord>> + 'a {
-        let ids_only = ids_only.map(|slice| HashSet::<u16>::from_iter(slice.iter().copied()));
-        let ids_skip = ids_skip.map(|slice| HashSet::<u16>::from_iter(slice.iter().copied()));
-        let mut scanned = 0_usize;
-        let mut lastrec: Option<BiffRecord> = None;
-
-        std::iter::from_fn(move || {
-            if let Some(break_id) = break_id {
-                if let Some(ref lastrec) = lastrec {
-                    if lastrec.id as u16 == break_id {
-                        return None;
-                    }
-                }
-            }
-            if let Some(max_scan) = max_scan {
-                if scanned >= max_scan {
-                    return None;
-                }
-            }
-            match BiffRecord::read(reader) {
-                Ok(Some(rec)) => {
-                    scanned += 1;
-                    lastrec.replace(rec.clone());
-                    if let Some(ref ids_only) = ids_only {
-                        if ids_only.contains(&(rec.id as u16)) {
-                            Some(ReadResult::Accepted(rec))
-                        } else {
-                            Some(ReadResult::Rejected)
-                        }
-                    } else {
-                        if let Some(ref ids_skip) = ids_skip {
-                            if ids_skip.contains(&(rec.id as u16)) {
-                                Some(ReadResult::Rejected)
-                            } else {
-                                Some(ReadResult::Accepted(rec))
-                            }
-                        } else {
-                            Some(ReadResult::Accepted(rec))
-                        }
-                    }
-                }
-                Ok(None) => None,
-                Err(e) => Some(ReadResult::Error(e)),
-            }
-        })
-        .filter_map(|res| match res {
-            ReadResult::Accepted(rec) => Some(Ok(rec)),
-            _ => None,
-        })
+    ) -> BiffScanner<R> {
+        let mut scanner = BiffScanner::new(reader);
+        if let Some(ids) = ids_only {
+            scanner = scanner.ids_only(ids);
+        }
+        if let Some(ids) = ids_skip {
+            scanner = scanner.ids_skip(ids);
+        }
+        if let Some(id) = break_id {
+            scanner = scanner.break_on(id);
+        }
+        if let Some(max_scan) = max_scan {
+            scanner = scanner.max_scan(max_scan);
+        }
+        scanner
     }
 
     pub const fn size_raw(&self) -> usize {
@@ -223,17 +165,22 @@ impl BiffRecord {
             + self.data.len()
     }
 
+    #[allow(dead_code)]
     pub const fn size(&self) -> usize {
         self.data.len()
     }
 
+    #[allow(dead_code)]
     pub const fn data<'a>(&'a self) -> DataSlice<'a> {
         DataSlice::new(unsafe { slice::from_raw_parts(self.data.as_ptr(), self.data.len()) })
     }
 
-    pub const fn data_as<'a, T: Sized + 'a>(&'a self) -> Result<&'a T, BiffError> {
-        if size_of::<T>() != self.size() {
-            Err(BiffError::SizeMismatch)
+    #[allow(dead_code)]
+    pub fn data_as<'a, T: Sized + 'a>(&'a self) -> crate::Result<&'a T> {
+        if size_of::<T>() > self.size() {
+            Err(crate::Error::truncated(size_of::<T>(), self.size()).with_record(self.id))
+        } else if size_of::<T>() < self.size() {
+            Err(crate::Error::trailing_data(size_of::<T>(), self.size()).with_record(self.id))
         } else {
             Ok(self.data_as_unchecked())
         }
@@ -242,47 +189,53 @@ impl BiffRecord {
     pub fn as_biff_data<T: BiffDataCompatible + FromBiffData>(
         &self,
         offset: usize,
-    ) -> std::io::Result<T> {
+    ) -> crate::Result<T> {
         let mut out_data = MaybeUninit::uninit();
         match T::from_biff_data(&self.data, offset, &mut out_data) {
             Ok(_) => Ok(unsafe { out_data.assume_init() }),
-            Err(e) => Err(e),
+            Err(e) => Err(e.with_record(self.id)),
         }
     }
 
     #[inline]
-    pub fn as_biff<T: BiffSerializable>(&self) -> std::io::Result<T> {
+    pub fn as_biff<T: BiffSerializable>(&self) -> crate::Result<T> {
         T::from_biff(self)
     }
 
+    #[allow(dead_code)]
     pub const fn data_as_unchecked<'a, T: Sized + 'a>(&'a self) -> &'a T {
         unsafe { transmute(&*self.data.as_ptr()) }
     }
 
-    pub fn data_as_xlws(&self) -> Result<Option<String>, BiffError> {
+    #[allow(dead_code)]
+    pub fn data_as_xlws(&self) -> crate::Result<Option<String>> {
         match self.size() {
-            0 => Err(BiffError::Empty),
-            n if n < 4 || n % 2 != 0 => Err(BiffError::SizeMismatch),
+            n if n < 4 => Err(crate::Error::truncated(4, n).with_record(self.id)),
             _ => match u32::from_le_bytes(self.data[0..4].try_into().unwrap()) {
                 0xffff_ffff => Ok(None),
                 0 => Ok(Some(String::new())),
-                n if n * 2 != ((self.size() as u32) - 4) => Err(BiffError::SizeMismatch),
-                n => Ok(Some(
-                    match encoding_rs::UTF_16LE
-                        .decode_without_bom_handling_and_without_replacement(
-                            &self.data[4..4 + (n as usize) * 2],
-                        )
-                        .expect("non utf-16")
-                    {
-                        Cow::Borrowed(s) => s.to_owned(),
-                        Cow::Owned(s) => s,
-                    },
-                )),
+                n if (n as usize) * 2 + 4 > self.size() => {
+                    Err(crate::Error::truncated(n as usize * 2 + 4, self.size())
+                        .with_record(self.id))
+                }
+                n if (n as usize) * 2 + 4 < self.size() => {
+                    Err(crate::Error::trailing_data(n as usize * 2 + 4, self.size())
+                        .with_record(self.id))
+                }
+                n => match encoding_rs::UTF_16LE
+                    .decode_without_bom_handling_and_without_replacement(
+                        &self.data[4..4 + (n as usize) * 2],
+                    ) {
+                    Some(Cow::Borrowed(s)) => Ok(Some(s.to_owned())),
+                    Some(Cow::Owned(s)) => Ok(Some(s)),
+                    None => Err(crate::Error::invalid_value().with_record(self.id)),
+                },
             },
         }
     }
 
-    pub fn from_sized<'a, T: Sized>(id: u16, data: &'a T) -> Self {
+    #[allow(dead_code)]
+    pub fn from_sized<T: Sized>(id: u16, data: &T) -> Self {
         BiffRecord {
             id: BiffId::from(id),
             data: Box::from(unsafe {
@@ -291,25 +244,26 @@ impl BiffRecord {
         }
     }
 
-    pub fn push(&self, writer: &mut impl Write) -> io::Result<usize> {
+    pub fn push<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<usize> {
         let boxed: Box<[u8]> = self.into();
-        writer.write(boxed.as_ref())
+        writer.write_all(boxed.as_ref())?;
+        Ok(boxed.len())
     }
 }
 
-impl Into<Box<[u8]>> for &BiffRecord {
-    fn into(self) -> Box<[u8]> {
-        let mut ret = box_alloc(self.size_raw());
+impl From<&BiffRecord> for Box<[u8]> {
+    fn from(rec: &BiffRecord) -> Self {
+        let mut ret = box_alloc(rec.size_raw());
         let mut idx = 0_usize;
 
-        if (self.id as u16) & 0x80 != 0 {
-            ret[idx..idx + 2].copy_from_slice(&(self.id as u16).to_le_bytes());
+        if (rec.id as u16) & 0x80 != 0 {
+            ret[idx..idx + 2].copy_from_slice(&(rec.id as u16).to_le_bytes());
             idx += 2;
         } else {
-            ret[idx] = self.id as u8;
+            ret[idx] = rec.id as u8;
             idx += 1;
         }
-        match BiffSize::from_size(self.data.len() as u32) {
+        match BiffSize::from_size(rec.data.len() as u32) {
             BiffSize::U8(data) => {
                 ret[idx] = data[0];
                 idx += 1;
@@ -327,7 +281,7 @@ impl Into<Box<[u8]>> for &BiffRecord {
                 idx += 4;
             }
         }
-        ret[idx..].copy_from_slice(&self.data);
+        ret[idx..].copy_from_slice(&rec.data);
         ret
     }
 }
@@ -392,14 +346,16 @@ mod tests {
     fn string_reader(id: BiffId, data: &str) -> Cursor<Vec<u8>> {
         let mut cur = Cursor::new(Vec::new());
         let id_raw: Box<[u8]> = id.into();
-        cur.write(&id_raw).unwrap();
-        cur.write(BiffSize::from_size((data.len() * 2 + 4) as u32).inner())
+        cur.write_all(&id_raw).unwrap();
+        cur.write_all(BiffSize::from_size((data.len() * 2 + 4) as u32).inner())
             .unwrap();
-        cur.write(&(data.len() as u32).to_le_bytes()).unwrap();
+        cur.write_all(&(data.len() as u32).to_le_bytes()).unwrap();
 
         let chars: Vec<_> = data.encode_utf16().collect();
-        cur.write(unsafe { &*slice_from_raw_parts(chars.as_ptr() as *const u8, chars.len() * 2) })
-            .unwrap();
+        cur.write_all(unsafe {
+            &*slice_from_raw_parts(chars.as_ptr() as *const u8, chars.len() * 2)
+        })
+        .unwrap();
 
         cur.flush().unwrap();
         cur.seek(io::SeekFrom::Start(0)).unwrap();
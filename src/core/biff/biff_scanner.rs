@@ -1,4 +1,5 @@
-use super::{BiffId, BiffRecord};
+use super::{box_alloc, BiffId, BiffRecord};
+use crate::{Error, Location};
 use std::{
     collections::HashSet,
     io::{self, Read},
@@ -6,29 +7,107 @@ use std::{
 };
 
 // Single threaded scanner for BIFF files.
-pub(super) struct BiffScanner<'a, R: Read + ?Sized> {
+//
+// Records, which are filtered out or have unknown IDs, are skipped without allocation.
+pub(crate) struct BiffScanner<R: Read> {
     // Underlying reader
-    pub reader: &'a mut R,
+    reader: R,
     // Reads only the specified IDs, all other will be ignored
-    pub ids_only: Option<Rc<HashSet<u16>>>,
+    ids_only: Option<Rc<HashSet<u16>>>,
     // Skips the specified IDs, all other will be read
-    pub ids_skip: Option<Rc<HashSet<u16>>>,
+    ids_skip: Option<Rc<HashSet<u16>>>,
     // Break on the specified ID
-    pub break_id: Option<u16>,
+    break_id: Option<u16>,
     // Maximum number of records to read
-    pub max_scan: Option<usize>,
+    max_scan: Option<usize>,
 
     // Scan counter.
-    pub scan_cnt: usize,
+    scan_cnt: usize,
+    // Count of bytes consumed from the reader
+    position: u64,
+    // Offset of the header of the last read record
+    record_offset: u64,
+    // Set after the break record, EOF or error
+    done: bool,
 }
 
-impl<'a, R: Read + ?Sized> BiffScanner<'a, R> {
+impl<R: Read> BiffScanner<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        BiffScanner {
+            reader,
+            ids_only: None,
+            ids_skip: None,
+            break_id: None,
+            max_scan: None,
+            scan_cnt: 0,
+            position: 0,
+            record_offset: 0,
+            done: false,
+        }
+    }
+
+    /// Read only records with the given IDs
+    pub(crate) fn ids_only(mut self, ids: &[BiffId]) -> Self {
+        self.ids_only = Some(Rc::new(ids.iter().map(|&id| id as u16).collect()));
+        self
+    }
+
+    /// Skip records with the given IDs
+    #[allow(dead_code)]
+    pub(crate) fn ids_skip(mut self, ids: &[BiffId]) -> Self {
+        self.ids_skip = Some(Rc::new(ids.iter().map(|&id| id as u16).collect()));
+        self
+    }
+
+    /// Stop scanning on the record with the given ID (the record itself is not returned)
+    pub(crate) fn break_on(mut self, id: BiffId) -> Self {
+        self.break_id = Some(id as u16);
+        self
+    }
+
+    /// Stop scanning after the given count of returned records
+    #[allow(dead_code)]
+    pub(crate) fn max_scan(mut self, max_scan: usize) -> Self {
+        self.max_scan = Some(max_scan);
+        self
+    }
+
+    /// Offset of the last returned record in the stream
+    pub(crate) fn record_offset(&self) -> u64 {
+        self.record_offset
+    }
+
     /// Read the next BIFF record header from the underlying reader (record ID and record size).
-    pub fn read_header(&mut self) -> io::Result<(u16, i64)> {
+    ///
+    /// Returns `None` on EOF before the start of the record.
+    pub(crate) fn read_header(&mut self) -> crate::Result<Option<(u16, u32)>> {
+        self.record_offset = self.position;
+        let mut len = 0;
+        match self.read_raw_header(&mut len) {
+            Ok(header) => {
+                self.position += len;
+                Ok(header)
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Err(Error::Malformed {
+                location: Location {
+                    offset: Some(self.record_offset),
+                    ..Default::default()
+                },
+                message: "stream ends inside the record header".to_owned(),
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn read_raw_header(&mut self, len: &mut u64) -> io::Result<Option<(u16, u32)>> {
         let mut id_buf = [0u8; 2];
         let mut sz_buf = [0u8; 1];
 
-        self.reader.read_exact(&mut id_buf)?;
+        match self.reader.read(&mut id_buf[..1])? {
+            0 => return Ok(None),
+            _ => self.reader.read_exact(&mut id_buf[1..])?,
+        }
+        *len = 2;
 
         let id: u16;
         let mut sz: u32;
@@ -36,6 +115,7 @@ impl<'a, R: Read + ?Sized> BiffScanner<'a, R> {
             id = u16::from_le_bytes(id_buf);
 
             self.reader.read_exact(&mut sz_buf)?;
+            *len += 1;
 
             sz = sz_buf[0] as u32;
         } else {
@@ -43,92 +123,180 @@ impl<'a, R: Read + ?Sized> BiffScanner<'a, R> {
             sz = id_buf[1] as u32;
         }
 
-        if sz == 0 {
-            Ok((id, sz as i64))
-        } else {
-            if sz & 0x80 != 0 {
+        if sz & 0x80 != 0 {
+            self.reader.read_exact(&mut sz_buf)?;
+            *len += 1;
+
+            sz = sz ^ 0x80 | (sz_buf[0] as u32) << 7;
+
+            if sz & 0x4000 != 0 {
                 self.reader.read_exact(&mut sz_buf)?;
+                *len += 1;
 
-                sz = sz ^ 0x80 | (sz_buf[0] as u32) << 7;
+                sz = sz ^ 0x4000 | (sz_buf[0] as u32) << 14;
 
-                if sz & 0x4000 != 0 {
+                if sz & 0x200000 != 0 {
                     self.reader.read_exact(&mut sz_buf)?;
+                    *len += 1;
+
+                    sz = sz ^ 0x200000 | (sz_buf[0] as u32) << 21;
+                }
+            }
+        }
 
-                    sz = sz ^ 0x4000 | (sz_buf[0] as u32) << 14;
+        Ok(Some((id, sz)))
+    }
 
-                    if sz & 0x200000 != 0 {
-                        self.reader.read_exact(&mut sz_buf)?;
+    fn accepts(&self, id: u16) -> bool {
+        self.ids_only
+            .as_ref()
+            .map(|set| set.contains(&id))
+            .unwrap_or(true)
+            && !self
+                .ids_skip
+                .as_ref()
+                .map(|set| set.contains(&id))
+                .unwrap_or(false)
+    }
 
-                        sz = sz ^ 0x200000 | (sz_buf[0] as u32) << 21;
+    fn next_record(&mut self) -> crate::Result<Option<BiffRecord>> {
+        while let Some((id, sz)) = self.read_header()? {
+            if self.break_id == Some(id) {
+                return Ok(None);
+            }
+            let read = match BiffId::from_repr(id) {
+                Some(id) if self.accepts(id as u16) => {
+                    let mut data = box_alloc(sz as usize);
+                    let read = read_full(&mut self.reader, &mut data)?;
+                    if read == data.len() {
+                        self.position += read as u64;
+                        return Ok(Some(BiffRecord { id, data }));
                     }
+                    read as u64
                 }
+                _ => io::copy(&mut (&mut self.reader).take(sz as u64), &mut io::sink())?,
+            };
+            self.position += read;
+            if read != sz as u64 {
+                return Err(Error::Truncated {
+                    location: Location {
+                        record: Some(id),
+                        offset: Some(self.record_offset),
+                        ..Default::default()
+                    },
+                    expected: sz as usize,
+                    actual: read as usize,
+                });
             }
+        }
+        Ok(None)
+    }
+}
 
-            Ok((id, sz as i64))
+/// Read into the buffer until it is full or the reader ends, returns the count of read bytes
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
         }
     }
+    Ok(read)
 }
 
 // Iterator implementation for BiffScanner
-impl<'a, R: Read + ?Sized> Iterator for BiffScanner<'a, R> {
-    type Item = Option<io::Result<BiffRecord>>;
+impl<R: Read> Iterator for BiffScanner<R> {
+    type Item = crate::Result<BiffRecord>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(true) = self.max_scan.map(|x| self.scan_cnt == x) {
+        if self.done || self.max_scan == Some(self.scan_cnt) {
             return None;
         }
-        match self.read_header() {
-            Ok((id, sz)) => {
-                if self.break_id.map(|u| u == id).unwrap_or(false) {
-                    None
-                } else if self
-                    .ids_only
-                    .as_ref()
-                    .map(|set| !set.contains(&id))
-                    .unwrap_or(false)
-                    || self
-                        .ids_skip
-                        .as_ref()
-                        .map(|set| set.contains(&id))
-                        .unwrap_or(false)
-                {
-                    if sz > 0 {
-                        if let Err(e) = io::copy(&mut self.reader.take(sz as u64), &mut io::sink())
-                        {
-                            Some(Some(Err(e)))
-                        } else {
-                            Some(None)
-                        }
-                    } else {
-                        Some(None)
-                    }
-                } else if sz > 0 {
-                    let mut data = vec![0u8; sz as usize];
-
-                    match self.reader.read_exact(&mut data) {
-                        Ok(()) => {
-                            self.scan_cnt += 1;
-                            return Some(Some(Ok(BiffRecord::Sized {
-                                id: BiffId::from(id),
-                                data: data.into_boxed_slice(),
-                            })));
-                        }
-                        Err(e) => return Some(Some(Err(e))),
-                    }
-                } else {
-                    self.scan_cnt += 1;
-                    return Some(Some(Ok(BiffRecord::Empty {
-                        id: BiffId::from(id),
-                    })));
-                }
+        match self.next_record() {
+            Ok(Some(rec)) => {
+                self.scan_cnt += 1;
+                Some(Ok(rec))
+            }
+            Ok(None) => {
+                self.done = true;
+                None
             }
             Err(e) => {
-                if e.kind() == io::ErrorKind::UnexpectedEof {
-                    None
-                } else {
-                    Some(Some(Err(e)))
-                }
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::core::biff::BiffWrite;
+
+    #[test]
+    fn test_scanner() {
+        let mut data = Vec::new();
+        data.write_empty(BiffId::BrtBeginSheet).unwrap();
+        // unknown record with 200 bytes of data (two-bytes size)
+        data.extend_from_slice(&[0x7F, 0xC8, 0x01]);
+        data.extend_from_slice(&[0xAA; 200]);
+        BiffRecord {
+            id: BiffId::BrtCellReal,
+            data: Box::new([0; 16]),
+        }
+        .push(&mut data)
+        .unwrap();
+        data.write_empty(BiffId::BrtEndSheetData).unwrap();
+        data.write_empty(BiffId::BrtEndSheet).unwrap();
+
+        let ids = BiffScanner::new(Cursor::new(&data))
+            .map(|rec| rec.unwrap().id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                BiffId::BrtBeginSheet,
+                BiffId::BrtCellReal,
+                BiffId::BrtEndSheetData,
+                BiffId::BrtEndSheet
+            ]
+        );
+
+        let ids = BiffScanner::new(Cursor::new(&data))
+            .ids_skip(&[BiffId::BrtBeginSheet])
+            .break_on(BiffId::BrtEndSheetData)
+            .map(|rec| rec.unwrap().id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [BiffId::BrtCellReal]);
+
+        let mut scanner = BiffScanner::new(Cursor::new(&data))
+            .ids_only(&[BiffId::BrtCellReal, BiffId::BrtEndSheet])
+            .max_scan(1);
+        assert_eq!(scanner.next().unwrap().unwrap().data.len(), 16);
+        assert!(scanner.next().is_none());
+
+        let mut scanner = BiffScanner::new(Cursor::new(&data)).ids_only(&[BiffId::BrtCellReal]);
+        scanner.next().unwrap().unwrap();
+        assert_eq!(scanner.record_offset(), 3 + 203);
+
+        let truncated = &data[..10];
+        let res = BiffScanner::new(Cursor::new(truncated)).collect::<Vec<_>>();
+        match res.last().unwrap() {
+            Err(Error::Truncated {
+                location,
+                expected,
+                actual,
+            }) => {
+                assert_eq!(location.offset, Some(3));
+                assert_eq!((*expected, *actual), (200, 4));
             }
+            other => panic!("unexpected result {:?}", other),
         }
     }
 }
@@ -29,7 +29,8 @@ impl BiffSize {
     }
 
     #[inline]
-    pub(crate) const fn inner<'a>(&'a self) -> &'a [u8] {
+    #[allow(dead_code)]
+    pub(crate) const fn inner(&self) -> &[u8] {
         match self {
             Self::U8(sz) => sz,
             Self::U16(sz) => sz,
@@ -0,0 +1,25 @@
+use std::io::Write;
+
+use super::{box_alloc, BiffId, BiffRecord, BiffSerializable};
+
+/// Extension for any `Write`, that allows to push BIFF records directly
+pub(crate) trait BiffWrite: Write {
+    /// Validate, serialize and push the typed record
+    #[inline]
+    fn write_record<T: BiffSerializable>(&mut self, record: &T) -> crate::Result<usize> {
+        Ok(record.into_biff()?.push(self)?)
+    }
+
+    /// Push the record without any data (`BrtBegin*` and `BrtEnd*` records, usually)
+    #[inline]
+    fn write_empty(&mut self, id: BiffId) -> crate::Result<usize> {
+        BiffRecord {
+            id,
+            data: box_alloc(0),
+        }
+        .push(self)
+        .map_err(crate::Error::from)
+    }
+}
+
+impl<W: Write + ?Sized> BiffWrite for W {}
@@ -0,0 +1,57 @@
+use super::{declare_packable, Cell, UncheckedCol};
+
+/// Last row index on the sheet
+pub(crate) const MAX_ROW: u32 = 1_048_575;
+/// Last column index on the sheet
+pub(crate) const MAX_COL: u32 = 16_383;
+
+declare_packable!(UncheckedRw, |x: &Self| x.inner <= MAX_ROW, inner, u32);
+
+// Range of cells
+declare_packable!(
+    UncheckedRfX,
+    |x: &Self| x.rw_first.inner <= x.rw_last.inner && x.col_first.inner <= x.col_last.inner,
+    rw_first,
+    UncheckedRw,
+    rw_last,
+    UncheckedRw,
+    col_first,
+    UncheckedCol,
+    col_last,
+    UncheckedCol
+);
+
+impl UncheckedRfX {
+    pub(crate) fn from_bounds(rw_first: u32, rw_last: u32, col_first: u32, col_last: u32) -> Self {
+        UncheckedRfX::new(
+            rw_first.into(),
+            rw_last.into(),
+            (col_first as i32).into(),
+            (col_last as i32).into(),
+        )
+    }
+}
+
+impl Cell {
+    pub(crate) fn at(column: u32, i_style_ref: u32) -> Self {
+        Cell::new((column as i32).into(), (i_style_ref as i32).into(), 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rfx() {
+        let rfx = UncheckedRfX::from_bounds(0, 10, 1, 2);
+        assert!(rfx.is_valid());
+        let data = pack_biff_data!(&rfx).unwrap();
+        assert_eq!(data.len(), 16);
+        assert_eq!(UncheckedRfX::deserialize(&data).unwrap(), rfx);
+
+        assert!(!UncheckedRfX::from_bounds(10, 0, 0, 0).is_valid());
+        assert!(!UncheckedRfX::from_bounds(0, MAX_ROW + 1, 0, 0).is_valid());
+        assert!(!UncheckedRfX::from_bounds(0, 0, 0, MAX_COL + 1).is_valid());
+    }
+}
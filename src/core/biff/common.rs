@@ -5,7 +5,7 @@ use super::{
     ValidBiff,
 };
 
-aligned_biff_data_impl!(u16, u32, u64, i16, i32, i64);
+aligned_biff_data_impl!(u16, u32, u64, i16, i32, i64, f64);
 
 impl BiffDataCompatible for u8 {
     fn size_of_type() -> usize {
@@ -24,9 +24,9 @@ impl IntoBiffData for u8 {
         1
     }
 
-    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> std::io::Result<usize> {
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
         if out_data.len() < offset + 1 {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            return Err(crate::Error::truncated(offset + 1, out_data.len()));
         }
         out_data[offset] = *self;
         Ok(1)
@@ -38,9 +38,9 @@ impl IntoBiffData for i8 {
         1
     }
 
-    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> std::io::Result<usize> {
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
         if out_data.len() < offset + 1 {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            return Err(crate::Error::truncated(offset + 1, out_data.len()));
         }
         out_data[offset] = *self as u8;
         Ok(1)
@@ -49,12 +49,12 @@ impl IntoBiffData for i8 {
 
 impl FromBiffData for u8 {
     fn from_biff_data(
-        data: &Box<[u8]>,
+        data: &[u8],
         offset: usize,
         out_data: &mut std::mem::MaybeUninit<Self>,
-    ) -> std::io::Result<usize> {
+    ) -> crate::Result<usize> {
         if data.len() < offset + 1 {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            return Err(crate::Error::truncated(offset + 1, data.len()));
         }
         out_data.write(data[offset]);
         Ok(1)
@@ -63,12 +63,12 @@ impl FromBiffData for u8 {
 
 impl FromBiffData for i8 {
     fn from_biff_data(
-        data: &Box<[u8]>,
+        data: &[u8],
         offset: usize,
         out_data: &mut std::mem::MaybeUninit<Self>,
-    ) -> std::io::Result<usize> {
+    ) -> crate::Result<usize> {
         if data.len() < offset + 1 {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            return Err(crate::Error::truncated(offset + 1, data.len()));
         }
         out_data.write(data[offset] as i8);
         Ok(1)
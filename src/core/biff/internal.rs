@@ -0,0 +1,602 @@
+use std::{borrow::Cow, mem::MaybeUninit};
+
+use super::{biff_data::CheckBiff, BiffDataCompatible, FromBiffData, IntoBiffData, ValidBiff};
+
+/// Decode `cch` UTF-16LE characters, starting at the given `offset`
+fn decode_utf16(data: &[u8], offset: usize, cch: usize) -> crate::Result<String> {
+    if data.len() < offset + cch * 2 {
+        return Err(crate::Error::truncated(offset + cch * 2, data.len()));
+    }
+    match encoding_rs::UTF_16LE
+        .decode_without_bom_handling_and_without_replacement(&data[offset..offset + cch * 2])
+    {
+        Some(Cow::Borrowed(s)) => Ok(s.to_owned()),
+        Some(Cow::Owned(s)) => Ok(s),
+        None => Err(crate::Error::invalid_value()),
+    }
+}
+
+/// Encode `value` as UTF-16LE characters, starting at the given `offset`
+fn encode_utf16(value: &str, offset: usize, out_data: &mut [u8]) -> usize {
+    let mut idx = offset;
+    for ch in value.encode_utf16() {
+        out_data[idx..idx + 2].copy_from_slice(&ch.to_le_bytes());
+        idx += 2;
+    }
+    idx - offset
+}
+
+/// Unicode string, prefixed with its length in characters
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct XLWideString {
+    pub(crate) inner: String,
+}
+
+impl XLWideString {
+    /// Count of UTF-16 code units in the string
+    #[inline]
+    pub(crate) fn cch(&self) -> usize {
+        self.inner.encode_utf16().count()
+    }
+}
+
+impl BiffDataCompatible for XLWideString {
+    fn size_of_type() -> usize {
+        4
+    }
+}
+
+impl IntoBiffData for XLWideString {
+    fn size_of(&self) -> usize {
+        4 + self.cch() * 2
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        let cch = self.cch();
+        if out_data.len() < offset + 4 + cch * 2 {
+            return Err(crate::Error::truncated(
+                offset + 4 + cch * 2,
+                out_data.len(),
+            ));
+        }
+        out_data[offset..offset + 4].copy_from_slice(&(cch as u32).to_le_bytes());
+        Ok(4 + encode_utf16(&self.inner, offset + 4, out_data))
+    }
+}
+
+impl FromBiffData for XLWideString {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 4 {
+            return Err(crate::Error::truncated(offset + 4, data.len()));
+        }
+        let cch = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        out_data.write(XLWideString {
+            inner: decode_utf16(data, offset + 4, cch)?,
+        });
+        Ok(4 + cch * 2)
+    }
+}
+
+impl From<&str> for XLWideString {
+    fn from(value: &str) -> Self {
+        XLWideString {
+            inner: value.to_owned(),
+        }
+    }
+}
+
+impl From<String> for XLWideString {
+    fn from(value: String) -> Self {
+        XLWideString { inner: value }
+    }
+}
+
+impl ValidBiff for XLWideString {}
+
+/// Unicode string, which can be `NULL` (length `0xFFFFFFFF`)
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct XLNullableWideString {
+    pub(crate) inner: Option<String>,
+}
+
+impl BiffDataCompatible for XLNullableWideString {
+    fn size_of_type() -> usize {
+        4
+    }
+}
+
+impl IntoBiffData for XLNullableWideString {
+    fn size_of(&self) -> usize {
+        4 + self
+            .inner
+            .as_ref()
+            .map(|s| s.encode_utf16().count() * 2)
+            .unwrap_or(0)
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        match self.inner {
+            None => {
+                if out_data.len() < offset + 4 {
+                    return Err(crate::Error::truncated(offset + 4, out_data.len()));
+                }
+                out_data[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+                Ok(4)
+            }
+            Some(ref s) => XLWideString::from(s.as_str()).into_biff_data(offset, out_data),
+        }
+    }
+}
+
+impl FromBiffData for XLNullableWideString {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 4 {
+            return Err(crate::Error::truncated(offset + 4, data.len()));
+        }
+        match u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) {
+            u32::MAX => {
+                out_data.write(XLNullableWideString { inner: None });
+                Ok(4)
+            }
+            cch => {
+                out_data.write(XLNullableWideString {
+                    inner: Some(decode_utf16(data, offset + 4, cch as usize)?),
+                });
+                Ok(4 + cch as usize * 2)
+            }
+        }
+    }
+}
+
+impl From<Option<&str>> for XLNullableWideString {
+    fn from(value: Option<&str>) -> Self {
+        XLNullableWideString {
+            inner: value.map(str::to_owned),
+        }
+    }
+}
+
+impl ValidBiff for XLNullableWideString {}
+
+/// Array of items, prefixed with their count
+#[derive(PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct CountedArray<T> {
+    pub(crate) items: Vec<T>,
+}
+
+impl<T> Default for CountedArray<T> {
+    fn default() -> Self {
+        CountedArray { items: Vec::new() }
+    }
+}
+
+impl<T> From<Vec<T>> for CountedArray<T> {
+    fn from(items: Vec<T>) -> Self {
+        CountedArray { items }
+    }
+}
+
+impl<T> CountedArray<T> {
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+impl<T> BiffDataCompatible for CountedArray<T> {
+    fn size_of_type() -> usize {
+        4
+    }
+}
+
+impl<T: IntoBiffData> IntoBiffData for CountedArray<T> {
+    fn size_of(&self) -> usize {
+        4 + self.items.iter().map(IntoBiffData::size_of).sum::<usize>()
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        if out_data.len() < offset + 4 {
+            return Err(crate::Error::truncated(offset + 4, out_data.len()));
+        }
+        out_data[offset..offset + 4].copy_from_slice(&(self.items.len() as u32).to_le_bytes());
+        let mut size = 4;
+        for item in &self.items {
+            size += item.into_biff_data(offset + size, out_data)?;
+        }
+        Ok(size)
+    }
+}
+
+impl<T: FromBiffData> FromBiffData for CountedArray<T> {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 4 {
+            return Err(crate::Error::truncated(offset + 4, data.len()));
+        }
+        let count = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        // The count comes from the file, don't trust it for the allocation
+        let min_size = offset + 4 + count * T::size_of_type().max(1);
+        if data.len() < min_size {
+            return Err(crate::Error::truncated(min_size, data.len()));
+        }
+        let mut items = Vec::with_capacity(count);
+        let mut size = 4;
+        for _ in 0..count {
+            let mut item = MaybeUninit::uninit();
+            size += T::from_biff_data(data, offset + size, &mut item)
+                .map_err(|e| e.with_field(&items.len().to_string()))?;
+            items.push(unsafe { item.assume_init() });
+        }
+        out_data.write(CountedArray { items });
+        Ok(size)
+    }
+}
+
+impl<T: CheckBiff> CheckBiff for CountedArray<T> {
+    fn validated(&self) -> crate::Result<&Self> {
+        for item in &self.items {
+            item.validated()?;
+        }
+        Ok(self)
+    }
+}
+
+/// Formatting run of the rich string: font `ifnt` starts at the character `ich`
+#[derive(Default, PartialEq, Clone, Copy)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct StrRun {
+    pub(crate) ich: u16,
+    pub(crate) ifnt: u16,
+}
+
+impl BiffDataCompatible for StrRun {
+    fn size_of_type() -> usize {
+        4
+    }
+}
+
+impl IntoBiffData for StrRun {
+    fn size_of(&self) -> usize {
+        4
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        if out_data.len() < offset + 4 {
+            return Err(crate::Error::truncated(offset + 4, out_data.len()));
+        }
+        out_data[offset..offset + 2].copy_from_slice(&self.ich.to_le_bytes());
+        out_data[offset + 2..offset + 4].copy_from_slice(&self.ifnt.to_le_bytes());
+        Ok(4)
+    }
+}
+
+impl FromBiffData for StrRun {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 4 {
+            return Err(crate::Error::truncated(offset + 4, data.len()));
+        }
+        out_data.write(StrRun {
+            ich: u16::from_le_bytes([data[offset], data[offset + 1]]),
+            ifnt: u16::from_le_bytes([data[offset + 2], data[offset + 3]]),
+        });
+        Ok(4)
+    }
+}
+
+impl ValidBiff for StrRun {}
+
+/// String with optional formatting runs and phonetic data (`RichStr`).
+///
+/// Phonetic data must end the record, so it is kept as raw bytes.
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct RichStr {
+    pub(crate) value: XLWideString,
+    pub(crate) runs: Option<CountedArray<StrRun>>,
+    pub(crate) phonetic: Option<Box<[u8]>>,
+}
+
+impl RichStr {
+    const F_RICH_STR: u8 = 0x01;
+    const F_EXT_STR: u8 = 0x02;
+
+    pub(crate) fn plain(value: XLWideString) -> Self {
+        RichStr {
+            value,
+            runs: None,
+            phonetic: None,
+        }
+    }
+}
+
+impl BiffDataCompatible for RichStr {
+    fn size_of_type() -> usize {
+        5
+    }
+}
+
+impl IntoBiffData for RichStr {
+    fn size_of(&self) -> usize {
+        1 + self.value.size_of()
+            + self.runs.as_ref().map(IntoBiffData::size_of).unwrap_or(0)
+            + self.phonetic.as_ref().map(|data| data.len()).unwrap_or(0)
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        if out_data.len() < offset + self.size_of() {
+            return Err(crate::Error::truncated(
+                offset + self.size_of(),
+                out_data.len(),
+            ));
+        }
+        let mut flags = 0;
+        if self.runs.is_some() {
+            flags |= Self::F_RICH_STR;
+        }
+        if self.phonetic.is_some() {
+            flags |= Self::F_EXT_STR;
+        }
+        out_data[offset] = flags;
+        let mut size = 1 + self.value.into_biff_data(offset + 1, out_data)?;
+        if let Some(runs) = &self.runs {
+            size += runs.into_biff_data(offset + size, out_data)?;
+        }
+        if let Some(phonetic) = &self.phonetic {
+            out_data[offset + size..offset + size + phonetic.len()].copy_from_slice(phonetic);
+            size += phonetic.len();
+        }
+        Ok(size)
+    }
+}
+
+impl FromBiffData for RichStr {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + Self::size_of_type() {
+            return Err(crate::Error::truncated(
+                offset + Self::size_of_type(),
+                data.len(),
+            ));
+        }
+        let flags = data[offset];
+        let mut value = MaybeUninit::uninit();
+        let mut size = 1 + XLWideString::from_biff_data(data, offset + 1, &mut value)?;
+        let runs = if flags & Self::F_RICH_STR != 0 {
+            let mut runs = MaybeUninit::uninit();
+            size += CountedArray::<StrRun>::from_biff_data(data, offset + size, &mut runs)?;
+            Some(unsafe { runs.assume_init() })
+        } else {
+            None
+        };
+        let phonetic = if flags & Self::F_EXT_STR != 0 {
+            let phonetic: Box<[u8]> = data[offset + size..].into();
+            size += phonetic.len();
+            Some(phonetic)
+        } else {
+            None
+        };
+        out_data.write(RichStr {
+            value: unsafe { value.assume_init() },
+            runs,
+            phonetic,
+        });
+        Ok(size)
+    }
+}
+
+impl CheckBiff for RichStr {
+    fn validated(&self) -> crate::Result<&Self> {
+        let cch = self.value.cch();
+        let runs_valid = self.runs.as_ref().is_none_or(|runs| {
+            runs.items.iter().all(|run| (run.ich as usize) < cch.max(1))
+                && runs.items.windows(2).all(|w| w[0].ich < w[1].ich)
+        });
+        if runs_valid {
+            Ok(self)
+        } else {
+            Err(crate::Error::invalid_value())
+        }
+    }
+}
+
+/// Color
+///
+/// First byte packs `fValidRGB` flag (lowest bit) and `xColorType` (other 7 bits).
+#[derive(Default, PartialEq, Clone, Copy, Hash, Eq)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct BrtColor {
+    pub(crate) x_color_type: u8,
+    pub(crate) index: u8,
+    pub(crate) n_tint_and_shade: i16,
+    pub(crate) b_red: u8,
+    pub(crate) b_green: u8,
+    pub(crate) b_blue: u8,
+    pub(crate) b_alpha: u8,
+}
+
+impl BrtColor {
+    pub(crate) const AUTO: u8 = 0x00;
+    pub(crate) const INDEXED: u8 = 0x01;
+    pub(crate) const RGB: u8 = 0x02;
+    pub(crate) const THEMED: u8 = 0x03;
+
+    pub(crate) const fn auto() -> Self {
+        BrtColor {
+            x_color_type: Self::AUTO,
+            index: 0,
+            n_tint_and_shade: 0,
+            b_red: 0,
+            b_green: 0,
+            b_blue: 0,
+            b_alpha: 0xFF,
+        }
+    }
+
+    pub(crate) const fn indexed(icv: u8) -> Self {
+        BrtColor {
+            x_color_type: Self::INDEXED,
+            index: icv,
+            ..Self::auto()
+        }
+    }
+
+    pub(crate) const fn themed(theme: u8) -> Self {
+        BrtColor {
+            x_color_type: Self::THEMED,
+            index: theme,
+            ..Self::auto()
+        }
+    }
+
+    pub(crate) const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        BrtColor {
+            x_color_type: Self::RGB,
+            index: 0xFF,
+            n_tint_and_shade: 0,
+            b_red: red,
+            b_green: green,
+            b_blue: blue,
+            b_alpha: 0xFF,
+        }
+    }
+}
+
+impl BiffDataCompatible for BrtColor {
+    fn size_of_type() -> usize {
+        8
+    }
+}
+
+impl IntoBiffData for BrtColor {
+    fn size_of(&self) -> usize {
+        8
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        if out_data.len() < offset + 8 {
+            return Err(crate::Error::truncated(offset + 8, out_data.len()));
+        }
+        out_data[offset] = 0x01 | (self.x_color_type << 1);
+        out_data[offset + 1] = self.index;
+        out_data[offset + 2..offset + 4].copy_from_slice(&self.n_tint_and_shade.to_le_bytes());
+        out_data[offset + 4] = self.b_red;
+        out_data[offset + 5] = self.b_green;
+        out_data[offset + 6] = self.b_blue;
+        out_data[offset + 7] = self.b_alpha;
+        Ok(8)
+    }
+}
+
+impl FromBiffData for BrtColor {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 8 {
+            return Err(crate::Error::truncated(offset + 8, data.len()));
+        }
+        out_data.write(BrtColor {
+            x_color_type: data[offset] >> 1,
+            index: data[offset + 1],
+            n_tint_and_shade: i16::from_le_bytes([data[offset + 2], data[offset + 3]]),
+            b_red: data[offset + 4],
+            b_green: data[offset + 5],
+            b_blue: data[offset + 6],
+            b_alpha: data[offset + 7],
+        });
+        Ok(8)
+    }
+}
+
+impl ValidBiff for BrtColor {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::biff::pack_biff_data;
+
+    #[test]
+    fn test_wide_strings() {
+        let s = XLWideString::from("Лист1");
+        let data = pack_biff_data!(&s).unwrap();
+        assert_eq!(data.len(), 4 + 5 * 2);
+        assert_eq!(&data[0..4], &[5, 0, 0, 0]);
+        assert_eq!(XLWideString::deserialize(&data).unwrap(), s);
+
+        let n = XLNullableWideString::from(None);
+        let data = pack_biff_data!(&n).unwrap();
+        assert_eq!(&data[..], &[0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(XLNullableWideString::deserialize(&data).unwrap(), n);
+
+        let n = XLNullableWideString::from(Some("rId1"));
+        let data = pack_biff_data!(&n).unwrap();
+        assert_eq!(XLNullableWideString::deserialize(&data).unwrap(), n);
+
+        let truncated: Box<[u8]> = Box::new([2, 0, 0, 0, 0x41, 0]);
+        assert!(XLWideString::deserialize(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_counted_array_and_rich_str() {
+        let runs = CountedArray::from(vec![StrRun { ich: 0, ifnt: 1 }, StrRun { ich: 2, ifnt: 0 }]);
+        let data = pack_biff_data!(&runs).unwrap();
+        assert_eq!(data.len(), 4 + 2 * 4);
+        assert_eq!(CountedArray::<StrRun>::deserialize(&data).unwrap(), runs);
+        // count is larger than the data
+        let bogus: Box<[u8]> = Box::new([0xFF, 0xFF, 0xFF, 0x0F, 0, 0, 0, 0]);
+        assert!(CountedArray::<StrRun>::deserialize(&bogus).is_err());
+
+        let rich = RichStr {
+            value: "abc".into(),
+            runs: Some(runs),
+            phonetic: Some(Box::new([1, 2, 3])),
+        };
+        assert!(rich.is_valid());
+        let data = pack_biff_data!(&rich).unwrap();
+        assert_eq!(data[0], 0x03);
+        assert_eq!(RichStr::deserialize(&data).unwrap(), rich);
+
+        let plain = RichStr::plain("abc".into());
+        let data = pack_biff_data!(&plain).unwrap();
+        assert_eq!(data.len(), 1 + 4 + 6);
+        assert_eq!(RichStr::deserialize(&data).unwrap(), plain);
+
+        let unordered = RichStr {
+            runs: Some(CountedArray::from(vec![
+                StrRun { ich: 2, ifnt: 1 },
+                StrRun { ich: 1, ifnt: 0 },
+            ])),
+            ..plain
+        };
+        assert!(!unordered.is_valid());
+    }
+
+    #[test]
+    fn test_brt_color() {
+        let data = pack_biff_data!(&BrtColor::themed(1)).unwrap();
+        assert_eq!(&data[..], &[0x07, 0x01, 0, 0, 0, 0, 0, 0xFF]);
+        assert_eq!(BrtColor::deserialize(&data).unwrap(), BrtColor::themed(1));
+    }
+}
@@ -2,12 +2,13 @@
 mod biff_data;
 mod biff_id;
 mod biff_record;
+mod biff_scanner;
 mod biff_size;
 mod biff_traits;
 mod cell;
 mod common;
 mod internal;
-mod prelude;
+pub(crate) mod prelude;
 
 pub(crate) type BiffSize = biff_size::BiffSize;
 
@@ -18,10 +19,16 @@ pub(crate) use biff_data::{
     impl_packable_for, internal_impl_packable_for, pack_biff_data, try_to_sized,
     BiffDataCompatible, BiffId, BiffRecord, BiffSerializable, FromBiffData, IntoBiffData,
 };
+pub(crate) use biff_scanner::BiffScanner;
+pub(crate) use biff_traits::BiffWrite;
+pub(crate) use cell::{UncheckedRfX, UncheckedRw, MAX_COL, MAX_ROW};
+pub(crate) use internal::{
+    BrtColor, CountedArray, RichStr, StrRun, XLNullableWideString, XLWideString,
+};
 
-mod records;
+pub(crate) mod records;
 
-#[cfg_attr(feature = "test", derive(Debug))]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
 #[derive(Default, PartialEq)]
 pub(crate) struct I24Adapter {
     inner: i32,
@@ -38,9 +45,9 @@ impl IntoBiffData for I24Adapter {
         3
     }
 
-    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> std::io::Result<usize> {
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
         if out_data.len() < offset + 3 {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            return Err(crate::Error::truncated(offset + 3, out_data.len()));
         }
         out_data[offset + 2] = (self.inner >> 16) as u8;
         out_data[offset + 1] = (self.inner >> 8) as u8;
@@ -51,12 +58,12 @@ impl IntoBiffData for I24Adapter {
 
 impl FromBiffData for I24Adapter {
     fn from_biff_data(
-        data: &Box<[u8]>,
+        data: &[u8],
         offset: usize,
         out_data: &mut MaybeUninit<Self>,
-    ) -> std::io::Result<usize> {
+    ) -> crate::Result<usize> {
         if data.len() < offset + 3 {
-            return Err(std::io::Error::from(std::io::ErrorKind::InvalidData));
+            return Err(crate::Error::truncated(offset + 3, data.len()));
         }
         out_data.write(I24Adapter {
             inner: i32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], 0]),
@@ -71,9 +78,9 @@ impl From<i32> for I24Adapter {
     }
 }
 
-impl Into<i32> for I24Adapter {
-    fn into(self) -> i32 {
-        self.inner
+impl From<I24Adapter> for i32 {
+    fn from(value: I24Adapter) -> Self {
+        value.inner
     }
 }
 
@@ -0,0 +1,8 @@
+//! Everything needed to declare and push BIFF records
+pub(crate) use super::biff_data::{CheckBiff, ValidBiff};
+pub(crate) use super::{
+    box_alloc, checked, declare_packable, pack_biff_data, BiffDataCompatible, BiffId, BiffRecord,
+    BiffSerializable, BiffWrite, BrtColor, Cell, CountedArray, FromBiffData, I24Adapter,
+    IntoBiffData, RichStr, StrRun, UncheckedCol, UncheckedRfX, UncheckedRw, XLNullableWideString,
+    XLWideString, MAX_COL, MAX_ROW,
+};
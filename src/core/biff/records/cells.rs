@@ -0,0 +1,286 @@
+//! Records of the sheet data: row headers and cell values
+use crate::core::biff::prelude::*;
+
+/// Known values of `BErr`
+pub(crate) const KNOWN_ERRORS: [u8; 8] = [0x00, 0x07, 0x0F, 0x17, 0x1D, 0x24, 0x2A, 0x2B];
+
+/// Maximal length of the string in the cell
+pub(crate) const MAX_STRING_LEN: usize = 32767;
+
+/// Maximal count of column spans in the row header
+pub(crate) const MAX_COLSPANS: usize = 16;
+
+// Columns, which have cells in a block of rows (`BrtColSpan`)
+declare_packable!(
+    ColSpan,
+    |x: &Self| x.col_mic.inner <= x.col_last.inner,
+    col_mic,
+    UncheckedCol,
+    col_last,
+    UncheckedCol
+);
+
+impl ColSpan {
+    pub(crate) fn from_bounds(col_mic: u32, col_last: u32) -> Self {
+        ColSpan::new((col_mic as i32).into(), (col_last as i32).into())
+    }
+}
+
+// Row header, must precede cells of the row
+declare_packable!(
+    BrtRowHdr,
+    |x: &Self| x.miy_rw <= 0x2000 && x.colspans.len() <= MAX_COLSPANS,
+    BrtRowHdr,
+    rw,
+    UncheckedRw,
+    ixfe,
+    u32,
+    miy_rw,
+    u16,
+    flags,
+    u16,
+    flags2,
+    u8,
+    colspans,
+    CountedArray<ColSpan>
+);
+
+impl BrtRowHdr {
+    /// Default row height in twips (15 pt)
+    pub(crate) const DEFAULT_HEIGHT: u16 = 300;
+    /// Maximal row height in twips (409.5 pt)
+    pub(crate) const MAX_HEIGHT: u16 = 8190;
+
+    /// Outline level, bits 8-10
+    pub(crate) const OUTLINE_LEVEL: u16 = 0x0700;
+    pub(crate) const F_COLLAPSED: u16 = 0x0800;
+    /// Row is hidden
+    pub(crate) const F_DY_ZERO: u16 = 0x1000;
+    /// Row has the custom height
+    pub(crate) const F_UNSYNCED: u16 = 0x2000;
+    /// Row has the style `ixfe`
+    pub(crate) const F_GHOST_DIRTY: u16 = 0x4000;
+    /// Count of rows in the block, which share column spans
+    pub(crate) const BLOCK_ROWS: u32 = 16;
+    /// Count of columns in the single column span
+    pub(crate) const SPAN_COLS: u32 = 1024;
+
+    pub(crate) fn at(rw: u32) -> Self {
+        BrtRowHdr::new(
+            rw.into(),
+            0,
+            Self::DEFAULT_HEIGHT,
+            0,
+            0,
+            CountedArray::default(),
+        )
+    }
+}
+
+declare_packable!(BrtCellBlank, checked, BrtCellBlank, cell, Cell);
+
+// Number, packed into 30 bits (RkNumber)
+declare_packable!(BrtCellRk, checked, BrtCellRk, cell, Cell, rk, u32);
+
+impl BrtCellRk {
+    const F_X100: u32 = 0x01;
+    const F_INT: u32 = 0x02;
+
+    /// Pack the number into one of four RK forms, if it is decoded back exactly
+    pub(crate) fn encode(value: f64) -> Option<u32> {
+        if !value.is_finite() {
+            return None;
+        }
+        let int = |num: f64| {
+            if num.fract() == 0.0 && (-(1i64 << 29) as f64..(1i64 << 29) as f64).contains(&num) {
+                Some(((num as i32) << 2) as u32 | Self::F_INT)
+            } else {
+                None
+            }
+        };
+        // only 30 high bits of the number are stored
+        let float = |num: f64| {
+            let bits = num.to_bits();
+            if bits & 0x3_FFFF_FFFF == 0 {
+                Some((bits >> 32) as u32)
+            } else {
+                None
+            }
+        };
+        let scaled = value * 100.0;
+        [
+            int(value),
+            float(value),
+            int(scaled.round()).map(|rk| rk | Self::F_X100),
+            float(scaled).map(|rk| rk | Self::F_X100),
+        ]
+        .into_iter()
+        .flatten()
+        .find(|&rk| Self::decode(rk).to_bits() == value.to_bits())
+    }
+
+    /// Decode the packed number
+    pub(crate) fn decode(rk: u32) -> f64 {
+        let num = if rk & Self::F_INT != 0 {
+            ((rk as i32) >> 2) as f64
+        } else {
+            f64::from_bits(((rk & !0x03) as u64) << 32)
+        };
+        if rk & Self::F_X100 != 0 {
+            num / 100.0
+        } else {
+            num
+        }
+    }
+
+    /// Decoded number of the cell
+    pub(crate) fn value(&self) -> f64 {
+        Self::decode(self.rk)
+    }
+}
+
+declare_packable!(
+    BrtCellReal,
+    |x: &Self| x.xnum.is_finite(),
+    BrtCellReal,
+    cell,
+    Cell,
+    xnum,
+    f64
+);
+
+declare_packable!(
+    BrtCellSt,
+    |x: &Self| x.value.cch() <= MAX_STRING_LEN,
+    BrtCellSt,
+    cell,
+    Cell,
+    value,
+    XLWideString
+);
+
+// String with formatting runs, stored in the cell
+declare_packable!(
+    BrtCellRString,
+    |x: &Self| x.value.value.cch() <= MAX_STRING_LEN,
+    BrtCellRString,
+    cell,
+    Cell,
+    value,
+    RichStr
+);
+
+// String from the shared strings table
+declare_packable!(BrtCellIsst, checked, BrtCellIsst, cell, Cell, isst, u32);
+
+declare_packable!(
+    BrtCellBool,
+    |x: &Self| x.f_bool <= 1,
+    BrtCellBool,
+    cell,
+    Cell,
+    f_bool,
+    u8
+);
+
+declare_packable!(
+    BrtCellError,
+    |x: &Self| KNOWN_ERRORS.contains(&x.b_error),
+    BrtCellError,
+    cell,
+    Cell,
+    b_error,
+    u8
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_records() {
+        let rec = BrtCellReal::new(Cell::at(3, 0), 1.5).into_biff().unwrap();
+        assert_eq!(rec.data.len(), 16);
+        assert_eq!(&rec.data[0..4], &[3, 0, 0, 0]);
+        assert_eq!(&rec.data[8..], &1.5f64.to_le_bytes());
+        assert!(BrtCellReal::new(Cell::at(0, 0), f64::NAN)
+            .into_biff()
+            .is_err());
+
+        let rec = BrtCellSt::new(Cell::at(1, 0), "abc".into())
+            .into_biff()
+            .unwrap();
+        assert_eq!(rec.data.len(), 8 + 4 + 6);
+        assert_eq!(
+            rec.as_biff::<BrtCellSt>().unwrap().value.inner.as_str(),
+            "abc"
+        );
+
+        assert!(BrtCellBool::new(Cell::at(0, 0), 2).into_biff().is_err());
+        assert!(BrtCellError::new(Cell::at(0, 0), 0x01).into_biff().is_err());
+        assert!(BrtCellBlank::new(Cell::at(16384, 0)).into_biff().is_err());
+
+        let rk = |rk: u32| BrtCellRk::new(Cell::at(0, 0), rk).value();
+        assert_eq!(rk(0x3FF0_0000), 1.0);
+        assert_eq!(rk(0x3FF0_0001), 0.01);
+        assert_eq!(rk((-5i32 << 2) as u32 | 0x02), -5.0);
+        assert_eq!(rk((1234 << 2) | 0x03), 12.34);
+        let forms = [
+            (0.0, 0x02),
+            (-5.0, 0x02),
+            ((1 << 29) as f64 - 1.0, 0x02),
+            (-((1 << 29) as f64), 0x02),
+            // integers above 30 bits and simple fractions fit into the float form
+            ((1i64 << 40) as f64, 0x00),
+            (0.5, 0x00),
+            (-0.0, 0x00),
+            (12.34, 0x03),
+            (-0.01, 0x03),
+            (0.015, 0x01),
+            (0.1, 0x03),
+        ];
+        for (value, flags) in forms {
+            let encoded = BrtCellRk::encode(value).unwrap();
+            assert_eq!(encoded & 0x03, flags, "{}", value);
+            assert_eq!(rk(encoded).to_bits(), value.to_bits());
+        }
+        for value in [
+            0.001,
+            1.0 / 3.0,
+            1e300,
+            123456789.123,
+            f64::NAN,
+            f64::INFINITY,
+        ] {
+            assert!(BrtCellRk::encode(value).is_none(), "{}", value);
+        }
+
+        let rec = BrtRowHdr::at(7).into_biff().unwrap();
+        assert_eq!(rec.data.len(), 17);
+        assert!(BrtRowHdr::at(MAX_ROW + 1).into_biff().is_err());
+
+        let mut hdr = BrtRowHdr::at(0);
+        hdr.colspans = vec![ColSpan::from_bounds(0, 15), ColSpan::from_bounds(32, 40)].into();
+        let rec = hdr.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 17 + 2 * 8);
+        assert_eq!(rec.as_biff::<BrtRowHdr>().unwrap(), hdr);
+        hdr.colspans = vec![ColSpan::from_bounds(2, 1)].into();
+        assert!(hdr.into_biff().is_err());
+        hdr.colspans = (0..=MAX_COLSPANS as u32)
+            .map(|col| ColSpan::from_bounds(col, col))
+            .collect::<Vec<_>>()
+            .into();
+        assert!(hdr.into_biff().is_err());
+
+        let rich = RichStr {
+            runs: Some(vec![StrRun { ich: 0, ifnt: 1 }].into()),
+            ..RichStr::plain("xyz".into())
+        };
+        let rec = BrtCellRString::new(Cell::at(2, 0), rich)
+            .into_biff()
+            .unwrap();
+        let cell = rec.as_biff::<BrtCellRString>().unwrap();
+        assert_eq!(cell.value.value.inner, "xyz");
+        assert_eq!(cell.value.runs.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,64 @@
+//! Records of the comments part (`xl/commentsN.bin`) and of the legacy drawing reference from
+//! the sheet
+use crate::core::biff::prelude::*;
+
+// Author of comments, referenced by its index in the list of authors
+declare_packable!(
+    BrtCommentAuthor,
+    checked,
+    BrtCommentAuthor,
+    xstr_author,
+    XLWideString
+);
+
+// Comment of the cell, followed by `BrtCommentText`
+declare_packable!(
+    BrtBeginComment,
+    |x: &Self| x.rfx.is_valid(),
+    BrtBeginComment,
+    iauthor,
+    u32,
+    rfx,
+    UncheckedRfX,
+    guid_lo,
+    u64,
+    guid_hi,
+    u64
+);
+
+// Text of the comment
+declare_packable!(BrtCommentText, checked, BrtCommentText, text, RichStr);
+
+// Relationship of the sheet to the VML drawing, which holds note boxes of comments
+declare_packable!(
+    BrtLegacyDrawing,
+    |x: &Self| x.st_rel_id.cch() > 0,
+    BrtLegacyDrawing,
+    st_rel_id,
+    XLWideString
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_records() {
+        let comment = BrtBeginComment::new(1, UncheckedRfX::from_bounds(2, 2, 3, 3), 0, 0);
+        let rec = comment.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 4 + 16 + 16);
+        assert_eq!(rec.as_biff::<BrtBeginComment>().unwrap(), comment);
+        assert!(
+            BrtBeginComment::new(0, UncheckedRfX::from_bounds(3, 2, 0, 0), 0, 0)
+                .into_biff()
+                .is_err()
+        );
+
+        let text = BrtCommentText::new(RichStr::plain("Check".into()));
+        let rec = text.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 1 + 4 + 10);
+        assert_eq!(rec.as_biff::<BrtCommentText>().unwrap(), text);
+        assert!(BrtCommentAuthor::new("Auditor".into()).into_biff().is_ok());
+        assert!(BrtLegacyDrawing::new("".into()).into_biff().is_err());
+    }
+}
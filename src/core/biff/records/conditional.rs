@@ -0,0 +1,488 @@
+//! Records of the conditional formatting and its Excel 2010 extension
+use std::mem::MaybeUninit;
+
+use super::ParsedFormula;
+use crate::core::biff::prelude::*;
+
+/// Formulas of the rule or its value, each with its extra data, up to the end of the record
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct FormulaList {
+    pub(crate) items: Vec<ParsedFormula>,
+}
+
+impl FormulaList {
+    /// Sizes of the formulas (`cbFmla`), `0` for missing ones
+    pub(crate) fn sizes<const N: usize>(&self) -> [u32; N] {
+        let mut sizes = [0; N];
+        for (size, formula) in sizes.iter_mut().zip(&self.items) {
+            *size = formula.size_of() as u32;
+        }
+        sizes
+    }
+}
+
+impl From<Vec<ParsedFormula>> for FormulaList {
+    fn from(items: Vec<ParsedFormula>) -> Self {
+        FormulaList { items }
+    }
+}
+
+impl BiffDataCompatible for FormulaList {
+    fn size_of_type() -> usize {
+        0
+    }
+}
+
+impl IntoBiffData for FormulaList {
+    fn size_of(&self) -> usize {
+        self.items.iter().map(IntoBiffData::size_of).sum()
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        let mut size = 0;
+        for item in &self.items {
+            size += item.into_biff_data(offset + size, out_data)?;
+        }
+        Ok(size)
+    }
+}
+
+impl FromBiffData for FormulaList {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        let mut items = Vec::new();
+        let mut size = 0;
+        while offset + size < data.len() {
+            let mut item = MaybeUninit::uninit();
+            size += ParsedFormula::from_biff_data(data, offset + size, &mut item)
+                .map_err(|e| e.with_field(&items.len().to_string()))?;
+            items.push(unsafe { item.assume_init() });
+        }
+        out_data.write(FormulaList { items });
+        Ok(size)
+    }
+}
+
+impl CheckBiff for FormulaList {
+    fn validated(&self) -> crate::Result<&Self> {
+        for item in &self.items {
+            item.validated()?;
+        }
+        Ok(self)
+    }
+}
+
+// Ranges of the conditional formatting, followed by `ccf` rules
+declare_packable!(
+    BrtBeginConditionalFormatting,
+    |x: &Self| x.sqrfx.len() > 0 && x.sqrfx.items.iter().all(UncheckedRfX::is_valid),
+    BrtBeginConditionalFormatting,
+    ccf,
+    u32,
+    f_pivot,
+    u32,
+    sqrfx,
+    CountedArray<UncheckedRfX>
+);
+
+// Rule of the conditional formatting with up to three formulas
+declare_packable!(
+    BrtBeginCFRule,
+    |x: &Self| [x.cb_fmla1, x.cb_fmla2, x.cb_fmla3] == x.formulas.sizes(),
+    BrtBeginCFRule,
+    i_type,
+    u32,
+    i_template,
+    u32,
+    dxf_id,
+    u32,
+    i_pri,
+    u32,
+    i_param,
+    u32,
+    reserved1,
+    u32,
+    reserved2,
+    u32,
+    flags,
+    u16,
+    cb_fmla1,
+    u32,
+    cb_fmla2,
+    u32,
+    cb_fmla3,
+    u32,
+    str_param,
+    XLNullableWideString,
+    formulas,
+    FormulaList
+);
+
+impl BrtBeginCFRule {
+    /// Types of rules (`CFType`)
+    pub(crate) const TYPE_CELL_IS: u32 = 1;
+    pub(crate) const TYPE_EXPRESSION: u32 = 2;
+    pub(crate) const TYPE_COLOR_SCALE: u32 = 3;
+    pub(crate) const TYPE_DATABAR: u32 = 4;
+    pub(crate) const TYPE_TOP10: u32 = 5;
+    pub(crate) const TYPE_ICON_SET: u32 = 6;
+
+    /// Templates of rules (`CFTemp`)
+    pub(crate) const TEMPLATE_CELL_IS: u32 = 0;
+    pub(crate) const TEMPLATE_EXPRESSION: u32 = 1;
+    pub(crate) const TEMPLATE_COLOR_SCALE: u32 = 2;
+    pub(crate) const TEMPLATE_DATABAR: u32 = 3;
+    pub(crate) const TEMPLATE_ICON_SET: u32 = 4;
+    pub(crate) const TEMPLATE_TOP10: u32 = 5;
+    pub(crate) const TEMPLATE_UNIQUE: u32 = 7;
+    pub(crate) const TEMPLATE_ABOVE_AVERAGE: u32 = 25;
+    pub(crate) const TEMPLATE_BELOW_AVERAGE: u32 = 26;
+    pub(crate) const TEMPLATE_DUPLICATE: u32 = 27;
+
+    pub(crate) const F_STOP_TRUE: u16 = 0x0002;
+    pub(crate) const F_ABOVE: u16 = 0x0004;
+    pub(crate) const F_BOTTOM: u16 = 0x0008;
+    pub(crate) const F_PERCENT: u16 = 0x0010;
+
+    /// Rule without the differential format, like color scales
+    pub(crate) const NO_DXF: u32 = u32::MAX;
+
+    /// Rule with sizes of its formulas
+    pub(crate) fn rule(
+        i_type: u32,
+        i_template: u32,
+        dxf_id: u32,
+        i_pri: u32,
+        i_param: u32,
+        flags: u16,
+        formulas: Vec<ParsedFormula>,
+    ) -> Self {
+        let formulas = FormulaList::from(formulas);
+        let [cb_fmla1, cb_fmla2, cb_fmla3] = formulas.sizes();
+        BrtBeginCFRule {
+            i_type,
+            i_template,
+            dxf_id,
+            i_pri,
+            i_param,
+            flags,
+            cb_fmla1,
+            cb_fmla2,
+            cb_fmla3,
+            formulas,
+            ..Default::default()
+        }
+    }
+}
+
+// Bar lengths of the data bar in percents of the cell width, followed by two `BrtCFVO` and
+// the color of the bar
+declare_packable!(
+    BrtBeginDatabar,
+    |x: &Self| x.b_len_min <= x.b_len_max && x.b_len_max <= 100,
+    BrtBeginDatabar,
+    b_len_min,
+    u8,
+    b_len_max,
+    u8,
+    f_show_value,
+    u8
+);
+
+// Icon set (`-1` for custom icons of the Excel 2010 extension), followed by its thresholds
+declare_packable!(
+    BrtBeginIconSet,
+    checked,
+    BrtBeginIconSet,
+    i_set,
+    i32,
+    flags,
+    u32
+);
+
+impl BrtBeginIconSet {
+    pub(crate) const F_ICON_ONLY: u32 = 0x0001;
+    pub(crate) const F_REVERSE: u32 = 0x0004;
+    /// Icons are chosen one by one by `BrtCFIcon`
+    pub(crate) const CUSTOM_SET: i32 = -1;
+}
+
+// Value of the color scale point, the data bar end or the icon threshold
+declare_packable!(
+    BrtCFVO,
+    |x: &Self| x.num_param.is_finite() && [x.cb_fmla] == x.formula.sizes(),
+    BrtCFVO,
+    i_type,
+    u32,
+    num_param,
+    f64,
+    flags,
+    u32,
+    cb_fmla,
+    u32,
+    formula,
+    FormulaList
+);
+
+impl BrtCFVO {
+    /// Types of values (`CFVOtype`)
+    pub(crate) const TYPE_NUM: u32 = 1;
+    pub(crate) const TYPE_MIN: u32 = 2;
+    pub(crate) const TYPE_MAX: u32 = 3;
+    pub(crate) const TYPE_PERCENT: u32 = 4;
+    pub(crate) const TYPE_PERCENTILE: u32 = 5;
+    pub(crate) const TYPE_FORMULA: u32 = 6;
+    pub(crate) const TYPE_AUTO_MIN: u32 = 7;
+    pub(crate) const TYPE_AUTO_MAX: u32 = 8;
+
+    /// Icon threshold includes its value (`>=` instead of `>`)
+    pub(crate) const F_SAVE_GTE: u32 = 0x0001;
+    pub(crate) const F_GTE: u32 = 0x0002;
+
+    /// Value with the number parameter or the formula
+    pub(crate) fn value(i_type: u32, num_param: f64, formula: Option<ParsedFormula>) -> Self {
+        let formula = FormulaList::from(formula.into_iter().collect::<Vec<_>>());
+        let [cb_fmla] = formula.sizes();
+        BrtCFVO::new(
+            i_type,
+            num_param,
+            Self::F_SAVE_GTE | Self::F_GTE,
+            cb_fmla,
+            formula,
+        )
+    }
+}
+
+// Color of the color scale point or the data bar
+declare_packable!(BrtColorRecord, checked, BrtColor, color, BrtColor);
+
+// Start of the future record type of the given version (`FRTVersion`)
+declare_packable!(
+    BrtFRTBegin,
+    checked,
+    BrtFRTBegin,
+    version,
+    u16,
+    product,
+    u16
+);
+
+impl BrtFRTBegin {
+    /// Records of Excel 2010
+    pub(crate) fn excel_2010() -> Self {
+        BrtFRTBegin::new(14, 0)
+    }
+}
+
+// Identifier of the rule in the Excel 2010 extension with the same rule
+declare_packable!(
+    BrtCFRuleExt,
+    checked,
+    BrtCFRuleExt,
+    guid_lo,
+    u64,
+    guid_hi,
+    u64
+);
+
+// Ranges of the conditional formatting of the Excel 2010 extension
+declare_packable!(
+    BrtBeginConditionalFormatting14,
+    |x: &Self| x.sqrfx.len() > 0 && x.sqrfx.items.iter().all(UncheckedRfX::is_valid),
+    BrtBeginConditionalFormatting14,
+    ccf,
+    u32,
+    f_pivot,
+    u32,
+    sqrfx,
+    CountedArray<UncheckedRfX>
+);
+
+// Rule of the Excel 2010 extension, its identifier links it to the rule of the sheet
+declare_packable!(
+    BrtBeginCFRule14,
+    |x: &Self| [x.cb_fmla1, x.cb_fmla2, x.cb_fmla3] == x.formulas.sizes(),
+    BrtBeginCFRule14,
+    i_type,
+    u32,
+    i_template,
+    u32,
+    guid_lo,
+    u64,
+    guid_hi,
+    u64,
+    i_pri,
+    u32,
+    i_param,
+    u32,
+    reserved1,
+    u32,
+    reserved2,
+    u32,
+    flags,
+    u16,
+    cb_fmla1,
+    u32,
+    cb_fmla2,
+    u32,
+    cb_fmla3,
+    u32,
+    str_param,
+    XLNullableWideString,
+    formulas,
+    FormulaList
+);
+
+impl BrtBeginCFRule14 {
+    /// Rule of the data bar or the icon set, which has no formulas
+    pub(crate) fn rule(i_type: u32, i_template: u32, guid: (u64, u64), i_pri: u32) -> Self {
+        BrtBeginCFRule14 {
+            i_type,
+            i_template,
+            guid_lo: guid.0,
+            guid_hi: guid.1,
+            i_pri,
+            ..Default::default()
+        }
+    }
+}
+
+// Data bar of the Excel 2010 extension, followed by two `BrtCFVO14` and its colors
+declare_packable!(
+    BrtBeginDatabar14,
+    |x: &Self| x.b_len_min <= x.b_len_max && x.b_len_max <= 100,
+    BrtBeginDatabar14,
+    b_len_min,
+    u8,
+    b_len_max,
+    u8,
+    flags,
+    u16,
+    axis_position,
+    u8,
+    direction,
+    u8
+);
+
+impl BrtBeginDatabar14 {
+    pub(crate) const F_SHOW_VALUE: u16 = 0x0001;
+    pub(crate) const F_GRADIENT: u16 = 0x0002;
+    pub(crate) const F_BORDER: u16 = 0x0004;
+    #[allow(dead_code)]
+    pub(crate) const F_NEG_SAME_FILL: u16 = 0x0008;
+    #[allow(dead_code)]
+    pub(crate) const F_NEG_SAME_BORDER: u16 = 0x0010;
+
+    /// The axis is at the position of zero, calculated from values
+    pub(crate) const AXIS_AUTO: u8 = 0;
+    /// Direction is taken from the sheet
+    pub(crate) const DIRECTION_CONTEXT: u8 = 0;
+}
+
+// Color of the data bar of the Excel 2010 extension
+declare_packable!(BrtColor14, checked, BrtColor14, kind, u32, color, BrtColor);
+
+impl BrtColor14 {
+    pub(crate) const FILL: u32 = 0;
+    pub(crate) const BORDER: u32 = 1;
+    pub(crate) const NEGATIVE_FILL: u32 = 2;
+    pub(crate) const NEGATIVE_BORDER: u32 = 3;
+    pub(crate) const AXIS: u32 = 4;
+}
+
+// Value of the Excel 2010 extension, has the same layout as `BrtCFVO`
+declare_packable!(
+    BrtCFVO14,
+    |x: &Self| x.num_param.is_finite() && [x.cb_fmla] == x.formula.sizes(),
+    BrtCFVO14,
+    i_type,
+    u32,
+    num_param,
+    f64,
+    flags,
+    u32,
+    cb_fmla,
+    u32,
+    formula,
+    FormulaList
+);
+
+impl From<BrtCFVO> for BrtCFVO14 {
+    fn from(cfvo: BrtCFVO) -> Self {
+        BrtCFVO14::new(
+            cfvo.i_type,
+            cfvo.num_param,
+            cfvo.flags,
+            cfvo.cb_fmla,
+            cfvo.formula,
+        )
+    }
+}
+
+// Icon set of the Excel 2010 extension, followed by its thresholds and custom icons
+declare_packable!(
+    BrtBeginIconSet14,
+    checked,
+    BrtBeginIconSet14,
+    i_set,
+    i32,
+    flags,
+    u32
+);
+
+// Icon of the threshold: the icon set and the index of the icon in it
+declare_packable!(BrtCFIcon, checked, BrtCFIcon, i_set, i32, i_icon, u32);
+
+impl BrtCFIcon {
+    /// No icon is shown for the threshold
+    #[allow(dead_code)]
+    pub(crate) const NO_ICON: i32 = -1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conditional_records() {
+        let formula = ParsedFormula::new(vec![0x1E, 10, 0], Vec::new());
+        let rule = BrtBeginCFRule::rule(
+            BrtBeginCFRule::TYPE_CELL_IS,
+            BrtBeginCFRule::TEMPLATE_CELL_IS,
+            0,
+            1,
+            1,
+            0,
+            vec![formula.clone(), formula],
+        );
+        assert_eq!((rule.cb_fmla1, rule.cb_fmla2, rule.cb_fmla3), (11, 11, 0));
+        let rec = rule.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 4 * 7 + 2 + 4 * 3 + 4 + 11 * 2);
+        // the missing string parameter is NULL
+        assert_eq!(&rec.data[42..46], [0xFF; 4]);
+        assert_eq!(rec.as_biff::<BrtBeginCFRule>().unwrap(), rule);
+        let mut bad = rule;
+        bad.cb_fmla3 = 4;
+        assert!(bad.into_biff().is_err());
+
+        let cfvo = BrtCFVO::value(BrtCFVO::TYPE_PERCENT, 50.0, None);
+        let rec = cfvo.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 20);
+        assert_eq!(rec.as_biff::<BrtCFVO>().unwrap(), cfvo);
+
+        let sqrfx = vec![UncheckedRfX::from_bounds(0, 9, 0, 0)].into();
+        let cf = BrtBeginConditionalFormatting::new(1, 0, sqrfx);
+        assert_eq!(cf.into_biff().unwrap().data.len(), 4 + 4 + 4 + 16);
+        assert!(BrtBeginConditionalFormatting::new(1, 0, Vec::new().into())
+            .into_biff()
+            .is_err());
+        assert_eq!(
+            &*BrtFRTBegin::excel_2010().into_biff().unwrap().data,
+            [14, 0, 0, 0]
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Records of the AutoFilter: the range with dropdown buttons and criteria of its columns
+use std::mem::MaybeUninit;
+
+use crate::core::biff::prelude::*;
+
+// Range of the AutoFilter, its first row has dropdown buttons
+declare_packable!(BrtBeginAFilter, checked, BrtBeginAFilter, rfx, UncheckedRfX);
+
+// Column of the AutoFilter with criteria, `dw_col` is relative to the first column of the range
+declare_packable!(
+    BrtBeginFilterColumn,
+    |x: &Self| x.dw_col <= MAX_COL && x.flags <= 3,
+    BrtBeginFilterColumn,
+    dw_col,
+    u32,
+    flags,
+    u16
+);
+
+impl BrtBeginFilterColumn {
+    #[allow(dead_code)]
+    pub(crate) const F_HIDE_ARROW: u16 = 0x0001;
+    #[allow(dead_code)]
+    pub(crate) const F_NO_BTN: u16 = 0x0002;
+}
+
+// List of values to show, followed by `BrtFilter` records
+declare_packable!(
+    BrtBeginFilters,
+    |x: &Self| x.f_blank <= 1,
+    BrtBeginFilters,
+    f_blank,
+    u32,
+    cal_type,
+    u32
+);
+
+// Value to show, compared with the displayed text of cells
+declare_packable!(
+    BrtFilter,
+    |x: &Self| x.rgch.cch() <= 255,
+    BrtFilter,
+    rgch,
+    XLWideString
+);
+
+// One or two custom comparisons, followed by `BrtCustomFilter` records
+declare_packable!(
+    BrtBeginCustomFilters,
+    |x: &Self| x.f_and <= 1,
+    BrtBeginCustomFilters,
+    f_and,
+    u32
+);
+
+// Comparison of cells with the value
+declare_packable!(
+    BrtCustomFilter,
+    checked,
+    BrtCustomFilter,
+    criterion,
+    FilterCriterion
+);
+
+// Top or bottom items or percent
+declare_packable!(
+    BrtTop10Filter,
+    |x: &Self| x.flags <= 3 && (1..=500).contains(&x.i_top_bottom_n),
+    BrtTop10Filter,
+    flags,
+    u32,
+    i_top_bottom_n,
+    u32,
+    xnum_filter,
+    f64
+);
+
+impl BrtTop10Filter {
+    pub(crate) const F_TOP: u32 = 0x0001;
+    pub(crate) const F_PERCENT: u32 = 0x0002;
+}
+
+// Filter, which depends on the data or the current date, like `aboveAverage` or `thisMonth`
+declare_packable!(
+    BrtDynamicFilter,
+    |x: &Self| x.cft <= BrtDynamicFilter::MAX_TYPE,
+    BrtDynamicFilter,
+    cft,
+    u32,
+    xnum_value,
+    f64,
+    xnum_max,
+    f64
+);
+
+impl BrtDynamicFilter {
+    /// The last type (`M12`)
+    pub(crate) const MAX_TYPE: u32 = 0x22;
+}
+
+/// Value of `BrtCustomFilter`, its type (`vts`) is stored before the operator
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) enum FilterOperand {
+    Number(f64),
+    String(XLWideString),
+    Bool(bool),
+    #[default]
+    Blanks,
+    NonBlanks,
+}
+
+impl FilterOperand {
+    const VTS_NUMBER: u8 = 0x04;
+    const VTS_STRING: u8 = 0x06;
+    const VTS_BOOL: u8 = 0x08;
+    const VTS_BLANKS: u8 = 0x0C;
+    const VTS_NON_BLANKS: u8 = 0x0E;
+
+    fn vts(&self) -> u8 {
+        match self {
+            FilterOperand::Number(_) => Self::VTS_NUMBER,
+            FilterOperand::String(_) => Self::VTS_STRING,
+            FilterOperand::Bool(_) => Self::VTS_BOOL,
+            FilterOperand::Blanks => Self::VTS_BLANKS,
+            FilterOperand::NonBlanks => Self::VTS_NON_BLANKS,
+        }
+    }
+}
+
+/// Type of the value, the comparison operator (`grbitSgn`) and the value itself
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct FilterCriterion {
+    pub(crate) grbit_sgn: u8,
+    pub(crate) value: FilterOperand,
+}
+
+impl FilterCriterion {
+    pub(crate) const LESS: u8 = 0x01;
+    pub(crate) const EQUAL: u8 = 0x02;
+    pub(crate) const LESS_OR_EQUAL: u8 = 0x03;
+    pub(crate) const GREATER: u8 = 0x04;
+    pub(crate) const NOT_EQUAL: u8 = 0x05;
+    pub(crate) const GREATER_OR_EQUAL: u8 = 0x06;
+
+    pub(crate) fn new(grbit_sgn: u8, value: FilterOperand) -> Self {
+        FilterCriterion { grbit_sgn, value }
+    }
+}
+
+impl BiffDataCompatible for FilterCriterion {
+    fn size_of_type() -> usize {
+        10
+    }
+}
+
+impl IntoBiffData for FilterCriterion {
+    fn size_of(&self) -> usize {
+        match self.value {
+            FilterOperand::String(ref value) => 2 + value.size_of(),
+            _ => 10,
+        }
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        if out_data.len() < offset + self.size_of() {
+            return Err(crate::Error::truncated(
+                offset + self.size_of(),
+                out_data.len(),
+            ));
+        }
+        out_data[offset] = self.value.vts();
+        out_data[offset + 1] = self.grbit_sgn;
+        let mut fixed = [0u8; 8];
+        match self.value {
+            FilterOperand::String(ref value) => {
+                return Ok(2 + value.into_biff_data(offset + 2, out_data)?)
+            }
+            FilterOperand::Number(value) => fixed = value.to_le_bytes(),
+            FilterOperand::Bool(value) => fixed[0] = value as u8,
+            FilterOperand::Blanks | FilterOperand::NonBlanks => {}
+        }
+        out_data[offset + 2..offset + 10].copy_from_slice(&fixed);
+        Ok(10)
+    }
+}
+
+impl FromBiffData for FilterCriterion {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 2 {
+            return Err(crate::Error::truncated(offset + 2, data.len()));
+        }
+        let (vts, grbit_sgn) = (data[offset], data[offset + 1]);
+        if vts == FilterOperand::VTS_STRING {
+            let mut value = MaybeUninit::uninit();
+            let size = XLWideString::from_biff_data(data, offset + 2, &mut value)?;
+            out_data.write(FilterCriterion::new(
+                grbit_sgn,
+                FilterOperand::String(unsafe { value.assume_init() }),
+            ));
+            return Ok(2 + size);
+        }
+        let fixed: [u8; 8] = match data.get(offset + 2..offset + 10) {
+            Some(fixed) => fixed.try_into().unwrap(),
+            None => return Err(crate::Error::truncated(offset + 10, data.len())),
+        };
+        let value = match vts {
+            FilterOperand::VTS_NUMBER => FilterOperand::Number(f64::from_le_bytes(fixed)),
+            FilterOperand::VTS_BOOL => FilterOperand::Bool(fixed[0] != 0),
+            FilterOperand::VTS_BLANKS => FilterOperand::Blanks,
+            FilterOperand::VTS_NON_BLANKS => FilterOperand::NonBlanks,
+            _ => return Err(crate::Error::invalid_value().at_offset(offset as u64)),
+        };
+        out_data.write(FilterCriterion::new(grbit_sgn, value));
+        Ok(10)
+    }
+}
+
+impl CheckBiff for FilterCriterion {
+    fn validated(&self) -> crate::Result<&Self> {
+        let valid_value = match self.value {
+            FilterOperand::Number(value) => value.is_finite(),
+            FilterOperand::String(ref value) => value.cch() <= 255,
+            _ => true,
+        };
+        if (Self::LESS..=Self::GREATER_OR_EQUAL).contains(&self.grbit_sgn) && valid_value {
+            Ok(self)
+        } else {
+            Err(crate::Error::invalid_value())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_records() {
+        let rec = BrtBeginAFilter::new(UncheckedRfX::from_bounds(0, 10, 1, 3))
+            .into_biff()
+            .unwrap();
+        assert_eq!(rec.data.len(), 16);
+        assert!(BrtBeginFilterColumn::new(0, 4).into_biff().is_err());
+
+        let number = BrtCustomFilter::new(FilterCriterion::new(
+            FilterCriterion::GREATER,
+            FilterOperand::Number(2.5),
+        ));
+        let rec = number.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 10);
+        assert_eq!(&rec.data[..2], [0x04, 0x04]);
+        assert_eq!(rec.as_biff::<BrtCustomFilter>().unwrap(), number);
+
+        let string = BrtCustomFilter::new(FilterCriterion::new(
+            FilterCriterion::EQUAL,
+            FilterOperand::String("a*".into()),
+        ));
+        let rec = string.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 2 + 4 + 4);
+        assert_eq!(rec.as_biff::<BrtCustomFilter>().unwrap(), string);
+
+        assert!(
+            BrtCustomFilter::new(FilterCriterion::new(0, FilterOperand::Blanks))
+                .into_biff()
+                .is_err()
+        );
+        assert!(BrtTop10Filter::new(BrtTop10Filter::F_TOP, 0, 0.0)
+            .into_biff()
+            .is_err());
+        assert_eq!(
+            BrtDynamicFilter::new(BrtDynamicFilter::MAX_TYPE, 0.0, 0.0)
+                .into_biff()
+                .unwrap()
+                .data
+                .len(),
+            20
+        );
+    }
+}
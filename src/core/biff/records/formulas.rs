@@ -0,0 +1,265 @@
+//! Records of the cells with formulas and their cached values
+use std::mem::MaybeUninit;
+
+use crate::core::biff::prelude::*;
+
+/// Maximal size of the formula tokens (`rgce`) in bytes
+pub(crate) const MAX_FORMULA_LEN: usize = 16384;
+
+/// Formula tokens (`rgce`) followed by their extra data (`rgcb`).
+///
+/// The same layout is used by `CellParsedFormula`, `ArrayParsedFormula` and
+/// `SharedParsedFormula`.
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct ParsedFormula {
+    pub(crate) rgce: Vec<u8>,
+    pub(crate) rgcb: Vec<u8>,
+}
+
+impl ParsedFormula {
+    /// `PtgArea3d` token of the reference class
+    const PTG_AREA_3D: u8 = 0x3B;
+    /// `PtgExp` token of cells of shared and array formulas
+    const PTG_EXP: u8 = 0x01;
+    /// `PtgUnion` token, which joins two preceding references
+    pub(crate) const PTG_UNION: u8 = 0x10;
+
+    pub(crate) fn new(rgce: Vec<u8>, rgcb: Vec<u8>) -> Self {
+        ParsedFormula { rgce, rgcb }
+    }
+
+    /// Absolute reference to the range on the sheet, `ixti` is the index in `BrtExternSheet`
+    pub(crate) fn area_3d(ixti: u16, rfx: &UncheckedRfX) -> Self {
+        let mut rgce = vec![Self::PTG_AREA_3D];
+        rgce.extend_from_slice(&ixti.to_le_bytes());
+        rgce.extend_from_slice(&rfx.rw_first.inner.to_le_bytes());
+        rgce.extend_from_slice(&rfx.rw_last.inner.to_le_bytes());
+        rgce.extend_from_slice(&(rfx.col_first.inner as u16).to_le_bytes());
+        rgce.extend_from_slice(&(rfx.col_last.inner as u16).to_le_bytes());
+        ParsedFormula::new(rgce, Vec::new())
+    }
+
+    /// Reference to the shared or the array formula, which starts in the cell; the column is
+    /// stored in the extra data (`PtgExtraCol`)
+    pub(crate) fn exp(row: u32, col: u16) -> Self {
+        let mut rgce = vec![Self::PTG_EXP];
+        rgce.extend_from_slice(&row.to_le_bytes());
+        ParsedFormula::new(rgce, (col as u32).to_le_bytes().to_vec())
+    }
+
+    /// First cell of the shared or the array formula, if this formula references it
+    pub(crate) fn exp_cell(&self) -> Option<(u32, u16)> {
+        match (self.rgce.as_slice(), self.rgcb.get(..4)) {
+            ([Self::PTG_EXP, row @ ..], Some(col)) if row.len() == 4 => Some((
+                u32::from_le_bytes(row.try_into().unwrap()),
+                u32::from_le_bytes(col.try_into().unwrap()) as u16,
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// Read the `u32` length and the bytes following it
+fn read_counted_bytes(data: &[u8], offset: usize) -> crate::Result<Vec<u8>> {
+    if data.len() < offset + 4 {
+        return Err(crate::Error::truncated(offset + 4, data.len()));
+    }
+    let len = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+    match data.get(offset + 4..offset + 4 + len) {
+        Some(bytes) => Ok(bytes.to_vec()),
+        None => Err(crate::Error::truncated(offset + 4 + len, data.len())),
+    }
+}
+
+/// Write the `u32` length and the bytes following it
+fn write_counted_bytes(bytes: &[u8], offset: usize, out_data: &mut [u8]) -> usize {
+    out_data[offset..offset + 4].copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+    out_data[offset + 4..offset + 4 + bytes.len()].copy_from_slice(bytes);
+    4 + bytes.len()
+}
+
+impl BiffDataCompatible for ParsedFormula {
+    fn size_of_type() -> usize {
+        8
+    }
+}
+
+impl IntoBiffData for ParsedFormula {
+    fn size_of(&self) -> usize {
+        8 + self.rgce.len() + self.rgcb.len()
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        if out_data.len() < offset + self.size_of() {
+            return Err(crate::Error::truncated(
+                offset + self.size_of(),
+                out_data.len(),
+            ));
+        }
+        let size = write_counted_bytes(&self.rgce, offset, out_data);
+        Ok(size + write_counted_bytes(&self.rgcb, offset + size, out_data))
+    }
+}
+
+impl FromBiffData for ParsedFormula {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        let rgce = read_counted_bytes(data, offset)?;
+        let rgcb = read_counted_bytes(data, offset + 4 + rgce.len())?;
+        let size = 8 + rgce.len() + rgcb.len();
+        out_data.write(ParsedFormula { rgce, rgcb });
+        Ok(size)
+    }
+}
+
+impl CheckBiff for ParsedFormula {
+    fn validated(&self) -> crate::Result<&Self> {
+        if self.rgce.len() <= MAX_FORMULA_LEN {
+            Ok(self)
+        } else {
+            Err(crate::Error::invalid_value())
+        }
+    }
+}
+
+/// Flags of the formula cells (`grbitFlags`)
+#[allow(dead_code)]
+pub(crate) const F_ALWAYS_CALC: u16 = 0x0002;
+
+// Formula with the cached string value
+declare_packable!(
+    BrtFmlaString,
+    |x: &Self| x.value.cch() <= super::MAX_STRING_LEN,
+    BrtFmlaString,
+    cell,
+    Cell,
+    value,
+    XLWideString,
+    grbit_flags,
+    u16,
+    formula,
+    ParsedFormula
+);
+
+// Formula with the cached number value
+declare_packable!(
+    BrtFmlaNum,
+    |x: &Self| x.xnum.is_finite(),
+    BrtFmlaNum,
+    cell,
+    Cell,
+    xnum,
+    f64,
+    grbit_flags,
+    u16,
+    formula,
+    ParsedFormula
+);
+
+// Formula with the cached boolean value
+declare_packable!(
+    BrtFmlaBool,
+    |x: &Self| x.f_bool <= 1,
+    BrtFmlaBool,
+    cell,
+    Cell,
+    f_bool,
+    u8,
+    grbit_flags,
+    u16,
+    formula,
+    ParsedFormula
+);
+
+// Formula with the cached error value
+declare_packable!(
+    BrtFmlaError,
+    |x: &Self| super::KNOWN_ERRORS.contains(&x.b_error),
+    BrtFmlaError,
+    cell,
+    Cell,
+    b_error,
+    u8,
+    grbit_flags,
+    u16,
+    formula,
+    ParsedFormula
+);
+
+// Array formula of the range, follows the formula record of its first cell
+declare_packable!(
+    BrtArrFmla,
+    |x: &Self| x.flags <= 1,
+    BrtArrFmla,
+    rfx,
+    UncheckedRfX,
+    flags,
+    u8,
+    formula,
+    ParsedFormula
+);
+
+// Shared formula of the range, follows the formula record of its first cell
+declare_packable!(
+    BrtShrFmla,
+    checked,
+    BrtShrFmla,
+    rfx,
+    UncheckedRfX,
+    formula,
+    ParsedFormula
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formula_records() {
+        // `=1+2`: PtgInt 1, PtgInt 2, PtgAdd
+        let formula = ParsedFormula::new(vec![0x1E, 1, 0, 0x1E, 2, 0, 0x03], vec![]);
+        let rec = BrtFmlaNum::new(Cell::at(1, 0), 3.0, 0, formula.clone())
+            .into_biff()
+            .unwrap();
+        assert_eq!(rec.data.len(), 8 + 8 + 2 + 4 + 7 + 4);
+        let fmla = rec.as_biff::<BrtFmlaNum>().unwrap();
+        assert_eq!(fmla.xnum, 3.0);
+        assert_eq!(fmla.formula, formula);
+
+        let rec = BrtFmlaString::new(Cell::at(0, 0), "ab".into(), F_ALWAYS_CALC, formula.clone())
+            .into_biff()
+            .unwrap();
+        assert_eq!(
+            rec.as_biff::<BrtFmlaString>().unwrap().value.inner.as_str(),
+            "ab"
+        );
+
+        assert!(BrtFmlaBool::new(Cell::at(0, 0), 2, 0, formula.clone())
+            .into_biff()
+            .is_err());
+        assert!(BrtFmlaError::new(Cell::at(0, 0), 0x07, 0, formula.clone())
+            .into_biff()
+            .is_ok());
+        let exp = ParsedFormula::exp(5, 2);
+        assert_eq!(exp.rgce, [0x01, 5, 0, 0, 0]);
+        assert_eq!(exp.exp_cell(), Some((5, 2)));
+        assert_eq!(formula.exp_cell(), None);
+        let long = ParsedFormula::new(vec![0; MAX_FORMULA_LEN + 1], vec![]);
+        assert!(BrtShrFmla::new(UncheckedRfX::from_bounds(0, 1, 0, 0), long)
+            .into_biff()
+            .is_err());
+
+        // truncated `rgcb`
+        let mut data = rec.data.to_vec();
+        data.truncate(data.len() - 1);
+        let rec = BiffRecord {
+            id: BiffId::BrtFmlaString,
+            data: data.into(),
+        };
+        assert!(rec.as_biff::<BrtFmlaString>().is_err());
+    }
+}
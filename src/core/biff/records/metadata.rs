@@ -0,0 +1,139 @@
+//! Records of the metadata part (`xl/metadata.bin`) and references to its blocks from cells
+use crate::core::biff::prelude::*;
+
+// Number of metadata types, which follow as `BrtMdtinfo` records
+declare_packable!(
+    BrtBeginEsmdtinfo,
+    checked,
+    BrtBeginEsmdtinfo,
+    c_mdtinfo,
+    u32,
+    reserved,
+    u32
+);
+
+// Metadata type: its name and how the metadata behaves on editing of cells
+declare_packable!(
+    BrtMdtinfo,
+    |x: &Self| (1..=255).contains(&x.st_name.cch()),
+    BrtMdtinfo,
+    flags,
+    u32,
+    metadata_id,
+    u32,
+    st_name,
+    XLWideString
+);
+
+impl BrtMdtinfo {
+    pub(crate) const F_COPY: u32 = 0x0000_0010;
+    pub(crate) const F_PASTE_ALL: u32 = 0x0000_0020;
+    pub(crate) const F_PASTE_VALUES: u32 = 0x0000_0080;
+    pub(crate) const F_MERGE: u32 = 0x0000_4000;
+    pub(crate) const F_SPLIT_FIRST: u32 = 0x0000_8000;
+    pub(crate) const F_ROW_COL_SHIFT: u32 = 0x0002_0000;
+    pub(crate) const F_CLEAR_FORMATS: u32 = 0x0008_0000;
+    pub(crate) const F_CLEAR_COMMENTS: u32 = 0x0020_0000;
+    pub(crate) const F_ASSIGN: u32 = 0x0040_0000;
+    pub(crate) const F_COERCE: u32 = 0x0080_0000;
+    pub(crate) const F_CELL_META: u32 = 0x0200_0000;
+
+    /// Name of the metadata type of dynamic array formulas
+    pub(crate) const DYNAMIC_ARRAY: &'static str = "XLDAPR";
+
+    /// Metadata type of dynamic array formulas with flags and the version, Excel uses for it
+    pub(crate) fn dynamic_array() -> Self {
+        BrtMdtinfo::new(
+            Self::F_COPY
+                | Self::F_PASTE_ALL
+                | Self::F_PASTE_VALUES
+                | Self::F_MERGE
+                | Self::F_SPLIT_FIRST
+                | Self::F_ROW_COL_SHIFT
+                | Self::F_CLEAR_FORMATS
+                | Self::F_CLEAR_COMMENTS
+                | Self::F_ASSIGN
+                | Self::F_COERCE
+                | Self::F_CELL_META,
+            120000,
+            Self::DYNAMIC_ARRAY.into(),
+        )
+    }
+}
+
+// Future metadata of the type with the given name, followed by `c_fmd` blocks of it
+declare_packable!(
+    BrtBeginEsfmd,
+    |x: &Self| (1..=255).contains(&x.st_name.cch()),
+    BrtBeginEsfmd,
+    c_fmd,
+    u32,
+    st_name,
+    XLWideString
+);
+
+// Properties of the dynamic array formula
+declare_packable!(
+    BrtBeginDynamicArrayPr,
+    checked,
+    BrtBeginDynamicArrayPr,
+    flags,
+    u32
+);
+
+impl BrtBeginDynamicArrayPr {
+    /// Formula spills its result into adjacent cells
+    pub(crate) const F_DYNAMIC: u32 = 0x0001;
+}
+
+// Metadata blocks of cells or values, followed by `c_mdb` records `BrtMdb`
+declare_packable!(
+    BrtBeginEsmdb,
+    checked,
+    BrtBeginEsmdb,
+    c_mdb,
+    u32,
+    flags,
+    u32
+);
+
+impl BrtBeginEsmdb {
+    /// Blocks are referenced by `BrtCellMeta` of cells
+    pub(crate) const F_CELL_META: u32 = 0x0001;
+}
+
+// Metadata record within the block: the one-based index of the type (`BrtMdtinfo`) and the
+// zero-based index of the record of this type
+declare_packable!(Mdir, |x: &Self| x.i_type >= 1, i_type, u32, imd, u32);
+
+// Metadata block, which is referenced by cells
+declare_packable!(BrtMdb, checked, BrtMdb, rg_mdir, CountedArray<Mdir>);
+
+// Zero-based index of the cell metadata block, precedes the record of the cell
+declare_packable!(BrtCellMeta, checked, BrtCellMeta, icmb, u32);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_records() {
+        let rec = BrtMdtinfo::dynamic_array().into_biff().unwrap();
+        assert_eq!(rec.data.len(), 4 + 4 + 4 + 12);
+        let info = rec.as_biff::<BrtMdtinfo>().unwrap();
+        assert_eq!(info.st_name.inner, BrtMdtinfo::DYNAMIC_ARRAY);
+        assert_eq!(
+            info.flags & BrtMdtinfo::F_CELL_META,
+            BrtMdtinfo::F_CELL_META
+        );
+
+        let rec = BrtMdb::new(vec![Mdir::new(1, 0)].into())
+            .into_biff()
+            .unwrap();
+        assert_eq!(&*rec.data, [1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(BrtMdb::new(vec![Mdir::new(0, 0)].into())
+            .into_biff()
+            .is_err());
+        assert!(BrtBeginEsfmd::new(1, "".into()).into_biff().is_err());
+    }
+}
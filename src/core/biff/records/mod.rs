@@ -0,0 +1,54 @@
+mod cells;
+mod comments;
+mod conditional;
+mod filters;
+mod formulas;
+mod metadata;
+mod sst;
+mod styles;
+mod tables;
+mod validation;
+mod workbook;
+mod worksheet;
+
+pub(crate) use cells::{
+    BrtCellBlank, BrtCellBool, BrtCellError, BrtCellIsst, BrtCellRString, BrtCellReal, BrtCellRk,
+    BrtCellSt, BrtRowHdr, ColSpan, KNOWN_ERRORS, MAX_COLSPANS, MAX_STRING_LEN,
+};
+pub(crate) use comments::{BrtBeginComment, BrtCommentAuthor, BrtCommentText, BrtLegacyDrawing};
+pub(crate) use conditional::{
+    BrtBeginCFRule, BrtBeginCFRule14, BrtBeginConditionalFormatting,
+    BrtBeginConditionalFormatting14, BrtBeginDatabar, BrtBeginDatabar14, BrtBeginIconSet,
+    BrtBeginIconSet14, BrtCFIcon, BrtCFRuleExt, BrtCFVO, BrtCFVO14, BrtColor14, BrtColorRecord,
+    BrtFRTBegin,
+};
+pub(crate) use filters::{
+    BrtBeginAFilter, BrtBeginCustomFilters, BrtBeginFilterColumn, BrtBeginFilters, BrtCustomFilter,
+    BrtDynamicFilter, BrtFilter, BrtTop10Filter, FilterCriterion, FilterOperand,
+};
+pub(crate) use formulas::{
+    BrtArrFmla, BrtFmlaBool, BrtFmlaError, BrtFmlaNum, BrtFmlaString, BrtShrFmla, ParsedFormula,
+    F_ALWAYS_CALC, MAX_FORMULA_LEN,
+};
+pub(crate) use metadata::{
+    BrtBeginDynamicArrayPr, BrtBeginEsfmd, BrtBeginEsmdb, BrtBeginEsmdtinfo, BrtCellMeta, BrtMdb,
+    BrtMdtinfo, Mdir,
+};
+pub(crate) use sst::{read_sst_item, BrtBeginSst, BrtSSTItem};
+pub(crate) use styles::{
+    Blxf, BrtBeginBorders, BrtBeginCellStyleXFs, BrtBeginCellXFs, BrtBeginDXFs, BrtBeginFills,
+    BrtBeginFmts, BrtBeginFonts, BrtBeginStyles, BrtBeginTableStyles, BrtBorder, BrtDXF, BrtFill,
+    BrtFmt, BrtFont, BrtStyle, BrtXF, XfProp,
+};
+pub(crate) use tables::{
+    BrtBeginList, BrtBeginListCol, BrtBeginListCols, BrtBeginListParts, BrtListPart,
+    BrtTableStyleClient,
+};
+pub(crate) use validation::{BrtBeginDVals, BrtBeginDVals14, BrtDVal, BrtDVal14, BrtDValList};
+pub(crate) use workbook::{
+    BrtBookView, BrtBundleSh, BrtCalcProp, BrtExternSheet, BrtFileVersion, BrtName, BrtWbProp, Xti,
+};
+pub(crate) use worksheet::{
+    BrtBeginMergeCells, BrtBeginWsView, BrtColInfo, BrtHLink, BrtMergeCell, BrtPane, BrtSel,
+    BrtWsDim, BrtWsFmtInfo, BrtWsProp,
+};
@@ -0,0 +1,26 @@
+//! Records of the shared strings part (`xl/sharedStrings.bin`)
+use crate::core::biff::prelude::*;
+
+declare_packable!(
+    BrtBeginSst,
+    |x: &Self| x.cst_unique <= x.cst_total,
+    BrtBeginSst,
+    cst_total,
+    u32,
+    cst_unique,
+    u32
+);
+
+// Item of the table
+declare_packable!(
+    BrtSSTItem,
+    |x: &Self| x.value.value.cch() <= super::MAX_STRING_LEN,
+    BrtSSTItem,
+    value,
+    RichStr
+);
+
+/// Read the text of the `BrtSSTItem` record, formatting runs and phonetic data are ignored
+pub(crate) fn read_sst_item(rec: &BiffRecord) -> crate::Result<String> {
+    Ok(rec.as_biff::<BrtSSTItem>()?.value.value.inner)
+}
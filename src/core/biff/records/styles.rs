@@ -0,0 +1,530 @@
+//! Records of the styles part (`xl/styles.bin`)
+use std::mem::MaybeUninit;
+
+use crate::core::biff::prelude::*;
+
+declare_packable!(BrtBeginFmts, checked, BrtBeginFmts, cfmt, u32);
+declare_packable!(BrtBeginFonts, checked, BrtBeginFonts, cfont, u32);
+declare_packable!(BrtBeginFills, checked, BrtBeginFills, cfill, u32);
+declare_packable!(BrtBeginBorders, checked, BrtBeginBorders, cborder, u32);
+declare_packable!(
+    BrtBeginCellStyleXFs,
+    checked,
+    BrtBeginCellStyleXFs,
+    cxfs,
+    u32
+);
+declare_packable!(BrtBeginCellXFs, checked, BrtBeginCellXFs, cxfs, u32);
+declare_packable!(BrtBeginStyles, checked, BrtBeginStyles, cstyles, u32);
+declare_packable!(BrtBeginDXFs, checked, BrtBeginDXFs, cdxfs, u32);
+
+// Number format
+declare_packable!(
+    BrtFmt,
+    |x: &Self| x.st_fmt_code.cch() <= 255,
+    BrtFmt,
+    ifmt,
+    u16,
+    st_fmt_code,
+    XLWideString
+);
+
+// Font
+declare_packable!(
+    BrtFont,
+    |x: &Self| (20..=8191).contains(&x.dy_height) && (100..=1000).contains(&x.bls) && x.sss <= 2,
+    BrtFont,
+    dy_height,
+    u16,
+    grbit,
+    u16,
+    bls,
+    u16,
+    sss,
+    u16,
+    uls,
+    u8,
+    b_family,
+    u8,
+    b_char_set,
+    u8,
+    unused,
+    u8,
+    brt_color,
+    BrtColor,
+    b_font_scheme,
+    u8,
+    name,
+    XLWideString
+);
+
+impl BrtFont {
+    pub(crate) const F_ITALIC: u16 = 0x0002;
+    pub(crate) const F_STRIKEOUT: u16 = 0x0008;
+    pub(crate) const BLS_NORMAL: u16 = 400;
+    pub(crate) const BLS_BOLD: u16 = 700;
+
+    /// Default font of the workbook: Calibri, 11 pt
+    pub(crate) fn standard() -> Self {
+        BrtFont::new(
+            220,
+            0,
+            Self::BLS_NORMAL,
+            0,
+            0,
+            2,
+            0,
+            0,
+            BrtColor::themed(1),
+            0,
+            "Calibri".into(),
+        )
+    }
+}
+
+// Fill, gradient stops are not supported
+declare_packable!(
+    BrtFill,
+    |x: &Self| x.fls <= 18 && x.c_num_stop == 0,
+    BrtFill,
+    fls,
+    u32,
+    brt_color_fore,
+    BrtColor,
+    brt_color_back,
+    BrtColor,
+    i_gradient_type,
+    u32,
+    xnum_degree,
+    f64,
+    xnum_fill_to_left,
+    f64,
+    xnum_fill_to_right,
+    f64,
+    xnum_fill_to_top,
+    f64,
+    xnum_fill_to_bottom,
+    f64,
+    c_num_stop,
+    u32
+);
+
+impl BrtFill {
+    pub(crate) const FLS_NONE: u32 = 0x00;
+    pub(crate) const FLS_SOLID: u32 = 0x01;
+    pub(crate) const FLS_GRAY_125: u32 = 0x11;
+
+    pub(crate) fn pattern(fls: u32) -> Self {
+        BrtFill::new(
+            fls,
+            BrtColor::indexed(64),
+            BrtColor::indexed(65),
+            0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            0,
+        )
+    }
+
+    /// Solid fill of the foreground color
+    pub(crate) fn solid(color: BrtColor) -> Self {
+        BrtFill {
+            brt_color_fore: color,
+            ..Self::pattern(Self::FLS_SOLID)
+        }
+    }
+}
+
+// Border line
+declare_packable!(
+    Blxf,
+    |x: &Self| x.dg <= 13,
+    dg,
+    u8,
+    reserved,
+    u8,
+    brt_color,
+    BrtColor
+);
+
+// Border
+declare_packable!(
+    BrtBorder,
+    |x: &Self| x.flags <= 3,
+    BrtBorder,
+    flags,
+    u8,
+    blxf_top,
+    Blxf,
+    blxf_bottom,
+    Blxf,
+    blxf_left,
+    Blxf,
+    blxf_right,
+    Blxf,
+    blxf_diag,
+    Blxf
+);
+
+impl BrtBorder {
+    #[allow(dead_code)]
+    pub(crate) fn empty() -> Self {
+        let line = || Blxf::new(0, 0, BrtColor::auto());
+        BrtBorder::new(0, line(), line(), line(), line(), line())
+    }
+}
+
+// Cell format, both for cell styles and for cells
+declare_packable!(
+    BrtXF,
+    |x: &Self| x.trot <= 180 || x.trot == 255,
+    BrtXF,
+    ixfe_parent,
+    u16,
+    i_fmt,
+    u16,
+    i_font,
+    u16,
+    i_fill,
+    u16,
+    ix_border,
+    u16,
+    trot,
+    u8,
+    indent,
+    u8,
+    flags,
+    u16,
+    xf_grbit_atr,
+    u8,
+    unused,
+    u8
+);
+
+impl BrtXF {
+    /// `ixfeParent` of the cell style formats
+    pub(crate) const NO_PARENT: u16 = 0xFFFF;
+    /// Horizontal alignment
+    pub(crate) const ALC: u16 = 0x0007;
+    /// Vertical alignment
+    pub(crate) const ALCV: u16 = 0x0038;
+    /// Bottom vertical alignment
+    pub(crate) const ALCV_BOTTOM: u16 = 0x0010;
+    pub(crate) const F_WRAP: u16 = 0x0040;
+    pub(crate) const F_LOCKED: u16 = 0x1000;
+    /// Number format differs from the parent one
+    pub(crate) const F_ATR_NUM: u8 = 0x01;
+    /// Font differs from the parent one
+    pub(crate) const F_ATR_FNT: u8 = 0x02;
+    /// Alignment differs from the parent one
+    pub(crate) const F_ATR_ALC: u8 = 0x04;
+    /// Border differs from the parent one
+    pub(crate) const F_ATR_BDR: u8 = 0x08;
+    /// Fill differs from the parent one
+    pub(crate) const F_ATR_PAT: u8 = 0x10;
+    /// Protection differs from the parent one
+    pub(crate) const F_ATR_PROT: u8 = 0x20;
+    /// Built-in number format `m/d/yyyy`
+    pub(crate) const IFMT_DATE: u16 = 14;
+    /// Built-in number format `m/d/yyyy h:mm`
+    pub(crate) const IFMT_DATETIME: u16 = 22;
+
+    pub(crate) fn standard(ixfe_parent: u16) -> Self {
+        BrtXF::new(
+            ixfe_parent,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            Self::ALCV_BOTTOM | Self::F_LOCKED,
+            0,
+            0,
+        )
+    }
+
+    pub(crate) fn with_number_format(ixfe_parent: u16, i_fmt: u16) -> Self {
+        BrtXF {
+            i_fmt,
+            xf_grbit_atr: Self::F_ATR_NUM,
+            ..Self::standard(ixfe_parent)
+        }
+    }
+
+    /// Cell style format of the built-in `Hyperlink` cell style, which applies only the font
+    /// (attribute flags of cell style formats mark attributes, which are not applied)
+    pub(crate) fn hyperlink_style(i_font: u16) -> Self {
+        BrtXF {
+            i_font,
+            xf_grbit_atr: Self::F_ATR_NUM
+                | Self::F_ATR_ALC
+                | Self::F_ATR_BDR
+                | Self::F_ATR_PAT
+                | Self::F_ATR_PROT,
+            ..Self::standard(Self::NO_PARENT)
+        }
+    }
+}
+
+// Cell style
+declare_packable!(
+    BrtStyle,
+    |x: &Self| x.st_name.cch() <= 255,
+    BrtStyle,
+    ixf,
+    u32,
+    grbit_obj1,
+    u16,
+    i_sty_built_in,
+    u8,
+    i_level,
+    u8,
+    st_name,
+    XLWideString
+);
+
+impl BrtStyle {
+    pub(crate) const F_BUILT_IN: u16 = 0x0001;
+
+    pub(crate) fn normal() -> Self {
+        BrtStyle::new(0, Self::F_BUILT_IN, 0, 0xFF, "Normal".into())
+    }
+
+    /// Built-in `Hyperlink` cell style with its cell style format
+    pub(crate) fn hyperlink(ixf: u32) -> Self {
+        BrtStyle::new(ixf, Self::F_BUILT_IN, 8, 0xFF, "Hyperlink".into())
+    }
+}
+
+// Table styles header, with default styles of tables and pivot tables
+declare_packable!(
+    BrtBeginTableStyles,
+    checked,
+    BrtBeginTableStyles,
+    c_table_styles,
+    u32,
+    rgch_def_table_style,
+    XLWideString,
+    rgch_def_pivot_style,
+    XLWideString
+);
+
+impl BrtBeginTableStyles {
+    pub(crate) fn standard() -> Self {
+        BrtBeginTableStyles::new(0, "TableStyleMedium2".into(), "PivotStyleLight16".into())
+    }
+}
+
+/// Single property of the differential format (`XFProp`): its type and data
+#[derive(Default, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct XfProp {
+    pub(crate) xf_prop_type: u16,
+    pub(crate) data: Vec<u8>,
+}
+
+impl XfProp {
+    pub(crate) const FILL_PATTERN: u16 = 0x00;
+    pub(crate) const FILL_FORE_COLOR: u16 = 0x01;
+    pub(crate) const FILL_BACK_COLOR: u16 = 0x02;
+    pub(crate) const FONT_COLOR: u16 = 0x05;
+    pub(crate) const BORDER_TOP: u16 = 0x06;
+    pub(crate) const BORDER_BOTTOM: u16 = 0x07;
+    pub(crate) const BORDER_LEFT: u16 = 0x08;
+    pub(crate) const BORDER_RIGHT: u16 = 0x09;
+    pub(crate) const FONT_WEIGHT: u16 = 0x19;
+    pub(crate) const FONT_UNDERLINE: u16 = 0x1A;
+    pub(crate) const FONT_ITALIC: u16 = 0x1C;
+    pub(crate) const FONT_STRIKEOUT: u16 = 0x1D;
+    pub(crate) const NUMBER_FORMAT: u16 = 0x26;
+    pub(crate) const NUMBER_FORMAT_ID: u16 = 0x29;
+
+    pub(crate) fn new(xf_prop_type: u16, data: Vec<u8>) -> Self {
+        XfProp { xf_prop_type, data }
+    }
+
+    pub(crate) fn color(xf_prop_type: u16, color: BrtColor) -> Self {
+        let mut data = vec![0; 8].into_boxed_slice();
+        // the color always fits into its 8 bytes
+        let _ = color.into_biff_data(0, &mut data);
+        XfProp::new(xf_prop_type, data.into_vec())
+    }
+
+    /// Border line: its color and style (`dg`)
+    pub(crate) fn border(xf_prop_type: u16, color: BrtColor, dg: u16) -> Self {
+        let mut prop = XfProp::color(xf_prop_type, color);
+        prop.data.extend_from_slice(&dg.to_le_bytes());
+        prop
+    }
+
+    /// String with the 16-bit length (`LPWideString`)
+    pub(crate) fn string(xf_prop_type: u16, value: &str) -> Self {
+        let chars = value.encode_utf16().collect::<Vec<_>>();
+        let mut data = (chars.len() as u16).to_le_bytes().to_vec();
+        data.extend(chars.into_iter().flat_map(u16::to_le_bytes));
+        XfProp::new(xf_prop_type, data)
+    }
+}
+
+impl BiffDataCompatible for XfProp {
+    fn size_of_type() -> usize {
+        4
+    }
+}
+
+impl IntoBiffData for XfProp {
+    fn size_of(&self) -> usize {
+        4 + self.data.len()
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        let size = self.size_of();
+        if out_data.len() < offset + size {
+            return Err(crate::Error::truncated(offset + size, out_data.len()));
+        }
+        out_data[offset..offset + 2].copy_from_slice(&self.xf_prop_type.to_le_bytes());
+        // the size includes the type and the size itself
+        out_data[offset + 2..offset + 4].copy_from_slice(&(size as u16).to_le_bytes());
+        out_data[offset + 4..offset + size].copy_from_slice(&self.data);
+        Ok(size)
+    }
+}
+
+impl FromBiffData for XfProp {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 4 {
+            return Err(crate::Error::truncated(offset + 4, data.len()));
+        }
+        let xf_prop_type = u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let size = u16::from_le_bytes([data[offset + 2], data[offset + 3]]) as usize;
+        match data.get(offset + 4..offset + size.max(4)) {
+            Some(bytes) => {
+                out_data.write(XfProp::new(xf_prop_type, bytes.to_vec()));
+                Ok(size.max(4))
+            }
+            None => Err(crate::Error::truncated(offset + size, data.len())),
+        }
+    }
+}
+
+impl CheckBiff for XfProp {
+    fn validated(&self) -> crate::Result<&Self> {
+        if 4 + self.data.len() <= u16::MAX as usize {
+            Ok(self)
+        } else {
+            Err(crate::Error::invalid_value())
+        }
+    }
+}
+
+/// Properties of the differential format with their 16-bit count
+#[derive(Default, PartialEq, Clone)]
+#[cfg_attr(any(test, feature = "test"), derive(Debug))]
+pub(crate) struct XfProps {
+    pub(crate) items: Vec<XfProp>,
+}
+
+impl From<Vec<XfProp>> for XfProps {
+    fn from(items: Vec<XfProp>) -> Self {
+        XfProps { items }
+    }
+}
+
+impl BiffDataCompatible for XfProps {
+    fn size_of_type() -> usize {
+        2
+    }
+}
+
+impl IntoBiffData for XfProps {
+    fn size_of(&self) -> usize {
+        2 + self.items.iter().map(IntoBiffData::size_of).sum::<usize>()
+    }
+
+    fn into_biff_data(&self, offset: usize, out_data: &mut Box<[u8]>) -> crate::Result<usize> {
+        if out_data.len() < offset + 2 {
+            return Err(crate::Error::truncated(offset + 2, out_data.len()));
+        }
+        out_data[offset..offset + 2].copy_from_slice(&(self.items.len() as u16).to_le_bytes());
+        let mut size = 2;
+        for item in &self.items {
+            size += item.into_biff_data(offset + size, out_data)?;
+        }
+        Ok(size)
+    }
+}
+
+impl FromBiffData for XfProps {
+    fn from_biff_data(
+        data: &[u8],
+        offset: usize,
+        out_data: &mut MaybeUninit<Self>,
+    ) -> crate::Result<usize> {
+        if data.len() < offset + 2 {
+            return Err(crate::Error::truncated(offset + 2, data.len()));
+        }
+        let count = u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
+        let mut items = Vec::new();
+        let mut size = 2;
+        for _ in 0..count {
+            let mut item = MaybeUninit::uninit();
+            size += XfProp::from_biff_data(data, offset + size, &mut item)
+                .map_err(|e| e.with_field(&items.len().to_string()))?;
+            items.push(unsafe { item.assume_init() });
+        }
+        out_data.write(XfProps { items });
+        Ok(size)
+    }
+}
+
+impl CheckBiff for XfProps {
+    fn validated(&self) -> crate::Result<&Self> {
+        for item in &self.items {
+            item.validated()?;
+        }
+        Ok(self)
+    }
+}
+
+// Differential format, which is referenced by conditional formatting rules
+declare_packable!(BrtDXF, checked, BrtDXF, flags, u16, reserved, u16, props, XfProps);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_records() {
+        assert_eq!(BrtXF::standard(0).into_biff().unwrap().data.len(), 16);
+        assert_eq!(BrtBorder::empty().into_biff().unwrap().data.len(), 51);
+        let fill = BrtFill::pattern(BrtFill::FLS_GRAY_125).into_biff().unwrap();
+        assert_eq!(fill.data.len(), 68);
+        let font = BrtFont::standard().into_biff().unwrap();
+        assert_eq!(font.data.len(), 21 + 4 + 7 * 2);
+        assert_eq!(font.as_biff::<BrtFont>().unwrap(), BrtFont::standard());
+
+        let dxf = BrtDXF::new(
+            0,
+            0,
+            vec![
+                XfProp::new(XfProp::FONT_ITALIC, vec![1]),
+                XfProp::color(XfProp::FONT_COLOR, BrtColor::rgb(0x9C, 0, 0x06)),
+                XfProp::string(XfProp::NUMBER_FORMAT, "0%"),
+            ]
+            .into(),
+        );
+        let rec = dxf.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 6 + 5 + 12 + 10);
+        // the size of the property includes its header
+        assert_eq!(&rec.data[6..11], [0x1C, 0, 5, 0, 1]);
+        assert_eq!(rec.as_biff::<BrtDXF>().unwrap(), dxf);
+    }
+}
@@ -0,0 +1,190 @@
+//! Records of tables (list objects): the table part (`xl/tables/tableN.bin`) and its reference
+//! from the sheet
+use crate::core::biff::prelude::*;
+
+// Table: its range, flags, differential formats and names
+declare_packable!(
+    BrtBeginList,
+    |x: &Self| x.lt <= 3
+        && x.id_list >= 1
+        && x.crw_header <= 1
+        && x.crw_totals <= 1
+        && (1..=255).contains(&x.st_display_name.cch()),
+    BrtBeginList,
+    rfx_list,
+    UncheckedRfX,
+    lt,
+    u32,
+    id_list,
+    u32,
+    crw_header,
+    u32,
+    crw_totals,
+    u32,
+    flags,
+    u32,
+    n_dxf_header,
+    u32,
+    n_dxf_data,
+    u32,
+    n_dxf_agg,
+    u32,
+    n_dxf_border,
+    u32,
+    n_dxf_header_border,
+    u32,
+    n_dxf_agg_border,
+    u32,
+    dw_conn_id,
+    u32,
+    iqsi_override,
+    u32,
+    st_name,
+    XLNullableWideString,
+    st_display_name,
+    XLWideString,
+    st_comment,
+    XLNullableWideString,
+    st_style_header,
+    XLNullableWideString,
+    st_style_data,
+    XLNullableWideString,
+    st_style_agg,
+    XLNullableWideString
+);
+
+impl BrtBeginList {
+    /// Source of the table is the range of the sheet
+    pub(crate) const LT_RANGE: u32 = 0;
+    pub(crate) const F_SHOWN_TOTAL_ROW: u32 = 0x0001;
+    /// No differential format is applied
+    pub(crate) const NO_DXF: u32 = u32::MAX;
+}
+
+// Count of table columns
+declare_packable!(BrtBeginListCols, checked, BrtBeginListCols, n_cols, u32);
+
+// Column of the table, `ilta` is the function of the totals row
+declare_packable!(
+    BrtBeginListCol,
+    |x: &Self| x.id_field >= 1 && x.ilta <= BrtBeginListCol::ILTA_CUSTOM,
+    BrtBeginListCol,
+    id_field,
+    u32,
+    ilta,
+    u32,
+    n_dxf_hdr,
+    u32,
+    n_dxf_insert_row,
+    u32,
+    n_dxf_agg,
+    u32,
+    idqsif,
+    u32,
+    st_name,
+    XLNullableWideString,
+    st_caption,
+    XLNullableWideString,
+    st_total,
+    XLNullableWideString,
+    st_style_header,
+    XLNullableWideString,
+    st_style_insert_row,
+    XLNullableWideString,
+    st_style_agg,
+    XLNullableWideString
+);
+
+impl BrtBeginListCol {
+    pub(crate) const ILTA_NONE: u32 = 0;
+    pub(crate) const ILTA_AVERAGE: u32 = 1;
+    pub(crate) const ILTA_COUNT: u32 = 2;
+    pub(crate) const ILTA_COUNT_NUMS: u32 = 3;
+    pub(crate) const ILTA_MAX: u32 = 4;
+    pub(crate) const ILTA_MIN: u32 = 5;
+    pub(crate) const ILTA_SUM: u32 = 6;
+    pub(crate) const ILTA_STD_DEV: u32 = 7;
+    pub(crate) const ILTA_VAR: u32 = 8;
+    pub(crate) const ILTA_CUSTOM: u32 = 9;
+}
+
+// Built-in or custom style of the table and its options
+declare_packable!(
+    BrtTableStyleClient,
+    checked,
+    BrtTableStyleClient,
+    flags,
+    u16,
+    st_style_name,
+    XLNullableWideString
+);
+
+impl BrtTableStyleClient {
+    pub(crate) const F_FIRST_COLUMN: u16 = 0x0001;
+    pub(crate) const F_LAST_COLUMN: u16 = 0x0002;
+    pub(crate) const F_ROW_STRIPES: u16 = 0x0004;
+    pub(crate) const F_COLUMN_STRIPES: u16 = 0x0008;
+}
+
+// Count of tables of the sheet
+declare_packable!(BrtBeginListParts, checked, BrtBeginListParts, c_parts, u32);
+
+// Relationship of the sheet to the table part
+declare_packable!(
+    BrtListPart,
+    |x: &Self| x.rel_id.cch() > 0,
+    BrtListPart,
+    rel_id,
+    XLWideString
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_records() {
+        let list = BrtBeginList::new(
+            UncheckedRfX::from_bounds(0, 10, 0, 2),
+            BrtBeginList::LT_RANGE,
+            1,
+            1,
+            0,
+            0,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            0,
+            0,
+            Some("Table1").into(),
+            "Table1".into(),
+            None.into(),
+            None.into(),
+            None.into(),
+            None.into(),
+        );
+        let rec = list.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 16 + 13 * 4 + (4 + 12) * 2 + 4 * 4);
+        assert_eq!(rec.as_biff::<BrtBeginList>().unwrap(), list);
+
+        let col = BrtBeginListCol::new(
+            0,
+            BrtBeginListCol::ILTA_SUM,
+            0,
+            0,
+            0,
+            0,
+            None.into(),
+            Some("Amount").into(),
+            None.into(),
+            None.into(),
+            None.into(),
+            None.into(),
+        );
+        assert!(col.into_biff().is_err());
+        assert!(BrtListPart::new("".into()).into_biff().is_err());
+    }
+}
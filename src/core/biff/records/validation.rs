@@ -0,0 +1,172 @@
+//! Records of the data validation and its Excel 2010 extension
+use super::ParsedFormula;
+use crate::core::biff::prelude::*;
+
+// Data validations of the sheet, followed by `idv_mac` records `BrtDVal`
+declare_packable!(
+    BrtBeginDVals,
+    checked,
+    BrtBeginDVals,
+    flags,
+    u16,
+    x_left,
+    u32,
+    y_top,
+    u32,
+    unused,
+    u32,
+    idv_mac,
+    u32
+);
+
+impl BrtBeginDVals {
+    pub(crate) fn with_count(idv_mac: u32) -> Self {
+        BrtBeginDVals::new(0, 0, 0, u32::MAX, idv_mac)
+    }
+}
+
+// Data validation of ranges: the type, the operator, messages and two formulas
+declare_packable!(
+    BrtDVal,
+    |x: &Self| x.sqrfx.len() > 0 && x.sqrfx.items.iter().all(UncheckedRfX::is_valid),
+    BrtDVal,
+    flags,
+    u32,
+    sqrfx,
+    CountedArray<UncheckedRfX>,
+    str_error_title,
+    XLNullableWideString,
+    str_error,
+    XLNullableWideString,
+    str_prompt_title,
+    XLNullableWideString,
+    str_prompt,
+    XLNullableWideString,
+    formula1,
+    ParsedFormula,
+    formula2,
+    ParsedFormula
+);
+
+impl BrtDVal {
+    /// Types of validation (`valType`), in the lowest four bits
+    pub(crate) const TYPE_ANY: u32 = 0;
+    pub(crate) const TYPE_WHOLE: u32 = 1;
+    pub(crate) const TYPE_DECIMAL: u32 = 2;
+    pub(crate) const TYPE_LIST: u32 = 3;
+    pub(crate) const TYPE_DATE: u32 = 4;
+    pub(crate) const TYPE_TIME: u32 = 5;
+    pub(crate) const TYPE_TEXT_LENGTH: u32 = 6;
+    pub(crate) const TYPE_CUSTOM: u32 = 7;
+
+    /// Style of the error alert (`errStyle`), from bit 4
+    pub(crate) const ERR_STYLE_SHIFT: u32 = 4;
+    /// The list is the string of comma-separated values instead of the range
+    pub(crate) const F_STR_LOOKUP: u32 = 0x0000_0080;
+    pub(crate) const F_ALLOW_BLANK: u32 = 0x0000_0100;
+    pub(crate) const F_SUPPRESS_COMBO: u32 = 0x0000_0200;
+    pub(crate) const F_SHOW_INPUT_MSG: u32 = 0x0004_0000;
+    pub(crate) const F_SHOW_ERROR_MSG: u32 = 0x0008_0000;
+    /// Comparison operator (`typOperator`), from bit 20
+    pub(crate) const OPERATOR_SHIFT: u32 = 20;
+}
+
+// Values of the list validation, which does not fit into the formula of `BrtDVal`
+declare_packable!(
+    BrtDValList,
+    |x: &Self| x.str_dval_list.cch() <= super::MAX_STRING_LEN,
+    BrtDValList,
+    str_dval_list,
+    XLWideString
+);
+
+// Data validations of the Excel 2010 extension, like lists of other sheets
+declare_packable!(
+    BrtBeginDVals14,
+    checked,
+    BrtBeginDVals14,
+    flags,
+    u16,
+    x_left,
+    u32,
+    y_top,
+    u32,
+    unused,
+    u32,
+    idv_mac,
+    u32
+);
+
+impl BrtBeginDVals14 {
+    pub(crate) fn with_count(idv_mac: u32) -> Self {
+        BrtBeginDVals14::new(0, 0, 0, u32::MAX, idv_mac)
+    }
+}
+
+// Data validation of the Excel 2010 extension, has the same layout as `BrtDVal`
+declare_packable!(
+    BrtDVal14,
+    |x: &Self| x.sqrfx.len() > 0 && x.sqrfx.items.iter().all(UncheckedRfX::is_valid),
+    BrtDVal14,
+    flags,
+    u32,
+    sqrfx,
+    CountedArray<UncheckedRfX>,
+    str_error_title,
+    XLNullableWideString,
+    str_error,
+    XLNullableWideString,
+    str_prompt_title,
+    XLNullableWideString,
+    str_prompt,
+    XLNullableWideString,
+    formula1,
+    ParsedFormula,
+    formula2,
+    ParsedFormula
+);
+
+impl From<BrtDVal> for BrtDVal14 {
+    fn from(dval: BrtDVal) -> Self {
+        BrtDVal14::new(
+            dval.flags,
+            dval.sqrfx,
+            dval.str_error_title,
+            dval.str_error,
+            dval.str_prompt_title,
+            dval.str_prompt,
+            dval.formula1,
+            dval.formula2,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validation_records() {
+        assert_eq!(
+            BrtBeginDVals::with_count(2).into_biff().unwrap().data.len(),
+            18
+        );
+
+        let dval = BrtDVal::new(
+            BrtDVal::TYPE_LIST | BrtDVal::F_STR_LOOKUP | BrtDVal::F_ALLOW_BLANK,
+            vec![UncheckedRfX::from_bounds(1, 10, 2, 2)].into(),
+            None.into(),
+            Some("Invalid").into(),
+            None.into(),
+            None.into(),
+            ParsedFormula::new(vec![0x17, 3, 0, b'a', 0, b',', 0, b'b', 0], Vec::new()),
+            ParsedFormula::default(),
+        );
+        let rec = dval.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 4 + 20 + 4 * 3 + 4 + 14 + 17 + 8);
+        assert_eq!(&rec.data[..4], [0x83, 0x01, 0, 0]);
+        assert_eq!(rec.as_biff::<BrtDVal>().unwrap(), dval);
+        assert_eq!(BrtDVal14::from(dval).into_biff().unwrap().data, rec.data);
+        assert!(BrtDValList::new("a,b".into()).into_biff().is_ok());
+    }
+}
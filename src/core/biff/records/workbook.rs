@@ -0,0 +1,251 @@
+//! Records of the workbook part (`xl/workbook.bin`)
+use super::ParsedFormula;
+use crate::core::biff::prelude::*;
+
+// Application that created the file
+declare_packable!(
+    BrtFileVersion,
+    checked,
+    BrtFileVersion,
+    guid_code_name_lo,
+    u64,
+    guid_code_name_hi,
+    u64,
+    st_app_name,
+    XLWideString,
+    st_last_edited,
+    XLWideString,
+    st_lowest_edited,
+    XLWideString,
+    st_rup_build,
+    XLWideString
+);
+
+impl BrtFileVersion {
+    pub(crate) fn current() -> Self {
+        BrtFileVersion::new(0, 0, "xl".into(), "7".into(), "7".into(), "27425".into())
+    }
+}
+
+// Workbook properties
+declare_packable!(
+    BrtWbProp,
+    checked,
+    BrtWbProp,
+    flags,
+    u32,
+    dw_theme_version,
+    u32,
+    str_name,
+    XLWideString
+);
+
+impl BrtWbProp {
+    /// Workbook uses the 1904 date system
+    pub(crate) const F_1904: u32 = 0x0000_0001;
+    pub(crate) const F_SHOW_INK_ANNOTATION: u32 = 0x0000_0020;
+    pub(crate) const F_AUTO_COMPRESS_PICTURES: u32 = 0x0001_0000;
+
+    pub(crate) fn with_flags(flags: u32) -> Self {
+        BrtWbProp::new(
+            Self::F_SHOW_INK_ANNOTATION | Self::F_AUTO_COMPRESS_PICTURES | flags,
+            0,
+            XLWideString::default(),
+        )
+    }
+}
+
+// Workbook window
+declare_packable!(
+    BrtBookView,
+    |x: &Self| x.i_tab_ratio <= 1000,
+    BrtBookView,
+    x_wn,
+    i32,
+    y_wn,
+    i32,
+    dx_wn,
+    u32,
+    dy_wn,
+    u32,
+    i_tab_ratio,
+    u32,
+    itab_first,
+    u32,
+    itab_cur,
+    u32,
+    flags,
+    u8
+);
+
+impl BrtBookView {
+    pub(crate) const F_DSP_H_SCROLL: u8 = 0x08;
+    pub(crate) const F_DSP_V_SCROLL: u8 = 0x10;
+    pub(crate) const F_BOT_ADORNMENT: u8 = 0x20;
+
+    pub(crate) fn with_active_tab(itab_cur: u32) -> Self {
+        BrtBookView::new(
+            240,
+            15,
+            16095,
+            9660,
+            600,
+            0,
+            itab_cur,
+            Self::F_DSP_H_SCROLL | Self::F_DSP_V_SCROLL | Self::F_BOT_ADORNMENT,
+        )
+    }
+}
+
+// Sheet in the workbook
+declare_packable!(
+    BrtBundleSh,
+    |x: &Self| x.hs_state <= 2 && x.i_tab_id >= 1,
+    BrtBundleSh,
+    hs_state,
+    u32,
+    i_tab_id,
+    u32,
+    str_rel_id,
+    XLNullableWideString,
+    str_name,
+    XLWideString
+);
+
+impl BrtBundleSh {
+    pub(crate) const VISIBLE: u32 = 0;
+    #[allow(dead_code)]
+    pub(crate) const HIDDEN: u32 = 1;
+    #[allow(dead_code)]
+    pub(crate) const VERY_HIDDEN: u32 = 2;
+}
+
+// Reference to the range of sheets (`XTI`), used by 3D references of formulas
+declare_packable!(
+    Xti,
+    |x: &Self| x.itab_first <= x.itab_last,
+    i_sup_book,
+    u32,
+    itab_first,
+    i32,
+    itab_last,
+    i32
+);
+
+// Sheets, referenced by formulas of the workbook (`ixti` is the index in `rg_xti`)
+declare_packable!(
+    BrtExternSheet,
+    checked,
+    BrtExternSheet,
+    rg_xti,
+    CountedArray<Xti>
+);
+
+// Defined name, `itab` is the index of the sheet for the local name. The comment is kept here,
+// `BrtNameExt` is not needed for it.
+declare_packable!(
+    BrtName,
+    |x: &Self| (1..=255).contains(&x.name.cch()) && x.flags & BrtName::F_PROC == 0,
+    BrtName,
+    flags,
+    u32,
+    ch_key,
+    u8,
+    itab,
+    u32,
+    name,
+    XLWideString,
+    formula,
+    ParsedFormula,
+    comment,
+    XLNullableWideString
+);
+
+impl BrtName {
+    pub(crate) const F_HIDDEN: u32 = 0x0001;
+    pub(crate) const F_FUNC: u32 = 0x0002;
+    /// Name of the macro, it has extra strings, which are not supported
+    pub(crate) const F_PROC: u32 = 0x0008;
+    pub(crate) const F_BUILTIN: u32 = 0x0020;
+    /// Name of the function, which was added after Excel 2007, like `_xlfn.UNIQUE`
+    pub(crate) const F_FUTURE_FUNCTION: u32 = 0x0002_0000;
+    /// Name of the workbook scope
+    pub(crate) const ITAB_WORKBOOK: u32 = u32::MAX;
+}
+
+// Calculation settings of the workbook
+declare_packable!(
+    BrtCalcProp,
+    |x: &Self| x.f_auto_recalc <= 2 && x.xnum_delta.is_finite(),
+    BrtCalcProp,
+    recalc_id,
+    u32,
+    f_auto_recalc,
+    u32,
+    c_calc_count,
+    u32,
+    xnum_delta,
+    f64,
+    c_user_thread_count,
+    i32,
+    flags,
+    u16
+);
+
+impl BrtCalcProp {
+    /// Recalculate all formulas when the workbook is opened
+    pub(crate) const F_FULL_CALC_ON_LOAD: u16 = 0x0001;
+    pub(crate) const F_REF_A1: u16 = 0x0002;
+    pub(crate) const F_FULL_PREC: u16 = 0x0008;
+    pub(crate) const F_SAVE_RECALC: u16 = 0x0020;
+    pub(crate) const F_MTR_ENABLED: u16 = 0x0040;
+
+    /// Automatic calculation with default settings
+    pub(crate) fn with_flags(flags: u16) -> Self {
+        BrtCalcProp::new(
+            0,
+            1,
+            100,
+            0.001,
+            1,
+            Self::F_REF_A1 | Self::F_FULL_PREC | Self::F_SAVE_RECALC | Self::F_MTR_ENABLED | flags,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle_sh() {
+        let sh = BrtBundleSh::new(
+            BrtBundleSh::VISIBLE,
+            1,
+            Some("rId1").into(),
+            "Sheet1".into(),
+        );
+        let rec = sh.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 4 + 4 + (4 + 8) + (4 + 12));
+        assert_eq!(rec.as_biff::<BrtBundleSh>().unwrap(), sh);
+
+        let sh = BrtBundleSh::new(3, 1, Some("rId1").into(), "Sheet1".into());
+        assert!(sh.into_biff().is_err());
+
+        let name = BrtName::new(
+            BrtName::F_HIDDEN | BrtName::F_BUILTIN,
+            0,
+            0,
+            "_xlnm._FilterDatabase".into(),
+            ParsedFormula::area_3d(0, &UncheckedRfX::from_bounds(0, 9, 0, 2)),
+            None.into(),
+        );
+        let rec = name.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 9 + (4 + 42) + (4 + 15 + 4) + 4);
+        assert_eq!(rec.as_biff::<BrtName>().unwrap(), name);
+        let sheets = BrtExternSheet::new(vec![Xti::new(0, 1, 1)].into());
+        assert_eq!(sheets.into_biff().unwrap().data.len(), 4 + 12);
+        let calc = BrtCalcProp::with_flags(BrtCalcProp::F_FULL_CALC_ON_LOAD);
+        assert_eq!(calc.into_biff().unwrap().data.len(), 26);
+    }
+}
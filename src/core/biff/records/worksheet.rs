@@ -0,0 +1,311 @@
+//! Records of the worksheet part (`xl/worksheets/sheetN.bin`), which are not related to cells
+use crate::core::biff::prelude::*;
+
+// Sheet properties
+declare_packable!(
+    BrtWsProp,
+    checked,
+    BrtWsProp,
+    flags,
+    u16,
+    flags2,
+    u8,
+    brtcolor_tab,
+    BrtColor,
+    rw_sync,
+    u32,
+    col_sync,
+    u32,
+    str_name,
+    XLWideString
+);
+
+impl BrtWsProp {
+    pub(crate) const F_SHOW_AUTO_BREAKS: u16 = 0x0001;
+    pub(crate) const F_ROW_SUMS_BELOW: u16 = 0x0040;
+    pub(crate) const F_COL_SUMS_RIGHT: u16 = 0x0080;
+    pub(crate) const F_SHOW_OUTLINE_SYMBOLS: u16 = 0x0400;
+    /// Stored in `flags2`, the AutoFilter of the sheet has criteria
+    pub(crate) const F_FILTER_MODE: u8 = 0x01;
+    /// Stored in `flags2`
+    pub(crate) const F_COND_FMT_CALC: u8 = 0x02;
+
+    pub(crate) fn standard() -> Self {
+        BrtWsProp::new(
+            Self::F_SHOW_AUTO_BREAKS
+                | Self::F_ROW_SUMS_BELOW
+                | Self::F_COL_SUMS_RIGHT
+                | Self::F_SHOW_OUTLINE_SYMBOLS,
+            Self::F_COND_FMT_CALC,
+            BrtColor::auto(),
+            u32::MAX,
+            u32::MAX,
+            XLWideString::default(),
+        )
+    }
+}
+
+// Used range of the sheet
+declare_packable!(BrtWsDim, checked, BrtWsDim, rfx, UncheckedRfX);
+
+// Width and formatting of the columns
+declare_packable!(
+    BrtColInfo,
+    |x: &Self| x.col_first.inner <= x.col_last.inner && x.coldx <= BrtColInfo::MAX_WIDTH,
+    BrtColInfo,
+    col_first,
+    UncheckedCol,
+    col_last,
+    UncheckedCol,
+    coldx,
+    u32,
+    ixfe,
+    u32,
+    flags,
+    u16
+);
+
+impl BrtColInfo {
+    /// Maximal width of the column, in 1/256 of the character width
+    pub(crate) const MAX_WIDTH: u32 = 255 * 256;
+
+    pub(crate) const F_HIDDEN: u16 = 0x0001;
+    pub(crate) const F_USER_SET: u16 = 0x0002;
+    #[allow(dead_code)]
+    pub(crate) const F_BEST_FIT: u16 = 0x0004;
+    #[allow(dead_code)]
+    pub(crate) const F_PHONETIC: u16 = 0x0008;
+    /// Outline level, bits 8-10
+    #[allow(dead_code)]
+    pub(crate) const OUTLINE_LEVEL: u16 = 0x0700;
+    pub(crate) const F_COLLAPSED: u16 = 0x1000;
+}
+
+// Count of merged ranges
+declare_packable!(BrtBeginMergeCells, checked, BrtBeginMergeCells, cmcs, u32);
+
+// Merged range, must contain more than one cell
+declare_packable!(
+    BrtMergeCell,
+    |x: &Self| x.rfx.rw_first.inner != x.rfx.rw_last.inner
+        || x.rfx.col_first.inner != x.rfx.col_last.inner,
+    BrtMergeCell,
+    rfx,
+    UncheckedRfX
+);
+
+// Sheet window
+declare_packable!(
+    BrtBeginWsView,
+    |x: &Self| x.xl_view <= 2 && (x.w_scale == 0 || (10..=400).contains(&x.w_scale)),
+    BrtBeginWsView,
+    flags,
+    u16,
+    xl_view,
+    u32,
+    rw_top,
+    UncheckedRw,
+    col_left,
+    UncheckedCol,
+    icv_hdr,
+    u8,
+    reserved2,
+    u8,
+    reserved3,
+    u16,
+    w_scale,
+    u16,
+    w_scale_normal,
+    u16,
+    w_scale_slv,
+    u16,
+    w_scale_plv,
+    u16,
+    i_wbk_view,
+    u32
+);
+
+impl BrtBeginWsView {
+    pub(crate) const F_DSP_GRID: u16 = 0x0004;
+    pub(crate) const F_DSP_RW_COL: u16 = 0x0008;
+    pub(crate) const F_DSP_ZEROS: u16 = 0x0010;
+    pub(crate) const F_RIGHT_TO_LEFT: u16 = 0x0020;
+    pub(crate) const F_SELECTED: u16 = 0x0040;
+    pub(crate) const F_DSP_RULER: u16 = 0x0080;
+    pub(crate) const F_DSP_GUTS: u16 = 0x0100;
+    pub(crate) const F_DEFAULT_HDR: u16 = 0x0200;
+
+    pub(crate) fn standard() -> Self {
+        BrtBeginWsView::new(
+            Self::F_DSP_GRID
+                | Self::F_DSP_RW_COL
+                | Self::F_DSP_ZEROS
+                | Self::F_DSP_RULER
+                | Self::F_DSP_GUTS
+                | Self::F_DEFAULT_HDR,
+            0,
+            0.into(),
+            0.into(),
+            64,
+            0,
+            0,
+            100,
+            0,
+            0,
+            0,
+            0,
+        )
+    }
+}
+
+// Panes of the sheet window: frozen or split
+declare_packable!(
+    BrtPane,
+    |x: &Self| x.xnum_x_split >= 0.0 && x.xnum_y_split >= 0.0 && x.pnn_acct <= 3 && x.flags <= 3,
+    BrtPane,
+    xnum_x_split,
+    f64,
+    xnum_y_split,
+    f64,
+    rw_top,
+    UncheckedRw,
+    col_left,
+    UncheckedCol,
+    pnn_acct,
+    u32,
+    flags,
+    u8
+);
+
+impl BrtPane {
+    pub(crate) const PNN_BOTTOM_RIGHT: u32 = 0;
+    pub(crate) const PNN_TOP_RIGHT: u32 = 1;
+    pub(crate) const PNN_BOTTOM_LEFT: u32 = 2;
+    pub(crate) const PNN_TOP_LEFT: u32 = 3;
+    pub(crate) const F_FROZEN: u8 = 0x01;
+    pub(crate) const F_FROZEN_NO_SPLIT: u8 = 0x02;
+}
+
+// Selection in the pane, the active cell is within one of ranges
+declare_packable!(
+    BrtSel,
+    |x: &Self| x.pnn <= 3 && (x.iref_act as usize) < x.rgrfx.len(),
+    BrtSel,
+    pnn,
+    u32,
+    rw_act,
+    UncheckedRw,
+    col_act,
+    UncheckedCol,
+    iref_act,
+    u32,
+    rgrfx,
+    CountedArray<UncheckedRfX>
+);
+
+// Default column and row formatting
+declare_packable!(
+    BrtWsFmtInfo,
+    |x: &Self| x.dy_def <= 0x2000 && x.i_out_level_rw <= 7 && x.i_out_level_col <= 7,
+    BrtWsFmtInfo,
+    dx_g_col,
+    u32,
+    dx_col,
+    u16,
+    dy_def,
+    u16,
+    flags,
+    u16,
+    i_out_level_rw,
+    u8,
+    i_out_level_col,
+    u8
+);
+
+impl BrtWsFmtInfo {
+    pub(crate) fn standard() -> Self {
+        BrtWsFmtInfo::new(u32::MAX, 8, 300, 0, 0, 0)
+    }
+}
+
+// Hyperlink of the range: the relationship of the external target and the location in it
+declare_packable!(
+    BrtHLink,
+    |x: &Self| x.rfx.is_valid(),
+    BrtHLink,
+    rfx,
+    UncheckedRfX,
+    rel_id,
+    XLNullableWideString,
+    location,
+    XLWideString,
+    tooltip,
+    XLWideString,
+    display,
+    XLWideString
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worksheet_records() {
+        let rec = BrtWsDim::new(UncheckedRfX::from_bounds(0, 9, 0, 2))
+            .into_biff()
+            .unwrap();
+        assert_eq!(rec.data.len(), 16);
+        assert!(BrtWsDim::new(UncheckedRfX::from_bounds(9, 0, 0, 2))
+            .into_biff()
+            .is_err());
+
+        let info = BrtColInfo::new(1.into(), 3.into(), 20 * 256, 0, BrtColInfo::F_USER_SET);
+        let rec = info.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 18);
+        assert_eq!(rec.as_biff::<BrtColInfo>().unwrap(), info);
+        assert!(BrtColInfo::new(3.into(), 1.into(), 0, 0, 0)
+            .into_biff()
+            .is_err());
+        assert!(
+            BrtColInfo::new(0.into(), 0.into(), BrtColInfo::MAX_WIDTH + 1, 0, 0)
+                .into_biff()
+                .is_err()
+        );
+
+        let pane = BrtPane::new(0.0, 1.0, 1.into(), 0.into(), BrtPane::PNN_BOTTOM_LEFT, 3);
+        assert_eq!(pane.into_biff().unwrap().data.len(), 29);
+        let sel = BrtSel::new(
+            BrtPane::PNN_TOP_LEFT,
+            2.into(),
+            3.into(),
+            0,
+            vec![UncheckedRfX::from_bounds(2, 2, 3, 3)].into(),
+        );
+        let rec = sel.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 20 + 16);
+        assert_eq!(rec.as_biff::<BrtSel>().unwrap(), sel);
+        assert!(
+            BrtSel::new(0, 0.into(), 0.into(), 0, CountedArray::default())
+                .into_biff()
+                .is_err()
+        );
+
+        assert!(BrtMergeCell::new(UncheckedRfX::from_bounds(0, 0, 0, 1))
+            .into_biff()
+            .is_ok());
+        assert!(BrtMergeCell::new(UncheckedRfX::from_bounds(2, 2, 3, 3))
+            .into_biff()
+            .is_err());
+
+        let link = BrtHLink::new(
+            UncheckedRfX::from_bounds(0, 0, 1, 1),
+            None.into(),
+            "'Sheet 2'!A1".into(),
+            "".into(),
+            "Details".into(),
+        );
+        let rec = link.into_biff().unwrap();
+        assert_eq!(rec.data.len(), 16 + 4 + 28 + 4 + 18);
+        assert_eq!(rec.as_biff::<BrtHLink>().unwrap(), link);
+    }
+}
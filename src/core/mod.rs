@@ -1,2 +1,2 @@
-mod biff;
-mod xml;
\ No newline at end of file
+pub(crate) mod biff;
+pub(crate) mod xml;
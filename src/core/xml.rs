@@ -0,0 +1,274 @@
+//! XML parts of the Open Packaging Conventions container: content types and relationships
+use std::{borrow::Cow, fmt::Write};
+
+use quick_xml::events::Event;
+
+pub(crate) const CT_MAIN: &str = "application/vnd.ms-excel.sheet.binary.macroEnabled.main";
+pub(crate) const CT_WORKSHEET: &str = "application/vnd.ms-excel.worksheet";
+pub(crate) const CT_STYLES: &str = "application/vnd.ms-excel.styles";
+pub(crate) const CT_SHARED_STRINGS: &str = "application/vnd.ms-excel.sharedStrings";
+pub(crate) const CT_TABLE: &str = "application/vnd.ms-excel.table";
+pub(crate) const CT_SHEET_METADATA: &str = "application/vnd.ms-excel.sheetMetadata";
+pub(crate) const CT_COMMENTS: &str = "application/vnd.ms-excel.comments";
+pub(crate) const CT_VML_DRAWING: &str = "application/vnd.openxmlformats-officedocument.vmlDrawing";
+pub(crate) const CT_RELATIONSHIPS: &str =
+    "application/vnd.openxmlformats-package.relationships+xml";
+pub(crate) const CT_XML: &str = "application/xml";
+
+pub(crate) const REL_OFFICE_DOCUMENT: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument";
+pub(crate) const REL_WORKSHEET: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet";
+pub(crate) const REL_STYLES: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles";
+pub(crate) const REL_SHARED_STRINGS: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings";
+pub(crate) const REL_TABLE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/table";
+pub(crate) const REL_SHEET_METADATA: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/sheetMetadata";
+pub(crate) const REL_HYPERLINK: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink";
+pub(crate) const REL_COMMENTS: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments";
+pub(crate) const REL_VML_DRAWING: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/vmlDrawing";
+
+const XML_HEADER: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\r\n";
+
+/// Escape special characters for usage in XML attributes and text
+pub(crate) fn escape(value: &str) -> Cow<'_, str> {
+    if !value.contains(['&', '<', '>', '"', '\'']) {
+        return Cow::Borrowed(value);
+    }
+    let mut ret = String::with_capacity(value.len() + 8);
+    for ch in value.chars() {
+        match ch {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            '\'' => ret.push_str("&apos;"),
+            ch => ret.push(ch),
+        }
+    }
+    Cow::Owned(ret)
+}
+
+/// `[Content_Types].xml` part
+pub(crate) struct ContentTypes {
+    defaults: Vec<(&'static str, &'static str)>,
+    overrides: Vec<(String, &'static str)>,
+}
+
+impl ContentTypes {
+    pub(crate) fn new() -> Self {
+        ContentTypes {
+            defaults: vec![
+                ("bin", CT_MAIN),
+                ("rels", CT_RELATIONSHIPS),
+                ("xml", CT_XML),
+            ],
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Register the content type of all parts with the extension, like `vml`
+    pub(crate) fn add_default(&mut self, extension: &'static str, content_type: &'static str) {
+        self.defaults.push((extension, content_type));
+    }
+
+    /// Register the content type of the part, given by its absolute name (like `/xl/styles.bin`)
+    pub(crate) fn add_override(
+        &mut self,
+        part_name: impl Into<String>,
+        content_type: &'static str,
+    ) {
+        self.overrides.push((part_name.into(), content_type));
+    }
+
+    pub(crate) fn to_xml(&self) -> String {
+        let mut ret = String::from(XML_HEADER);
+        ret.push_str(
+            "<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">",
+        );
+        for (extension, content_type) in self.defaults.iter() {
+            let _ = write!(
+                ret,
+                "<Default Extension=\"{}\" ContentType=\"{}\"/>",
+                extension, content_type
+            );
+        }
+        for (part_name, content_type) in self.overrides.iter() {
+            let _ = write!(
+                ret,
+                "<Override PartName=\"{}\" ContentType=\"{}\"/>",
+                escape(part_name),
+                content_type
+            );
+        }
+        ret.push_str("</Types>");
+        ret
+    }
+}
+
+/// Single relationship of the part
+pub(crate) struct Relationship {
+    pub(crate) id: String,
+    pub(crate) rel_type: String,
+    pub(crate) target: String,
+    /// The target is outside of the package, like URL
+    pub(crate) external: bool,
+}
+
+/// Relationships part (`*.rels`) of the package or of the single part
+#[derive(Default)]
+pub(crate) struct Relationships {
+    items: Vec<Relationship>,
+}
+
+impl Relationships {
+    pub(crate) fn new() -> Self {
+        Relationships::default()
+    }
+
+    /// Add relationship and return its identifier (`rIdN`)
+    pub(crate) fn add(&mut self, rel_type: &str, target: impl Into<String>) -> String {
+        self.push(rel_type, target.into(), false)
+    }
+
+    /// Add relationship to the external target and return its identifier
+    pub(crate) fn add_external(&mut self, rel_type: &str, target: impl Into<String>) -> String {
+        self.push(rel_type, target.into(), true)
+    }
+
+    fn push(&mut self, rel_type: &str, target: String, external: bool) -> String {
+        let id = format!("rId{}", self.items.len() + 1);
+        self.items.push(Relationship {
+            id: id.clone(),
+            rel_type: rel_type.to_owned(),
+            target,
+            external,
+        });
+        id
+    }
+
+    /// Find the relationship by its identifier
+    pub(crate) fn get(&self, id: &str) -> Option<&Relationship> {
+        self.items.iter().find(|rel| rel.id == id)
+    }
+
+    /// Find the first relationship of the given type
+    pub(crate) fn find_by_type(&self, rel_type: &str) -> Option<&Relationship> {
+        self.items.iter().find(|rel| rel.rel_type == rel_type)
+    }
+
+    /// All relationships of the given type
+    pub(crate) fn filter_by_type<'a>(
+        &'a self,
+        rel_type: &'a str,
+    ) -> impl Iterator<Item = &'a Relationship> {
+        self.items
+            .iter()
+            .filter(move |rel| rel.rel_type == rel_type)
+    }
+
+    /// Parse the relationships part
+    pub(crate) fn parse(xml: &[u8]) -> crate::Result<Self> {
+        let invalid = |e: &dyn std::fmt::Display| crate::Error::malformed(e.to_string());
+        let mut reader = quick_xml::Reader::from_reader(xml);
+        let mut buf = Vec::new();
+        let mut rels = Relationships::new();
+        loop {
+            match reader.read_event_into(&mut buf).map_err(|e| invalid(&e))? {
+                Event::Empty(ref e) | Event::Start(ref e)
+                    if e.local_name().as_ref() == b"Relationship" =>
+                {
+                    let mut rel = Relationship {
+                        id: String::new(),
+                        rel_type: String::new(),
+                        target: String::new(),
+                        external: false,
+                    };
+                    for attr in e.attributes() {
+                        let attr = attr.map_err(|e| invalid(&e))?;
+                        let value = attr.unescape_value().map_err(|e| invalid(&e))?.into_owned();
+                        match attr.key.as_ref() {
+                            b"Id" => rel.id = value,
+                            b"Type" => rel.rel_type = value,
+                            b"Target" => rel.target = value,
+                            b"TargetMode" => rel.external = value == "External",
+                            _ => {}
+                        }
+                    }
+                    rels.items.push(rel);
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+        Ok(rels)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub(crate) fn to_xml(&self) -> String {
+        let mut ret = String::from(XML_HEADER);
+        ret.push_str(
+            "<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">",
+        );
+        for rel in self.items.iter() {
+            let _ = write!(
+                ret,
+                "<Relationship Id=\"{}\" Type=\"{}\" Target=\"{}\"{}/>",
+                rel.id,
+                rel.rel_type,
+                escape(&rel.target),
+                if rel.external {
+                    " TargetMode=\"External\""
+                } else {
+                    ""
+                }
+            );
+        }
+        ret.push_str("</Relationships>");
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relationships() {
+        let mut rels = Relationships::new();
+        assert!(rels.is_empty());
+        assert_eq!(rels.add(REL_WORKSHEET, "worksheets/sheet1.bin"), "rId1");
+        assert_eq!(rels.add(REL_STYLES, "styles.bin"), "rId2");
+        let xml = rels.to_xml();
+        assert!(xml.contains("<Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" Target=\"styles.bin\"/>"));
+
+        let parsed = Relationships::parse(xml.as_bytes()).unwrap();
+        assert_eq!(parsed.get("rId1").unwrap().target, "worksheets/sheet1.bin");
+        assert_eq!(parsed.find_by_type(REL_STYLES).unwrap().id, "rId2");
+        assert!(parsed.get("rId3").is_none());
+        assert!(!parsed.get("rId1").unwrap().external);
+
+        assert_eq!(
+            rels.add_external(REL_HYPERLINK, "https://example.com/?a=1&b=2"),
+            "rId3"
+        );
+        let xml = rels.to_xml();
+        assert!(
+            xml.contains("Target=\"https://example.com/?a=1&amp;b=2\" TargetMode=\"External\"/>")
+        );
+        let parsed = Relationships::parse(xml.as_bytes()).unwrap();
+        assert!(parsed.get("rId3").unwrap().external);
+
+        assert_eq!(escape("a<b & 'c'"), "a&lt;b &amp; &apos;c&apos;");
+    }
+}
@@ -0,0 +1,154 @@
+//! Conversion of dates into serial numbers of Excel date systems
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// Days from the Unix epoch to 1899-12-31, which is serial 0 in the 1900 date system
+const UNIX_DAYS_1900: i64 = -25568;
+/// Days from the Unix epoch to 1900-03-01, the first date after the fictitious 1900-02-29
+const UNIX_DAYS_1900_MARCH: i64 = -25508;
+/// Serial number of the fictitious 1900-02-29
+const SERIAL_LEAP_BUG: i64 = 60;
+/// Days from the Unix epoch to 1904-01-01, which is serial 0 in the 1904 date system
+const UNIX_DAYS_1904: i64 = -24107;
+/// Days from the Unix epoch to 9999-12-31, the last date supported by Excel
+const UNIX_DAYS_MAX: i64 = 2_932_896;
+
+/// Date system of the workbook, which defines the day of serial number 0.
+///
+/// Dates and times are stored in cells as numbers of days (with the fraction of the day)
+/// since the start of the system, with the precision of milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DateSystem {
+    /// Serial 1 is 1900-01-01. Serial 60 is the fictitious 1900-02-29, which was kept
+    /// for compatibility with Lotus 1-2-3, so dates before March 1900 are shifted by one day.
+    #[default]
+    V1900,
+    /// Serial 0 is 1904-01-01, used by old Excel versions for Mac
+    V1904,
+}
+
+impl DateSystem {
+    /// Serial number of the date, if it is within the range of the system
+    pub fn date_to_serial(self, date: NaiveDate) -> Option<f64> {
+        self.datetime_to_serial(date.and_time(NaiveTime::MIN))
+    }
+
+    /// Serial number of the date and time, if it is within the range of the system
+    pub fn datetime_to_serial(self, datetime: NaiveDateTime) -> Option<f64> {
+        self.millis_to_serial(datetime.and_utc().timestamp_millis())
+    }
+
+    /// Date and time of the serial number, rounded to milliseconds
+    pub fn serial_to_datetime(self, serial: f64) -> Option<NaiveDateTime> {
+        chrono::DateTime::from_timestamp_millis(self.serial_to_millis(serial)?)
+            .map(|datetime| datetime.naive_utc())
+    }
+
+    /// Date of the serial number, the time is dropped
+    pub fn serial_to_date(self, serial: f64) -> Option<NaiveDate> {
+        self.serial_to_datetime(serial)
+            .map(|datetime| datetime.date())
+    }
+
+    /// Serial number of the day, which starts `unix_days` after the Unix epoch
+    fn day_serial(self, unix_days: i64) -> Option<i64> {
+        let serial = match self {
+            DateSystem::V1900 if unix_days >= UNIX_DAYS_1900_MARCH => {
+                unix_days - UNIX_DAYS_1900 + 1
+            }
+            DateSystem::V1900 => unix_days - UNIX_DAYS_1900,
+            DateSystem::V1904 => unix_days - UNIX_DAYS_1904,
+        };
+        (serial >= 0 && unix_days <= UNIX_DAYS_MAX).then_some(serial)
+    }
+
+    /// Days since the Unix epoch of the day with the serial number
+    fn serial_day(self, serial: i64) -> Option<i64> {
+        let unix_days = match self {
+            DateSystem::V1900 if serial == SERIAL_LEAP_BUG => return None,
+            DateSystem::V1900 if serial > SERIAL_LEAP_BUG => serial + UNIX_DAYS_1900 - 1,
+            DateSystem::V1900 => serial + UNIX_DAYS_1900,
+            DateSystem::V1904 => serial + UNIX_DAYS_1904,
+        };
+        (serial >= 0 && unix_days <= UNIX_DAYS_MAX).then_some(unix_days)
+    }
+
+    /// Serial number of the moment `millis` after the Unix epoch
+    pub(crate) fn millis_to_serial(self, millis: i64) -> Option<f64> {
+        let day = self.day_serial(millis.div_euclid(MILLIS_PER_DAY))?;
+        Some(day as f64 + millis.rem_euclid(MILLIS_PER_DAY) as f64 / MILLIS_PER_DAY as f64)
+    }
+
+    /// Milliseconds since the Unix epoch of the serial number
+    pub(crate) fn serial_to_millis(self, serial: f64) -> Option<i64> {
+        if !serial.is_finite() || !(0.0..=i32::MAX as f64).contains(&serial) {
+            return None;
+        }
+        let day = serial.floor();
+        let unix_days = self.serial_day(day as i64)?;
+        let millis = ((serial - day) * MILLIS_PER_DAY as f64).round() as i64;
+        Some(unix_days * MILLIS_PER_DAY + millis)
+    }
+
+    /// Days since the Unix epoch of the date part of the serial number
+    pub(crate) fn serial_to_days(self, serial: f64) -> Option<i32> {
+        if !serial.is_finite() || !(0.0..=i32::MAX as f64).contains(&serial) {
+            return None;
+        }
+        self.serial_day(serial.floor() as i64)
+            .map(|days| days as i32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_date_systems() {
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let v1900 = DateSystem::V1900;
+        for (value, serial) in [
+            (date(1899, 12, 31), 0.0),
+            (date(1900, 1, 1), 1.0),
+            (date(1900, 2, 28), 59.0),
+            (date(1900, 3, 1), 61.0),
+            (date(1970, 1, 1), 25569.0),
+            (date(2024, 1, 1), 45292.0),
+            (date(9999, 12, 31), 2958465.0),
+        ] {
+            assert_eq!(v1900.date_to_serial(value), Some(serial), "{}", value);
+            assert_eq!(v1900.serial_to_date(serial), Some(value));
+        }
+        assert_eq!(v1900.date_to_serial(date(1899, 12, 30)), None);
+        assert_eq!(v1900.date_to_serial(date(10000, 1, 1)), None);
+        // the fictitious leap day
+        assert_eq!(v1900.serial_to_date(60.0), None);
+        assert_eq!(v1900.serial_to_date(60.5), None);
+        assert_eq!(v1900.serial_to_date(-1.0), None);
+        assert_eq!(v1900.serial_to_date(f64::NAN), None);
+
+        let v1904 = DateSystem::V1904;
+        assert_eq!(v1904.date_to_serial(date(1904, 1, 1)), Some(0.0));
+        assert_eq!(v1904.date_to_serial(date(1903, 12, 31)), None);
+        assert_eq!(v1904.date_to_serial(date(2024, 1, 1)), Some(43830.0));
+        assert_eq!(v1904.serial_to_date(43830.0), Some(date(2024, 1, 1)));
+
+        let noon = date(1900, 2, 28).and_hms_milli_opt(12, 0, 0, 0).unwrap();
+        assert_eq!(v1900.datetime_to_serial(noon), Some(59.5));
+        let datetime = date(2024, 5, 17)
+            .and_hms_milli_opt(13, 45, 30, 250)
+            .unwrap();
+        for system in [v1900, v1904] {
+            let serial = system.datetime_to_serial(datetime).unwrap();
+            assert_eq!(system.serial_to_datetime(serial), Some(datetime));
+        }
+        // the end of the day is rounded to the next one
+        assert_eq!(
+            v1900.serial_to_datetime(59.999_999_999),
+            Some(date(1900, 3, 1).and_time(NaiveTime::MIN))
+        );
+        assert_eq!(v1900.serial_to_days(25570.75), Some(1));
+    }
+}
@@ -0,0 +1,314 @@
+//! Error type of the crate
+use std::{fmt::Display, io};
+
+use arrow::error::ArrowError;
+use zip::result::ZipError;
+
+use crate::core::biff::BiffId;
+
+/// Result with the crate [`Error`]
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Where the malformed data was found, every part of the location is optional
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Location {
+    /// Path of the package part, like `xl/worksheets/sheet1.bin`
+    pub part: Option<String>,
+    /// Id of the BIFF12 record
+    pub record: Option<u16>,
+    /// Byte offset of the record in the part stream
+    pub offset: Option<u64>,
+    /// Path of the record field, like `cell.column`
+    pub field: Option<String>,
+}
+
+impl Location {
+    /// Name of the record, like `BrtCellReal`
+    pub fn record_name(&self) -> Option<&'static str> {
+        self.record
+            .and_then(BiffId::from_repr)
+            .map(<&'static str>::from)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.part.is_none()
+            && self.record.is_none()
+            && self.offset.is_none()
+            && self.field.is_none()
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(part) = &self.part {
+            parts.push(format!("part {}", part));
+        }
+        match (self.record, self.record_name()) {
+            (Some(id), Some(name)) => parts.push(format!("record {} ({:#06X})", name, id)),
+            (Some(id), None) => parts.push(format!("record {:#06X}", id)),
+            _ => {}
+        }
+        if let Some(offset) = self.offset {
+            parts.push(format!("offset {}", offset));
+        }
+        if let Some(field) = &self.field {
+            parts.push(format!("field {}", field));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Error of reading or writing the workbook
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    Io(io::Error),
+    Zip(ZipError),
+    Arrow(ArrowError),
+    /// Record data ends before all its fields are read
+    Truncated {
+        location: Location,
+        expected: usize,
+        actual: usize,
+    },
+    /// Record data has bytes after its last field
+    TrailingData {
+        location: Location,
+        expected: usize,
+        actual: usize,
+    },
+    /// Field (or the whole record, if the field is not set) has an invalid value
+    InvalidValue {
+        location: Location,
+    },
+    /// Record id is not known
+    UnknownRecord {
+        location: Location,
+    },
+    /// Package structure is broken: missing parts, dangling references, misplaced records
+    Malformed {
+        location: Location,
+        message: String,
+    },
+    /// Workbook has no sheet with the name
+    SheetNotFound(String),
+    /// Arguments of the call are not valid
+    InvalidInput(String),
+}
+
+impl Error {
+    pub(crate) fn truncated(expected: usize, actual: usize) -> Self {
+        Error::Truncated {
+            location: Location::default(),
+            expected,
+            actual,
+        }
+    }
+
+    pub(crate) fn trailing_data(expected: usize, actual: usize) -> Self {
+        Error::TrailingData {
+            location: Location::default(),
+            expected,
+            actual,
+        }
+    }
+
+    pub(crate) fn invalid_value() -> Self {
+        Error::InvalidValue {
+            location: Location::default(),
+        }
+    }
+
+    pub(crate) fn malformed(message: impl Into<String>) -> Self {
+        Error::Malformed {
+            location: Location::default(),
+            message: message.into(),
+        }
+    }
+
+    pub(crate) fn invalid_input(message: impl Into<String>) -> Self {
+        Error::InvalidInput(message.into())
+    }
+
+    /// Location of the malformed data, if the error has it
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Error::Truncated { location, .. }
+            | Error::TrailingData { location, .. }
+            | Error::InvalidValue { location }
+            | Error::UnknownRecord { location }
+            | Error::Malformed { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    fn location_mut(&mut self) -> Option<&mut Location> {
+        match self {
+            Error::Truncated { location, .. }
+            | Error::TrailingData { location, .. }
+            | Error::InvalidValue { location }
+            | Error::UnknownRecord { location }
+            | Error::Malformed { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Prepend the field name to the field path (outer structures are applied last)
+    pub(crate) fn with_field(mut self, field: &str) -> Self {
+        if let Some(location) = self.location_mut() {
+            location.field = Some(match location.field.take() {
+                Some(inner) => format!("{}.{}", field, inner),
+                None => field.to_owned(),
+            });
+        }
+        self
+    }
+
+    /// Set the record id, unless it is already known
+    pub(crate) fn with_record(mut self, id: BiffId) -> Self {
+        if let Some(location) = self.location_mut() {
+            location.record.get_or_insert(id as u16);
+        }
+        self
+    }
+
+    /// Set the offset of the record in the stream, unless it is already known
+    pub(crate) fn at_offset(mut self, offset: u64) -> Self {
+        if let Some(location) = self.location_mut() {
+            location.offset.get_or_insert(offset);
+        }
+        self
+    }
+
+    /// Set the package part, unless it is already known
+    pub(crate) fn in_part(mut self, part: &str) -> Self {
+        if let Some(location) = self.location_mut() {
+            location.part.get_or_insert_with(|| part.to_owned());
+        }
+        self
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let at = |location: &Location| {
+            if location.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", location)
+            }
+        };
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Zip(e) => write!(f, "ZIP error: {}", e),
+            Error::Arrow(e) => write!(f, "Arrow error: {}", e),
+            Error::Truncated {
+                location,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "truncated record{}: {} bytes expected, {} available",
+                at(location),
+                expected,
+                actual
+            ),
+            Error::TrailingData {
+                location,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "record has trailing data{}: {} bytes expected, {} found",
+                at(location),
+                expected,
+                actual
+            ),
+            Error::InvalidValue { location } => write!(f, "invalid value{}", at(location)),
+            Error::UnknownRecord { location } => write!(f, "unknown record{}", at(location)),
+            Error::Malformed { location, message } => {
+                write!(f, "malformed workbook{}: {}", at(location), message)
+            }
+            Error::SheetNotFound(name) => write!(f, "sheet {:?} not found", name),
+            Error::InvalidInput(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Zip(e) => Some(e),
+            Error::Arrow(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ZipError> for Error {
+    fn from(e: ZipError) -> Self {
+        match e {
+            ZipError::Io(e) => Error::Io(e),
+            e => Error::Zip(e),
+        }
+    }
+}
+
+impl From<ArrowError> for Error {
+    fn from(e: ArrowError) -> Self {
+        Error::Arrow(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            e @ (Error::InvalidInput(_) | Error::SheetNotFound(_)) => {
+                io::Error::new(io::ErrorKind::InvalidInput, e)
+            }
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_location() {
+        let e = Error::truncated(16, 12)
+            .with_field("xnum")
+            .with_record(BiffId::BrtCellReal)
+            .at_offset(120)
+            .in_part("xl/worksheets/sheet1.bin");
+        assert_eq!(
+            e.to_string(),
+            "truncated record (part xl/worksheets/sheet1.bin, record BrtCellReal (0x0005), \
+             offset 120, field xnum): 16 bytes expected, 12 available"
+        );
+
+        let e = Error::invalid_value()
+            .with_field("column")
+            .with_field("cell")
+            .with_record(BiffId::BrtCellBool)
+            .with_record(BiffId::BrtRowHdr);
+        let location = e.location().unwrap();
+        assert_eq!(location.field.as_deref(), Some("cell.column"));
+        assert_eq!(location.record_name(), Some("BrtCellBool"));
+
+        let e = Error::invalid_input("bad").with_field("ignored");
+        assert!(e.location().is_none());
+        assert_eq!(io::Error::from(e).kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,613 @@
+//! Compiler of the formula text into parsed tokens (`Rgce`)
+use super::{
+    functions::{is_future_name, Class, Function},
+    lexer::{tokenize, CellRef, Spanned, Token},
+    ptg,
+};
+use crate::{
+    core::biff::records::{ParsedFormula, MAX_FORMULA_LEN},
+    CellError, Error, Result,
+};
+
+/// Maximal length of the string constant of the formula
+const MAX_STRING_LEN: usize = 255;
+
+/// Workbook-level objects, which are referenced by formulas
+pub(crate) trait FormulaContext {
+    /// Index of the sheet in `BrtExternSheet` (`ixti`) for 3D references
+    fn sheet(&mut self, name: &str) -> Result<u16>;
+
+    /// One-based index of the defined name
+    fn name(&mut self, name: &str) -> Result<u32>;
+
+    /// One-based index of the hidden name of the future function, like `_xlfn.UNIQUE`
+    fn function(&mut self, name: &str) -> Result<u32>;
+}
+
+/// Type of the formula, which defines how its tokens are stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FormulaType {
+    /// Formula of the single cell
+    Cell,
+    /// Formula, which is shared by the range of cells. Relative references are stored as
+    /// offsets from the cell, the formula is evaluated for (its first cell on write).
+    Shared { row: u32, col: u16 },
+    /// Formula, which is evaluated as an array into the range of cells
+    Array,
+    /// Formula of the defined name, its references must have sheets
+    Name,
+}
+
+impl FormulaType {
+    /// Cell, which relative references are offsets from
+    pub(crate) fn base(&self) -> Option<(u32, u16)> {
+        match *self {
+            FormulaType::Shared { row, col } => Some((row, col)),
+            _ => None,
+        }
+    }
+}
+
+/// Compile the formula text in A1 notation, like `=SUM(A1:A10)*2`, the leading `=` is optional
+pub(crate) fn compile(
+    text: &str,
+    kind: FormulaType,
+    context: &mut impl FormulaContext,
+) -> Result<ParsedFormula> {
+    let invalid =
+        |reason: String| Error::invalid_input(format!("invalid formula {:?}: {}", text, reason));
+    let body = text.strip_prefix('=').unwrap_or(text);
+    let tokens = tokenize(body).map_err(|e| invalid(e.to_string()))?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.formula().map_err(invalid)?;
+
+    let mut emitter = Emitter {
+        rgce: Vec::new(),
+        context,
+        base: kind.base(),
+        qualified: kind == FormulaType::Name,
+        volatile: false,
+    };
+    let class = match kind {
+        FormulaType::Array => Class::Array,
+        FormulaType::Name => Class::Ref,
+        _ => Class::Value,
+    };
+    emitter.emit(&expr, class)?;
+    let mut rgce = emitter.rgce;
+    if emitter.volatile {
+        // `PtgAttrSemi` marks the volatile formula
+        rgce.splice(0..0, [ptg::ATTR, ptg::ATTR_SEMI, 0, 0]);
+    }
+    if rgce.len() > MAX_FORMULA_LEN {
+        return Err(invalid("formula is too long".to_owned()));
+    }
+    Ok(ParsedFormula::new(rgce, Vec::new()))
+}
+
+/// Parsed formula, which is converted into tokens in reverse Polish notation
+enum Expr {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Error(CellError),
+    /// Omitted argument of the function
+    Missing,
+    Ref(Option<String>, CellRef),
+    Area(Option<String>, CellRef, CellRef),
+    Name(String),
+    /// Unary operator (`PtgUplus`, `PtgUminus` or `PtgPercent`)
+    Unary(u8, Box<Expr>),
+    Binary(u8, Box<Expr>, Box<Expr>),
+    Paren(Box<Expr>),
+    Function(&'static Function, Vec<Expr>),
+    /// Future function, which is not known, like `_xlfn.NEWFUNC`
+    UserFunction(String, Vec<Expr>),
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+type ParseResult = std::result::Result<Expr, String>;
+
+/// Binary operators by the level of precedence, from the lowest one
+const BINARY_OPERATORS: [&[(&str, u8)]; 5] = [
+    &[
+        ("=", ptg::EQ),
+        ("<>", ptg::NE),
+        ("<", ptg::LT),
+        ("<=", ptg::LE),
+        (">", ptg::GT),
+        (">=", ptg::GE),
+    ],
+    &[("&", ptg::CONCAT)],
+    &[("+", ptg::ADD), ("-", ptg::SUB)],
+    &[("*", ptg::MUL), ("/", ptg::DIV)],
+    &[("^", ptg::POWER)],
+];
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(token, _)| token.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn is_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Some(Token::Op(other)) if *other == op)
+    }
+
+    fn expect_op(&mut self, op: &str) -> std::result::Result<(), String> {
+        match self.next() {
+            Some(Token::Op(other)) if other == op => Ok(()),
+            Some(other) => Err(format!("expected '{}', found {:?}", op, other)),
+            None => Err(format!("expected '{}'", op)),
+        }
+    }
+
+    fn formula(&mut self) -> ParseResult {
+        if self.tokens.is_empty() {
+            return Err("formula is empty".to_owned());
+        }
+        let expr = self.binary(0)?;
+        match self.peek() {
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Ok(expr),
+        }
+    }
+
+    fn binary(&mut self, level: usize) -> ParseResult {
+        let Some(operators) = BINARY_OPERATORS.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        while let Some(&(_, ptg)) = operators.iter().find(|(op, _)| self.is_op(op)) {
+            self.pos += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(ptg, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> ParseResult {
+        for (op, ptg) in [("-", ptg::UMINUS), ("+", ptg::UPLUS)] {
+            if self.is_op(op) {
+                self.pos += 1;
+                return Ok(Expr::Unary(ptg, Box::new(self.unary()?)));
+            }
+        }
+        let mut expr = self.intersection()?;
+        while self.is_op("%") {
+            self.pos += 1;
+            expr = Expr::Unary(ptg::PERCENT, Box::new(expr));
+        }
+        Ok(expr)
+    }
+
+    /// References, separated by spaces
+    fn intersection(&mut self) -> ParseResult {
+        let mut left = self.range()?;
+        while let Some((token, true)) = self.tokens.get(self.pos) {
+            let starts_reference = matches!(
+                token,
+                Token::Ref(_)
+                    | Token::Area(..)
+                    | Token::Sheet(_)
+                    | Token::Name(_)
+                    | Token::Function(_)
+            ) || matches!(token, Token::Op("("));
+            if !starts_reference {
+                break;
+            }
+            let right = self.range()?;
+            left = Expr::Binary(ptg::ISECT, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    /// Range between two references, like `A1:INDEX(B:B, 2)`
+    fn range(&mut self) -> ParseResult {
+        let mut left = self.primary()?;
+        while self.is_op(":") {
+            self.pos += 1;
+            let right = self.primary()?;
+            left = Expr::Binary(ptg::RANGE, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn primary(&mut self) -> ParseResult {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::String(value)) => {
+                if value.encode_utf16().count() > MAX_STRING_LEN {
+                    Err("string constant is longer than 255 characters".to_owned())
+                } else {
+                    Ok(Expr::String(value))
+                }
+            }
+            Some(Token::Bool(value)) => Ok(Expr::Bool(value)),
+            Some(Token::Error(value)) => Ok(Expr::Error(value)),
+            Some(Token::Ref(cell)) => Ok(Expr::Ref(None, cell)),
+            Some(Token::Area(first, last)) => Ok(Expr::Area(None, first, last)),
+            Some(Token::Sheet(sheet)) => match self.next() {
+                Some(Token::Ref(cell)) => Ok(Expr::Ref(Some(sheet), cell)),
+                Some(Token::Area(first, last)) => Ok(Expr::Area(Some(sheet), first, last)),
+                _ => Err(format!("expected reference after sheet {:?}", sheet)),
+            },
+            Some(Token::Name(name)) => Ok(Expr::Name(name)),
+            Some(Token::Function(name)) => self.function(&name),
+            Some(Token::Op("(")) => {
+                let mut expr = self.binary(0)?;
+                while self.is_op(",") {
+                    self.pos += 1;
+                    let right = self.binary(0)?;
+                    expr = Expr::Binary(ptg::UNION, Box::new(expr), Box::new(right));
+                }
+                self.expect_op(")")?;
+                Ok(Expr::Paren(Box::new(expr)))
+            }
+            Some(Token::Op("{")) => Err("array constants are not supported".to_owned()),
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("unexpected end of formula".to_owned()),
+        }
+    }
+
+    fn function(&mut self, name: &str) -> ParseResult {
+        let function = Function::by_name(name);
+        // other future functions, like `_xlfn.NEWFUNC`, are called by their names
+        if function.is_none() && !is_future_name(name) {
+            return Err(format!("unknown function {}", name));
+        }
+        self.expect_op("(")?;
+        let mut args = Vec::new();
+        if self.is_op(")") {
+            self.pos += 1;
+        } else {
+            loop {
+                if self.is_op(",") || self.is_op(")") {
+                    args.push(Expr::Missing);
+                } else {
+                    args.push(self.binary(0)?);
+                }
+                if self.is_op(",") {
+                    self.pos += 1;
+                } else {
+                    self.expect_op(")")?;
+                    break;
+                }
+            }
+        }
+        let Some(function) = function else {
+            // the name of the function takes one of 255 arguments
+            if args.len() >= u8::MAX as usize {
+                return Err(format!("function {} has too many arguments", name));
+            }
+            return Ok(Expr::UserFunction(name.to_owned(), args));
+        };
+        if !(function.min_args as usize..=function.max_args as usize).contains(&args.len()) {
+            return Err(format!(
+                "function {} takes from {} to {} arguments, but {} are given",
+                function.display_name(),
+                function.min_args,
+                function.max_args,
+                args.len()
+            ));
+        }
+        Ok(Expr::Function(function, args))
+    }
+}
+
+/// Writer of tokens, operands precede their operators
+struct Emitter<'a, C: FormulaContext> {
+    rgce: Vec<u8>,
+    context: &'a mut C,
+    /// First cell of the shared formula
+    base: Option<(u32, u16)>,
+    /// References without sheets are not allowed, as in formulas of defined names
+    qualified: bool,
+    volatile: bool,
+}
+
+impl<C: FormulaContext> Emitter<'_, C> {
+    fn emit(&mut self, expr: &Expr, class: Class) -> Result<()> {
+        match expr {
+            Expr::Number(value) => {
+                if value.fract() == 0.0 && (0.0..=u16::MAX as f64).contains(value) {
+                    self.rgce.push(ptg::INT);
+                    self.rgce.extend_from_slice(&(*value as u16).to_le_bytes());
+                } else {
+                    self.rgce.push(ptg::NUM);
+                    self.rgce.extend_from_slice(&value.to_le_bytes());
+                }
+            }
+            Expr::String(value) => {
+                let chars = value.encode_utf16().collect::<Vec<_>>();
+                self.rgce.push(ptg::STR);
+                self.rgce
+                    .extend_from_slice(&(chars.len() as u16).to_le_bytes());
+                self.rgce
+                    .extend(chars.into_iter().flat_map(|ch| ch.to_le_bytes()));
+            }
+            Expr::Bool(value) => self.rgce.extend_from_slice(&[ptg::BOOL, *value as u8]),
+            Expr::Error(value) => self.rgce.extend_from_slice(&[ptg::ERR, value.code()]),
+            Expr::Missing => self.rgce.push(ptg::MISS_ARG),
+            Expr::Ref(sheet, cell) => {
+                match sheet {
+                    Some(sheet) => {
+                        let ixti = self.context.sheet(sheet)?;
+                        self.rgce.push(ptg::with_class(ptg::REF_3D, class));
+                        self.rgce.extend_from_slice(&ixti.to_le_bytes());
+                    }
+                    None if self.qualified => return Err(unqualified()),
+                    None if self.base.is_some() => {
+                        self.rgce.push(ptg::with_class(ptg::REF_N, class))
+                    }
+                    None => self.rgce.push(ptg::with_class(ptg::REF, class)),
+                }
+                let (row, col) = ptg::loc(cell, self.base);
+                self.rgce.extend_from_slice(&row.to_le_bytes());
+                self.rgce.extend_from_slice(&col.to_le_bytes());
+            }
+            Expr::Area(sheet, first, last) => {
+                match sheet {
+                    Some(sheet) => {
+                        let ixti = self.context.sheet(sheet)?;
+                        self.rgce.push(ptg::with_class(ptg::AREA_3D, class));
+                        self.rgce.extend_from_slice(&ixti.to_le_bytes());
+                    }
+                    None if self.qualified => return Err(unqualified()),
+                    None if self.base.is_some() => {
+                        self.rgce.push(ptg::with_class(ptg::AREA_N, class))
+                    }
+                    None => self.rgce.push(ptg::with_class(ptg::AREA, class)),
+                }
+                // bounds are ordered, like Excel does it
+                let (first, last) = (
+                    CellRef {
+                        row: first.row.min(last.row),
+                        col: first.col.min(last.col),
+                        ..*first
+                    },
+                    CellRef {
+                        row: first.row.max(last.row),
+                        col: first.col.max(last.col),
+                        ..*last
+                    },
+                );
+                let (first_row, first_col) = ptg::loc(&first, self.base);
+                let (last_row, last_col) = ptg::loc(&last, self.base);
+                self.rgce.extend_from_slice(&first_row.to_le_bytes());
+                self.rgce.extend_from_slice(&last_row.to_le_bytes());
+                self.rgce.extend_from_slice(&first_col.to_le_bytes());
+                self.rgce.extend_from_slice(&last_col.to_le_bytes());
+            }
+            Expr::Name(name) => {
+                let index = self.context.name(name)?;
+                self.rgce.push(ptg::with_class(ptg::NAME, class));
+                self.rgce.extend_from_slice(&index.to_le_bytes());
+            }
+            Expr::Unary(op, operand) => {
+                self.emit(operand, operand_class(class))?;
+                self.rgce.push(*op);
+            }
+            Expr::Binary(op @ (ptg::ISECT | ptg::UNION | ptg::RANGE), left, right) => {
+                self.emit(left, Class::Ref)?;
+                self.emit(right, Class::Ref)?;
+                self.rgce.push(*op);
+            }
+            Expr::Binary(op, left, right) => {
+                self.emit(left, operand_class(class))?;
+                self.emit(right, operand_class(class))?;
+                self.rgce.push(*op);
+            }
+            Expr::Paren(inner) => {
+                self.emit(inner, class)?;
+                self.rgce.push(ptg::PAREN);
+            }
+            Expr::Function(function, args) => {
+                if function.is_future() {
+                    // the name of the function is the first argument
+                    let index = self.context.function(function.name)?;
+                    self.rgce.push(ptg::with_class(ptg::NAME, Class::Ref));
+                    self.rgce.extend_from_slice(&index.to_le_bytes());
+                }
+                for (idx, arg) in args.iter().enumerate() {
+                    let param = match function.param(idx) {
+                        Class::Value if class == Class::Array => Class::Array,
+                        param => param,
+                    };
+                    self.emit(arg, param)?;
+                }
+                let result = match (function.class, class) {
+                    (Class::Ref, Class::Ref) => Class::Ref,
+                    (_, Class::Array) | (Class::Array, _) => Class::Array,
+                    _ => Class::Value,
+                };
+                if function.is_fixed() {
+                    self.rgce.push(ptg::with_class(ptg::FUNC, result));
+                } else {
+                    self.rgce.push(ptg::with_class(ptg::FUNC_VAR, result));
+                    self.rgce
+                        .push(args.len() as u8 + function.is_future() as u8);
+                }
+                self.rgce.extend_from_slice(&function.iftab.to_le_bytes());
+                self.volatile |= function.is_volatile();
+            }
+            Expr::UserFunction(name, args) => {
+                let index = self.context.function(name)?;
+                self.rgce.push(ptg::with_class(ptg::NAME, Class::Ref));
+                self.rgce.extend_from_slice(&index.to_le_bytes());
+                for arg in args {
+                    self.emit(arg, Class::Ref)?;
+                }
+                self.rgce
+                    .push(ptg::with_class(ptg::FUNC_VAR, operand_class(class)));
+                self.rgce.push(args.len() as u8 + 1);
+                self.rgce
+                    .extend_from_slice(&ptg::IFTAB_USER_DEFINED.to_le_bytes());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Operands of arithmetic, comparison and concatenation operators are values, unless the
+/// whole expression is evaluated as an array
+fn operand_class(class: Class) -> Class {
+    match class {
+        Class::Array => Class::Array,
+        _ => Class::Value,
+    }
+}
+
+fn unqualified() -> Error {
+    Error::invalid_input("references of defined names must have sheets, like Data!$A$1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sheets and names are numbered in the order of their first use
+    #[derive(Default)]
+    struct Context {
+        sheets: Vec<String>,
+        names: Vec<String>,
+    }
+
+    impl FormulaContext for Context {
+        fn sheet(&mut self, name: &str) -> Result<u16> {
+            self.sheets.push(name.to_owned());
+            Ok(self.sheets.len() as u16 - 1)
+        }
+
+        fn name(&mut self, name: &str) -> Result<u32> {
+            self.names.push(name.to_owned());
+            Ok(self.names.len() as u32)
+        }
+
+        fn function(&mut self, name: &str) -> Result<u32> {
+            self.name(name)
+        }
+    }
+
+    #[test]
+    fn test_compile() {
+        let rgce = |text: &str| {
+            compile(text, FormulaType::Cell, &mut Context::default())
+                .unwrap()
+                .rgce
+        };
+        // PtgArea (reference), PtgFuncVar SUM, PtgInt 2, PtgMul
+        assert_eq!(
+            rgce("=SUM(A1:A10)*2"),
+            [0x25, 0, 0, 0, 0, 9, 0, 0, 0, 0, 0xC0, 0, 0xC0, 0x42, 1, 4, 0, 0x1E, 2, 0, 0x05]
+        );
+        // unary minus binds tighter than the power, PtgRef of the value class with $B absolute
+        assert_eq!(
+            rgce("-$B3^2"),
+            [0x44, 2, 0, 0, 0, 1, 0x80, 0x13, 0x1E, 2, 0, 0x07]
+        );
+        // comparison is the lowest, PtgStr, PtgBool, PtgFunc of the fixed function
+        assert_eq!(
+            rgce("LEN(\"ab\")+1>=TRUE"),
+            [0x17, 2, 0, b'a', 0, b'b', 0, 0x41, 32, 0, 0x1E, 1, 0, 0x03, 0x1D, 1, 0x0C]
+        );
+        // missing argument and 3D reference
+        let mut context = Context::default();
+        let formula = compile("IF(Data!B2,,0.5)", FormulaType::Cell, &mut context).unwrap();
+        assert_eq!(context.sheets, ["Data"]);
+        assert_eq!(
+            formula.rgce,
+            [
+                0x5A, 0, 0, 1, 0, 0, 0, 1, 0xC0, 0x16, 0x1F, 0, 0, 0, 0, 0, 0, 0xE0, 0x3F, 0x42, 3,
+                1, 0
+            ]
+        );
+        // volatile function, name and parentheses
+        let mut context = Context::default();
+        let formula = compile("(NOW()-Start)", FormulaType::Cell, &mut context).unwrap();
+        assert_eq!(context.names, ["Start"]);
+        assert_eq!(
+            formula.rgce,
+            [0x19, 0x01, 0, 0, 0x41, 74, 0, 0x43, 1, 0, 0, 0, 0x04, 0x15]
+        );
+        // intersection and union of references
+        assert_eq!(
+            rgce("SUM((A1,B1) A:A)"),
+            [
+                0x24, 0, 0, 0, 0, 0, 0xC0, 0x24, 0, 0, 0, 0, 1, 0xC0, 0x10, 0x15, 0x25, 0, 0, 0, 0,
+                0xFF, 0xFF, 0x0F, 0, 0, 0x40, 0, 0x40, 0x0F, 0x42, 1, 4, 0
+            ]
+        );
+        // shared formula of C2: PtgRefN with offsets (-1 row, -2 columns) and PtgAreaN with the
+        // absolute row
+        let formula = compile(
+            "A1+SUM(D$1:D2)",
+            FormulaType::Shared { row: 1, col: 2 },
+            &mut Context::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            formula.rgce,
+            [
+                0x4C, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, 0xFF, 0x2D, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x40, 1,
+                0xC0, 0x42, 1, 4, 0, 0x03
+            ]
+        );
+        // operands of the array formula are arrays
+        let formula = compile("A1:A3*2", FormulaType::Array, &mut Context::default()).unwrap();
+        assert_eq!(formula.rgce[0], 0x65);
+        // future function: PtgName of its hidden name, arguments, PtgFuncVar of the user
+        // defined function
+        let mut context = Context::default();
+        let formula = compile("=unique(A1:A3)", FormulaType::Array, &mut context).unwrap();
+        assert_eq!(context.names, ["_xlfn.UNIQUE"]);
+        assert_eq!(
+            formula.rgce,
+            [0x23, 1, 0, 0, 0, 0x25, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0xC0, 0, 0xC0, 0x62, 2, 0xFF, 0]
+        );
+        // unknown future function is called by its name
+        let mut context = Context::default();
+        let formula = compile("=_xlfn.NEWFUNC(1)", FormulaType::Cell, &mut context).unwrap();
+        assert_eq!(context.names, ["_xlfn.NEWFUNC"]);
+        assert_eq!(
+            formula.rgce,
+            [0x23, 1, 0, 0, 0, 0x1E, 1, 0, 0x42, 2, 0xFF, 0]
+        );
+
+        // references of defined names have the reference class
+        let formula = compile(
+            "Data!$A$1:$C$10",
+            FormulaType::Name,
+            &mut Context::default(),
+        );
+        assert_eq!(formula.unwrap().rgce[0], 0x3B);
+        assert!(compile("=$A$1", FormulaType::Name, &mut Context::default()).is_err());
+
+        for invalid in [
+            "",
+            "=SUM(A1",
+            "=FOO(1)",
+            "=_xlfn.(1)",
+            "=ROUND(1)",
+            "=1+",
+            "=A1 2",
+            "={1,2}",
+            "=Data!1",
+        ] {
+            assert!(
+                compile(invalid, FormulaType::Cell, &mut Context::default()).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+}
@@ -0,0 +1,672 @@
+//! Decompiler of parsed tokens (`Rgce`) into the formula text in A1 notation
+use super::{
+    compiler::FormulaType,
+    functions::{display_name, Function},
+    lexer::CellRef,
+    ptg,
+};
+use crate::{
+    core::biff::{records::ParsedFormula, MAX_COL, MAX_ROW},
+    range::column_name,
+    CellError, Error, Result,
+};
+
+/// Workbook-level objects, which are referenced by parsed formulas
+pub(crate) trait DecompileContext {
+    /// Sheet prefix of the 3D reference (without `!`) by the index in `BrtExternSheet`
+    fn sheet(&self, ixti: u16) -> Result<String>;
+
+    /// Defined name by its one-based index
+    fn name(&self, index: u32) -> Result<String>;
+
+    /// Name of the workbook or the add-in, referenced by the index in `BrtExternSheet`, with
+    /// its prefix
+    fn external_name(&self, ixti: u16, index: u32) -> Result<String>;
+
+    /// Name of the table and names of its columns by the id of the table (`idList`)
+    fn table(&self, id: u32) -> Result<(&str, &[String])>;
+}
+
+/// Render the formula tokens as the text without the leading `=`, like `SUM(A1:A10)*2`
+pub(crate) fn decompile(
+    formula: &ParsedFormula,
+    kind: FormulaType,
+    context: &impl DecompileContext,
+) -> Result<String> {
+    let mut tokens = Tokens {
+        data: &formula.rgce,
+        pos: 0,
+        base: kind.base(),
+    };
+    // extra data of tokens, in the order of the tokens
+    let mut extra = Tokens {
+        data: &formula.rgcb,
+        pos: 0,
+        base: None,
+    };
+    let mut stack = Vec::new();
+    while tokens.pos < formula.rgce.len() {
+        let ptg = tokens.u8()?;
+        match ptg {
+            ptg::ADD..=ptg::RANGE => {
+                let right = pop(&mut stack)?;
+                let left = pop(&mut stack)?;
+                stack.push(format!("{}{}{}", left, binary_operator(ptg), right));
+            }
+            ptg::UPLUS | ptg::UMINUS => {
+                let operand = pop(&mut stack)?;
+                let op = if ptg == ptg::UPLUS { '+' } else { '-' };
+                stack.push(format!("{}{}", op, operand));
+            }
+            ptg::PERCENT => {
+                let operand = pop(&mut stack)?;
+                stack.push(format!("{}%", operand));
+            }
+            ptg::PAREN => {
+                let operand = pop(&mut stack)?;
+                stack.push(format!("({})", operand));
+            }
+            ptg::MISS_ARG => stack.push(String::new()),
+            ptg::STR => stack.push(tokens.string()?),
+            ptg::EXTENDED => match tokens.u8()? {
+                ptg::EXTENDED_LIST => stack.push(list(&mut tokens, context)?),
+                eptg => return Err(unsupported_extended(eptg)),
+            },
+            ptg::ATTR => attr(&mut tokens, &mut stack)?,
+            ptg::ERR => stack.push(error_text(tokens.u8()?)?.to_owned()),
+            ptg::BOOL => stack.push(if tokens.u8()? != 0 { "TRUE" } else { "FALSE" }.to_owned()),
+            ptg::INT => stack.push(tokens.u16()?.to_string()),
+            ptg::NUM => stack.push(number_text(tokens.f64()?)),
+            0x20..=0x7F => operand(ptg & 0x1F, &mut tokens, &mut extra, &mut stack, context)?,
+            _ => return Err(unsupported(ptg)),
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(formula), true) => Ok(formula),
+        _ => Err(unbalanced()),
+    }
+}
+
+/// Sheet name for the 3D reference, which is quoted if it is not a plain identifier, like
+/// `'Q1 Sales'` or `'It''s'`. Both sheets of the range (`First:Last`) are quoted together.
+pub(crate) fn quote_sheet(sheet: &str) -> String {
+    if sheet.split(':').any(needs_quotes) {
+        format!("'{}'", sheet.replace('\'', "''"))
+    } else {
+        sheet.to_owned()
+    }
+}
+
+fn needs_quotes(sheet: &str) -> bool {
+    let starts_with_digit = sheet.chars().next().is_none_or(|ch| ch.is_ascii_digit());
+    let letters = sheet.chars().take_while(char::is_ascii_alphabetic).count();
+    let digits = &sheet[letters..];
+    // `A1` and `R1C1` would be read as references
+    let is_reference = (1..=3).contains(&letters)
+        && !digits.is_empty()
+        && digits.chars().all(|ch| ch.is_ascii_digit());
+    let is_r1c1 = sheet.starts_with(['R', 'r', 'C', 'c'])
+        && sheet[1..]
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || ch == 'C' || ch == 'c');
+    starts_with_digit
+        || is_reference
+        || is_r1c1
+        || !sheet
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '.')
+}
+
+/// Reader of token data
+struct Tokens<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// Cell of the shared formula, which relative references are offsets from
+    base: Option<(u32, u16)>,
+}
+
+impl Tokens<'_> {
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        match self.data.get(self.pos..self.pos + N) {
+            Some(bytes) => {
+                self.pos += N;
+                Ok(bytes.try_into().unwrap())
+            }
+            None => Err(Error::truncated(self.pos + N, self.data.len()).with_field("formula")),
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        self.bytes::<1>().map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        self.bytes().map(u16::from_le_bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        self.bytes().map(u32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        self.bytes().map(f64::from_le_bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        if self.pos + len > self.data.len() {
+            return Err(Error::truncated(self.pos + len, self.data.len()).with_field("formula"));
+        }
+        self.pos += len;
+        Ok(())
+    }
+
+    /// String literal with its `u16` length, in quotes
+    fn string(&mut self) -> Result<String> {
+        let cch = self.u16()? as usize;
+        let chars = (0..cch).map(|_| self.u16()).collect::<Result<Vec<_>>>()?;
+        let value = String::from_utf16_lossy(&chars);
+        Ok(format!("\"{}\"", value.replace('"', "\"\"")))
+    }
+
+    fn cell(&mut self) -> Result<CellRef> {
+        let row = self.u32()?;
+        Ok(ptg::cell_ref(row, self.u16()?, self.base))
+    }
+
+    fn area(&mut self) -> Result<(CellRef, CellRef)> {
+        let (row_first, row_last) = (self.u32()?, self.u32()?);
+        let (col_first, col_last) = (self.u16()?, self.u16()?);
+        Ok((
+            ptg::cell_ref(row_first, col_first, self.base),
+            ptg::cell_ref(row_last, col_last, self.base),
+        ))
+    }
+}
+
+/// Operand token without its class
+fn operand(
+    ptg: u8,
+    tokens: &mut Tokens,
+    extra: &mut Tokens,
+    stack: &mut Vec<String>,
+    context: &impl DecompileContext,
+) -> Result<()> {
+    let text = match ptg {
+        ptg::ARRAY => {
+            tokens.skip(14)?;
+            array(extra)?
+        }
+        ptg::FUNC => {
+            let iftab = tokens.u16()?;
+            let function = function(iftab)?;
+            let args = pop_args(stack, function.min_args as usize)?;
+            format!("{}({})", function.name, args.join(","))
+        }
+        ptg::FUNC_VAR => {
+            // high bits are flags: the prompt of the user (`fPrompt`) and the command (`fCeFunc`)
+            let argc = (tokens.u8()? & 0x7F) as usize;
+            let iftab = tokens.u16()? & 0x7FFF;
+            let mut args = pop_args(stack, argc)?;
+            if iftab == ptg::IFTAB_USER_DEFINED {
+                // the name of the function is the first argument
+                if args.is_empty() {
+                    return Err(unbalanced());
+                }
+                let name = args.remove(0);
+                // prefixes of unknown future functions are kept, so they can be written back
+                let name = match Function::by_name(&name) {
+                    Some(_) => display_name(&name),
+                    None => &name,
+                };
+                format!("{}({})", name, args.join(","))
+            } else {
+                format!("{}({})", function(iftab)?.name, args.join(","))
+            }
+        }
+        ptg::NAME => context.name(tokens.u32()?)?,
+        ptg::REF | ptg::REF_N => cell_text(&tokens.cell()?),
+        ptg::AREA | ptg::AREA_N => {
+            let (first, last) = tokens.area()?;
+            area_text(&first, &last)
+        }
+        ptg::MEM_AREA | ptg::MEM_ERR | ptg::MEM_NO_MEM => {
+            if ptg == ptg::MEM_AREA {
+                // the cached ranges (`PtgExtraMem`) are kept in the extra data
+                let count = extra.u32()? as usize;
+                extra.skip(count * 16)?;
+            }
+            // the cached result is followed by tokens of the reference expression
+            return tokens.skip(6);
+        }
+        ptg::MEM_FUNC => return tokens.skip(2),
+        ptg::REF_ERR => {
+            tokens.skip(6)?;
+            CellError::Ref.to_string()
+        }
+        ptg::AREA_ERR => {
+            tokens.skip(12)?;
+            CellError::Ref.to_string()
+        }
+        ptg::NAME_X => {
+            let ixti = tokens.u16()?;
+            context.external_name(ixti, tokens.u32()?)?
+        }
+        ptg::REF_3D => {
+            let sheet = context.sheet(tokens.u16()?)?;
+            format!("{}!{}", sheet, cell_text(&tokens.cell()?))
+        }
+        ptg::AREA_3D => {
+            let sheet = context.sheet(tokens.u16()?)?;
+            let (first, last) = tokens.area()?;
+            format!("{}!{}", sheet, area_text(&first, &last))
+        }
+        ptg::REF_ERR_3D | ptg::AREA_ERR_3D => {
+            let sheet = context.sheet(tokens.u16()?)?;
+            tokens.skip(if ptg == ptg::REF_ERR_3D { 6 } else { 12 })?;
+            format!("{}!{}", sheet, CellError::Ref)
+        }
+        _ => return Err(unsupported(ptg | 0x20)),
+    };
+    stack.push(text);
+    Ok(())
+}
+
+/// Array constant (`PtgExtraArray`), like `{1,2;"a",TRUE}`
+fn array(extra: &mut Tokens) -> Result<String> {
+    let rows = extra.u32()?;
+    let cols = extra.u32()?;
+    let mut text = String::from("{");
+    for row in 0..rows {
+        if row > 0 {
+            text.push(';');
+        }
+        for col in 0..cols {
+            if col > 0 {
+                text.push(',');
+            }
+            // `SerAr`: the type of the item and its value
+            match extra.u8()? {
+                0x00 => text.push_str(&number_text(extra.f64()?)),
+                0x01 => text.push_str(&extra.string()?),
+                0x02 => text.push_str(if extra.u8()? != 0 { "TRUE" } else { "FALSE" }),
+                0x04 => {
+                    text.push_str(error_text(extra.u8()?)?);
+                    extra.skip(3)?;
+                }
+                _ => return Err(Error::invalid_value().with_field("formula")),
+            }
+        }
+    }
+    text.push('}');
+    Ok(text)
+}
+
+/// Structured reference to the table (`PtgList`), like `Sales[[#Totals],[Amount]]`
+fn list(tokens: &mut Tokens, context: &impl DecompileContext) -> Result<String> {
+    // the workbook of the table is not needed, ids of tables are unique
+    tokens.skip(2)?;
+    let flags = tokens.u16()?;
+    let (name, columns) = context.table(tokens.u32()?)?;
+    let (col_first, col_last) = (tokens.u16()?, tokens.u16()?);
+    if flags & ptg::LIST_INVALID != 0 {
+        return Ok(CellError::Ref.to_string());
+    }
+    let column = |col: u16| match columns.get(col as usize) {
+        Some(column) => Ok(format!("[{}]", escape_column(column))),
+        None => Err(Error::malformed(format!(
+            "formula references unknown column {} of the table {}",
+            col, name
+        ))),
+    };
+    let mut items = match (flags >> 2) & 0x1F {
+        ptg::LIST_DEFAULT => vec![],
+        ptg::LIST_ALL => vec!["[#All]".to_owned()],
+        ptg::LIST_HEADERS => vec!["[#Headers]".to_owned()],
+        ptg::LIST_DATA => vec!["[#Data]".to_owned()],
+        ptg::LIST_HEADERS_DATA => vec!["[#Headers]".to_owned(), "[#Data]".to_owned()],
+        ptg::LIST_TOTALS => vec!["[#Totals]".to_owned()],
+        ptg::LIST_DATA_TOTALS => vec!["[#Data]".to_owned(), "[#Totals]".to_owned()],
+        ptg::LIST_THIS_ROW => vec!["[#This Row]".to_owned()],
+        _ => return Err(Error::invalid_value().with_field("formula")),
+    };
+    match flags & 0x03 {
+        0 => {}
+        1 => items.push(column(col_first)?),
+        _ => items.push(format!("{}:{}", column(col_first)?, column(col_last)?)),
+    }
+    Ok(match items.len() {
+        0 => format!("{}[]", name),
+        1 => format!("{}{}", name, items[0]),
+        _ => format!("{}[{}]", name, items.join(",")),
+    })
+}
+
+/// Column name of the structured reference, special characters are escaped with `'`
+fn escape_column(column: &str) -> String {
+    let mut escaped = String::with_capacity(column.len());
+    for ch in column.chars() {
+        if matches!(ch, '[' | ']' | '#' | '\'') {
+            escaped.push('\'');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// `PtgAttr` tokens, which only affect the calculation, except for `PtgAttrSum`
+fn attr(tokens: &mut Tokens, stack: &mut Vec<String>) -> Result<()> {
+    let kind = tokens.u8()?;
+    match kind {
+        ptg::ATTR_SUM => {
+            tokens.skip(2)?;
+            let operand = pop(stack)?;
+            stack.push(format!("SUM({})", operand));
+        }
+        ptg::ATTR_CHOOSE => {
+            let count = tokens.u16()? as usize;
+            tokens.skip((count + 1) * 2)?;
+        }
+        _ => tokens.skip(2)?,
+    }
+    Ok(())
+}
+
+fn function(iftab: u16) -> Result<&'static Function> {
+    Function::by_iftab(iftab)
+        .ok_or_else(|| Error::malformed(format!("formula calls unknown function {}", iftab)))
+}
+
+fn binary_operator(ptg: u8) -> &'static str {
+    match ptg {
+        ptg::ADD => "+",
+        ptg::SUB => "-",
+        ptg::MUL => "*",
+        ptg::DIV => "/",
+        ptg::POWER => "^",
+        ptg::CONCAT => "&",
+        ptg::LT => "<",
+        ptg::LE => "<=",
+        ptg::EQ => "=",
+        ptg::GE => ">=",
+        ptg::GT => ">",
+        ptg::NE => "<>",
+        ptg::ISECT => " ",
+        ptg::UNION => ",",
+        _ => ":",
+    }
+}
+
+fn error_text(code: u8) -> Result<&'static str> {
+    match CellError::from_code(code) {
+        Some(error) => Ok(error.as_str()),
+        None => Err(Error::invalid_value().with_field("formula")),
+    }
+}
+
+/// Reference in A1 notation, like `$B3`
+fn cell_text(cell: &CellRef) -> String {
+    format!(
+        "{}{}{}{}",
+        if cell.col_abs { "$" } else { "" },
+        column_name(cell.col),
+        if cell.row_abs { "$" } else { "" },
+        cell.row + 1
+    )
+}
+
+/// Range in A1 notation, whole columns and rows are written as `A:C` and `1:3`
+fn area_text(first: &CellRef, last: &CellRef) -> String {
+    let dollar = |abs: bool| if abs { "$" } else { "" };
+    if first.row == 0 && last.row == MAX_ROW {
+        format!(
+            "{}{}:{}{}",
+            dollar(first.col_abs),
+            column_name(first.col),
+            dollar(last.col_abs),
+            column_name(last.col)
+        )
+    } else if first.col == 0 && last.col as u32 == MAX_COL {
+        format!(
+            "{}{}:{}{}",
+            dollar(first.row_abs),
+            first.row + 1,
+            dollar(last.row_abs),
+            last.row + 1
+        )
+    } else {
+        format!("{}:{}", cell_text(first), cell_text(last))
+    }
+}
+
+/// Number in the form of Excel: up to 15 significant digits, and the exponent form for large
+/// and small magnitudes, like `1E+20` or `1.5E-07`
+pub(crate) fn number_text(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_owned();
+    }
+    let scientific = format!("{:.14E}", value);
+    let (mantissa, exp) = scientific.split_once('E').unwrap_or((&scientific, "0"));
+    let exp = exp.parse::<i32>().unwrap_or_default();
+    let trim = |text: &str| match text.contains('.') {
+        true => text.trim_end_matches('0').trim_end_matches('.').to_owned(),
+        false => text.to_owned(),
+    };
+    if (-5..15).contains(&exp) {
+        trim(&format!("{:.*}", (14 - exp) as usize, value))
+    } else {
+        let sign = if exp < 0 { '-' } else { '+' };
+        format!("{}E{}{:02}", trim(mantissa), sign, exp.abs())
+    }
+}
+
+fn pop(stack: &mut Vec<String>) -> Result<String> {
+    stack.pop().ok_or_else(unbalanced)
+}
+
+fn pop_args(stack: &mut Vec<String>, count: usize) -> Result<Vec<String>> {
+    match stack.len().checked_sub(count) {
+        Some(start) => Ok(stack.split_off(start)),
+        None => Err(unbalanced()),
+    }
+}
+
+fn unbalanced() -> Error {
+    Error::malformed("formula has unbalanced operands")
+}
+
+fn unsupported(ptg: u8) -> Error {
+    Error::malformed(format!("formula token {:#04X} is not supported", ptg))
+}
+
+fn unsupported_extended(eptg: u8) -> Error {
+    Error::malformed(format!(
+        "extended formula token {:#04X} is not supported",
+        eptg
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::{compile, FormulaContext};
+
+    /// Sheets and names are numbered in the order of their first use
+    #[derive(Default)]
+    struct Context {
+        sheets: Vec<String>,
+        names: Vec<String>,
+        columns: Vec<String>,
+    }
+
+    impl FormulaContext for Context {
+        fn sheet(&mut self, name: &str) -> Result<u16> {
+            self.sheets.push(name.to_owned());
+            Ok(self.sheets.len() as u16 - 1)
+        }
+
+        fn name(&mut self, name: &str) -> Result<u32> {
+            self.names.push(name.to_owned());
+            Ok(self.names.len() as u32)
+        }
+
+        fn function(&mut self, name: &str) -> Result<u32> {
+            FormulaContext::name(self, name)
+        }
+    }
+
+    impl DecompileContext for Context {
+        fn sheet(&self, ixti: u16) -> Result<String> {
+            Ok(quote_sheet(&self.sheets[ixti as usize]))
+        }
+
+        fn name(&self, index: u32) -> Result<String> {
+            Ok(self.names[index as usize - 1].clone())
+        }
+
+        fn external_name(&self, ixti: u16, index: u32) -> Result<String> {
+            Ok(format!(
+                "{}!{}",
+                self.sheets[ixti as usize],
+                self.name(index)?
+            ))
+        }
+
+        fn table(&self, id: u32) -> Result<(&str, &[String])> {
+            match id {
+                1 => Ok(("Sales", &self.columns)),
+                _ => Err(Error::malformed("unknown table")),
+            }
+        }
+    }
+
+    fn tokens(rgce: &[u8]) -> ParsedFormula {
+        ParsedFormula::new(rgce.to_vec(), Vec::new())
+    }
+
+    #[test]
+    fn test_decompile() {
+        for text in [
+            "SUM(A1:A10)*2",
+            "-$B3^2%",
+            "LEN(\"a\"\"b\")+1>=TRUE",
+            "IF(Data!B$2,,0.5)<>#N/A",
+            "(NOW()-Start)&'Q1 Sales'!$A:$C",
+            "SUM((A1,B1) 2:2)",
+            "'It''s'!A1+'A1'!B2",
+            "SORT(FILTER(A1:B9,A1:A9>0),2)",
+            "IFNA(VLOOKUP(A1,B1:C5,2,FALSE),\"\")&TEXTJOIN(\",\",TRUE,A1:A3)",
+            "_xlfn.NEWFUNC(A1,2)+MAXIFS(A1:A9,B1:B9,\">0\")",
+            "0.1+1E+20-1.5E-07*-123456.789",
+        ] {
+            let mut context = Context::default();
+            let formula = compile(text, FormulaType::Cell, &mut context).unwrap();
+            assert_eq!(
+                decompile(&formula, FormulaType::Cell, &context).unwrap(),
+                text
+            );
+        }
+        // shared formula of B1, rendered for B3
+        let mut context = Context::default();
+        let formula = compile(
+            "A1*$C$1+Data!A1",
+            FormulaType::Shared { row: 0, col: 1 },
+            &mut context,
+        )
+        .unwrap();
+        assert_eq!(
+            decompile(&formula, FormulaType::Shared { row: 2, col: 1 }, &context).unwrap(),
+            "A3*$C$1+Data!A3"
+        );
+
+        let context = Context::default();
+        // PtgAttrSum, PtgRefErr and PtgAttrSpace before PtgInt
+        assert_eq!(
+            decompile(
+                &tokens(&[
+                    0x2A, 0, 0, 0, 0, 0, 0, 0x19, 0x10, 0, 0, 0x19, 0x40, 0, 1, 0x1E, 1, 0, 0x03
+                ]),
+                FormulaType::Cell,
+                &context
+            )
+            .unwrap(),
+            "SUM(#REF!)+1"
+        );
+        // PtgFuncVar SUM with flags fPrompt and fCeFunc
+        assert_eq!(
+            decompile(
+                &tokens(&[0x1E, 1, 0, 0x42, 0x81, 4, 0x80]),
+                FormulaType::Cell,
+                &context
+            )
+            .unwrap(),
+            "SUM(1)"
+        );
+        assert_eq!(number_text(0.1 + 0.2), "0.3");
+        assert_eq!(number_text(123456789012345678.0), "1.23456789012346E+17");
+        assert_eq!(number_text(999999999999999.9), "1E+15");
+        assert_eq!(number_text(-0.00001), "-0.00001");
+        // user-defined function, called by the name
+        let context = Context {
+            names: vec!["MyFunc".to_owned()],
+            ..Context::default()
+        };
+        assert_eq!(
+            decompile(
+                &tokens(&[0x23, 1, 0, 0, 0, 0x1E, 7, 0, 0x42, 2, 0xFF, 0]),
+                FormulaType::Cell,
+                &context
+            )
+            .unwrap(),
+            "MyFunc(7)"
+        );
+        assert!(decompile(&tokens(&[0x03]), FormulaType::Cell, &context).is_err());
+        assert!(decompile(&tokens(&[0x1E, 1]), FormulaType::Cell, &context).is_err());
+        assert!(decompile(
+            &tokens(&[0x1E, 1, 0, 0x1E, 2, 0]),
+            FormulaType::Cell,
+            &context
+        )
+        .is_err());
+        assert!(decompile(&tokens(&[0x21, 53, 0]), FormulaType::Cell, &context).is_err());
+        assert!(decompile(&tokens(&[0x18, 0x19]), FormulaType::Cell, &context).is_err());
+
+        // PtgArray with `{1,"a";TRUE,#N/A}` in the extra data
+        let mut rgcb = vec![2, 0, 0, 0, 2, 0, 0, 0, 0x00];
+        rgcb.extend_from_slice(&1.0f64.to_le_bytes());
+        rgcb.extend_from_slice(&[0x01, 1, 0, b'a', 0, 0x02, 1, 0x04, 0x2A, 0, 0, 0]);
+        let mut rgce = vec![0x60];
+        rgce.extend_from_slice(&[0; 14]);
+        assert_eq!(
+            decompile(&ParsedFormula::new(rgce, rgcb), FormulaType::Cell, &context).unwrap(),
+            "{1,\"a\";TRUE,#N/A}"
+        );
+
+        // PtgList of the table 1 with the row type and columns
+        let list = |flags: u16, col_last: u16| {
+            let mut rgce = vec![0x18, 0x19, 0, 0];
+            rgce.extend_from_slice(&flags.to_le_bytes());
+            rgce.extend_from_slice(&1u32.to_le_bytes());
+            rgce.extend_from_slice(&0u16.to_le_bytes());
+            rgce.extend_from_slice(&col_last.to_le_bytes());
+            tokens(&rgce)
+        };
+        let context = Context {
+            columns: vec!["Region".to_owned(), "Q1 [EUR]".to_owned()],
+            ..Context::default()
+        };
+        for (flags, text) in [
+            (0x0000, "Sales[]"),
+            (0x0001, "Sales[Region]"),
+            (0x0008 | 0x0002, "Sales[[#Headers],[Region]:[Q1 '[EUR']]]"),
+            (0x0030 | 0x0001, "Sales[[#Data],[#Totals],[Region]]"),
+            (0x0040, "Sales[#This Row]"),
+            (0x1001, "#REF!"),
+        ] {
+            assert_eq!(
+                decompile(&list(flags, 1), FormulaType::Cell, &context).unwrap(),
+                text
+            );
+        }
+        assert!(decompile(&list(0x0002, 2), FormulaType::Cell, &context).is_err());
+    }
+}
@@ -0,0 +1,679 @@
+//! Built-in functions of the worksheet, which are stored by their index in the function
+//! table (`Ftab`)
+use super::ptg;
+
+/// Prefixes of names of future functions, which were added after the function table was fixed
+const FUTURE_PREFIXES: [&str; 2] = ["_xlfn.", "_xlws."];
+
+/// Name of the function without prefixes of future functions: `FILTER` for
+/// `_xlfn._xlws.FILTER`
+pub(crate) fn display_name(name: &str) -> &str {
+    FUTURE_PREFIXES
+        .iter()
+        .fold(name, |name, prefix| match name.get(..prefix.len()) {
+            Some(head) if head.eq_ignore_ascii_case(prefix) => &name[prefix.len()..],
+            _ => name,
+        })
+}
+
+/// Name has the prefix of future functions, like `_xlfn.NEWFUNC`
+pub(crate) fn is_future_name(name: &str) -> bool {
+    let display = display_name(name);
+    display.len() < name.len() && !display.is_empty()
+}
+
+/// Class of the operand or of the result of the function
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Class {
+    /// Reference to cells
+    Ref,
+    /// Single value, references are implicitly intersected with the formula cell
+    Value,
+    /// Array of values
+    Array,
+}
+
+/// Built-in function with its arguments
+#[derive(Debug)]
+pub(crate) struct Function {
+    /// Name of the function, future functions have prefixes, like `_xlfn.UNIQUE`
+    pub(crate) name: &'static str,
+    pub(crate) iftab: u16,
+    pub(crate) min_args: u8,
+    pub(crate) max_args: u8,
+    /// Class of the result
+    pub(crate) class: Class,
+    /// Classes of parameters, the last one is repeated
+    params: &'static [Class],
+}
+
+impl Function {
+    const fn new(
+        iftab: u16,
+        name: &'static str,
+        min_args: u8,
+        max_args: u8,
+        class: Class,
+        params: &'static [Class],
+    ) -> Self {
+        Function {
+            name,
+            iftab,
+            min_args,
+            max_args,
+            class,
+            params,
+        }
+    }
+
+    /// Find the function by its name (case-insensitive), prefixes of future functions are
+    /// optional
+    pub(crate) fn by_name(name: &str) -> Option<&'static Function> {
+        let name = display_name(name);
+        FUNCTIONS
+            .iter()
+            .chain(FUTURE_FUNCTIONS)
+            .find(|function| function.display_name().eq_ignore_ascii_case(name))
+    }
+
+    /// Find the function by its index in the function table
+    pub(crate) fn by_iftab(iftab: u16) -> Option<&'static Function> {
+        FUNCTIONS
+            .binary_search_by_key(&iftab, |function| function.iftab)
+            .ok()
+            .map(|idx| &FUNCTIONS[idx])
+    }
+
+    /// Name of the function, which is shown to the user
+    pub(crate) fn display_name(&self) -> &'static str {
+        display_name(self.name)
+    }
+
+    /// Function is not in the function table, it is called by the name, which is stored as the
+    /// hidden defined name
+    pub(crate) fn is_future(&self) -> bool {
+        self.iftab == ptg::IFTAB_USER_DEFINED
+    }
+
+    /// Number of arguments is fixed, the function is stored as `PtgFunc`, not as `PtgFuncVar`
+    pub(crate) fn is_fixed(&self) -> bool {
+        self.min_args == self.max_args && !self.is_future()
+    }
+
+    /// Function is recalculated on every change of the workbook
+    pub(crate) fn is_volatile(&self) -> bool {
+        // RAND, NOW, OFFSET, CELL, INDIRECT, TODAY, INFO, RANDBETWEEN
+        matches!(self.iftab, 63 | 74 | 78 | 125 | 148 | 221 | 244 | 464)
+            || self.name == "_xlfn.RANDARRAY"
+    }
+
+    /// Class of the parameter by its zero-based position
+    pub(crate) fn param(&self, idx: usize) -> Class {
+        self.params[idx.min(self.params.len() - 1)]
+    }
+}
+
+use Class::{Array as A, Ref as R, Value as V};
+
+const FUTURE: u16 = ptg::IFTAB_USER_DEFINED;
+
+/// Functions of worksheets, ordered by `iftab`; functions of macro sheets are omitted
+#[rustfmt::skip]
+static FUNCTIONS: &[Function] = &[
+    Function::new(0, "COUNT", 1, 255, V, &[R]),
+    Function::new(1, "IF", 2, 3, R, &[V, R]),
+    Function::new(2, "ISNA", 1, 1, V, &[V]),
+    Function::new(3, "ISERROR", 1, 1, V, &[V]),
+    Function::new(4, "SUM", 1, 255, V, &[R]),
+    Function::new(5, "AVERAGE", 1, 255, V, &[R]),
+    Function::new(6, "MIN", 1, 255, V, &[R]),
+    Function::new(7, "MAX", 1, 255, V, &[R]),
+    Function::new(8, "ROW", 0, 1, V, &[R]),
+    Function::new(9, "COLUMN", 0, 1, V, &[R]),
+    Function::new(10, "NA", 0, 0, V, &[V]),
+    Function::new(11, "NPV", 2, 255, V, &[V, R]),
+    Function::new(12, "STDEV", 1, 255, V, &[R]),
+    Function::new(13, "DOLLAR", 1, 2, V, &[V]),
+    Function::new(14, "FIXED", 1, 3, V, &[V]),
+    Function::new(15, "SIN", 1, 1, V, &[V]),
+    Function::new(16, "COS", 1, 1, V, &[V]),
+    Function::new(17, "TAN", 1, 1, V, &[V]),
+    Function::new(18, "ATAN", 1, 1, V, &[V]),
+    Function::new(19, "PI", 0, 0, V, &[V]),
+    Function::new(20, "SQRT", 1, 1, V, &[V]),
+    Function::new(21, "EXP", 1, 1, V, &[V]),
+    Function::new(22, "LN", 1, 1, V, &[V]),
+    Function::new(23, "LOG10", 1, 1, V, &[V]),
+    Function::new(24, "ABS", 1, 1, V, &[V]),
+    Function::new(25, "INT", 1, 1, V, &[V]),
+    Function::new(26, "SIGN", 1, 1, V, &[V]),
+    Function::new(27, "ROUND", 2, 2, V, &[V]),
+    Function::new(28, "LOOKUP", 2, 3, V, &[V, R]),
+    Function::new(29, "INDEX", 2, 4, R, &[R, V]),
+    Function::new(30, "REPT", 2, 2, V, &[V]),
+    Function::new(31, "MID", 3, 3, V, &[V]),
+    Function::new(32, "LEN", 1, 1, V, &[V]),
+    Function::new(33, "VALUE", 1, 1, V, &[V]),
+    Function::new(34, "TRUE", 0, 0, V, &[V]),
+    Function::new(35, "FALSE", 0, 0, V, &[V]),
+    Function::new(36, "AND", 1, 255, V, &[R]),
+    Function::new(37, "OR", 1, 255, V, &[R]),
+    Function::new(38, "NOT", 1, 1, V, &[V]),
+    Function::new(39, "MOD", 2, 2, V, &[V]),
+    Function::new(40, "DCOUNT", 3, 3, V, &[R]),
+    Function::new(41, "DSUM", 3, 3, V, &[R]),
+    Function::new(42, "DAVERAGE", 3, 3, V, &[R]),
+    Function::new(43, "DMIN", 3, 3, V, &[R]),
+    Function::new(44, "DMAX", 3, 3, V, &[R]),
+    Function::new(45, "DSTDEV", 3, 3, V, &[R]),
+    Function::new(46, "VAR", 1, 255, V, &[R]),
+    Function::new(47, "DVAR", 3, 3, V, &[R]),
+    Function::new(48, "TEXT", 2, 2, V, &[V]),
+    Function::new(49, "LINEST", 1, 4, A, &[R, R, V]),
+    Function::new(50, "TREND", 1, 4, A, &[R, R, R, V]),
+    Function::new(51, "LOGEST", 1, 4, A, &[R, R, V]),
+    Function::new(52, "GROWTH", 1, 4, A, &[R, R, R, V]),
+    Function::new(56, "PV", 3, 5, V, &[V]),
+    Function::new(57, "FV", 3, 5, V, &[V]),
+    Function::new(58, "NPER", 3, 5, V, &[V]),
+    Function::new(59, "PMT", 3, 5, V, &[V]),
+    Function::new(60, "RATE", 3, 6, V, &[V]),
+    Function::new(61, "MIRR", 3, 3, V, &[R, V]),
+    Function::new(62, "IRR", 1, 2, V, &[R, V]),
+    Function::new(63, "RAND", 0, 0, V, &[V]),
+    Function::new(64, "MATCH", 2, 3, V, &[V, R, V]),
+    Function::new(65, "DATE", 3, 3, V, &[V]),
+    Function::new(66, "TIME", 3, 3, V, &[V]),
+    Function::new(67, "DAY", 1, 1, V, &[V]),
+    Function::new(68, "MONTH", 1, 1, V, &[V]),
+    Function::new(69, "YEAR", 1, 1, V, &[V]),
+    Function::new(70, "WEEKDAY", 1, 2, V, &[V]),
+    Function::new(71, "HOUR", 1, 1, V, &[V]),
+    Function::new(72, "MINUTE", 1, 1, V, &[V]),
+    Function::new(73, "SECOND", 1, 1, V, &[V]),
+    Function::new(74, "NOW", 0, 0, V, &[V]),
+    Function::new(75, "AREAS", 1, 1, V, &[R]),
+    Function::new(76, "ROWS", 1, 1, V, &[R]),
+    Function::new(77, "COLUMNS", 1, 1, V, &[R]),
+    Function::new(78, "OFFSET", 3, 5, R, &[R, V]),
+    Function::new(82, "SEARCH", 2, 3, V, &[V]),
+    Function::new(83, "TRANSPOSE", 1, 1, A, &[A]),
+    Function::new(86, "TYPE", 1, 1, V, &[V]),
+    Function::new(97, "ATAN2", 2, 2, V, &[V]),
+    Function::new(98, "ASIN", 1, 1, V, &[V]),
+    Function::new(99, "ACOS", 1, 1, V, &[V]),
+    Function::new(100, "CHOOSE", 2, 255, R, &[V, R]),
+    Function::new(101, "HLOOKUP", 3, 4, V, &[V, R, R, V]),
+    Function::new(102, "VLOOKUP", 3, 4, V, &[V, R, R, V]),
+    Function::new(105, "ISREF", 1, 1, V, &[R]),
+    Function::new(109, "LOG", 1, 2, V, &[V]),
+    Function::new(111, "CHAR", 1, 1, V, &[V]),
+    Function::new(112, "LOWER", 1, 1, V, &[V]),
+    Function::new(113, "UPPER", 1, 1, V, &[V]),
+    Function::new(114, "PROPER", 1, 1, V, &[V]),
+    Function::new(115, "LEFT", 1, 2, V, &[V]),
+    Function::new(116, "RIGHT", 1, 2, V, &[V]),
+    Function::new(117, "EXACT", 2, 2, V, &[V]),
+    Function::new(118, "TRIM", 1, 1, V, &[V]),
+    Function::new(119, "REPLACE", 4, 4, V, &[V]),
+    Function::new(120, "SUBSTITUTE", 3, 4, V, &[V]),
+    Function::new(121, "CODE", 1, 1, V, &[V]),
+    Function::new(124, "FIND", 2, 3, V, &[V]),
+    Function::new(125, "CELL", 1, 2, V, &[V, R]),
+    Function::new(126, "ISERR", 1, 1, V, &[V]),
+    Function::new(127, "ISTEXT", 1, 1, V, &[V]),
+    Function::new(128, "ISNUMBER", 1, 1, V, &[V]),
+    Function::new(129, "ISBLANK", 1, 1, V, &[V]),
+    Function::new(130, "T", 1, 1, V, &[V]),
+    Function::new(131, "N", 1, 1, V, &[V]),
+    Function::new(140, "DATEVALUE", 1, 1, V, &[V]),
+    Function::new(141, "TIMEVALUE", 1, 1, V, &[V]),
+    Function::new(142, "SLN", 3, 3, V, &[V]),
+    Function::new(143, "SYD", 4, 4, V, &[V]),
+    Function::new(144, "DDB", 4, 5, V, &[V]),
+    Function::new(148, "INDIRECT", 1, 2, R, &[V]),
+    Function::new(162, "CLEAN", 1, 1, V, &[V]),
+    Function::new(163, "MDETERM", 1, 1, V, &[A]),
+    Function::new(164, "MINVERSE", 1, 1, A, &[A]),
+    Function::new(165, "MMULT", 2, 2, A, &[A]),
+    Function::new(167, "IPMT", 4, 6, V, &[V]),
+    Function::new(168, "PPMT", 4, 6, V, &[V]),
+    Function::new(169, "COUNTA", 1, 255, V, &[R]),
+    Function::new(183, "PRODUCT", 1, 255, V, &[R]),
+    Function::new(184, "FACT", 1, 1, V, &[V]),
+    Function::new(189, "DPRODUCT", 3, 3, V, &[R]),
+    Function::new(190, "ISNONTEXT", 1, 1, V, &[V]),
+    Function::new(193, "STDEVP", 1, 255, V, &[R]),
+    Function::new(194, "VARP", 1, 255, V, &[R]),
+    Function::new(195, "DSTDEVP", 3, 3, V, &[R]),
+    Function::new(196, "DVARP", 3, 3, V, &[R]),
+    Function::new(197, "TRUNC", 1, 2, V, &[V]),
+    Function::new(198, "ISLOGICAL", 1, 1, V, &[V]),
+    Function::new(199, "DCOUNTA", 3, 3, V, &[R]),
+    Function::new(204, "USDOLLAR", 1, 2, V, &[V]),
+    Function::new(205, "FINDB", 2, 3, V, &[V]),
+    Function::new(206, "SEARCHB", 2, 3, V, &[V]),
+    Function::new(207, "REPLACEB", 4, 4, V, &[V]),
+    Function::new(208, "LEFTB", 1, 2, V, &[V]),
+    Function::new(209, "RIGHTB", 1, 2, V, &[V]),
+    Function::new(210, "MIDB", 3, 3, V, &[V]),
+    Function::new(211, "LENB", 1, 1, V, &[V]),
+    Function::new(212, "ROUNDUP", 2, 2, V, &[V]),
+    Function::new(213, "ROUNDDOWN", 2, 2, V, &[V]),
+    Function::new(214, "ASC", 1, 1, V, &[V]),
+    Function::new(215, "DBCS", 1, 1, V, &[V]),
+    Function::new(216, "RANK", 2, 3, V, &[V, R, V]),
+    Function::new(219, "ADDRESS", 2, 5, V, &[V]),
+    Function::new(220, "DAYS360", 2, 3, V, &[V]),
+    Function::new(221, "TODAY", 0, 0, V, &[V]),
+    Function::new(222, "VDB", 5, 7, V, &[V]),
+    Function::new(227, "MEDIAN", 1, 255, V, &[R]),
+    Function::new(228, "SUMPRODUCT", 1, 255, V, &[A]),
+    Function::new(229, "SINH", 1, 1, V, &[V]),
+    Function::new(230, "COSH", 1, 1, V, &[V]),
+    Function::new(231, "TANH", 1, 1, V, &[V]),
+    Function::new(232, "ASINH", 1, 1, V, &[V]),
+    Function::new(233, "ACOSH", 1, 1, V, &[V]),
+    Function::new(234, "ATANH", 1, 1, V, &[V]),
+    Function::new(235, "DGET", 3, 3, V, &[R]),
+    Function::new(244, "INFO", 1, 1, V, &[V]),
+    Function::new(247, "DB", 4, 5, V, &[V]),
+    Function::new(252, "FREQUENCY", 2, 2, A, &[R]),
+    Function::new(261, "ERROR.TYPE", 1, 1, V, &[V]),
+    Function::new(269, "AVEDEV", 1, 255, V, &[R]),
+    Function::new(270, "BETADIST", 3, 5, V, &[V]),
+    Function::new(271, "GAMMALN", 1, 1, V, &[V]),
+    Function::new(272, "BETAINV", 3, 5, V, &[V]),
+    Function::new(273, "BINOMDIST", 4, 4, V, &[V]),
+    Function::new(274, "CHIDIST", 2, 2, V, &[V]),
+    Function::new(275, "CHIINV", 2, 2, V, &[V]),
+    Function::new(276, "COMBIN", 2, 2, V, &[V]),
+    Function::new(277, "CONFIDENCE", 3, 3, V, &[V]),
+    Function::new(278, "CRITBINOM", 3, 3, V, &[V]),
+    Function::new(279, "EVEN", 1, 1, V, &[V]),
+    Function::new(280, "EXPONDIST", 3, 3, V, &[V]),
+    Function::new(281, "FDIST", 3, 3, V, &[V]),
+    Function::new(282, "FINV", 3, 3, V, &[V]),
+    Function::new(283, "FISHER", 1, 1, V, &[V]),
+    Function::new(284, "FISHERINV", 1, 1, V, &[V]),
+    Function::new(285, "FLOOR", 2, 2, V, &[V]),
+    Function::new(286, "GAMMADIST", 4, 4, V, &[V]),
+    Function::new(287, "GAMMAINV", 3, 3, V, &[V]),
+    Function::new(288, "CEILING", 2, 2, V, &[V]),
+    Function::new(289, "HYPGEOMDIST", 4, 4, V, &[V]),
+    Function::new(290, "LOGNORMDIST", 3, 3, V, &[V]),
+    Function::new(291, "LOGINV", 3, 3, V, &[V]),
+    Function::new(292, "NEGBINOMDIST", 3, 3, V, &[V]),
+    Function::new(293, "NORMDIST", 4, 4, V, &[V]),
+    Function::new(294, "NORMSDIST", 1, 1, V, &[V]),
+    Function::new(295, "NORMINV", 3, 3, V, &[V]),
+    Function::new(296, "NORMSINV", 1, 1, V, &[V]),
+    Function::new(297, "STANDARDIZE", 3, 3, V, &[V]),
+    Function::new(298, "ODD", 1, 1, V, &[V]),
+    Function::new(299, "PERMUT", 2, 2, V, &[V]),
+    Function::new(300, "POISSON", 3, 3, V, &[V]),
+    Function::new(301, "TDIST", 3, 3, V, &[V]),
+    Function::new(302, "WEIBULL", 4, 4, V, &[V]),
+    Function::new(303, "SUMXMY2", 2, 2, V, &[A]),
+    Function::new(304, "SUMX2MY2", 2, 2, V, &[A]),
+    Function::new(305, "SUMX2PY2", 2, 2, V, &[A]),
+    Function::new(306, "CHITEST", 2, 2, V, &[A]),
+    Function::new(307, "CORREL", 2, 2, V, &[A]),
+    Function::new(308, "COVAR", 2, 2, V, &[A]),
+    Function::new(309, "FORECAST", 3, 3, V, &[V, A]),
+    Function::new(310, "FTEST", 2, 2, V, &[A]),
+    Function::new(311, "INTERCEPT", 2, 2, V, &[A]),
+    Function::new(312, "PEARSON", 2, 2, V, &[A]),
+    Function::new(313, "RSQ", 2, 2, V, &[A]),
+    Function::new(314, "STEYX", 2, 2, V, &[A]),
+    Function::new(315, "SLOPE", 2, 2, V, &[A]),
+    Function::new(316, "TTEST", 4, 4, V, &[A, A, V]),
+    Function::new(317, "PROB", 3, 4, V, &[A, A, V]),
+    Function::new(318, "DEVSQ", 1, 255, V, &[R]),
+    Function::new(319, "GEOMEAN", 1, 255, V, &[R]),
+    Function::new(320, "HARMEAN", 1, 255, V, &[R]),
+    Function::new(321, "SUMSQ", 1, 255, V, &[R]),
+    Function::new(322, "KURT", 1, 255, V, &[R]),
+    Function::new(323, "SKEW", 1, 255, V, &[R]),
+    Function::new(324, "ZTEST", 2, 3, V, &[R, V]),
+    Function::new(325, "LARGE", 2, 2, V, &[R, V]),
+    Function::new(326, "SMALL", 2, 2, V, &[R, V]),
+    Function::new(327, "QUARTILE", 2, 2, V, &[R, V]),
+    Function::new(328, "PERCENTILE", 2, 2, V, &[R, V]),
+    Function::new(329, "PERCENTRANK", 2, 3, V, &[R, V]),
+    Function::new(330, "MODE", 1, 255, V, &[A]),
+    Function::new(331, "TRIMMEAN", 2, 2, V, &[R, V]),
+    Function::new(332, "TINV", 2, 2, V, &[V]),
+    Function::new(336, "CONCATENATE", 1, 255, V, &[V]),
+    Function::new(337, "POWER", 2, 2, V, &[V]),
+    Function::new(342, "RADIANS", 1, 1, V, &[V]),
+    Function::new(343, "DEGREES", 1, 1, V, &[V]),
+    Function::new(344, "SUBTOTAL", 2, 255, V, &[V, R]),
+    Function::new(345, "SUMIF", 2, 3, V, &[R, V, R]),
+    Function::new(346, "COUNTIF", 2, 2, V, &[R, V]),
+    Function::new(347, "COUNTBLANK", 1, 1, V, &[R]),
+    Function::new(350, "ISPMT", 4, 4, V, &[V]),
+    Function::new(351, "DATEDIF", 3, 3, V, &[V]),
+    Function::new(352, "DATESTRING", 1, 1, V, &[V]),
+    Function::new(353, "NUMBERSTRING", 2, 2, V, &[V]),
+    Function::new(354, "ROMAN", 1, 2, V, &[V]),
+    Function::new(358, "GETPIVOTDATA", 2, 255, V, &[V, R, V]),
+    Function::new(359, "HYPERLINK", 1, 2, V, &[V]),
+    Function::new(360, "PHONETIC", 1, 1, V, &[R]),
+    Function::new(361, "AVERAGEA", 1, 255, V, &[R]),
+    Function::new(362, "MAXA", 1, 255, V, &[R]),
+    Function::new(363, "MINA", 1, 255, V, &[R]),
+    Function::new(364, "STDEVPA", 1, 255, V, &[R]),
+    Function::new(365, "VARPA", 1, 255, V, &[R]),
+    Function::new(366, "STDEVA", 1, 255, V, &[R]),
+    Function::new(367, "VARA", 1, 255, V, &[R]),
+    Function::new(368, "BAHTTEXT", 1, 1, V, &[V]),
+    Function::new(369, "THAIDAYOFWEEK", 1, 1, V, &[V]),
+    Function::new(370, "THAIDIGIT", 1, 1, V, &[V]),
+    Function::new(371, "THAIMONTHOFYEAR", 1, 1, V, &[V]),
+    Function::new(372, "THAINUMSOUND", 1, 1, V, &[V]),
+    Function::new(373, "THAINUMSTRING", 1, 1, V, &[V]),
+    Function::new(374, "THAISTRINGLENGTH", 1, 1, V, &[V]),
+    Function::new(375, "ISTHAIDIGIT", 1, 1, V, &[V]),
+    Function::new(376, "ROUNDBAHTDOWN", 1, 1, V, &[V]),
+    Function::new(377, "ROUNDBAHTUP", 1, 1, V, &[V]),
+    Function::new(378, "THAIYEAR", 1, 1, V, &[V]),
+    Function::new(379, "RTD", 3, 255, V, &[V]),
+    Function::new(380, "CUBEVALUE", 1, 255, V, &[V]),
+    Function::new(381, "CUBEMEMBER", 2, 3, V, &[V]),
+    Function::new(382, "CUBEMEMBERPROPERTY", 3, 3, V, &[V]),
+    Function::new(383, "CUBERANKEDMEMBER", 3, 4, V, &[V]),
+    Function::new(384, "HEX2BIN", 1, 2, V, &[V]),
+    Function::new(385, "HEX2DEC", 1, 1, V, &[V]),
+    Function::new(386, "HEX2OCT", 1, 2, V, &[V]),
+    Function::new(387, "DEC2BIN", 1, 2, V, &[V]),
+    Function::new(388, "DEC2HEX", 1, 2, V, &[V]),
+    Function::new(389, "DEC2OCT", 1, 2, V, &[V]),
+    Function::new(390, "OCT2BIN", 1, 2, V, &[V]),
+    Function::new(391, "OCT2HEX", 1, 2, V, &[V]),
+    Function::new(392, "OCT2DEC", 1, 1, V, &[V]),
+    Function::new(393, "BIN2DEC", 1, 1, V, &[V]),
+    Function::new(394, "BIN2OCT", 1, 2, V, &[V]),
+    Function::new(395, "BIN2HEX", 1, 2, V, &[V]),
+    Function::new(396, "IMSUB", 2, 2, V, &[V]),
+    Function::new(397, "IMDIV", 2, 2, V, &[V]),
+    Function::new(398, "IMPOWER", 2, 2, V, &[V]),
+    Function::new(399, "IMABS", 1, 1, V, &[V]),
+    Function::new(400, "IMSQRT", 1, 1, V, &[V]),
+    Function::new(401, "IMLN", 1, 1, V, &[V]),
+    Function::new(402, "IMLOG2", 1, 1, V, &[V]),
+    Function::new(403, "IMLOG10", 1, 1, V, &[V]),
+    Function::new(404, "IMSIN", 1, 1, V, &[V]),
+    Function::new(405, "IMCOS", 1, 1, V, &[V]),
+    Function::new(406, "IMEXP", 1, 1, V, &[V]),
+    Function::new(407, "IMARGUMENT", 1, 1, V, &[V]),
+    Function::new(408, "IMCONJUGATE", 1, 1, V, &[V]),
+    Function::new(409, "IMAGINARY", 1, 1, V, &[V]),
+    Function::new(410, "IMREAL", 1, 1, V, &[V]),
+    Function::new(411, "COMPLEX", 2, 3, V, &[V]),
+    Function::new(412, "IMSUM", 1, 255, V, &[R]),
+    Function::new(413, "IMPRODUCT", 1, 255, V, &[R]),
+    Function::new(414, "SERIESSUM", 4, 4, V, &[V, V, V, R]),
+    Function::new(415, "FACTDOUBLE", 1, 1, V, &[V]),
+    Function::new(416, "SQRTPI", 1, 1, V, &[V]),
+    Function::new(417, "QUOTIENT", 2, 2, V, &[V]),
+    Function::new(418, "DELTA", 1, 2, V, &[V]),
+    Function::new(419, "GESTEP", 1, 2, V, &[V]),
+    Function::new(420, "ISEVEN", 1, 1, V, &[V]),
+    Function::new(421, "ISODD", 1, 1, V, &[V]),
+    Function::new(422, "MROUND", 2, 2, V, &[V]),
+    Function::new(423, "ERF", 1, 2, V, &[V]),
+    Function::new(424, "ERFC", 1, 1, V, &[V]),
+    Function::new(425, "BESSELJ", 2, 2, V, &[V]),
+    Function::new(426, "BESSELK", 2, 2, V, &[V]),
+    Function::new(427, "BESSELY", 2, 2, V, &[V]),
+    Function::new(428, "BESSELI", 2, 2, V, &[V]),
+    Function::new(429, "XIRR", 2, 3, V, &[R, R, V]),
+    Function::new(430, "XNPV", 3, 3, V, &[V, R]),
+    Function::new(431, "PRICEMAT", 5, 6, V, &[V]),
+    Function::new(432, "YIELDMAT", 5, 6, V, &[V]),
+    Function::new(433, "INTRATE", 4, 5, V, &[V]),
+    Function::new(434, "RECEIVED", 4, 5, V, &[V]),
+    Function::new(435, "DISC", 4, 5, V, &[V]),
+    Function::new(436, "PRICEDISC", 4, 5, V, &[V]),
+    Function::new(437, "YIELDDISC", 4, 5, V, &[V]),
+    Function::new(438, "TBILLEQ", 3, 3, V, &[V]),
+    Function::new(439, "TBILLPRICE", 3, 3, V, &[V]),
+    Function::new(440, "TBILLYIELD", 3, 3, V, &[V]),
+    Function::new(441, "PRICE", 6, 7, V, &[V]),
+    Function::new(442, "YIELD", 6, 7, V, &[V]),
+    Function::new(443, "DOLLARDE", 2, 2, V, &[V]),
+    Function::new(444, "DOLLARFR", 2, 2, V, &[V]),
+    Function::new(445, "NOMINAL", 2, 2, V, &[V]),
+    Function::new(446, "EFFECT", 2, 2, V, &[V]),
+    Function::new(447, "CUMPRINC", 6, 6, V, &[V]),
+    Function::new(448, "CUMIPMT", 6, 6, V, &[V]),
+    Function::new(449, "EDATE", 2, 2, V, &[V]),
+    Function::new(450, "EOMONTH", 2, 2, V, &[V]),
+    Function::new(451, "YEARFRAC", 2, 3, V, &[V]),
+    Function::new(452, "COUPDAYBS", 3, 4, V, &[V]),
+    Function::new(453, "COUPDAYS", 3, 4, V, &[V]),
+    Function::new(454, "COUPDAYSNC", 3, 4, V, &[V]),
+    Function::new(455, "COUPNCD", 3, 4, V, &[V]),
+    Function::new(456, "COUPNUM", 3, 4, V, &[V]),
+    Function::new(457, "COUPPCD", 3, 4, V, &[V]),
+    Function::new(458, "DURATION", 5, 6, V, &[V]),
+    Function::new(459, "MDURATION", 5, 6, V, &[V]),
+    Function::new(460, "ODDLPRICE", 7, 8, V, &[V]),
+    Function::new(461, "ODDLYIELD", 7, 8, V, &[V]),
+    Function::new(462, "ODDFPRICE", 8, 9, V, &[V]),
+    Function::new(463, "ODDFYIELD", 8, 9, V, &[V]),
+    Function::new(464, "RANDBETWEEN", 2, 2, V, &[V]),
+    Function::new(465, "WEEKNUM", 1, 2, V, &[V]),
+    Function::new(466, "AMORDEGRC", 6, 7, V, &[V]),
+    Function::new(467, "AMORLINC", 6, 7, V, &[V]),
+    Function::new(468, "CONVERT", 3, 3, V, &[V]),
+    Function::new(469, "ACCRINT", 6, 8, V, &[V]),
+    Function::new(470, "ACCRINTM", 4, 5, V, &[V]),
+    Function::new(471, "WORKDAY", 2, 3, V, &[V, V, R]),
+    Function::new(472, "NETWORKDAYS", 2, 3, V, &[V, V, R]),
+    Function::new(473, "GCD", 1, 255, V, &[R]),
+    Function::new(474, "MULTINOMIAL", 1, 255, V, &[R]),
+    Function::new(475, "LCM", 1, 255, V, &[R]),
+    Function::new(476, "FVSCHEDULE", 2, 2, V, &[V, R]),
+    Function::new(477, "CUBEKPIMEMBER", 3, 4, V, &[V]),
+    Function::new(478, "CUBESET", 2, 5, V, &[V]),
+    Function::new(479, "CUBESETCOUNT", 1, 1, V, &[V]),
+    Function::new(480, "IFERROR", 2, 2, V, &[V]),
+    Function::new(481, "COUNTIFS", 2, 254, V, &[R]),
+    Function::new(482, "SUMIFS", 3, 255, V, &[R]),
+    Function::new(483, "AVERAGEIF", 2, 3, V, &[R, V, R]),
+    Function::new(484, "AVERAGEIFS", 3, 255, V, &[R]),
+];
+
+/// Future functions, which were added since Excel 2010, and functions, which return dynamic
+/// arrays or work with them
+#[rustfmt::skip]
+static FUTURE_FUNCTIONS: &[Function] = &[
+    // Excel 2010
+    Function::new(FUTURE, "_xlfn.AGGREGATE", 3, 254, V, &[V, V, R]),
+    Function::new(FUTURE, "_xlfn.BETA.DIST", 4, 6, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BETA.INV", 3, 5, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BINOM.DIST", 4, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BINOM.INV", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CEILING.PRECISE", 1, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CHISQ.DIST", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CHISQ.DIST.RT", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CHISQ.INV", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CHISQ.INV.RT", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CHISQ.TEST", 2, 2, V, &[R]),
+    Function::new(FUTURE, "_xlfn.CONFIDENCE.NORM", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CONFIDENCE.T", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.COVARIANCE.P", 2, 2, V, &[R]),
+    Function::new(FUTURE, "_xlfn.COVARIANCE.S", 2, 2, V, &[R]),
+    Function::new(FUTURE, "_xlfn.ERF.PRECISE", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.ERFC.PRECISE", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.EXPON.DIST", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.F.DIST", 4, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.F.DIST.RT", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.F.INV", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.F.INV.RT", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.F.TEST", 2, 2, V, &[R]),
+    Function::new(FUTURE, "_xlfn.FLOOR.PRECISE", 1, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.GAMMA.DIST", 4, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.GAMMA.INV", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.GAMMALN.PRECISE", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.HYPGEOM.DIST", 5, 5, V, &[V]),
+    Function::new(FUTURE, "_xlfn.ISO.CEILING", 1, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.LOGNORM.DIST", 4, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.LOGNORM.INV", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.MODE.MULT", 1, 254, A, &[R]),
+    Function::new(FUTURE, "_xlfn.MODE.SNGL", 1, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.NEGBINOM.DIST", 4, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.NETWORKDAYS.INTL", 2, 4, V, &[V, V, V, R]),
+    Function::new(FUTURE, "_xlfn.NORM.DIST", 4, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.NORM.INV", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.NORM.S.DIST", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.NORM.S.INV", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.PERCENTILE.EXC", 2, 2, V, &[R, V]),
+    Function::new(FUTURE, "_xlfn.PERCENTILE.INC", 2, 2, V, &[R, V]),
+    Function::new(FUTURE, "_xlfn.PERCENTRANK.EXC", 2, 3, V, &[R, V]),
+    Function::new(FUTURE, "_xlfn.PERCENTRANK.INC", 2, 3, V, &[R, V]),
+    Function::new(FUTURE, "_xlfn.POISSON.DIST", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.QUARTILE.EXC", 2, 2, V, &[R, V]),
+    Function::new(FUTURE, "_xlfn.QUARTILE.INC", 2, 2, V, &[R, V]),
+    Function::new(FUTURE, "_xlfn.RANK.AVG", 2, 3, V, &[V, R, V]),
+    Function::new(FUTURE, "_xlfn.RANK.EQ", 2, 3, V, &[V, R, V]),
+    Function::new(FUTURE, "_xlfn.STDEV.P", 1, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.STDEV.S", 1, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.T.DIST", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.T.DIST.2T", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.T.DIST.RT", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.T.INV", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.T.INV.2T", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.T.TEST", 4, 4, V, &[R, R, V]),
+    Function::new(FUTURE, "_xlfn.VAR.P", 1, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.VAR.S", 1, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.WEIBULL.DIST", 4, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.WORKDAY.INTL", 2, 4, V, &[V, V, V, R]),
+    Function::new(FUTURE, "_xlfn.Z.TEST", 2, 3, V, &[R, V]),
+    // Excel 2013
+    Function::new(FUTURE, "_xlfn.ACOT", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.ACOTH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.ARABIC", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BASE", 2, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BINOM.DIST.RANGE", 3, 4, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BITAND", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BITLSHIFT", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BITOR", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BITRSHIFT", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.BITXOR", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CEILING.MATH", 1, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.COMBINA", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.COT", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.COTH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CSC", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.CSCH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.DAYS", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.DECIMAL", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.ENCODEURL", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.FILTERXML", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.FLOOR.MATH", 1, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.FORMULATEXT", 1, 1, V, &[R]),
+    Function::new(FUTURE, "_xlfn.GAMMA", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.GAUSS", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IFNA", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMCOSH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMCOT", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMCSC", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMCSCH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMSEC", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMSECH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMSINH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.IMTAN", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.ISFORMULA", 1, 1, V, &[R]),
+    Function::new(FUTURE, "_xlfn.ISOWEEKNUM", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.MUNIT", 1, 1, A, &[V]),
+    Function::new(FUTURE, "_xlfn.NUMBERVALUE", 1, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.PDURATION", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.PERMUTATIONA", 2, 2, V, &[V]),
+    Function::new(FUTURE, "_xlfn.PHI", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.RRI", 3, 3, V, &[V]),
+    Function::new(FUTURE, "_xlfn.SEC", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.SECH", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.SHEET", 0, 1, V, &[R]),
+    Function::new(FUTURE, "_xlfn.SHEETS", 0, 1, V, &[R]),
+    Function::new(FUTURE, "_xlfn.SKEW.P", 1, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.UNICHAR", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.UNICODE", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.WEBSERVICE", 1, 1, V, &[V]),
+    Function::new(FUTURE, "_xlfn.XOR", 1, 254, V, &[R]),
+    // Excel 2016
+    Function::new(FUTURE, "_xlfn.FORECAST.ETS", 3, 6, V, &[V, R, R, V]),
+    Function::new(FUTURE, "_xlfn.FORECAST.ETS.CONFINT", 3, 7, V, &[V, R, R, V]),
+    Function::new(FUTURE, "_xlfn.FORECAST.ETS.SEASONALITY", 2, 4, V, &[R, R, V]),
+    Function::new(FUTURE, "_xlfn.FORECAST.ETS.STAT", 3, 6, V, &[R, R, V]),
+    Function::new(FUTURE, "_xlfn.FORECAST.LINEAR", 3, 3, V, &[V, R, R]),
+    // Excel 2019
+    Function::new(FUTURE, "_xlfn.CONCAT", 1, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.IFS", 2, 254, V, &[V]),
+    Function::new(FUTURE, "_xlfn.MAXIFS", 3, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.MINIFS", 3, 254, V, &[R]),
+    Function::new(FUTURE, "_xlfn.SWITCH", 3, 254, V, &[V]),
+    Function::new(FUTURE, "_xlfn.TEXTJOIN", 3, 254, V, &[V, V, R]),
+    // dynamic arrays
+    Function::new(FUTURE, "_xlfn._xlws.FILTER", 2, 3, A, &[R, A, V]),
+    Function::new(FUTURE, "_xlfn._xlws.SORT", 1, 4, A, &[R, V]),
+    Function::new(FUTURE, "_xlfn.SORTBY", 2, 254, A, &[R, R, V]),
+    Function::new(FUTURE, "_xlfn.UNIQUE", 1, 3, A, &[R, V]),
+    Function::new(FUTURE, "_xlfn.SEQUENCE", 1, 4, A, &[V]),
+    Function::new(FUTURE, "_xlfn.RANDARRAY", 0, 5, A, &[V]),
+    Function::new(FUTURE, "_xlfn.XLOOKUP", 3, 6, R, &[V, R, R, V]),
+    Function::new(FUTURE, "_xlfn.XMATCH", 2, 4, V, &[V, R, V]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_functions() {
+        assert!(FUNCTIONS
+            .windows(2)
+            .all(|pair| pair[0].iftab < pair[1].iftab));
+        assert!(FUNCTIONS
+            .iter()
+            .all(|function| function.min_args <= function.max_args && !function.params.is_empty()));
+
+        let sum = Function::by_name("sum").unwrap();
+        assert_eq!(sum.iftab, 4);
+        assert!(!sum.is_fixed());
+        assert_eq!(sum.param(10), Class::Ref);
+        let vlookup = Function::by_iftab(102).unwrap();
+        assert_eq!(vlookup.name, "VLOOKUP");
+        assert_eq!(
+            (0..4).map(|idx| vlookup.param(idx)).collect::<Vec<_>>(),
+            [Class::Value, Class::Ref, Class::Ref, Class::Value]
+        );
+        assert!(Function::by_name("NOW").unwrap().is_volatile());
+        assert!(Function::by_name("GOTO").is_none());
+        assert!(Function::by_iftab(53).is_none());
+
+        let filter = Function::by_name("Filter").unwrap();
+        assert_eq!(filter.name, "_xlfn._xlws.FILTER");
+        assert_eq!(filter.display_name(), "FILTER");
+        assert_eq!(Function::by_name("_xlfn.UNIQUE").unwrap().min_args, 1);
+        assert!(!Function::by_name("XMATCH").unwrap().is_fixed());
+        assert!(Function::by_name("RANDARRAY").unwrap().is_volatile());
+        assert_eq!(display_name("_XLFN.CONCAT"), "CONCAT");
+        for name in [
+            "IFNA",
+            "_xlfn.STDEV.S",
+            "textjoin",
+            "MaxIfs",
+            "FORECAST.LINEAR",
+        ] {
+            assert!(Function::by_name(name).unwrap().is_future(), "{}", name);
+        }
+        assert!(FUTURE_FUNCTIONS.iter().all(|function| function.is_future()
+            && function.min_args <= function.max_args
+            && function.max_args < u8::MAX
+            && Function::by_name(function.display_name())
+                .is_some_and(|other| other.name == function.name)));
+    }
+}
@@ -0,0 +1,411 @@
+//! Tokenizer of the formula text in A1 notation
+use crate::{
+    core::biff::{MAX_COL, MAX_ROW},
+    CellError, Error, Result,
+};
+
+/// Reference to the cell, `$` makes the row or the column absolute
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CellRef {
+    pub(crate) row: u32,
+    pub(crate) col: u16,
+    pub(crate) row_abs: bool,
+    pub(crate) col_abs: bool,
+}
+
+impl CellRef {
+    pub(crate) fn new(row: u32, col: u16, row_abs: bool, col_abs: bool) -> Self {
+        CellRef {
+            row,
+            col,
+            row_abs,
+            col_abs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Token {
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Error(CellError),
+    Ref(CellRef),
+    /// Range of cells, whole columns and rows are expanded to the sheet bounds
+    Area(CellRef, CellRef),
+    /// Name of the sheet before `!`
+    Sheet(String),
+    /// Defined name
+    Name(String),
+    /// Name of the function, which is followed by `(`
+    Function(String),
+    /// Operator or punctuation, like `<=`, `(` or `,`
+    Op(&'static str),
+}
+
+/// Token and whether it is preceded by spaces (the intersection operator)
+pub(super) type Spanned = (Token, bool);
+
+const OPERATORS: [&str; 19] = [
+    "<=", ">=", "<>", "+", "-", "*", "/", "^", "&", "=", "<", ">", "%", ":", ",", "(", ")", "{",
+    "}",
+];
+
+const ERRORS: [CellError; 8] = [
+    CellError::Null,
+    CellError::Div0,
+    CellError::Value,
+    CellError::Ref,
+    CellError::Name,
+    CellError::Num,
+    CellError::NA,
+    CellError::GettingData,
+];
+
+/// Split the formula text (without the leading `=`) into tokens
+pub(super) fn tokenize(text: &str) -> Result<Vec<Spanned>> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut lexer = Lexer { chars, pos: 0 };
+    let mut tokens = Vec::new();
+    loop {
+        let space = lexer.skip_spaces();
+        if lexer.pos == lexer.chars.len() {
+            return Ok(tokens);
+        }
+        let token = lexer.next_token()?;
+        tokens.push((token, space));
+    }
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn error(&self, reason: &str) -> Error {
+        Error::invalid_input(format!("{} at position {}", reason, self.pos))
+    }
+
+    fn skip_spaces(&mut self) -> bool {
+        let start = self.pos;
+        while self.peek(0).is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn next_token(&mut self) -> Result<Token> {
+        let ch = self.peek(0).unwrap_or_default();
+        if ch == '"' {
+            return self.quoted('"').map(Token::String);
+        }
+        if ch == '\'' {
+            let sheet = self.quoted('\'')?;
+            return match self.peek(0) {
+                Some('!') => {
+                    self.pos += 1;
+                    Ok(Token::Sheet(sheet))
+                }
+                _ => Err(self.error("quoted sheet name must be followed by '!'")),
+            };
+        }
+        if ch == '#' {
+            return self.error_literal();
+        }
+        if let Some(token) = self.reference() {
+            return Ok(token);
+        }
+        if ch.is_ascii_digit() || (ch == '.' && self.peek(1).is_some_and(|ch| ch.is_ascii_digit()))
+        {
+            return self.number();
+        }
+        if is_name_start(ch) {
+            return Ok(self.word());
+        }
+        let rest = self.chars[self.pos..].iter().take(2).collect::<String>();
+        match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            Some(op) => {
+                self.pos += op.len();
+                Ok(Token::Op(op))
+            }
+            None => Err(self.error(&format!("unexpected character {:?}", ch))),
+        }
+    }
+
+    /// String or sheet name between quotes, which are escaped by doubling
+    fn quoted(&mut self, quote: char) -> Result<String> {
+        let mut value = String::new();
+        self.pos += 1;
+        loop {
+            match self.peek(0) {
+                Some(ch) if ch == quote && self.peek(1) == Some(quote) => {
+                    value.push(quote);
+                    self.pos += 2;
+                }
+                Some(ch) if ch == quote => {
+                    self.pos += 1;
+                    return Ok(value);
+                }
+                Some(ch) => {
+                    value.push(ch);
+                    self.pos += 1;
+                }
+                None => return Err(self.error("unterminated quoted text")),
+            }
+        }
+    }
+
+    fn error_literal(&mut self) -> Result<Token> {
+        for error in ERRORS {
+            let text = error.as_str();
+            let len = text.chars().count();
+            let matches = self.chars[self.pos..]
+                .iter()
+                .take(len)
+                .collect::<String>()
+                .eq_ignore_ascii_case(text);
+            if matches {
+                self.pos += len;
+                return Ok(Token::Error(error));
+            }
+        }
+        Err(self.error("unknown error value"))
+    }
+
+    fn number(&mut self) -> Result<Token> {
+        let start = self.pos;
+        let digits = |lexer: &mut Lexer| {
+            while lexer.peek(0).is_some_and(|ch| ch.is_ascii_digit()) {
+                lexer.pos += 1;
+            }
+        };
+        digits(self);
+        if self.peek(0) == Some('.') {
+            self.pos += 1;
+            digits(self);
+        }
+        if matches!(self.peek(0), Some('e' | 'E')) {
+            let sign = matches!(self.peek(1), Some('+' | '-')) as usize;
+            if self.peek(1 + sign).is_some_and(|ch| ch.is_ascii_digit()) {
+                self.pos += 1 + sign;
+                digits(self);
+            }
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        match text.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Token::Number(value)),
+            _ => Err(self.error(&format!("invalid number {:?}", text))),
+        }
+    }
+
+    /// Identifier: the function, the sheet, the boolean or the defined name
+    fn word(&mut self) -> Token {
+        let start = self.pos;
+        while self.peek(0).is_some_and(is_name_char) {
+            self.pos += 1;
+        }
+        let word = self.chars[start..self.pos].iter().collect::<String>();
+        match self.peek(0) {
+            Some('!') => {
+                self.pos += 1;
+                Token::Sheet(word)
+            }
+            Some('(') => Token::Function(word),
+            _ if word.eq_ignore_ascii_case("TRUE") => Token::Bool(true),
+            _ if word.eq_ignore_ascii_case("FALSE") => Token::Bool(false),
+            _ => Token::Name(word),
+        }
+    }
+
+    /// Cell (`B$2`), range (`A1:C3`), column range (`A:$C`) or row range (`1:$3`), which is not
+    /// a part of the longer identifier
+    fn reference(&mut self) -> Option<Token> {
+        let start = self.pos;
+        let token = self.scan_reference();
+        let complete = !self
+            .peek(0)
+            .is_some_and(|ch| is_name_char(ch) || ch == '(' || ch == '!');
+        match token {
+            Some(token) if complete => Some(token),
+            _ => {
+                self.pos = start;
+                None
+            }
+        }
+    }
+
+    fn scan_reference(&mut self) -> Option<Token> {
+        let (col, row) = (self.column(), self.row());
+        match (col, row) {
+            (Some((col, col_abs)), Some((row, row_abs))) => {
+                let first = CellRef::new(row, col, row_abs, col_abs);
+                let start = self.pos;
+                if self.peek(0) == Some(':') {
+                    self.pos += 1;
+                    if let (Some((col, col_abs)), Some((row, row_abs))) =
+                        (self.column(), self.row())
+                    {
+                        let last = CellRef::new(row, col, row_abs, col_abs);
+                        return Some(Token::Area(first, last));
+                    }
+                }
+                self.pos = start;
+                Some(Token::Ref(first))
+            }
+            (Some((first, first_abs)), None) => {
+                self.expect(':')?;
+                let (last, last_abs) = self.column()?;
+                Some(Token::Area(
+                    CellRef::new(0, first, true, first_abs),
+                    CellRef::new(MAX_ROW, last, true, last_abs),
+                ))
+            }
+            (None, Some((first, first_abs))) => {
+                self.expect(':')?;
+                let (last, last_abs) = self.row()?;
+                Some(Token::Area(
+                    CellRef::new(first, 0, first_abs, true),
+                    CellRef::new(last, MAX_COL as u16, last_abs, true),
+                ))
+            }
+            (None, None) => None,
+        }
+    }
+
+    fn expect(&mut self, ch: char) -> Option<()> {
+        if self.peek(0) == Some(ch) {
+            self.pos += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Column letters with the optional `$`, the position is restored if there are none
+    fn column(&mut self) -> Option<(u16, bool)> {
+        let start = self.pos;
+        let abs = self.expect('$').is_some();
+        let mut col = 0u32;
+        let mut len = 0;
+        while let Some(ch) = self.peek(0).filter(char::is_ascii_alphabetic) {
+            col = col * 26 + (ch.to_ascii_uppercase() as u32 - 'A' as u32 + 1);
+            len += 1;
+            self.pos += 1;
+            if len > 3 {
+                break;
+            }
+        }
+        if (1..=3).contains(&len) && col - 1 <= MAX_COL {
+            Some(((col - 1) as u16, abs))
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+
+    /// One-based row number with the optional `$`, the position is restored if there is none
+    fn row(&mut self) -> Option<(u32, bool)> {
+        let start = self.pos;
+        let abs = self.expect('$').is_some();
+        let mut row = 0u64;
+        let mut len = 0;
+        while let Some(digit) = self.peek(0).and_then(|ch| ch.to_digit(10)) {
+            row = row * 10 + digit as u64;
+            len += 1;
+            self.pos += 1;
+            if len > 7 {
+                break;
+            }
+        }
+        if len > 0 && (1..=MAX_ROW as u64 + 1).contains(&row) {
+            Some(((row - 1) as u32, abs))
+        } else {
+            self.pos = start;
+            None
+        }
+    }
+}
+
+fn is_name_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '_' || ch == '\\'
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '_' | '.' | '\\' | '?')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = |text: &str| {
+            tokenize(text)
+                .unwrap()
+                .into_iter()
+                .map(|(token, _)| token)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            tokens("SUM($A1:B$10)*2.5e1"),
+            [
+                Token::Function("SUM".to_owned()),
+                Token::Op("("),
+                Token::Area(
+                    CellRef::new(0, 0, false, true),
+                    CellRef::new(9, 1, true, false)
+                ),
+                Token::Op(")"),
+                Token::Op("*"),
+                Token::Number(25.0),
+            ]
+        );
+        assert_eq!(
+            tokens("'It''s'!C:C<>\"a\"\"b\"&#N/A"),
+            [
+                Token::Sheet("It's".to_owned()),
+                Token::Area(
+                    CellRef::new(0, 2, true, false),
+                    CellRef::new(MAX_ROW, 2, true, false)
+                ),
+                Token::Op("<>"),
+                Token::String("a\"b".to_owned()),
+                Token::Op("&"),
+                Token::Error(CellError::NA),
+            ]
+        );
+        // identifiers, which look like references
+        assert_eq!(
+            tokens("LOG10(XFE1)+Data1!A1+true"),
+            [
+                Token::Function("LOG10".to_owned()),
+                Token::Op("("),
+                Token::Name("XFE1".to_owned()),
+                Token::Op(")"),
+                Token::Op("+"),
+                Token::Sheet("Data1".to_owned()),
+                Token::Ref(CellRef::new(0, 0, false, false)),
+                Token::Op("+"),
+                Token::Bool(true),
+            ]
+        );
+        assert_eq!(
+            tokenize("A1 B2")
+                .unwrap()
+                .iter()
+                .map(|(_, space)| *space)
+                .collect::<Vec<_>>(),
+            [false, true]
+        );
+        assert_eq!(tokens("2:$3").len(), 1);
+        assert!(tokenize("\"open").is_err());
+        assert!(tokenize("#BAD!").is_err());
+        assert!(tokenize("'Sheet'A1").is_err());
+    }
+}
@@ -0,0 +1,9 @@
+//! Formulas in A1 notation and their binary form (`Rgce`)
+mod compiler;
+mod decompiler;
+mod functions;
+mod lexer;
+mod ptg;
+
+pub(crate) use compiler::{compile, FormulaContext, FormulaType};
+pub(crate) use decompiler::{decompile, number_text, quote_sheet, DecompileContext};
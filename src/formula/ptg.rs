@@ -0,0 +1,130 @@
+//! Parse things (`Ptg`): identifiers of formula tokens
+use super::{functions::Class, lexer::CellRef};
+use crate::core::biff::{MAX_COL, MAX_ROW};
+
+pub(crate) const ADD: u8 = 0x03;
+pub(crate) const SUB: u8 = 0x04;
+pub(crate) const MUL: u8 = 0x05;
+pub(crate) const DIV: u8 = 0x06;
+pub(crate) const POWER: u8 = 0x07;
+pub(crate) const CONCAT: u8 = 0x08;
+pub(crate) const LT: u8 = 0x09;
+pub(crate) const LE: u8 = 0x0A;
+pub(crate) const EQ: u8 = 0x0B;
+pub(crate) const GE: u8 = 0x0C;
+pub(crate) const GT: u8 = 0x0D;
+pub(crate) const NE: u8 = 0x0E;
+pub(crate) const ISECT: u8 = 0x0F;
+pub(crate) const UNION: u8 = 0x10;
+pub(crate) const RANGE: u8 = 0x11;
+pub(crate) const UPLUS: u8 = 0x12;
+pub(crate) const UMINUS: u8 = 0x13;
+pub(crate) const PERCENT: u8 = 0x14;
+pub(crate) const PAREN: u8 = 0x15;
+pub(crate) const MISS_ARG: u8 = 0x16;
+pub(crate) const STR: u8 = 0x17;
+/// Extended tokens, which are followed by their type (`eptg`)
+pub(crate) const EXTENDED: u8 = 0x18;
+pub(crate) const ATTR: u8 = 0x19;
+pub(crate) const ERR: u8 = 0x1C;
+pub(crate) const BOOL: u8 = 0x1D;
+pub(crate) const INT: u8 = 0x1E;
+pub(crate) const NUM: u8 = 0x1F;
+
+/// Operand tokens, their class is added by [`with_class`]
+pub(crate) const ARRAY: u8 = 0x00;
+pub(crate) const FUNC: u8 = 0x01;
+pub(crate) const FUNC_VAR: u8 = 0x02;
+pub(crate) const NAME: u8 = 0x03;
+pub(crate) const REF: u8 = 0x04;
+pub(crate) const AREA: u8 = 0x05;
+pub(crate) const MEM_AREA: u8 = 0x06;
+pub(crate) const MEM_ERR: u8 = 0x07;
+pub(crate) const MEM_NO_MEM: u8 = 0x08;
+pub(crate) const MEM_FUNC: u8 = 0x09;
+pub(crate) const REF_ERR: u8 = 0x0A;
+pub(crate) const AREA_ERR: u8 = 0x0B;
+/// References of shared formulas, relative to the cell
+pub(crate) const REF_N: u8 = 0x0C;
+pub(crate) const AREA_N: u8 = 0x0D;
+pub(crate) const NAME_X: u8 = 0x19;
+pub(crate) const REF_3D: u8 = 0x1A;
+pub(crate) const AREA_3D: u8 = 0x1B;
+pub(crate) const REF_ERR_3D: u8 = 0x1C;
+pub(crate) const AREA_ERR_3D: u8 = 0x1D;
+
+/// `PtgList`: the structured reference to the table
+pub(crate) const EXTENDED_LIST: u8 = 0x19;
+/// Rows of the table (`rowType`), which are referenced by `PtgList`
+pub(crate) const LIST_DEFAULT: u16 = 0x00;
+pub(crate) const LIST_ALL: u16 = 0x01;
+pub(crate) const LIST_HEADERS: u16 = 0x02;
+pub(crate) const LIST_DATA: u16 = 0x04;
+pub(crate) const LIST_HEADERS_DATA: u16 = 0x06;
+pub(crate) const LIST_TOTALS: u16 = 0x08;
+pub(crate) const LIST_DATA_TOTALS: u16 = 0x0C;
+pub(crate) const LIST_THIS_ROW: u16 = 0x10;
+/// The referenced table or its columns were deleted
+pub(crate) const LIST_INVALID: u16 = 0x1000;
+
+/// `PtgAttr` types
+pub(crate) const ATTR_SEMI: u8 = 0x01;
+pub(crate) const ATTR_CHOOSE: u8 = 0x04;
+pub(crate) const ATTR_SUM: u8 = 0x10;
+
+/// Index of the function table for functions, which are called by their name
+pub(crate) const IFTAB_USER_DEFINED: u16 = 0xFF;
+
+/// Token of the operand with its class
+pub(crate) fn with_class(ptg: u8, class: Class) -> u8 {
+    ptg | match class {
+        Class::Ref => 0x20,
+        Class::Value => 0x40,
+        Class::Array => 0x60,
+    }
+}
+
+/// Row and column (`ColRelShort`) of the reference with flags of the relative column and row.
+///
+/// Relative parts are offsets from the `base` cell in shared formulas.
+pub(crate) fn loc(cell: &CellRef, base: Option<(u32, u16)>) -> (u32, u16) {
+    let (row, col) = match base {
+        Some((row, col)) => (
+            if cell.row_abs {
+                cell.row
+            } else {
+                cell.row.wrapping_sub(row)
+            },
+            if cell.col_abs {
+                cell.col
+            } else {
+                cell.col.wrapping_sub(col) & 0x3FFF
+            },
+        ),
+        _ => (cell.row, cell.col),
+    };
+    (
+        row,
+        col | (!cell.col_abs as u16) << 14 | (!cell.row_abs as u16) << 15,
+    )
+}
+
+/// Reference by its row and column (`ColRelShort`), the reverse of [`loc`]
+pub(crate) fn cell_ref(row: u32, col_rel: u16, base: Option<(u32, u16)>) -> CellRef {
+    let (row_abs, col_abs) = (col_rel & 0x8000 == 0, col_rel & 0x4000 == 0);
+    let col = col_rel & 0x3FFF;
+    let (row, col) = match base {
+        Some((base_row, base_col)) => (
+            match row_abs {
+                true => row,
+                false => base_row.wrapping_add(row) % (MAX_ROW + 1),
+            },
+            match col_abs {
+                true => col,
+                false => (base_col + col) % (MAX_COL as u16 + 1),
+            },
+        ),
+        None => (row, col),
+    };
+    CellRef::new(row, col, row_abs, col_abs)
+}
@@ -13,7 +13,33 @@
  See the License for the specific language governing permissions and
  limitations under the License.
 */
-#![feature(const_vec_string_slice)]
-#![feature(iter_array_chunks)]
-#![feature(assert_matches)]
+// Low-level BIFF12 toolkit: a record catalog, shared by the writer and the reader
 mod core;
+mod date;
+mod error;
+mod formula;
+mod range;
+mod reader;
+mod style;
+mod value;
+mod writer;
+
+pub use date::DateSystem;
+pub use error::{Error, Location, Result};
+pub use range::CellRange;
+pub use reader::{
+    BatchOptions, Cells, SheetBatches, SheetCell, SheetComment, SheetFormula, WorkbookName,
+    XlsbReader,
+};
+pub use style::{
+    Border, BorderLine, BorderStyle, Color, DifferentialFormat, Font, HorizontalAlign, Style,
+    StyleId, VerticalAlign,
+};
+pub use value::{CellError, CellValue};
+pub use writer::{
+    CellComment, CellCondition, ColorScale, ColumnFilter, ColumnOptions, ConditionValue,
+    ConditionalFormat, ConditionalRule, DataBar, DataValidation, DefinedName, DynamicFilter,
+    FilterComparison, FilterOperator, Hyperlink, IconSet, IconSetStyle, Panes, RowOptions,
+    SheetView, SheetWriter, TableColumn, TableOptions, TableStyle, TotalFunction,
+    ValidationErrorStyle, ValidationRule, XlsbWriter,
+};
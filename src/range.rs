@@ -0,0 +1,190 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    core::biff::{UncheckedRfX, MAX_COL, MAX_ROW},
+    Error, Result,
+};
+
+/// Rectangular range of cells, both bounds are inclusive and zero-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellRange {
+    pub first_row: u32,
+    pub first_col: u16,
+    pub last_row: u32,
+    pub last_col: u16,
+}
+
+impl CellRange {
+    pub fn new(first_row: u32, first_col: u16, last_row: u32, last_col: u16) -> Self {
+        CellRange {
+            first_row,
+            first_col,
+            last_row,
+            last_col,
+        }
+    }
+
+    /// Range of the single cell
+    pub fn cell(row: u32, col: u16) -> Self {
+        CellRange::new(row, col, row, col)
+    }
+
+    pub fn contains(&self, row: u32, col: u16) -> bool {
+        (self.first_row..=self.last_row).contains(&row)
+            && (self.first_col..=self.last_col).contains(&col)
+    }
+
+    /// Both ranges have at least one common cell
+    pub fn intersects(&self, other: &CellRange) -> bool {
+        self.first_row <= other.last_row
+            && other.first_row <= self.last_row
+            && self.first_col <= other.last_col
+            && other.first_col <= self.last_col
+    }
+
+    pub fn is_single_cell(&self) -> bool {
+        self.first_row == self.last_row && self.first_col == self.last_col
+    }
+
+    /// Check, that bounds are ordered and within the sheet
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.first_row > self.last_row || self.first_col > self.last_col {
+            return Err(Error::invalid_input(format!(
+                "range {} has reversed bounds",
+                self
+            )));
+        }
+        if self.last_row > MAX_ROW || self.last_col as u32 > MAX_COL {
+            return Err(Error::invalid_input(format!(
+                "range {} is out of the sheet bounds",
+                self
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_rfx(self) -> UncheckedRfX {
+        UncheckedRfX::from_bounds(
+            self.first_row,
+            self.last_row,
+            self.first_col as u32,
+            self.last_col as u32,
+        )
+    }
+
+    pub(crate) fn from_rfx(rfx: &UncheckedRfX) -> Self {
+        CellRange::new(
+            rfx.rw_first.inner,
+            rfx.col_first.inner as u16,
+            rfx.rw_last.inner,
+            rfx.col_last.inner as u16,
+        )
+    }
+}
+
+/// Name of the column in A1 notation, like `A` or `XFD`
+pub(crate) fn column_name(col: u16) -> String {
+    let mut name = Vec::new();
+    let mut col = col as u32 + 1;
+    while col > 0 {
+        col -= 1;
+        name.push(b'A' + (col % 26) as u8);
+        col /= 26;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+impl Display for CellRange {
+    /// Reference in A1 notation, like `B2:D4`, or `B2` for the single cell
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", column_name(self.first_col), self.first_row + 1)?;
+        if !self.is_single_cell() {
+            write!(f, ":{}{}", column_name(self.last_col), self.last_row + 1)?;
+        }
+        Ok(())
+    }
+}
+
+/// Ranges, which do not overlap each other, in the order of addition. They are indexed by
+/// their first rows, so only ranges near the rows of the new range are checked for overlaps.
+#[derive(Default)]
+pub(crate) struct RangeIndex {
+    items: Vec<CellRange>,
+    /// Indices of ranges by their first rows
+    rows: BTreeMap<u32, Vec<usize>>,
+    /// Maximal count of rows of ranges below their first rows
+    max_height: u32,
+}
+
+impl RangeIndex {
+    /// Range, which overlaps the given one
+    pub(crate) fn find_overlap(&self, range: &CellRange) -> Option<&CellRange> {
+        let first_row = range.first_row.saturating_sub(self.max_height);
+        self.rows
+            .range(first_row..=range.last_row)
+            .flat_map(|(_, indices)| indices)
+            .map(|&idx| &self.items[idx])
+            .find(|other| other.intersects(range))
+    }
+
+    pub(crate) fn push(&mut self, range: CellRange) {
+        self.rows
+            .entry(range.first_row)
+            .or_default()
+            .push(self.items.len());
+        self.max_height = self.max_height.max(range.last_row - range.first_row);
+        self.items.push(range);
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub(crate) fn iter(&self) -> std::slice::Iter<'_, CellRange> {
+        self.items.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_range() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(MAX_COL as u16), "XFD");
+        assert_eq!(CellRange::new(1, 1, 3, 27).to_string(), "B2:AB4");
+        assert_eq!(CellRange::cell(0, 0).to_string(), "A1");
+
+        let range = CellRange::new(2, 2, 4, 4);
+        assert!(range.intersects(&CellRange::new(4, 4, 6, 6)));
+        assert!(range.intersects(&CellRange::new(0, 3, 10, 3)));
+        assert!(!range.intersects(&CellRange::new(5, 0, 5, 10)));
+        assert!(range.contains(3, 4) && !range.contains(3, 5));
+        assert!(range.validate().is_ok());
+        assert!(CellRange::new(4, 0, 2, 0).validate().is_err());
+        assert!(CellRange::new(0, 0, MAX_ROW + 1, 0).validate().is_err());
+        assert_eq!(CellRange::from_rfx(&range.to_rfx()), range);
+
+        let mut index = RangeIndex::default();
+        index.push(CellRange::new(0, 0, 9, 0));
+        index.push(range);
+        assert_eq!(
+            index.find_overlap(&CellRange::cell(9, 0)),
+            Some(&CellRange::new(0, 0, 9, 0))
+        );
+        assert_eq!(
+            index.find_overlap(&CellRange::new(3, 3, 3, 8)),
+            Some(&range)
+        );
+        assert_eq!(index.find_overlap(&CellRange::new(5, 1, 9, 9)), None);
+        assert_eq!(index.iter().copied().collect::<Vec<_>>()[1], range);
+    }
+}
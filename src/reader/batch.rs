@@ -0,0 +1,548 @@
+//! Reading of the sheet into Arrow record batches
+use std::{
+    collections::VecDeque,
+    io::{Read, Seek},
+    sync::Arc,
+};
+
+use arrow::{
+    array::{
+        ArrayBuilder, ArrayRef, BooleanBuilder, Date32Builder, Float64Builder, StringBuilder,
+        TimestampMillisecondBuilder,
+    },
+    compute::cast,
+    datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit},
+    record_batch::{RecordBatch, RecordBatchOptions},
+};
+
+use super::{
+    format::{CellFormats, FormatKind},
+    Cells, SheetCell, XlsbReader,
+};
+use crate::{CellValue, DateSystem, Error, Result};
+
+/// Options of reading the sheet into Arrow record batches
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Count of sheet rows in every batch, except the last one
+    pub batch_size: usize,
+    /// The first non-empty row contains names of columns
+    pub header: bool,
+    /// Count of rows after the header, which are used to infer the schema
+    pub infer_rows: usize,
+    /// Explicit schema, columns of the sheet are mapped to its fields by position
+    pub schema: Option<SchemaRef>,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        BatchOptions {
+            batch_size: 8192,
+            header: true,
+            infer_rows: 1000,
+            schema: None,
+        }
+    }
+}
+
+impl<R: Read + Seek> XlsbReader<R> {
+    /// Read the sheet into record batches with the inferred schema.
+    ///
+    /// The first non-empty row is used as the header.
+    pub fn sheet_batches(&mut self, sheet: &str, batch_size: usize) -> Result<SheetBatches<'_>> {
+        self.sheet_batches_with(
+            sheet,
+            BatchOptions {
+                batch_size,
+                ..BatchOptions::default()
+            },
+        )
+    }
+
+    /// Read the sheet into record batches with the given options
+    pub fn sheet_batches_with(
+        &mut self,
+        sheet: &str,
+        options: BatchOptions,
+    ) -> Result<SheetBatches<'_>> {
+        if options.batch_size == 0 {
+            return Err(Error::invalid_input("batch size must be positive"));
+        }
+        let XlsbReader {
+            zip,
+            sheets,
+            strings,
+            formats,
+            date_system,
+            ..
+        } = self;
+        let cells = Cells::open(zip, sheets, strings, sheet)?;
+        SheetBatches::new(cells, formats, *date_system, options)
+    }
+}
+
+/// Iterator over record batches of the sheet, created by [`XlsbReader::sheet_batches`]
+pub struct SheetBatches<'a> {
+    cells: Cells<'a>,
+    formats: &'a CellFormats,
+    date_system: DateSystem,
+    /// Cells, which were read ahead (for schema inference or from the next batch)
+    buffer: VecDeque<SheetCell>,
+    schema: SchemaRef,
+    batch_size: usize,
+    /// First row of the next batch
+    next_row: u32,
+    done: bool,
+}
+
+impl<'a> SheetBatches<'a> {
+    fn new(
+        mut cells: Cells<'a>,
+        formats: &'a CellFormats,
+        date_system: DateSystem,
+        options: BatchOptions,
+    ) -> Result<Self> {
+        let mut buffer = VecDeque::new();
+        let mut names = Vec::new();
+        let mut next_row = 0;
+
+        if let Some(first) = cells.next().transpose()? {
+            next_row = first.row;
+            buffer.push_back(first);
+        }
+        if options.header && !buffer.is_empty() {
+            let header_row = next_row;
+            while let Some(cell) = next_cell(&mut buffer, &mut cells)? {
+                if cell.row != header_row {
+                    buffer.push_front(cell);
+                    break;
+                }
+                let col = cell.col as usize;
+                if names.len() <= col {
+                    names.resize(col + 1, None);
+                }
+                names[col] = header_name(&cell.value);
+            }
+            next_row = header_row + 1;
+        }
+
+        let schema = match options.schema {
+            Some(schema) => schema,
+            None => {
+                let last_row = next_row as u64 + options.infer_rows as u64;
+                while let Some(cell) = cells.next().transpose()? {
+                    let stop = cell.row as u64 >= last_row;
+                    buffer.push_back(cell);
+                    if stop {
+                        break;
+                    }
+                }
+                infer_schema(
+                    &names,
+                    buffer.iter().filter(|cell| (cell.row as u64) < last_row),
+                    formats,
+                )
+            }
+        };
+
+        Ok(SheetBatches {
+            cells,
+            formats,
+            date_system,
+            buffer,
+            schema,
+            batch_size: options.batch_size,
+            next_row,
+            done: false,
+        })
+    }
+
+    /// Schema of batches
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn read_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let first_row = self.next_row;
+        let end_row = first_row as u64 + self.batch_size as u64;
+        let mut columns = self
+            .schema
+            .fields()
+            .iter()
+            .map(|field| ColumnBuilder::new(field.data_type()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut last_row = None;
+        while let Some(cell) = next_cell(&mut self.buffer, &mut self.cells)? {
+            if cell.row < first_row {
+                continue;
+            }
+            if cell.row as u64 >= end_row {
+                self.buffer.push_front(cell);
+                break;
+            }
+            last_row = Some(cell.row);
+            if let Some(column) = columns.get_mut(cell.col as usize) {
+                let offset = (cell.row - first_row) as usize;
+                column.append(
+                    offset,
+                    &cell.value,
+                    self.formats.kind(cell.style),
+                    self.date_system,
+                );
+            }
+        }
+
+        let num_rows = match (last_row, self.buffer.is_empty()) {
+            (None, true) => return Ok(None),
+            (_, false) => self.batch_size,
+            (Some(last_row), true) => (last_row - first_row) as usize + 1,
+        };
+        self.next_row = (first_row as u64 + num_rows as u64) as u32;
+
+        let arrays = columns
+            .into_iter()
+            .zip(self.schema.fields())
+            .map(|(column, field)| column.finish(num_rows, field.data_type()))
+            .collect::<Result<Vec<_>>>()?;
+        RecordBatch::try_new_with_options(
+            self.schema.clone(),
+            arrays,
+            &RecordBatchOptions::new().with_row_count(Some(num_rows)),
+        )
+        .map(Some)
+        .map_err(Error::from)
+    }
+}
+
+impl Iterator for SheetBatches<'_> {
+    type Item = Result<RecordBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.read_batch().transpose();
+        if !matches!(res, Some(Ok(_))) {
+            self.done = true;
+        }
+        res
+    }
+}
+
+fn next_cell(buffer: &mut VecDeque<SheetCell>, cells: &mut Cells) -> Result<Option<SheetCell>> {
+    match buffer.pop_front() {
+        Some(cell) => Ok(Some(cell)),
+        None => cells.next().transpose(),
+    }
+}
+
+fn header_name(value: &CellValue) -> Option<String> {
+    match value {
+        CellValue::String(value) => Some(value.clone()),
+        CellValue::Number(value) => Some(value.to_string()),
+        CellValue::Bool(value) => Some(value.to_string()),
+        CellValue::Error(_) | CellValue::Blank => None,
+    }
+}
+
+/// Type of the column, which can hold the single value
+fn value_type(value: &CellValue, kind: FormatKind) -> Option<DataType> {
+    match (value, kind) {
+        (CellValue::Number(_), FormatKind::Date) => Some(DataType::Date32),
+        (CellValue::Number(_), FormatKind::DateTime) => {
+            Some(DataType::Timestamp(TimeUnit::Millisecond, None))
+        }
+        (CellValue::Number(_), FormatKind::Number) => Some(DataType::Float64),
+        (CellValue::String(_), _) => Some(DataType::Utf8),
+        (CellValue::Bool(_), _) => Some(DataType::Boolean),
+        (CellValue::Error(_), _) | (CellValue::Blank, _) => None,
+    }
+}
+
+/// Common type of two columns: dates are widened to timestamps, any other mix becomes strings
+fn merge_types(left: DataType, right: DataType) -> DataType {
+    match (left, right) {
+        (left, right) if left == right => left,
+        (DataType::Date32, DataType::Timestamp(unit, tz))
+        | (DataType::Timestamp(unit, tz), DataType::Date32) => DataType::Timestamp(unit, tz),
+        _ => DataType::Utf8,
+    }
+}
+
+fn infer_schema<'c>(
+    names: &[Option<String>],
+    cells: impl Iterator<Item = &'c SheetCell>,
+    formats: &CellFormats,
+) -> SchemaRef {
+    let mut types: Vec<Option<DataType>> = vec![None; names.len()];
+    for cell in cells {
+        let col = cell.col as usize;
+        if types.len() <= col {
+            types.resize(col + 1, None);
+        }
+        if let Some(value_type) = value_type(&cell.value, formats.kind(cell.style)) {
+            types[col] = Some(match types[col].take() {
+                Some(current) => merge_types(current, value_type),
+                None => value_type,
+            });
+        }
+    }
+    let fields = types
+        .into_iter()
+        .enumerate()
+        .map(|(col, data_type)| {
+            let name = match names.get(col) {
+                Some(Some(name)) => name.clone(),
+                _ => format!("column_{}", col + 1),
+            };
+            Field::new(name, data_type.unwrap_or(DataType::Utf8), true)
+        })
+        .collect::<Vec<_>>();
+    Arc::new(Schema::new(fields))
+}
+
+/// Builder of the single column, values are converted to the target type on finish
+enum ColumnBuilder {
+    Bool(BooleanBuilder),
+    Number(Float64Builder),
+    String(StringBuilder),
+    Date(Date32Builder),
+    Timestamp(TimestampMillisecondBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(data_type: &DataType) -> Result<Self> {
+        Ok(match data_type {
+            DataType::Boolean => ColumnBuilder::Bool(BooleanBuilder::new()),
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float16
+            | DataType::Float32
+            | DataType::Float64 => ColumnBuilder::Number(Float64Builder::new()),
+            DataType::Utf8 | DataType::LargeUtf8 => ColumnBuilder::String(StringBuilder::new()),
+            DataType::Date32 => ColumnBuilder::Date(Date32Builder::new()),
+            DataType::Date64 | DataType::Timestamp(_, _) => {
+                ColumnBuilder::Timestamp(TimestampMillisecondBuilder::new())
+            }
+            other => {
+                return Err(Error::invalid_input(format!(
+                    "unsupported column type {}",
+                    other
+                )))
+            }
+        })
+    }
+
+    fn builder(&mut self) -> &mut dyn ArrayBuilder {
+        match self {
+            ColumnBuilder::Bool(builder) => builder,
+            ColumnBuilder::Number(builder) => builder,
+            ColumnBuilder::String(builder) => builder,
+            ColumnBuilder::Date(builder) => builder,
+            ColumnBuilder::Timestamp(builder) => builder,
+        }
+    }
+
+    /// Pad the column with nulls up to the given length
+    fn pad(&mut self, len: usize) {
+        let missing = len.saturating_sub(self.builder().len());
+        match self {
+            ColumnBuilder::Bool(builder) => builder.append_nulls(missing),
+            ColumnBuilder::Number(builder) => builder.append_nulls(missing),
+            ColumnBuilder::String(builder) => (0..missing).for_each(|_| builder.append_null()),
+            ColumnBuilder::Date(builder) => builder.append_nulls(missing),
+            ColumnBuilder::Timestamp(builder) => builder.append_nulls(missing),
+        }
+    }
+
+    /// Append the value at the given offset; values, which cannot be converted, become nulls
+    fn append(
+        &mut self,
+        offset: usize,
+        value: &CellValue,
+        kind: FormatKind,
+        date_system: DateSystem,
+    ) {
+        if self.builder().len() > offset {
+            return;
+        }
+        self.pad(offset);
+        match self {
+            ColumnBuilder::Bool(builder) => builder.append_option(match value {
+                CellValue::Bool(value) => Some(*value),
+                CellValue::Number(value) => Some(*value != 0.0),
+                _ => None,
+            }),
+            ColumnBuilder::Number(builder) => builder.append_option(match value {
+                CellValue::Number(value) => Some(*value),
+                CellValue::Bool(value) => Some(*value as u8 as f64),
+                CellValue::String(value) => value.trim().parse().ok(),
+                _ => None,
+            }),
+            ColumnBuilder::String(builder) => builder.append_option(match (value, kind) {
+                (CellValue::String(value), _) => Some(value.clone()),
+                (CellValue::Number(value), FormatKind::Date) => date_system
+                    .serial_to_date(*value)
+                    .map(|date| date.to_string()),
+                (CellValue::Number(value), FormatKind::DateTime) => date_system
+                    .serial_to_datetime(*value)
+                    .map(|datetime| datetime.to_string()),
+                (CellValue::Number(value), FormatKind::Number) => Some(value.to_string()),
+                (CellValue::Bool(value), _) => Some(value.to_string().to_uppercase()),
+                _ => None,
+            }),
+            ColumnBuilder::Date(builder) => builder.append_option(match value {
+                CellValue::Number(value) => date_system.serial_to_days(*value),
+                _ => None,
+            }),
+            ColumnBuilder::Timestamp(builder) => builder.append_option(match value {
+                CellValue::Number(value) => date_system.serial_to_millis(*value),
+                _ => None,
+            }),
+        }
+    }
+
+    fn finish(mut self, len: usize, data_type: &DataType) -> Result<ArrayRef> {
+        self.pad(len);
+        let array = self.builder().finish();
+        if array.data_type() == data_type {
+            Ok(array)
+        } else {
+            cast(&array, data_type).map_err(Error::from)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use arrow::{
+        array::{
+            Array, AsArray, BooleanArray, Date32Array, Int32Array, StringArray,
+            TimestampMillisecondArray,
+        },
+        datatypes::{Date32Type, Float64Type, Int64Type, TimestampMillisecondType},
+    };
+
+    use super::*;
+    use crate::XlsbWriter;
+
+    fn sample() -> Vec<u8> {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "int",
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(-7)])) as ArrayRef,
+            ),
+            (
+                "text",
+                Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])) as ArrayRef,
+            ),
+            (
+                "flag",
+                Arc::new(BooleanArray::from(vec![true, false, true])) as ArrayRef,
+            ),
+            (
+                "date",
+                Arc::new(Date32Array::from(vec![0, 19723, 1])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(TimestampMillisecondArray::from(vec![0, 43_200_000, 1])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet.write_header(&batch.schema()).unwrap();
+        sheet.write_batch(&batch).unwrap();
+        sheet.write(4, 1, 5.5).unwrap();
+        sheet.finish().unwrap();
+        book.close().unwrap().into_inner()
+    }
+
+    #[test]
+    fn test_sheet_batches() {
+        let mut reader = XlsbReader::new(Cursor::new(sample())).unwrap();
+        let batches = reader
+            .sheet_batches("Data", 2)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            [2, 2]
+        );
+
+        let schema = batches[0].schema();
+        let types = schema
+            .fields()
+            .iter()
+            .map(|field| (field.name().as_str(), field.data_type().clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            [
+                ("int", DataType::Float64),
+                // strings, mixed with numbers
+                ("text", DataType::Utf8),
+                ("flag", DataType::Boolean),
+                ("date", DataType::Date32),
+                ("ts", DataType::Timestamp(TimeUnit::Millisecond, None)),
+            ]
+        );
+
+        let ints = batches[1].column(0).as_primitive::<Float64Type>();
+        assert_eq!(ints.value(0), -7.0);
+        assert!(ints.is_null(1));
+        let texts = batches[0].column(1).as_string::<i32>();
+        assert_eq!(texts.value(0), "a");
+        assert!(texts.is_null(1));
+        assert_eq!(batches[1].column(1).as_string::<i32>().value(1), "5.5");
+        assert!(!batches[0].column(2).as_boolean().value(1));
+        let dates = batches[0].column(3).as_primitive::<Date32Type>();
+        assert_eq!(dates.values(), &[0, 19723]);
+        let ts = batches[0]
+            .column(4)
+            .as_primitive::<TimestampMillisecondType>();
+        assert_eq!(ts.values(), &[0, 43_200_000]);
+    }
+
+    #[test]
+    fn test_explicit_schema() {
+        let mut reader = XlsbReader::new(Cursor::new(sample())).unwrap();
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int64, true),
+            Field::new("b", DataType::LargeUtf8, true),
+        ]));
+        let options = BatchOptions {
+            schema: Some(schema.clone()),
+            ..BatchOptions::default()
+        };
+        let batches = reader
+            .sheet_batches_with("Data", options)
+            .unwrap()
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].schema(), schema);
+        assert_eq!(batches[0].num_rows(), 4);
+        let ints = batches[0].column(0).as_primitive::<Int64Type>();
+        assert_eq!(
+            ints.iter().collect::<Vec<_>>(),
+            [Some(1), None, Some(-7), None]
+        );
+        assert_eq!(batches[0].column(1).as_string::<i64>().value(3), "5.5");
+
+        assert!(reader.sheet_batches("Data", 0).is_err());
+        assert!(reader.sheet_batches("Missing", 10).is_err());
+    }
+}
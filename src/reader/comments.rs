@@ -0,0 +1,125 @@
+//! Comments (notes) of cells from the comments part of the sheet
+use std::io::{Read, Seek};
+
+use super::{find_sheet, read_rels, resolve_target, scan_part, split_path, XlsbReader};
+use crate::{
+    core::{
+        biff::{
+            records::{BrtBeginComment, BrtCommentAuthor, BrtCommentText},
+            BiffId,
+        },
+        xml::REL_COMMENTS,
+    },
+    CellRange, Error, Result,
+};
+
+/// Comment of the cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetComment {
+    /// Zero-based row index of the cell
+    pub row: u32,
+    /// Zero-based column index of the cell
+    pub col: u16,
+    pub author: String,
+    pub text: String,
+}
+
+impl<R: Read + Seek> XlsbReader<R> {
+    /// Comments of the sheet, in the order of the comments part
+    pub fn comments(&mut self, sheet: &str) -> Result<Vec<SheetComment>> {
+        let path = find_sheet(&self.sheets, sheet)?;
+        let (dir, name) = split_path(path);
+        let rels_path = format!("{}_rels/{}.rels", dir, name);
+        if self.zip.index_for_name(&rels_path).is_none() {
+            return Ok(Vec::new());
+        }
+        let rels = read_rels(&mut self.zip, &rels_path)?;
+        let Some(rel) = rels.find_by_type(REL_COMMENTS) else {
+            return Ok(Vec::new());
+        };
+        let path = resolve_target(dir, &rel.target);
+
+        let ids = [
+            BiffId::BrtCommentAuthor,
+            BiffId::BrtBeginComment,
+            BiffId::BrtCommentText,
+        ];
+        let mut authors = Vec::new();
+        // anchor cell and author of the comment, which text is not read yet
+        let mut current = None;
+        let mut comments = Vec::new();
+        scan_part(&mut self.zip, &path, &ids, BiffId::BrtEndComments, |rec| {
+            match rec.id {
+                BiffId::BrtCommentAuthor => {
+                    authors.push(rec.as_biff::<BrtCommentAuthor>()?.xstr_author.inner);
+                }
+                BiffId::BrtBeginComment => {
+                    let comment = rec.as_biff::<BrtBeginComment>()?;
+                    let range = CellRange::from_rfx(&comment.rfx);
+                    match authors.get(comment.iauthor as usize) {
+                        Some(author) => {
+                            current = Some((range.first_row, range.first_col, author.clone()))
+                        }
+                        None => return Err(Error::invalid_value().with_field("iauthor")),
+                    }
+                }
+                _ => match current.take() {
+                    Some((row, col, author)) => comments.push(SheetComment {
+                        row,
+                        col,
+                        author,
+                        text: rec.as_biff::<BrtCommentText>()?.text.value.inner,
+                    }),
+                    None => return Err(Error::malformed("comment text outside of the comment")),
+                },
+            }
+            Ok(())
+        })?;
+        Ok(comments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{CellComment, XlsbWriter};
+
+    #[test]
+    fn test_comments() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Checks").unwrap();
+        sheet.write(3, 1, -5).unwrap();
+        sheet
+            .add_comment(3, 1, &CellComment::new("Negative").with_author("Auditor"))
+            .unwrap();
+        sheet
+            .add_comment(0, 4, &CellComment::new("Line 1\nLine 2"))
+            .unwrap();
+        sheet.finish().unwrap();
+        book.add_sheet("Clean").unwrap().finish().unwrap();
+        let data = book.close().unwrap();
+
+        let mut reader = XlsbReader::new(Cursor::new(data.into_inner())).unwrap();
+        assert_eq!(
+            reader.comments("Checks").unwrap(),
+            [
+                SheetComment {
+                    row: 0,
+                    col: 4,
+                    author: String::new(),
+                    text: "Line 1\nLine 2".to_owned(),
+                },
+                SheetComment {
+                    row: 3,
+                    col: 1,
+                    author: "Auditor".to_owned(),
+                    text: "Negative".to_owned(),
+                },
+            ]
+        );
+        assert!(reader.comments("Clean").unwrap().is_empty());
+        assert!(reader.comments("Missing").is_err());
+    }
+}
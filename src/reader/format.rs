@@ -0,0 +1,125 @@
+//! Classification of number formats: which cells hold dates
+use std::collections::HashMap;
+
+/// Kind of values, which are displayed by the number format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum FormatKind {
+    #[default]
+    Number,
+    /// Date without time
+    Date,
+    /// Date with time, or time only
+    DateTime,
+}
+
+/// Kind of the built-in number format
+fn builtin_kind(ifmt: u16) -> FormatKind {
+    match ifmt {
+        14..=17 | 27..=31 | 34..=36 | 50..=58 => FormatKind::Date,
+        18..=22 | 32 | 33 | 45..=47 => FormatKind::DateTime,
+        _ => FormatKind::Number,
+    }
+}
+
+/// Kind of the custom number format, based on its first section
+fn code_kind(code: &str) -> FormatKind {
+    let (mut date, mut time, mut month) = (false, false, false);
+    let mut chars = code.chars();
+    while let Some(ch) = chars.next() {
+        match ch.to_ascii_lowercase() {
+            ';' => break,
+            // literal text
+            '"' => {
+                for ch in chars.by_ref() {
+                    if ch == '"' {
+                        break;
+                    }
+                }
+            }
+            // escaped, repeated or padding character
+            '\\' | '*' | '_' => {
+                chars.next();
+            }
+            // color, condition, locale or elapsed time (like `[h]`)
+            '[' => {
+                let section = chars
+                    .by_ref()
+                    .take_while(|&ch| ch != ']')
+                    .collect::<String>();
+                if !section.is_empty()
+                    && section
+                        .chars()
+                        .all(|ch| matches!(ch.to_ascii_lowercase(), 'h' | 'm' | 's'))
+                {
+                    time = true;
+                }
+            }
+            'y' | 'd' => date = true,
+            'h' | 's' => time = true,
+            'm' => month = true,
+            _ => {}
+        }
+    }
+    match (date || (month && !time), time) {
+        (true, true) => FormatKind::DateTime,
+        (true, false) => FormatKind::Date,
+        (false, true) => FormatKind::DateTime,
+        (false, false) => FormatKind::Number,
+    }
+}
+
+/// Kinds of all cell formats (`cellXfs`) of the workbook
+#[derive(Default)]
+pub(crate) struct CellFormats {
+    kinds: Vec<FormatKind>,
+}
+
+impl CellFormats {
+    /// Classify cell formats by their number formats: `custom` maps `ifmt` to the format code
+    pub(crate) fn new(ifmts: &[u16], custom: &HashMap<u16, String>) -> Self {
+        CellFormats {
+            kinds: ifmts
+                .iter()
+                .map(|ifmt| match custom.get(ifmt) {
+                    Some(code) => code_kind(code),
+                    None => builtin_kind(*ifmt),
+                })
+                .collect(),
+        }
+    }
+
+    /// Kind of the cell format, unknown formats are treated as numbers
+    pub(crate) fn kind(&self, style: u32) -> FormatKind {
+        self.kinds.get(style as usize).copied().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_kinds() {
+        assert_eq!(builtin_kind(0), FormatKind::Number);
+        assert_eq!(builtin_kind(14), FormatKind::Date);
+        assert_eq!(builtin_kind(22), FormatKind::DateTime);
+        assert_eq!(builtin_kind(49), FormatKind::Number);
+
+        assert_eq!(code_kind("General"), FormatKind::Number);
+        assert_eq!(code_kind("0.00%"), FormatKind::Number);
+        assert_eq!(code_kind("yyyy-mm-dd"), FormatKind::Date);
+        assert_eq!(code_kind("mmm"), FormatKind::Date);
+        assert_eq!(code_kind("dd/mm/yyyy hh:mm:ss"), FormatKind::DateTime);
+        assert_eq!(code_kind("h:mm AM/PM"), FormatKind::DateTime);
+        assert_eq!(code_kind("[h]:mm"), FormatKind::DateTime);
+        assert_eq!(code_kind("[Red]0.00;\"days\" 0"), FormatKind::Number);
+        assert_eq!(code_kind("0\\d"), FormatKind::Number);
+        assert_eq!(code_kind("0.00E+00"), FormatKind::Number);
+
+        let custom = HashMap::from([(164, "d-mmm-yy".to_owned())]);
+        let formats = CellFormats::new(&[0, 164, 22], &custom);
+        assert_eq!(formats.kind(1), FormatKind::Date);
+        assert_eq!(formats.kind(2), FormatKind::DateTime);
+        assert_eq!(formats.kind(3), FormatKind::Number);
+    }
+}
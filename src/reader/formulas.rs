@@ -0,0 +1,476 @@
+//! Formulas of cells, rendered back into the text in A1 notation
+use std::{
+    collections::HashMap,
+    io::{Read, Seek},
+};
+
+use zip::ZipArchive;
+
+use super::{find_sheet, read_rels, resolve_target, scan_part, split_path, XlsbReader};
+use crate::{
+    core::{
+        biff::{
+            records::{
+                BrtArrFmla, BrtBeginList, BrtBeginListCol, BrtExternSheet, BrtFmlaBool,
+                BrtFmlaError, BrtFmlaNum, BrtFmlaString, BrtName, BrtRowHdr, BrtShrFmla,
+                ParsedFormula,
+            },
+            BiffId, BiffRecord, CountedArray, XLWideString,
+        },
+        xml::REL_TABLE,
+    },
+    formula::{decompile, quote_sheet, DecompileContext, FormulaType},
+    CellError, CellRange, Error, Result,
+};
+
+/// Formula of the cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetFormula {
+    /// Zero-based row index
+    pub row: u32,
+    /// Zero-based column index
+    pub col: u16,
+    /// Formula text without the leading `=`, like `SUM(A1:A10)*2`
+    pub formula: String,
+    /// Range of the array formula, which the cell belongs to
+    pub array: Option<CellRange>,
+    /// Reason, why the formula cannot be rendered (like an unsupported token); `formula` is
+    /// empty then
+    pub error: Option<String>,
+}
+
+/// Workbook, which is referenced by `Xti` of `BrtExternSheet`
+enum SupBook {
+    /// This workbook
+    Local,
+    /// Other workbook with its one-based number in formulas, like `[1]Sheet1!A1`
+    External {
+        number: usize,
+        sheets: Vec<String>,
+        names: Vec<String>,
+    },
+    /// Add-in with functions, which are called by their names
+    AddIn { names: Vec<String> },
+}
+
+/// Sheets and names, which are referenced by formulas of the workbook
+#[derive(Default)]
+pub(super) struct FormulaRefs {
+    supbooks: Vec<SupBook>,
+    xtis: Vec<(u32, i32, i32)>,
+    /// Defined names of the workbook, names of macros have only flags and names
+    pub(super) names: Vec<BrtName>,
+    /// Sheets of the workbook, in their order
+    pub(super) sheets: Vec<String>,
+    /// Names of tables and of their columns by ids of tables
+    tables: HashMap<u32, (String, Vec<String>)>,
+}
+
+impl FormulaRefs {
+    /// Records of the workbook part, which are passed to [`FormulaRefs::read`]
+    pub(super) const IDS: [BiffId; 8] = [
+        BiffId::BrtSupBookSrc,
+        BiffId::BrtSupSelf,
+        BiffId::BrtSupSame,
+        BiffId::BrtSupAddin,
+        BiffId::BrtSupTabs,
+        BiffId::BrtSupNameStart,
+        BiffId::BrtExternSheet,
+        BiffId::BrtName,
+    ];
+
+    pub(super) fn read(&mut self, rec: &BiffRecord) -> Result<()> {
+        match rec.id {
+            BiffId::BrtSupBookSrc => {
+                let number = 1 + self
+                    .supbooks
+                    .iter()
+                    .filter(|book| matches!(book, SupBook::External { .. }))
+                    .count();
+                self.supbooks.push(SupBook::External {
+                    number,
+                    sheets: Vec::new(),
+                    names: Vec::new(),
+                });
+            }
+            BiffId::BrtSupSelf | BiffId::BrtSupSame => self.supbooks.push(SupBook::Local),
+            BiffId::BrtSupAddin => self.supbooks.push(SupBook::AddIn { names: Vec::new() }),
+            BiffId::BrtSupTabs => {
+                let tabs = rec.as_biff_data::<CountedArray<XLWideString>>(0)?;
+                if let Some(SupBook::External { sheets, .. }) = self.supbooks.last_mut() {
+                    sheets.extend(tabs.items.into_iter().map(|sheet| sheet.inner));
+                }
+            }
+            BiffId::BrtSupNameStart => {
+                let name = rec.as_biff_data::<XLWideString>(0)?.inner;
+                if let Some(SupBook::External { names, .. } | SupBook::AddIn { names }) =
+                    self.supbooks.last_mut()
+                {
+                    names.push(name);
+                }
+            }
+            BiffId::BrtExternSheet => {
+                let sheets = rec.as_biff::<BrtExternSheet>()?;
+                self.xtis = sheets
+                    .rg_xti
+                    .items
+                    .iter()
+                    .map(|xti| (xti.i_sup_book, xti.itab_first, xti.itab_last))
+                    .collect();
+            }
+            BiffId::BrtName => {
+                let flags = rec.as_biff_data::<u32>(0)?;
+                // names of macros have extra fields, only their names are needed
+                let name = match flags & BrtName::F_PROC {
+                    0 => rec.as_biff::<BrtName>()?,
+                    _ => BrtName {
+                        flags,
+                        name: rec.as_biff_data::<XLWideString>(9)?,
+                        ..Default::default()
+                    },
+                };
+                self.names.push(name);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn supbook(&self, ixti: u16) -> Result<(&SupBook, i32, i32)> {
+        let (isupbook, first, last) =
+            self.xtis.get(ixti as usize).copied().ok_or_else(|| {
+                Error::malformed(format!("formula references unknown Xti {}", ixti))
+            })?;
+        match self.supbooks.get(isupbook as usize) {
+            Some(book) => Ok((book, first, last)),
+            None => Err(Error::malformed(format!(
+                "formula references unknown workbook {}",
+                isupbook
+            ))),
+        }
+    }
+}
+
+impl FormulaRefs {
+    /// Read names of tables of the sheet, which are referenced by structured references
+    pub(super) fn read_tables<R: Read + Seek>(
+        &mut self,
+        zip: &mut ZipArchive<R>,
+        sheet: &str,
+    ) -> Result<()> {
+        let (dir, name) = split_path(sheet);
+        let rels_path = format!("{}_rels/{}.rels", dir, name);
+        if zip.index_for_name(&rels_path).is_none() {
+            return Ok(());
+        }
+        let rels = read_rels(zip, &rels_path)?;
+        for rel in rels.filter_by_type(REL_TABLE) {
+            let path = resolve_target(dir, &rel.target);
+            let ids = [BiffId::BrtBeginList, BiffId::BrtBeginListCol];
+            let mut table = None;
+            scan_part(zip, &path, &ids, BiffId::BrtEndListCols, |rec| {
+                if rec.id == BiffId::BrtBeginList {
+                    let list = rec.as_biff::<BrtBeginList>()?;
+                    table = Some((list.id_list, list.st_display_name.inner, Vec::new()));
+                } else if let Some((_, _, ref mut columns)) = table {
+                    let column = rec.as_biff::<BrtBeginListCol>()?;
+                    columns.push(column.st_name.inner.unwrap_or_default());
+                }
+                Ok(())
+            })?;
+            if let Some((id, name, columns)) = table {
+                self.tables.insert(id, (name, columns));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sheet of this workbook, which is referenced by `ixti` alone
+    pub(super) fn local_sheet(&self, ixti: u16) -> Option<&str> {
+        match self.supbook(ixti).ok()? {
+            (SupBook::Local, first, last) if first == last => self
+                .sheets
+                .get(usize::try_from(first).ok()?)
+                .map(String::as_str),
+            _ => None,
+        }
+    }
+}
+
+/// Names of the sheet range, the first and the last sheets are the same for the single sheet
+fn sheet_range(sheets: &[String], first: i32, last: i32) -> Option<String> {
+    let first = sheets.get(usize::try_from(first).ok()?)?;
+    let last = sheets.get(usize::try_from(last).ok()?)?;
+    match first == last {
+        true => Some(first.clone()),
+        false => Some(format!("{}:{}", first, last)),
+    }
+}
+
+impl DecompileContext for FormulaRefs {
+    fn sheet(&self, ixti: u16) -> Result<String> {
+        let (book, first, last) = self.supbook(ixti)?;
+        let (prefix, sheets) = match book {
+            SupBook::Local => (String::new(), &self.sheets),
+            SupBook::External { number, sheets, .. } => (format!("[{}]", number), sheets),
+            SupBook::AddIn { .. } => return Ok(String::new()),
+        };
+        Ok(match sheet_range(sheets, first, last) {
+            // the workbook prefix is inside quotes: `'[1]Q1 Sales'!A1`
+            Some(range) => match quote_sheet(&range).strip_prefix('\'') {
+                Some(quoted) => format!("'{}{}", prefix, quoted),
+                None => format!("{}{}", prefix, range),
+            },
+            // deleted sheet
+            None if first == -1 => format!("{}{}", prefix, CellError::Ref.as_str()),
+            None => prefix,
+        })
+    }
+
+    fn name(&self, index: u32) -> Result<String> {
+        match index
+            .checked_sub(1)
+            .and_then(|idx| self.names.get(idx as usize))
+        {
+            Some(name) => Ok(name.name.inner.clone()),
+            None => Err(Error::malformed(format!(
+                "formula references unknown name {}",
+                index
+            ))),
+        }
+    }
+
+    fn external_name(&self, ixti: u16, index: u32) -> Result<String> {
+        let names = match self.supbook(ixti)?.0 {
+            SupBook::Local => return self.name(index),
+            SupBook::External { names, .. } | SupBook::AddIn { names } => names,
+        };
+        let name = index
+            .checked_sub(1)
+            .and_then(|idx| names.get(idx as usize))
+            .ok_or_else(|| {
+                Error::malformed(format!("formula references unknown name {}", index))
+            })?;
+        match self.sheet(ixti)? {
+            prefix if prefix.is_empty() => Ok(name.clone()),
+            prefix => Ok(format!("{}!{}", prefix, name)),
+        }
+    }
+
+    fn table(&self, id: u32) -> Result<(&str, &[String])> {
+        match self.tables.get(&id) {
+            Some((name, columns)) => Ok((name, columns)),
+            None => Err(Error::malformed(format!(
+                "formula references unknown table {}",
+                id
+            ))),
+        }
+    }
+}
+
+impl<R: Read + Seek> XlsbReader<R> {
+    /// Formulas of the sheet, row by row. Cached values of formula cells are read by
+    /// [`XlsbReader::cells`].
+    ///
+    /// Shared formulas are rendered for every cell, which shares them; every cell of the array
+    /// formula has the formula of the whole range. Formulas, which cannot be rendered, have
+    /// the reason in [`SheetFormula::error`].
+    pub fn formulas(&mut self, sheet: &str) -> Result<Vec<SheetFormula>> {
+        let path = find_sheet(&self.sheets, sheet)?.to_owned();
+        let ids = [
+            BiffId::BrtRowHdr,
+            BiffId::BrtFmlaString,
+            BiffId::BrtFmlaNum,
+            BiffId::BrtFmlaBool,
+            BiffId::BrtFmlaError,
+            BiffId::BrtShrFmla,
+            BiffId::BrtArrFmla,
+        ];
+        let mut row = None;
+        let mut formulas = Vec::new();
+        // cells of shared and array formulas (with their first cells), which are rendered
+        // when all formulas of ranges are read
+        let mut members = Vec::new();
+        let mut ranges = HashMap::new();
+        scan_part(&mut self.zip, &path, &ids, BiffId::BrtEndSheetData, |rec| {
+            let (cell, formula) = match rec.id {
+                BiffId::BrtRowHdr => {
+                    row = Some(rec.as_biff::<BrtRowHdr>()?.rw.inner);
+                    return Ok(());
+                }
+                BiffId::BrtShrFmla | BiffId::BrtArrFmla => {
+                    let (range, formula) = match rec.id {
+                        BiffId::BrtShrFmla => {
+                            let rec = rec.as_biff::<BrtShrFmla>()?;
+                            (CellRange::from_rfx(&rec.rfx), rec.formula)
+                        }
+                        _ => {
+                            let rec = rec.as_biff::<BrtArrFmla>()?;
+                            (CellRange::from_rfx(&rec.rfx), rec.formula)
+                        }
+                    };
+                    let anchor = (range.first_row, range.first_col);
+                    ranges.insert(anchor, (rec.id, range, formula));
+                    return Ok(());
+                }
+                BiffId::BrtFmlaString => {
+                    let rec = rec.as_biff::<BrtFmlaString>()?;
+                    (rec.cell, rec.formula)
+                }
+                BiffId::BrtFmlaNum => {
+                    let rec = rec.as_biff::<BrtFmlaNum>()?;
+                    (rec.cell, rec.formula)
+                }
+                BiffId::BrtFmlaBool => {
+                    let rec = rec.as_biff::<BrtFmlaBool>()?;
+                    (rec.cell, rec.formula)
+                }
+                _ => {
+                    let rec = rec.as_biff::<BrtFmlaError>()?;
+                    (rec.cell, rec.formula)
+                }
+            };
+            let row = row.ok_or_else(|| Error::malformed("cell record before the row header"))?;
+            let mut cell = SheetFormula {
+                row,
+                col: cell.column.inner as u16,
+                formula: String::new(),
+                array: None,
+                error: None,
+            };
+            match formula.exp_cell() {
+                Some(anchor) => members.push((formulas.len(), anchor)),
+                None => cell.render(&formula, FormulaType::Cell, &self.refs),
+            }
+            formulas.push(cell);
+            Ok(())
+        })?;
+
+        for (idx, (row, col)) in members {
+            let cell = &mut formulas[idx];
+            let Some((id, range, formula)) = ranges.get(&(row, col)) else {
+                return Err(Error::malformed(format!(
+                    "cell ({}, {}) references missing formula of the cell ({}, {})",
+                    cell.row, cell.col, row, col
+                ))
+                .in_part(&path));
+            };
+            let kind = match id {
+                BiffId::BrtArrFmla => {
+                    cell.array = Some(*range);
+                    FormulaType::Array
+                }
+                _ => FormulaType::Shared {
+                    row: cell.row,
+                    col: cell.col,
+                },
+            };
+            cell.render(formula, kind, &self.refs);
+        }
+        Ok(formulas)
+    }
+}
+
+impl SheetFormula {
+    /// Set the text of the formula, or the reason why it cannot be rendered
+    fn render(&mut self, formula: &ParsedFormula, kind: FormulaType, refs: &FormulaRefs) {
+        match decompile(formula, kind, refs) {
+            Ok(text) => self.formula = text,
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{TableColumn, TableOptions, XlsbWriter};
+
+    #[test]
+    fn test_formulas() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet.write(0, 0, 1.5).unwrap();
+        sheet.write_formula(0, 1, "=SUM($A1:A10)*2", 3.0).unwrap();
+        sheet
+            .write_formula(1, 0, "'Q1 Sales'!B2&\"x\"", "x")
+            .unwrap();
+        sheet
+            .write_formula(1, 1, "=IF(Rate>0,NOW(),)", 0.0)
+            .unwrap();
+        sheet.autofilter(0, 0, 1, 1).unwrap();
+        sheet.finish().unwrap();
+        let mut sheet = book.add_sheet("Q1 Sales").unwrap();
+        sheet
+            .write_shared_formula(0, 2, 2, 2, "=A1*$B$1+Data!A1", 0.0)
+            .unwrap();
+        sheet.write_formula_result(1, 2, 0.0).unwrap();
+        sheet.write_formula_result(2, 2, 0.0).unwrap();
+        sheet
+            .write_array_formula(4, 0, 5, 0, "=B1:B2*2", 0.0)
+            .unwrap();
+        sheet.write_formula_result(5, 0, 0.0).unwrap();
+        let options = TableOptions {
+            name: Some("Sales".to_owned()),
+            columns: vec![TableColumn::new("Region"), TableColumn::new("Amount")],
+            ..TableOptions::default()
+        };
+        sheet.add_table(8, 0, 10, &options).unwrap();
+        sheet.finish().unwrap();
+        let data = book.close().unwrap();
+
+        let mut reader = XlsbReader::new(Cursor::new(data.into_inner())).unwrap();
+        let formulas = reader
+            .formulas("Data")
+            .unwrap()
+            .into_iter()
+            .map(|formula| (formula.row, formula.col, formula.formula))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            formulas,
+            [
+                (0, 1, "SUM($A1:A10)*2".to_owned()),
+                (1, 0, "'Q1 Sales'!B2&\"x\"".to_owned()),
+                (1, 1, "IF(Rate>0,NOW(),)".to_owned()),
+            ]
+        );
+
+        let array = Some(CellRange::new(4, 0, 5, 0));
+        let formulas = reader.formulas("Q1 Sales").unwrap();
+        assert_eq!(
+            formulas
+                .iter()
+                .map(|formula| (formula.row, formula.col, formula.formula.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                (0, 2, "A1*$B$1+Data!A1"),
+                (1, 2, "A2*$B$1+Data!A2"),
+                (2, 2, "A3*$B$1+Data!A3"),
+                (4, 0, "B1:B2*2"),
+                (5, 0, "B1:B2*2"),
+            ]
+        );
+        assert_eq!(formulas[0].array, None);
+        assert_eq!(formulas[3].array, array);
+        assert_eq!(formulas[4].array, array);
+        assert!(formulas.iter().all(|formula| formula.error.is_none()));
+        assert!(reader.formulas("Missing").is_err());
+
+        // tables are known to structured references
+        let (name, columns) = reader.refs.table(1).unwrap();
+        assert_eq!(
+            (name, columns),
+            ("Sales", &["Region", "Amount"].map(String::from)[..])
+        );
+        // formulas with unsupported tokens keep the reason instead of failing the sheet
+        let mut cell = formulas[0].clone();
+        cell.render(
+            &ParsedFormula::new(vec![0x18, 0x1D], vec![]),
+            FormulaType::Cell,
+            &reader.refs,
+        );
+        assert!(cell.error.unwrap().contains("not supported"));
+    }
+}
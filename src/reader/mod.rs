@@ -0,0 +1,573 @@
+//! Reader of the XLSB package
+mod batch;
+mod comments;
+mod format;
+mod formulas;
+mod names;
+
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::Path,
+};
+
+use chrono::NaiveDateTime;
+use zip::ZipArchive;
+
+use crate::{
+    core::{
+        biff::{
+            records::{
+                read_sst_item, BrtBundleSh, BrtCellBlank, BrtCellBool, BrtCellError, BrtCellIsst,
+                BrtCellRString, BrtCellReal, BrtCellRk, BrtCellSt, BrtFmlaBool, BrtFmlaError,
+                BrtFmlaNum, BrtFmlaString, BrtFmt, BrtMergeCell, BrtRowHdr, BrtWbProp, BrtXF,
+            },
+            BiffId, BiffRecord, BiffScanner, Cell,
+        },
+        xml::{Relationships, REL_OFFICE_DOCUMENT, REL_SHARED_STRINGS, REL_STYLES},
+    },
+    CellError, CellRange, CellValue, DateSystem, Error, Result,
+};
+pub use batch::{BatchOptions, SheetBatches};
+pub use comments::SheetComment;
+use format::{CellFormats, FormatKind};
+use formulas::FormulaRefs;
+pub use formulas::SheetFormula;
+pub use names::WorkbookName;
+
+/// Sheet, which is listed in the workbook
+struct SheetEntry {
+    name: String,
+    /// Full path of the part inside the package
+    path: String,
+}
+
+/// Single cell of the sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetCell {
+    /// Zero-based row index
+    pub row: u32,
+    /// Zero-based column index
+    pub col: u16,
+    /// Index of the cell format (`cellXfs`) in the styles part
+    pub style: u32,
+    pub value: CellValue,
+}
+
+/// Reader of XLSB workbook.
+///
+/// Workbook part and shared strings are loaded on opening, while sheets are read lazily,
+/// record by record.
+pub struct XlsbReader<R: Read + Seek> {
+    zip: ZipArchive<R>,
+    sheets: Vec<SheetEntry>,
+    strings: Vec<String>,
+    formats: CellFormats,
+    date_system: DateSystem,
+    /// Sheets and names, which are referenced by formulas
+    refs: FormulaRefs,
+}
+
+impl XlsbReader<BufReader<File>> {
+    /// Open the file by the given `path`
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        XlsbReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> XlsbReader<R> {
+    pub fn new(inner: R) -> Result<Self> {
+        let mut zip = ZipArchive::new(inner)?;
+
+        let package_rels = read_rels(&mut zip, "_rels/.rels")?;
+        let book_path = match package_rels.find_by_type(REL_OFFICE_DOCUMENT) {
+            Some(rel) => resolve_target("", &rel.target),
+            None => {
+                return Err(Error::malformed("package has no workbook part").in_part("_rels/.rels"))
+            }
+        };
+        let (book_dir, book_name) = split_path(&book_path);
+        let book_rels = read_rels(&mut zip, &format!("{}_rels/{}.rels", book_dir, book_name))?;
+
+        let mut sheets = Vec::new();
+        let mut date_system = DateSystem::default();
+        let mut refs = FormulaRefs::default();
+        let mut ids = vec![BiffId::BrtWbProp, BiffId::BrtBundleSh];
+        ids.extend(FormulaRefs::IDS);
+        scan_part(&mut zip, &book_path, &ids, BiffId::BrtEndBook, |rec| {
+            if rec.id == BiffId::BrtWbProp {
+                if rec.as_biff::<BrtWbProp>()?.flags & BrtWbProp::F_1904 != 0 {
+                    date_system = DateSystem::V1904;
+                }
+                return Ok(());
+            }
+            if rec.id != BiffId::BrtBundleSh {
+                return refs.read(&rec);
+            }
+            let sheet = rec.as_biff::<BrtBundleSh>()?;
+            let rel_id = sheet.str_rel_id.inner.unwrap_or_default();
+            match book_rels.get(&rel_id) {
+                Some(rel) => sheets.push(SheetEntry {
+                    name: sheet.str_name.inner,
+                    path: resolve_target(book_dir, &rel.target),
+                }),
+                None => {
+                    return Err(Error::malformed(format!(
+                        "sheet {:?} refers to missing relationship {:?}",
+                        sheet.str_name.inner, rel_id
+                    ))
+                    .with_record(rec.id))
+                }
+            }
+            Ok(())
+        })?;
+
+        refs.sheets = sheets.iter().map(|sheet| sheet.name.clone()).collect();
+        for sheet in &sheets {
+            refs.read_tables(&mut zip, &sheet.path)?;
+        }
+
+        let mut strings = Vec::new();
+        if let Some(rel) = book_rels.find_by_type(REL_SHARED_STRINGS) {
+            let path = resolve_target(book_dir, &rel.target);
+            scan_part(
+                &mut zip,
+                &path,
+                &[BiffId::BrtSSTItem],
+                BiffId::BrtEndSst,
+                |rec| {
+                    strings.push(read_sst_item(&rec)?);
+                    Ok(())
+                },
+            )?;
+        }
+
+        let formats = match book_rels.find_by_type(REL_STYLES) {
+            Some(rel) => read_formats(&mut zip, &resolve_target(book_dir, &rel.target))?,
+            None => CellFormats::default(),
+        };
+
+        Ok(XlsbReader {
+            zip,
+            sheets,
+            strings,
+            formats,
+            date_system,
+            refs,
+        })
+    }
+
+    /// Date system of the workbook
+    pub fn date_system(&self) -> DateSystem {
+        self.date_system
+    }
+
+    /// Date and time of the cell, if it holds a number with the date or time format
+    pub fn datetime(&self, cell: &SheetCell) -> Option<NaiveDateTime> {
+        match (&cell.value, self.formats.kind(cell.style)) {
+            (CellValue::Number(_), FormatKind::Number) => None,
+            (CellValue::Number(serial), _) => self.date_system.serial_to_datetime(*serial),
+            _ => None,
+        }
+    }
+
+    /// Names of the sheets, in the order of the workbook
+    pub fn sheet_names(&self) -> Vec<&str> {
+        self.sheets
+            .iter()
+            .map(|sheet| sheet.name.as_str())
+            .collect()
+    }
+
+    /// Iterate over cells of the sheet, row by row
+    pub fn cells(&mut self, sheet: &str) -> Result<Cells<'_>> {
+        Cells::open(&mut self.zip, &self.sheets, &self.strings, sheet)
+    }
+
+    /// Merged ranges of the sheet
+    pub fn merged_ranges(&mut self, sheet: &str) -> Result<Vec<CellRange>> {
+        let path = find_sheet(&self.sheets, sheet)?.to_owned();
+        let mut ranges = Vec::new();
+        scan_part(
+            &mut self.zip,
+            &path,
+            &[BiffId::BrtMergeCell],
+            BiffId::BrtEndMergeCells,
+            |rec| {
+                ranges.push(CellRange::from_rfx(&rec.as_biff::<BrtMergeCell>()?.rfx));
+                Ok(())
+            },
+        )?;
+        Ok(ranges)
+    }
+}
+
+/// Records of the sheet data, which are processed by [`Cells`].
+///
+/// Formula cells are read as their cached values.
+const CELL_IDS: [BiffId; 13] = [
+    BiffId::BrtRowHdr,
+    BiffId::BrtCellBlank,
+    BiffId::BrtCellRk,
+    BiffId::BrtCellError,
+    BiffId::BrtCellBool,
+    BiffId::BrtCellReal,
+    BiffId::BrtCellSt,
+    BiffId::BrtCellIsst,
+    BiffId::BrtCellRString,
+    BiffId::BrtFmlaString,
+    BiffId::BrtFmlaNum,
+    BiffId::BrtFmlaBool,
+    BiffId::BrtFmlaError,
+];
+
+/// Iterator over cells of the sheet, created by [`XlsbReader::cells`]
+pub struct Cells<'a> {
+    /// Records of the sheet part; the type of the zip entry is erased, as it depends on the
+    /// version of `zip`
+    scanner: BiffScanner<BufReader<Box<dyn Read + 'a>>>,
+    strings: &'a [String],
+    row: Option<u32>,
+    /// Path of the sheet part, to locate errors
+    part: String,
+}
+
+impl<'a> Cells<'a> {
+    fn open<R: Read + Seek>(
+        zip: &'a mut ZipArchive<R>,
+        sheets: &[SheetEntry],
+        strings: &'a [String],
+        sheet: &str,
+    ) -> Result<Self> {
+        let path = find_sheet(sheets, sheet)?;
+        let part: Box<dyn Read + 'a> = Box::new(zip.by_name(path)?);
+        Ok(Cells {
+            scanner: BiffScanner::new(BufReader::new(part))
+                .ids_only(&CELL_IDS)
+                .break_on(BiffId::BrtEndSheetData),
+            strings,
+            row: None,
+            part: path.to_owned(),
+        })
+    }
+
+    fn read_cell(&mut self, rec: BiffRecord) -> Result<Option<SheetCell>> {
+        let (cell, value) = match rec.id {
+            BiffId::BrtRowHdr => {
+                self.row = Some(rec.as_biff::<BrtRowHdr>()?.rw.inner);
+                return Ok(None);
+            }
+            BiffId::BrtCellBlank => (rec.as_biff::<BrtCellBlank>()?.cell, CellValue::Blank),
+            BiffId::BrtCellRk => {
+                let rec = rec.as_biff::<BrtCellRk>()?;
+                let value = rec.value();
+                (rec.cell, CellValue::Number(value))
+            }
+            BiffId::BrtCellError => {
+                let rec = rec.as_biff::<BrtCellError>()?;
+                (rec.cell, error_value(rec.b_error)?)
+            }
+            BiffId::BrtCellBool => {
+                let rec = rec.as_biff::<BrtCellBool>()?;
+                (rec.cell, CellValue::Bool(rec.f_bool != 0))
+            }
+            BiffId::BrtCellReal => {
+                let rec = rec.as_biff::<BrtCellReal>()?;
+                (rec.cell, CellValue::Number(rec.xnum))
+            }
+            BiffId::BrtCellSt => {
+                let rec = rec.as_biff::<BrtCellSt>()?;
+                (rec.cell, CellValue::String(rec.value.inner))
+            }
+            BiffId::BrtCellIsst => {
+                let rec = rec.as_biff::<BrtCellIsst>()?;
+                match self.strings.get(rec.isst as usize) {
+                    Some(value) => (rec.cell, CellValue::String(value.clone())),
+                    None => return Err(Error::invalid_value().with_field("isst")),
+                }
+            }
+            BiffId::BrtCellRString => {
+                let rec = rec.as_biff::<BrtCellRString>()?;
+                (rec.cell, CellValue::String(rec.value.value.inner))
+            }
+            BiffId::BrtFmlaString => {
+                let rec = rec.as_biff::<BrtFmlaString>()?;
+                (rec.cell, CellValue::String(rec.value.inner))
+            }
+            BiffId::BrtFmlaNum => {
+                let rec = rec.as_biff::<BrtFmlaNum>()?;
+                (rec.cell, CellValue::Number(rec.xnum))
+            }
+            BiffId::BrtFmlaBool => {
+                let rec = rec.as_biff::<BrtFmlaBool>()?;
+                (rec.cell, CellValue::Bool(rec.f_bool != 0))
+            }
+            BiffId::BrtFmlaError => {
+                let rec = rec.as_biff::<BrtFmlaError>()?;
+                (rec.cell, error_value(rec.b_error)?)
+            }
+            _ => return Ok(None),
+        };
+        self.make_cell(cell, value).map(Some)
+    }
+
+    fn make_cell(&self, cell: Cell, value: CellValue) -> Result<SheetCell> {
+        match self.row {
+            Some(row) => Ok(SheetCell {
+                row,
+                col: cell.column.inner as u16,
+                style: i32::from(cell.i_style_ref) as u32,
+                value,
+            }),
+            None => Err(Error::malformed("cell record before the row header")),
+        }
+    }
+}
+
+impl Iterator for Cells<'_> {
+    type Item = Result<SheetCell>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rec = match self.scanner.next()? {
+                Ok(rec) => rec,
+                Err(e) => return Some(Err(e.in_part(&self.part))),
+            };
+            let id = rec.id;
+            match self.read_cell(rec) {
+                Ok(Some(cell)) => return Some(Ok(cell)),
+                Ok(None) => continue,
+                Err(e) => {
+                    return Some(Err(e
+                        .with_record(id)
+                        .at_offset(self.scanner.record_offset())
+                        .in_part(&self.part)))
+                }
+            }
+        }
+    }
+}
+
+/// Path of the sheet part by the sheet name
+fn find_sheet<'a>(sheets: &'a [SheetEntry], name: &str) -> Result<&'a str> {
+    match sheets.iter().find(|entry| entry.name == name) {
+        Some(entry) => Ok(entry.path.as_str()),
+        None => Err(Error::SheetNotFound(name.to_owned())),
+    }
+}
+
+fn error_value(b_error: u8) -> Result<CellValue> {
+    match CellError::from_code(b_error) {
+        Some(error) => Ok(CellValue::Error(error)),
+        None => Err(Error::invalid_value().with_field("b_error")),
+    }
+}
+
+/// Pass records of the part with the given ids to `f`, until the `break_id` record.
+///
+/// Errors are located at the part and the record.
+fn scan_part<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    path: &str,
+    ids: &[BiffId],
+    break_id: BiffId,
+    mut f: impl FnMut(BiffRecord) -> Result<()>,
+) -> Result<()> {
+    let mut scanner = BiffScanner::new(BufReader::new(zip.by_name(path)?))
+        .ids_only(ids)
+        .break_on(break_id);
+    while let Some(rec) = scanner.next() {
+        let rec = rec.map_err(|e| e.in_part(path))?;
+        f(rec).map_err(|e| e.at_offset(scanner.record_offset()).in_part(path))?;
+    }
+    Ok(())
+}
+
+/// Read number formats of cell formats from the styles part
+fn read_formats<R: Read + Seek>(zip: &mut ZipArchive<R>, path: &str) -> Result<CellFormats> {
+    let mut custom = HashMap::new();
+    let mut ifmts = Vec::new();
+    let mut in_cell_xfs = false;
+    let ids = [
+        BiffId::BrtFmt,
+        BiffId::BrtBeginCellXFs,
+        BiffId::BrtXF,
+        BiffId::BrtEndCellXFs,
+    ];
+    scan_part(zip, path, &ids, BiffId::BrtEndStyleSheet, |rec| {
+        match rec.id {
+            BiffId::BrtFmt => {
+                let fmt = rec.as_biff::<BrtFmt>()?;
+                custom.insert(fmt.ifmt, fmt.st_fmt_code.inner);
+            }
+            BiffId::BrtBeginCellXFs => in_cell_xfs = true,
+            BiffId::BrtEndCellXFs => in_cell_xfs = false,
+            // formats of cell styles (`cellStyleXfs`) are not referenced by cells
+            BiffId::BrtXF if in_cell_xfs => ifmts.push(rec.as_biff::<BrtXF>()?.i_fmt),
+            _ => {}
+        }
+        Ok(())
+    })?;
+    Ok(CellFormats::new(&ifmts, &custom))
+}
+
+fn read_rels<R: Read + Seek>(zip: &mut ZipArchive<R>, path: &str) -> Result<Relationships> {
+    let mut xml = Vec::new();
+    zip.by_name(path)?.read_to_end(&mut xml)?;
+    Relationships::parse(&xml).map_err(|e| e.in_part(path))
+}
+
+/// Split the part path into the directory (with trailing slash) and the file name
+fn split_path(path: &str) -> (&str, &str) {
+    match path.rfind('/') {
+        Some(idx) => path.split_at(idx + 1),
+        None => ("", path),
+    }
+}
+
+/// Resolve the target of the relationship into the full path of the part
+fn resolve_target(base_dir: &str, target: &str) -> String {
+    let full = match target.strip_prefix('/') {
+        Some(absolute) => absolute.to_owned(),
+        None => format!("{}{}", base_dir, target),
+    };
+    let mut parts: Vec<&str> = Vec::new();
+    for part in full.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use chrono::{FixedOffset, NaiveDate, NaiveTime};
+
+    use super::*;
+    use crate::XlsbWriter;
+
+    #[test]
+    fn test_resolve_target() {
+        assert_eq!(resolve_target("", "xl/workbook.bin"), "xl/workbook.bin");
+        assert_eq!(
+            resolve_target("xl/", "worksheets/sheet1.bin"),
+            "xl/worksheets/sheet1.bin"
+        );
+        assert_eq!(
+            resolve_target("xl/", "/xl/worksheets/sheet1.bin"),
+            "xl/worksheets/sheet1.bin"
+        );
+        assert_eq!(
+            resolve_target("xl/worksheets/", "../sharedStrings.bin"),
+            "xl/sharedStrings.bin"
+        );
+        assert_eq!(split_path("xl/workbook.bin"), ("xl/", "workbook.bin"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("First").unwrap();
+        sheet.write(0, 0, "name").unwrap();
+        sheet.write(0, 2, 2.5).unwrap();
+        sheet.write(3, 1, false).unwrap();
+        sheet.write(3, 2, CellError::NA).unwrap();
+        sheet.write_blank(4, 0).unwrap();
+        sheet.merge_range(0, 0, 0, 1).unwrap();
+        sheet.merge_range(5, 2, 7, 3).unwrap();
+        assert!(sheet.merge_range(6, 3, 6, 5).is_err());
+        assert!(sheet.merge_range(8, 0, 8, 0).is_err());
+        sheet.finish().unwrap();
+        book.add_sheet("Second").unwrap().finish().unwrap();
+        let data = book.close().unwrap();
+
+        let mut reader = XlsbReader::new(Cursor::new(data.into_inner())).unwrap();
+        assert_eq!(reader.sheet_names(), ["First", "Second"]);
+        let cells = reader
+            .cells("First")
+            .unwrap()
+            .map(|cell| {
+                let cell = cell.unwrap();
+                (cell.row, cell.col, cell.value)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            [
+                (0, 0, CellValue::String("name".to_owned())),
+                (0, 2, CellValue::Number(2.5)),
+                (3, 1, CellValue::Bool(false)),
+                (3, 2, CellValue::Error(CellError::NA)),
+                (4, 0, CellValue::Blank),
+            ]
+        );
+        assert_eq!(
+            reader.merged_ranges("First").unwrap(),
+            [CellRange::new(0, 0, 0, 1), CellRange::new(5, 2, 7, 3)]
+        );
+        assert!(reader.merged_ranges("Second").unwrap().is_empty());
+        assert_eq!(reader.cells("Second").unwrap().count(), 0);
+        assert!(reader.cells("Third").is_err());
+    }
+
+    #[test]
+    fn test_dates() {
+        let date = NaiveDate::from_ymd_opt(1900, 2, 28).unwrap();
+        let datetime = NaiveDate::from_ymd_opt(2024, 5, 17)
+            .unwrap()
+            .and_hms_milli_opt(13, 45, 30, 250)
+            .unwrap();
+        let tz = FixedOffset::east_opt(3 * 3600).unwrap();
+        for system in [DateSystem::V1900, DateSystem::V1904] {
+            let mut book = XlsbWriter::new(Cursor::new(Vec::new())).with_date_system(system);
+            let mut sheet = book.add_sheet("Dates").unwrap();
+            sheet.write_datetime(0, 0, datetime).unwrap();
+            sheet
+                .write_datetime_tz(0, 1, &datetime.and_utc().with_timezone(&tz))
+                .unwrap();
+            sheet.write_number(0, 2, 45000.0).unwrap();
+            let written = sheet.write_date(1, 0, date);
+            sheet.finish().unwrap();
+            let data = book.close().unwrap();
+
+            let mut reader = XlsbReader::new(Cursor::new(data.into_inner())).unwrap();
+            assert_eq!(reader.date_system(), system);
+            let cells = reader
+                .cells("Dates")
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap();
+            let values = cells
+                .iter()
+                .map(|cell| reader.datetime(cell))
+                .collect::<Vec<_>>();
+            let local = datetime + chrono::Duration::hours(3);
+            match system {
+                DateSystem::V1900 => {
+                    assert!(written.is_ok());
+                    assert_eq!(
+                        values,
+                        [
+                            Some(datetime),
+                            Some(local),
+                            None,
+                            Some(date.and_time(NaiveTime::MIN))
+                        ]
+                    );
+                }
+                DateSystem::V1904 => {
+                    assert!(written.is_err());
+                    assert_eq!(values, [Some(datetime), Some(local), None]);
+                }
+            }
+        }
+    }
+}
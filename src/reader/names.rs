@@ -0,0 +1,200 @@
+//! Defined names of the workbook, with ranges they refer to
+use std::io::{Read, Seek};
+
+use super::XlsbReader;
+use crate::{
+    core::biff::records::BrtName,
+    formula::{decompile, FormulaType},
+    CellRange, Result,
+};
+
+/// Defined name of the workbook or of the sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkbookName {
+    pub name: String,
+    /// Sheet, which the name is local to, `None` for names of the workbook
+    pub sheet: Option<String>,
+    /// Formula text without the leading `=`, like `Data!$A$1:$C$10`
+    pub formula: String,
+    pub comment: Option<String>,
+    pub hidden: bool,
+    /// Sheet and range, when the name refers to the single range
+    pub range: Option<(String, CellRange)>,
+}
+
+impl<R: Read + Seek> XlsbReader<R> {
+    /// Defined names of the workbook, including built-in names, like `_xlnm.Print_Area`;
+    /// names of functions and macros are skipped
+    pub fn defined_names(&self) -> Result<Vec<WorkbookName>> {
+        let refs = &self.refs;
+        let mut names = Vec::new();
+        for name in &refs.names {
+            if name.flags & (BrtName::F_FUNC | BrtName::F_PROC) != 0 {
+                continue;
+            }
+            names.push(WorkbookName {
+                name: name.name.inner.clone(),
+                sheet: refs.sheets.get(name.itab as usize).cloned(),
+                // names, which are referenced by formulas but not defined, have no formula
+                formula: match name.formula.rgce.is_empty() {
+                    true => String::new(),
+                    false => decompile(&name.formula, FormulaType::Name, refs)?,
+                },
+                comment: name.comment.inner.clone(),
+                hidden: name.flags & BrtName::F_HIDDEN != 0,
+                range: self.name_range(name),
+            });
+        }
+        Ok(names)
+    }
+
+    /// Sheet and range of the name, like `InputData`; names are case-insensitive, and the name
+    /// of the workbook is preferred to names of sheets
+    pub fn named_range(&self, name: &str) -> Option<(String, CellRange)> {
+        let lowercase = name.to_lowercase();
+        let mut found = self
+            .refs
+            .names
+            .iter()
+            .filter(|other| other.name.inner.to_lowercase() == lowercase)
+            .collect::<Vec<_>>();
+        found.sort_by_key(|other| other.itab != BrtName::ITAB_WORKBOOK);
+        found.first().and_then(|other| self.name_range(other))
+    }
+
+    /// Range of the name, which formula is the single 3D reference, like `Data!$A$1:$C$10`
+    fn name_range(&self, name: &BrtName) -> Option<(String, CellRange)> {
+        let rgce = name.formula.rgce.as_slice();
+        let u16_at = |pos: usize| u16::from_le_bytes([rgce[pos], rgce[pos + 1]]);
+        let u32_at = |pos: usize| u32::from_le_bytes(rgce[pos..pos + 4].try_into().unwrap());
+        // the column has flags of relative references in its high bits
+        let col_at = |pos: usize| u16_at(pos) & 0x3FFF;
+        let range = match (rgce.first()? & 0x1F, rgce.len()) {
+            // PtgRef3d
+            (0x1A, 9) => {
+                let (row, col) = (u32_at(3), col_at(7));
+                CellRange::new(row, col, row, col)
+            }
+            // PtgArea3d
+            (0x1B, 15) => CellRange::new(u32_at(3), col_at(11), u32_at(7), col_at(13)),
+            _ => return None,
+        };
+        let sheet = self.refs.local_sheet(u16_at(1))?;
+        Some((sheet.to_owned(), range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{DefinedName, XlsbWriter};
+
+    #[test]
+    fn test_defined_names() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        book.define_name(
+            &DefinedName::new("InputData", "='Raw Data'!$A$1:$C$10").with_comment("Inputs"),
+        )
+        .unwrap();
+        let mut rate = DefinedName::new("Rate", "=0.2");
+        rate.hidden = true;
+        book.define_name(&rate).unwrap();
+        assert!(book.define_name(&rate).is_err());
+        assert!(book
+            .define_name(&DefinedName::new("B12", "='Raw Data'!$A$1"))
+            .is_err());
+        assert!(book.define_name(&DefinedName::new("Bad", "=$A$1")).is_err());
+
+        let mut sheet = book.add_sheet("Raw Data").unwrap();
+        sheet
+            .define_name(&DefinedName::new("Rate", "='Raw Data'!$E$1"))
+            .unwrap();
+        sheet.write_formula(0, 3, "Rate*2", 0.0).unwrap();
+        sheet.set_print_area(0, 0, 9, 2).unwrap();
+        sheet.set_repeat_rows(0, 0).unwrap();
+        sheet.set_repeat_columns(0, 1).unwrap();
+        sheet.finish().unwrap();
+        let mut sheet = book.add_sheet("Report").unwrap();
+        sheet
+            .write_formula(0, 0, "SUM(InputData)*Rate", 0.0)
+            .unwrap();
+        sheet.finish().unwrap();
+        let data = book.close().unwrap();
+
+        let mut reader = XlsbReader::new(Cursor::new(data.into_inner())).unwrap();
+        assert_eq!(
+            reader.named_range("inputdata"),
+            Some(("Raw Data".to_owned(), CellRange::new(0, 0, 9, 2)))
+        );
+        assert_eq!(reader.named_range("Rate"), None);
+        assert_eq!(reader.named_range("Missing"), None);
+        let names = reader.defined_names().unwrap();
+        let summary = names
+            .iter()
+            .map(|name| {
+                (
+                    name.name.as_str(),
+                    name.sheet.as_deref(),
+                    name.formula.as_str(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("InputData", None, "'Raw Data'!$A$1:$C$10"),
+                ("Rate", None, "0.2"),
+                ("Rate", Some("Raw Data"), "'Raw Data'!$E$1"),
+                (
+                    "_xlnm.Print_Area",
+                    Some("Raw Data"),
+                    "'Raw Data'!$A$1:$C$10"
+                ),
+                (
+                    "_xlnm.Print_Titles",
+                    Some("Raw Data"),
+                    "'Raw Data'!$A:$B,'Raw Data'!$1:$1"
+                ),
+            ]
+        );
+        assert_eq!(names[0].comment.as_deref(), Some("Inputs"));
+        assert!(names[1].hidden);
+        assert_eq!(
+            names[2].range,
+            Some(("Raw Data".to_owned(), CellRange::new(0, 4, 0, 4)))
+        );
+
+        assert!(names.iter().all(|name| !name.formula.is_empty()));
+
+        let formulas = reader.formulas("Raw Data").unwrap();
+        assert_eq!(formulas[0].formula, "Rate*2");
+        let formulas = reader.formulas("Report").unwrap();
+        assert_eq!(formulas[0].formula, "SUM(InputData)*Rate");
+    }
+
+    #[test]
+    fn test_undefined_names() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet.write_formula(0, 0, "Undefined*2", 0.0).unwrap();
+        sheet.finish().unwrap();
+        let data = book.close().unwrap();
+
+        let reader = XlsbReader::new(Cursor::new(data.into_inner())).unwrap();
+        let names = reader.defined_names().unwrap();
+        assert_eq!(
+            names,
+            [WorkbookName {
+                name: "Undefined".to_owned(),
+                sheet: None,
+                formula: String::new(),
+                comment: None,
+                hidden: true,
+                range: None,
+            }]
+        );
+        assert_eq!(reader.named_range("Undefined"), None);
+    }
+}
@@ -0,0 +1,186 @@
+//! Cell styles: fonts, fills, borders, alignment and number formats
+
+/// Color of the font, fill or border line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Color {
+    /// Color, which is chosen by the application (usually black)
+    #[default]
+    Auto,
+    Rgb(u8, u8, u8),
+    /// Color of the workbook theme by its index, like `1` for the dark text color
+    Theme(u8),
+}
+
+/// Font of the cell text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    pub name: String,
+    /// Size in points, from 1 to 409
+    pub size: f64,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikeout: bool,
+    pub color: Color,
+}
+
+impl Default for Font {
+    /// Default font of the workbook: Calibri, 11 pt
+    fn default() -> Self {
+        Font {
+            name: "Calibri".to_owned(),
+            size: 11.0,
+            bold: false,
+            italic: false,
+            underline: false,
+            strikeout: false,
+            color: Color::Theme(1),
+        }
+    }
+}
+
+/// Style of the border line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u8)]
+pub enum BorderStyle {
+    #[default]
+    None = 0,
+    Thin = 1,
+    Medium = 2,
+    Dashed = 3,
+    Dotted = 4,
+    Thick = 5,
+    Double = 6,
+    Hair = 7,
+    MediumDashed = 8,
+    DashDot = 9,
+    MediumDashDot = 10,
+    DashDotDot = 11,
+    MediumDashDotDot = 12,
+    SlantDashDot = 13,
+}
+
+/// Single side of the cell border
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BorderLine {
+    pub style: BorderStyle,
+    pub color: Color,
+}
+
+impl BorderLine {
+    pub fn new(style: BorderStyle) -> Self {
+        BorderLine {
+            style,
+            color: Color::Auto,
+        }
+    }
+}
+
+/// Border of the cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Border {
+    pub left: BorderLine,
+    pub right: BorderLine,
+    pub top: BorderLine,
+    pub bottom: BorderLine,
+}
+
+impl Border {
+    /// Border with the same line on all sides
+    pub fn all(style: BorderStyle) -> Self {
+        let line = BorderLine::new(style);
+        Border {
+            left: line,
+            right: line,
+            top: line,
+            bottom: line,
+        }
+    }
+}
+
+/// Horizontal alignment of the cell content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u8)]
+pub enum HorizontalAlign {
+    /// Text to the left, numbers to the right
+    #[default]
+    General = 0,
+    Left = 1,
+    Center = 2,
+    Right = 3,
+    Fill = 4,
+    Justify = 5,
+    CenterContinuous = 6,
+    Distributed = 7,
+}
+
+/// Vertical alignment of the cell content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[repr(u8)]
+pub enum VerticalAlign {
+    Top = 0,
+    Center = 1,
+    #[default]
+    Bottom = 2,
+    Justify = 3,
+    Distributed = 4,
+}
+
+/// Format of the cell, which is registered by [`XlsbWriter::add_style`](crate::XlsbWriter::add_style)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub font: Font,
+    /// Solid background color
+    pub fill: Option<Color>,
+    pub border: Border,
+    /// Number format code, like `0.00%` or `"$"#,##0.00`
+    pub number_format: String,
+    pub horizontal_align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    pub wrap_text: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            font: Font::default(),
+            fill: None,
+            border: Border::default(),
+            number_format: "General".to_owned(),
+            horizontal_align: HorizontalAlign::default(),
+            vertical_align: VerticalAlign::default(),
+            wrap_text: false,
+        }
+    }
+}
+
+/// Registered cell format of the workbook, which can be applied to cells.
+///
+/// The default value is the format of unstyled cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct StyleId(pub(crate) u32);
+
+impl StyleId {
+    /// Index of the cell format (`cellXfs`), as it is stored in [`SheetCell::style`](crate::SheetCell::style)
+    pub fn index(self) -> u32 {
+        self.0
+    }
+}
+
+/// Format, which is applied over the cell style by conditional formatting.
+///
+/// Only set attributes override the style of the cell.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct DifferentialFormat {
+    pub font_color: Option<Color>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub underline: Option<bool>,
+    pub strikeout: Option<bool>,
+    /// Solid background color
+    pub fill: Option<Color>,
+    /// Line on all sides of the cell
+    pub border: Option<BorderLine>,
+    /// Number format code, like `0.00%`
+    pub number_format: Option<String>,
+}
@@ -0,0 +1,118 @@
+use std::fmt::Display;
+
+/// Error value of the cell
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum CellError {
+    /// `#NULL!`
+    Null = 0x00,
+    /// `#DIV/0!`
+    Div0 = 0x07,
+    /// `#VALUE!`
+    Value = 0x0F,
+    /// `#REF!`
+    Ref = 0x17,
+    /// `#NAME?`
+    Name = 0x1D,
+    /// `#NUM!`
+    Num = 0x24,
+    /// `#N/A`
+    NA = 0x2A,
+    /// `#GETTING_DATA`
+    GettingData = 0x2B,
+}
+
+impl CellError {
+    /// Binary code of the error, as it stored in the file
+    pub const fn code(self) -> u8 {
+        self as u8
+    }
+
+    pub const fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0x00 => Some(CellError::Null),
+            0x07 => Some(CellError::Div0),
+            0x0F => Some(CellError::Value),
+            0x17 => Some(CellError::Ref),
+            0x1D => Some(CellError::Name),
+            0x24 => Some(CellError::Num),
+            0x2A => Some(CellError::NA),
+            0x2B => Some(CellError::GettingData),
+            _ => None,
+        }
+    }
+
+    /// Text of the error, as it displayed by Excel
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            CellError::Null => "#NULL!",
+            CellError::Div0 => "#DIV/0!",
+            CellError::Value => "#VALUE!",
+            CellError::Ref => "#REF!",
+            CellError::Name => "#NAME?",
+            CellError::Num => "#NUM!",
+            CellError::NA => "#N/A",
+            CellError::GettingData => "#GETTING_DATA",
+        }
+    }
+}
+
+impl Display for CellError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Value of the single cell
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum CellValue {
+    /// Cell without value (it still can have a style)
+    #[default]
+    Blank,
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Error(CellError),
+}
+
+impl From<f64> for CellValue {
+    fn from(value: f64) -> Self {
+        CellValue::Number(value)
+    }
+}
+
+impl From<i32> for CellValue {
+    fn from(value: i32) -> Self {
+        CellValue::Number(value as f64)
+    }
+}
+
+impl From<&str> for CellValue {
+    fn from(value: &str) -> Self {
+        CellValue::String(value.to_owned())
+    }
+}
+
+impl From<String> for CellValue {
+    fn from(value: String) -> Self {
+        CellValue::String(value)
+    }
+}
+
+impl From<bool> for CellValue {
+    fn from(value: bool) -> Self {
+        CellValue::Bool(value)
+    }
+}
+
+impl From<CellError> for CellValue {
+    fn from(value: CellError) -> Self {
+        CellValue::Error(value)
+    }
+}
+
+impl<T: Into<CellValue>> From<Option<T>> for CellValue {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or_default()
+    }
+}
@@ -0,0 +1,485 @@
+//! Writing of Arrow record batches into the sheet
+use std::io::{Seek, Write};
+
+use super::{
+    table::{subtotal_formula, ColumnTotals},
+    Panes, SheetWriter, TableColumn, TableOptions, TotalFunction, XF_DATE, XF_DATETIME,
+};
+use crate::{
+    core::biff::{MAX_COL, MAX_ROW},
+    CellError, CellRange, DateSystem, Error, Result,
+};
+use arrow::{
+    array::{
+        timezone::Tz, Array, ArrayRef, AsArray, BooleanArray, Float64Array, Int64Array,
+        LargeStringArray, PrimitiveArray, StringArray,
+    },
+    compute::cast,
+    datatypes::{
+        ArrowTimestampType, DataType, Date32Type, Date64Type, Float64Type, Int64Type, Schema,
+        TimeUnit, TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+        TimestampSecondType, UInt64Type,
+    },
+    record_batch::RecordBatch,
+    temporal_conversions::{as_datetime, as_datetime_with_timezone},
+};
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// Column of the batch, converted to the form, which is ready to be written
+enum BatchColumn<'a> {
+    Int(Int64Array, u32),
+    Float(Float64Array, u32),
+    Bool(&'a BooleanArray),
+    Utf8(&'a StringArray),
+    LargeUtf8(&'a LargeStringArray),
+}
+
+impl<'a> BatchColumn<'a> {
+    /// Dates out of the range of the date system become NaN (and are written as errors)
+    fn new(name: &str, array: &'a ArrayRef, date_system: DateSystem) -> Result<Self> {
+        let serial = |millis: i64| date_system.millis_to_serial(millis).unwrap_or(f64::NAN);
+        match array.data_type() {
+            DataType::Int64 => check_exact_integers(
+                name,
+                array
+                    .as_primitive::<Int64Type>()
+                    .iter()
+                    .flatten()
+                    .map(i128::from),
+            )?,
+            DataType::UInt64 => check_exact_integers(
+                name,
+                array
+                    .as_primitive::<UInt64Type>()
+                    .iter()
+                    .flatten()
+                    .map(i128::from),
+            )?,
+            _ => {}
+        }
+        Ok(match array.data_type() {
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32 => BatchColumn::Int(
+                cast(array, &DataType::Int64)?
+                    .as_primitive::<Int64Type>()
+                    .clone(),
+                0,
+            ),
+            DataType::UInt64 | DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+                BatchColumn::Float(
+                    cast(array, &DataType::Float64)?
+                        .as_primitive::<Float64Type>()
+                        .clone(),
+                    0,
+                )
+            }
+            DataType::Boolean => BatchColumn::Bool(array.as_boolean()),
+            DataType::Utf8 => BatchColumn::Utf8(array.as_string::<i32>()),
+            DataType::LargeUtf8 => BatchColumn::LargeUtf8(array.as_string::<i64>()),
+            DataType::Date32 => BatchColumn::Float(
+                array
+                    .as_primitive::<Date32Type>()
+                    .unary(|days| serial(days as i64 * MILLIS_PER_DAY)),
+                XF_DATE,
+            ),
+            DataType::Date64 => BatchColumn::Float(
+                array.as_primitive::<Date64Type>().unary(serial),
+                XF_DATETIME,
+            ),
+            DataType::Timestamp(unit, tz) => {
+                let tz = match tz {
+                    Some(tz) => Some(tz.parse::<Tz>()?),
+                    None => None,
+                };
+                let serials = match unit {
+                    TimeUnit::Second => timestamp_serials(
+                        array.as_primitive::<TimestampSecondType>(),
+                        tz,
+                        date_system,
+                    ),
+                    TimeUnit::Millisecond => timestamp_serials(
+                        array.as_primitive::<TimestampMillisecondType>(),
+                        tz,
+                        date_system,
+                    ),
+                    TimeUnit::Microsecond => timestamp_serials(
+                        array.as_primitive::<TimestampMicrosecondType>(),
+                        tz,
+                        date_system,
+                    ),
+                    TimeUnit::Nanosecond => timestamp_serials(
+                        array.as_primitive::<TimestampNanosecondType>(),
+                        tz,
+                        date_system,
+                    ),
+                };
+                BatchColumn::Float(serials, XF_DATETIME)
+            }
+            other => {
+                return Err(Error::invalid_input(format!(
+                    "column {:?} has unsupported type {}",
+                    name, other
+                )))
+            }
+        })
+    }
+
+    /// Style of dates and timestamps
+    fn style(&self) -> u32 {
+        match self {
+            BatchColumn::Int(_, style) | BatchColumn::Float(_, style) => *style,
+            _ => 0,
+        }
+    }
+
+    /// Aggregates of the first `len` values, as they are written into cells
+    fn totals(&self, len: usize) -> ColumnTotals {
+        let mut totals = ColumnTotals::default();
+        for idx in 0..len {
+            match self {
+                BatchColumn::Int(array, _) if array.is_valid(idx) => {
+                    totals.add_number(array.value(idx) as f64)
+                }
+                BatchColumn::Float(array, _) if array.is_valid(idx) => {
+                    let value = array.value(idx);
+                    if value.is_finite() {
+                        totals.add_number(value)
+                    } else {
+                        totals.add_error(CellError::Num)
+                    }
+                }
+                BatchColumn::Bool(array) if array.is_valid(idx) => totals.add_other(),
+                BatchColumn::Utf8(array) if array.is_valid(idx) => totals.add_other(),
+                BatchColumn::LargeUtf8(array) if array.is_valid(idx) => totals.add_other(),
+                _ => {}
+            }
+        }
+        totals
+    }
+}
+
+/// Largest magnitude of integers, which are all stored exactly by `f64`
+const MAX_EXACT_INTEGER: i128 = 1 << 53;
+
+/// Numbers of cells are `f64`, so integers beyond 2^53 are rejected: most of them would be
+/// rounded, and the rest (like 2^60) are exact only by chance
+fn check_exact_integers(name: &str, mut values: impl Iterator<Item = i128>) -> Result<()> {
+    match values.find(|value| value.abs() > MAX_EXACT_INTEGER) {
+        Some(value) => Err(Error::invalid_input(format!(
+            "column {:?} has integer {}, which cannot be written exactly as the number; \
+             cast the column to strings to keep it",
+            name, value
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Convert timestamps into serial numbers of the wall clock time (in the given time zone)
+fn timestamp_serials<T: ArrowTimestampType>(
+    array: &PrimitiveArray<T>,
+    tz: Option<Tz>,
+    date_system: DateSystem,
+) -> Float64Array {
+    array
+        .iter()
+        .map(|value| {
+            let datetime = match tz {
+                Some(tz) => as_datetime_with_timezone::<T>(value?, tz)?.naive_local(),
+                None => as_datetime::<T>(value?)?,
+            };
+            Some(date_system.datetime_to_serial(datetime).unwrap_or(f64::NAN))
+        })
+        .collect()
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Write names of the schema fields into the next row.
+    ///
+    /// Rows up to the header are frozen, unless the sheet already has panes.
+    pub fn write_header(&mut self, schema: &Schema) -> Result<()> {
+        if schema.fields().len() > MAX_COL as usize + 1 {
+            return Err(Error::invalid_input(
+                "schema has more fields than columns on the sheet",
+            ));
+        }
+        let row = self.next_row();
+        for (col, field) in schema.fields().iter().enumerate() {
+            self.write_string(row, col as u16, field.name())?;
+        }
+        if self.view.panes == Panes::None && row < MAX_ROW {
+            self.freeze_panes(row + 1, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Write rows of the batch, starting from the next row of the sheet.
+    ///
+    /// Null values are skipped, non-finite floats and dates out of the range of the date system
+    /// are written as `#NUM!` errors.
+    ///
+    /// Numbers are stored as `f64`: the batch with 64-bit integers beyond ±2^53 (like large
+    /// IDs) is rejected as a whole, even if some of them would be stored exactly; such
+    /// columns should be cast to strings.
+    pub fn write_batch(&mut self, batch: &RecordBatch) -> Result<()> {
+        let columns = self.batch_columns(batch)?;
+        let first_row = self.next_row();
+        if batch.num_rows() > (MAX_ROW + 1 - first_row) as usize {
+            return Err(Error::invalid_input(
+                "batch has more rows than left on the sheet",
+            ));
+        }
+        self.write_batch_rows(first_row, batch.num_rows(), &columns)?;
+        self.skip_rows_to(first_row + batch.num_rows() as u32);
+        Ok(())
+    }
+
+    /// Write the batch as the table with the header row and the totals row (if any column
+    /// has the total function), starting from the next row of the sheet.
+    ///
+    /// Names of columns are taken from the schema, unless they are set in `options`. Rows up
+    /// to the header are frozen, unless the sheet already has panes.
+    pub fn write_table(&mut self, batch: &RecordBatch, options: &TableOptions) -> Result<()> {
+        let schema = batch.schema();
+        let mut options = options.clone();
+        if options.columns.is_empty() {
+            options.columns = schema
+                .fields()
+                .iter()
+                .map(|field| TableColumn::new(field.name()))
+                .collect();
+        } else if options.columns.len() != schema.fields().len() {
+            return Err(Error::invalid_input(format!(
+                "table has {} columns, but the batch has {}",
+                options.columns.len(),
+                schema.fields().len()
+            )));
+        }
+        let columns = self.batch_columns(batch)?;
+        let header_row = self.next_row();
+        // the empty table still has one data row
+        let data_rows = batch.num_rows().max(1) as u64;
+        let totals = options.has_totals();
+        let last_row = header_row as u64 + data_rows + totals as u64;
+        if last_row > MAX_ROW as u64 {
+            return Err(Error::invalid_input(
+                "table has more rows than left on the sheet",
+            ));
+        }
+        self.add_table(header_row, 0, last_row as u32, &options)?;
+
+        for (col, column) in options.columns.iter().enumerate() {
+            self.write_string(header_row, col as u16, &column.name)?;
+        }
+        if self.view.panes == Panes::None {
+            self.freeze_panes(header_row + 1, 0)?;
+        }
+        self.write_batch_rows(header_row + 1, batch.num_rows(), &columns)?;
+        if totals {
+            let totals_row = last_row as u32;
+            for (col, (column, data)) in options.columns.iter().zip(&columns).enumerate() {
+                let col = col as u16;
+                if let TotalFunction::Label(ref label) = column.total {
+                    self.write_string(totals_row, col, label)?;
+                }
+                let Some(function) = column.total.subtotal() else {
+                    continue;
+                };
+                let style = match column.total {
+                    TotalFunction::Count | TotalFunction::CountNums => 0,
+                    _ => data.style(),
+                };
+                let range = CellRange::new(header_row + 1, col, totals_row - 1, col);
+                let value = data.totals(batch.num_rows()).value(&column.total);
+                self.put_formula(
+                    totals_row,
+                    col,
+                    subtotal_formula(function, range),
+                    &value,
+                    style,
+                )?;
+            }
+        }
+        self.skip_rows_to(last_row as u32 + 1);
+        Ok(())
+    }
+
+    /// Convert columns of the batch, which must fit into the sheet
+    fn batch_columns<'a>(&self, batch: &'a RecordBatch) -> Result<Vec<BatchColumn<'a>>> {
+        let schema = batch.schema();
+        if schema.fields().len() > MAX_COL as usize + 1 {
+            return Err(Error::invalid_input(
+                "batch has more columns than the sheet",
+            ));
+        }
+        schema
+            .fields()
+            .iter()
+            .zip(batch.columns())
+            .map(|(field, array)| BatchColumn::new(field.name(), array, self.date_system()))
+            .collect()
+    }
+
+    fn write_batch_rows(
+        &mut self,
+        first_row: u32,
+        len: usize,
+        columns: &[BatchColumn],
+    ) -> Result<()> {
+        for idx in 0..len {
+            let row = first_row + idx as u32;
+            for (col, column) in columns.iter().enumerate() {
+                self.write_batch_cell(row, col as u16, column, idx)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_batch_cell(
+        &mut self,
+        row: u32,
+        col: u16,
+        column: &BatchColumn,
+        idx: usize,
+    ) -> Result<()> {
+        match column {
+            BatchColumn::Int(array, style) if array.is_valid(idx) => {
+                self.put_number(row, col, array.value(idx) as f64, *style)
+            }
+            BatchColumn::Float(array, style) if array.is_valid(idx) => {
+                let value = array.value(idx);
+                if value.is_finite() {
+                    self.put_number(row, col, value, *style)
+                } else {
+                    self.write_error(row, col, CellError::Num)
+                }
+            }
+            BatchColumn::Bool(array) if array.is_valid(idx) => {
+                self.write_bool(row, col, array.value(idx))
+            }
+            BatchColumn::Utf8(array) if array.is_valid(idx) => {
+                self.write_string(row, col, array.value(idx))
+            }
+            BatchColumn::LargeUtf8(array) if array.is_valid(idx) => {
+                self.write_string(row, col, array.value(idx))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc};
+
+    use arrow::{
+        array::{Date32Array, Float64Array, Int32Array, TimestampMillisecondArray, UInt64Array},
+        datatypes::Field,
+    };
+
+    use super::*;
+    use crate::{CellValue, XlsbReader, XlsbWriter};
+
+    #[test]
+    fn test_write_batch() {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "int",
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(-7)])) as ArrayRef,
+            ),
+            (
+                "float",
+                Arc::new(Float64Array::from(vec![0.5, f64::NAN, 1e300])) as ArrayRef,
+            ),
+            (
+                "text",
+                Arc::new(StringArray::from(vec![Some("a"), Some("b"), Some("a")])) as ArrayRef,
+            ),
+            (
+                "flag",
+                Arc::new(BooleanArray::from(vec![true, false, true])) as ArrayRef,
+            ),
+            (
+                "date",
+                Arc::new(Date32Array::from(vec![0, 19723, 1])) as ArrayRef,
+            ),
+            (
+                "ts",
+                Arc::new(
+                    TimestampMillisecondArray::from(vec![0, 43_200_000, 86_400_000])
+                        .with_timezone("+06:00"),
+                ) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Batch").unwrap();
+        sheet.write_header(&batch.schema()).unwrap();
+        sheet.write_batch(&batch).unwrap();
+        sheet.write_batch(&batch.slice(1, 1)).unwrap();
+        sheet.write_batch(&batch.slice(0, 1)).unwrap();
+        // integers beyond 2^53 are rejected before any row is written, even exact ones
+        for values in [
+            vec![1 << 60],
+            vec![(1 << 53) + 2],
+            vec![-(1 << 53) - 1],
+            vec![i64::MAX],
+        ] {
+            let ids = RecordBatch::try_from_iter([(
+                "id",
+                Arc::new(Int64Array::from(values)) as ArrayRef,
+            )])
+            .unwrap();
+            assert!(sheet.write_batch(&ids).is_err());
+        }
+        assert!(check_exact_integers("id", [1 << 53, -(1 << 53)].into_iter()).is_ok());
+        let ids = RecordBatch::try_from_iter([(
+            "id",
+            Arc::new(UInt64Array::from(vec![u64::MAX])) as ArrayRef,
+        )])
+        .unwrap();
+        assert!(sheet.write_batch(&ids).is_err());
+        let unsupported = Schema::new(vec![Field::new("l", DataType::Null, true)]);
+        assert!(sheet
+            .write_batch(&RecordBatch::new_empty(Arc::new(unsupported)))
+            .is_err());
+        sheet.finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut reader = XlsbReader::new(Cursor::new(data)).unwrap();
+        let cells = reader
+            .cells("Batch")
+            .unwrap()
+            .map(Result::unwrap)
+            .collect::<Vec<_>>();
+        let at = |row: u32, col: u16| {
+            cells
+                .iter()
+                .find(|cell| cell.row == row && cell.col == col)
+                .map(|cell| (cell.value.clone(), cell.style))
+        };
+        assert_eq!(at(0, 5), Some((CellValue::String("ts".to_owned()), 0)));
+        assert_eq!(at(1, 0), Some((CellValue::Number(1.0), 0)));
+        assert_eq!(at(2, 0), None);
+        assert_eq!(at(3, 0), Some((CellValue::Number(-7.0), 0)));
+        assert_eq!(at(1, 1), Some((CellValue::Number(0.5), 0)));
+        assert_eq!(at(2, 1), Some((CellValue::Error(CellError::Num), 0)));
+        assert_eq!(at(3, 2), Some((CellValue::String("a".to_owned()), 0)));
+        assert_eq!(at(2, 3), Some((CellValue::Bool(false), 0)));
+        assert_eq!(at(1, 4), Some((CellValue::Number(25569.0), XF_DATE)));
+        assert_eq!(at(2, 4), Some((CellValue::Number(45292.0), XF_DATE)));
+        assert_eq!(at(3, 4), Some((CellValue::Number(25570.0), XF_DATE)));
+        assert_eq!(at(1, 5), Some((CellValue::Number(25569.25), XF_DATETIME)));
+        assert_eq!(at(2, 5), Some((CellValue::Number(25569.75), XF_DATETIME)));
+        // slices are appended after the previous batches, even if they have only nulls
+        assert_eq!(at(4, 1), Some((CellValue::Error(CellError::Num), 0)));
+        assert_eq!(at(5, 0), Some((CellValue::Number(1.0), 0)));
+        assert_eq!(cells.iter().map(|cell| cell.row).max(), Some(5));
+    }
+}
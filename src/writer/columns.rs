@@ -0,0 +1,321 @@
+//! Widths and formatting of sheet columns
+use std::{
+    collections::BTreeMap,
+    io::{Seek, Write},
+};
+
+use super::SheetWriter;
+use crate::{
+    core::biff::{records::BrtColInfo, BiffId, BiffWrite, MAX_COL},
+    Error, Result, StyleId,
+};
+
+/// Width of columns without settings, in characters (`baseColWidth` of 8 with the padding)
+const DEFAULT_WIDTH: f64 = 9.140625;
+
+/// Maximal width of the column, in characters
+const MAX_WIDTH: f64 = 255.0;
+
+/// Maximal outline level of columns and rows
+pub(super) const MAX_OUTLINE_LEVEL: u8 = 7;
+
+/// Settings of the column, which are applied by [`SheetWriter::set_columns`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColumnOptions {
+    /// Width in characters of the default font, from 0 to 255.
+    ///
+    /// If not set, the width is estimated from the content (see [`SheetWriter::set_autofit`])
+    /// or the default width is used.
+    pub width: Option<f64>,
+    /// Style of empty cells in the column
+    pub style: StyleId,
+    pub hidden: bool,
+    /// Outline (grouping) level, from 0 to 7
+    pub outline_level: u8,
+    /// The outline group, which is adjacent to the column, is collapsed
+    pub collapsed: bool,
+}
+
+/// Columns of the sheet: explicit settings and widths of the content
+#[derive(Default)]
+pub(super) struct Columns {
+    options: BTreeMap<u16, ColumnOptions>,
+    /// Estimated widths of the content in characters, if auto-fit is enabled
+    content: Option<BTreeMap<u16, usize>>,
+}
+
+impl Columns {
+    /// Remember the width of the written cell
+    pub(super) fn measure(&mut self, col: u16, width: impl FnOnce() -> usize) {
+        if let Some(ref mut content) = self.content {
+            let width = width();
+            let max = content.entry(col).or_default();
+            *max = (*max).max(width);
+        }
+    }
+
+    /// Maximal outline level of columns
+    pub(super) fn outline_level(&self) -> u8 {
+        self.options
+            .values()
+            .map(|options| options.outline_level)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn col_info(&self, col: u16) -> Option<BrtColInfo> {
+        let options = self.options.get(&col);
+        let content = self
+            .content
+            .as_ref()
+            .and_then(|content| content.get(&col))
+            .map(|&chars| fit_width(chars));
+        let (width, user_set) = match (options.and_then(|options| options.width), content) {
+            (Some(width), _) => (width, true),
+            (None, Some(width)) => (width, true),
+            (None, None) if options.is_some() => (DEFAULT_WIDTH, false),
+            (None, None) => return None,
+        };
+        let options = options.cloned().unwrap_or_default();
+        let flags = [
+            (options.hidden, BrtColInfo::F_HIDDEN),
+            (user_set, BrtColInfo::F_USER_SET),
+            (options.collapsed, BrtColInfo::F_COLLAPSED),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .fold((options.outline_level as u16) << 8, |flags, (_, flag)| {
+            flags | flag
+        });
+        Some(BrtColInfo::new(
+            (col as i32).into(),
+            (col as i32).into(),
+            (width * 256.0).round() as u32,
+            options.style.index(),
+            flags,
+        ))
+    }
+
+    /// Write column infos, adjacent columns with the same settings share the record
+    pub(super) fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut cols = self.options.keys().copied().collect::<Vec<_>>();
+        if let Some(ref content) = self.content {
+            cols.extend(content.keys());
+            cols.sort_unstable();
+            cols.dedup();
+        }
+        let mut infos: Vec<BrtColInfo> = Vec::new();
+        for info in cols.into_iter().filter_map(|col| self.col_info(col)) {
+            match infos.last_mut() {
+                Some(last)
+                    if last.col_last.inner + 1 == info.col_first.inner
+                        && (last.coldx, last.ixfe, last.flags)
+                            == (info.coldx, info.ixfe, info.flags) =>
+                {
+                    last.col_last = info.col_last;
+                }
+                _ => infos.push(info),
+            }
+        }
+        if infos.is_empty() {
+            return Ok(());
+        }
+        out.write_empty(BiffId::BrtBeginColInfos)?;
+        for info in &infos {
+            out.write_record(info)?;
+        }
+        out.write_empty(BiffId::BrtEndColInfos)?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Apply settings to columns from `first_col` to `last_col`, replacing the previous ones
+    pub fn set_columns(
+        &mut self,
+        first_col: u16,
+        last_col: u16,
+        options: &ColumnOptions,
+    ) -> Result<()> {
+        if first_col > last_col || last_col as u32 > MAX_COL {
+            return Err(Error::invalid_input(format!(
+                "invalid column range from {} to {}",
+                first_col, last_col
+            )));
+        }
+        if let Some(width) = options.width {
+            if !(0.0..=MAX_WIDTH).contains(&width) {
+                return Err(Error::invalid_input(format!(
+                    "column width {} must be from 0 to 255",
+                    width
+                )));
+            }
+        }
+        if options.outline_level > MAX_OUTLINE_LEVEL {
+            return Err(Error::invalid_input(format!(
+                "outline level {} must be from 0 to 7",
+                options.outline_level
+            )));
+        }
+        self.check_style(options.style)?;
+        for col in first_col..=last_col {
+            self.columns.options.insert(col, options.clone());
+        }
+        Ok(())
+    }
+
+    /// Estimate widths of columns without explicit width from cells, which are written
+    /// after the call: lengths of strings and numbers, displayed by their formats.
+    /// Excel does not fit widths on open, so they are stored in the file.
+    pub fn set_autofit(&mut self, enabled: bool) {
+        match (enabled, self.columns.content.is_some()) {
+            (true, false) => self.columns.content = Some(BTreeMap::new()),
+            (false, true) => self.columns.content = None,
+            _ => {}
+        }
+    }
+}
+
+/// Width of the column in characters, which fits the content of the given length
+fn fit_width(chars: usize) -> f64 {
+    (chars as f64 * 1.1 + 2.0).min(MAX_WIDTH)
+}
+
+/// Displayed length of the string: the longest line
+pub(super) fn string_width(value: &str) -> usize {
+    value
+        .lines()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Estimated length of the number, displayed by the number format
+pub(super) fn number_width(value: f64, code: &str) -> usize {
+    if code.eq_ignore_ascii_case("General") {
+        // up to 11 characters are displayed, then the scientific notation is used
+        return value.to_string().len().min(11);
+    }
+    let mut literal = 0;
+    let (mut int_digits, mut frac_digits) = (0, 0);
+    let (mut point, mut grouping, mut percent, mut date) = (false, false, false, false);
+    let mut chars = code.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            ';' => break,
+            '"' => literal += chars.by_ref().take_while(|&ch| ch != '"').count(),
+            '\\' | '_' => {
+                chars.next();
+                literal += 1;
+            }
+            '*' => {
+                chars.next();
+            }
+            '[' => {
+                chars.by_ref().find(|&ch| ch == ']');
+            }
+            '0' | '#' | '?' if point => frac_digits += 1,
+            // optional digits are not displayed, when the number has no such digit
+            '0' | '?' => int_digits += 1,
+            '#' => {}
+            '.' => point = true,
+            ',' => grouping = true,
+            '%' => {
+                percent = true;
+                literal += 1;
+            }
+            'y' | 'Y' | 'm' | 'M' | 'd' | 'D' | 'h' | 'H' | 's' | 'S' => {
+                date = true;
+                literal += 1;
+            }
+            _ => literal += 1,
+        }
+    }
+    if date {
+        return literal + int_digits + frac_digits + point as usize;
+    }
+    let value = if percent { value * 100.0 } else { value };
+    let digits = match value.abs().trunc() {
+        int if int >= 1.0 => int.log10().floor() as usize + 1,
+        _ => 1,
+    };
+    let digits = if grouping {
+        digits + (digits - 1) / 3
+    } else {
+        digits
+    };
+    let fraction = if frac_digits > 0 { frac_digits + 1 } else { 0 };
+    literal + digits.max(int_digits) + fraction + (value < 0.0) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{writer::part_records, Style, XlsbWriter};
+
+    #[test]
+    fn test_columns() {
+        assert_eq!(string_width("abc\nlonger line"), 11);
+        assert_eq!(number_width(1.0 / 3.0, "General"), 11);
+        assert_eq!(number_width(1234567.891, "#,##0.00"), 12);
+        assert_eq!(number_width(-5.0, "\"$\"#,##0.00"), 6);
+        assert_eq!(number_width(0.25, "0.0%"), 5);
+        assert_eq!(number_width(45000.0, "yyyy-mm-dd"), 10);
+
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let currency = book
+            .add_style(&Style {
+                number_format: "#,##0.00".to_owned(),
+                ..Style::default()
+            })
+            .unwrap();
+        let mut sheet = book.add_sheet("Columns").unwrap();
+        let hidden = ColumnOptions {
+            hidden: true,
+            outline_level: 1,
+            ..ColumnOptions::default()
+        };
+        sheet.set_columns(3, 5, &hidden).unwrap();
+        let wide = ColumnOptions {
+            width: Some(30.0),
+            ..ColumnOptions::default()
+        };
+        sheet.set_columns(5, 5, &wide).unwrap();
+        assert!(sheet.set_columns(2, 1, &wide).is_err());
+        assert!(sheet.set_columns(0, MAX_COL as u16 + 1, &wide).is_err());
+        let invalid = ColumnOptions {
+            outline_level: 8,
+            ..ColumnOptions::default()
+        };
+        assert!(sheet.set_columns(0, 0, &invalid).is_err());
+
+        sheet.write(0, 0, "short").unwrap();
+        sheet.set_autofit(true);
+        sheet.write(1, 0, "much longer text").unwrap();
+        sheet.write_with_style(1, 1, 1234567.891, currency).unwrap();
+        sheet.finish().unwrap();
+
+        let data = book.close().unwrap().into_inner();
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let columns = part_records(&mut zip, "xl/worksheets/sheet1.bin")
+            .into_iter()
+            .filter(|rec| rec.id == BiffId::BrtColInfo)
+            .map(|rec| rec.as_biff::<BrtColInfo>().unwrap())
+            .collect::<Vec<_>>();
+        let bounds = columns
+            .iter()
+            .map(|info| (info.col_first.inner, info.col_last.inner))
+            .collect::<Vec<_>>();
+        assert_eq!(bounds, [(0, 0), (1, 1), (3, 4), (5, 5)]);
+        assert_eq!(columns[0].coldx, (fit_width(16) * 256.0).round() as u32);
+        assert_eq!(columns[1].coldx, (fit_width(12) * 256.0).round() as u32);
+        assert_eq!(
+            columns[2].flags,
+            BrtColInfo::F_HIDDEN | (1 << 8),
+            "default width is not set by the user"
+        );
+        assert_eq!(columns[3].coldx, 30 * 256);
+    }
+}
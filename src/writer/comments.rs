@@ -0,0 +1,325 @@
+//! Comments (notes) of cells: the comments part and the legacy VML drawing with note boxes
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{Seek, Write},
+};
+
+use super::SheetWriter;
+use crate::{
+    core::biff::{
+        records::{BrtBeginComment, BrtCommentAuthor, BrtCommentText, MAX_STRING_LEN},
+        BiffId, BiffWrite, RichStr, UncheckedRfX, MAX_COL, MAX_ROW,
+    },
+    Error, Result,
+};
+
+/// Default size of the note box in pixels
+const NOTE_WIDTH: u32 = 128;
+const NOTE_HEIGHT: u32 = 74;
+
+/// Count of shape ids in the block of `o:idmap`
+const SHAPE_BLOCK: u32 = 1024;
+
+/// Default size of cells in pixels, the note box is anchored by them
+const COL_WIDTH: u32 = 64;
+const ROW_HEIGHT: u32 = 20;
+
+/// Comment of the cell, which is shown in the note box when the cell is hovered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellComment {
+    pub text: String,
+    pub author: String,
+    /// The note box is always shown, not only when the cell is hovered
+    pub visible: bool,
+}
+
+impl CellComment {
+    pub fn new(text: &str) -> Self {
+        CellComment {
+            text: text.to_owned(),
+            author: String::new(),
+            visible: false,
+        }
+    }
+
+    /// Set the author of the comment
+    pub fn with_author(mut self, author: &str) -> Self {
+        self.author = author.to_owned();
+        self
+    }
+}
+
+/// Comments of the sheet, ordered by their cells
+#[derive(Default)]
+pub(super) struct Comments {
+    items: BTreeMap<(u32, u16), CellComment>,
+}
+
+impl Comments {
+    pub(super) fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Write the comments part with the list of authors
+    pub(super) fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        let mut authors: Vec<&str> = Vec::new();
+        for comment in self.items.values() {
+            if !authors.contains(&comment.author.as_str()) {
+                authors.push(&comment.author);
+            }
+        }
+
+        out.write_empty(BiffId::BrtBeginComments)?;
+        out.write_empty(BiffId::BrtBeginCommentAuthors)?;
+        for author in &authors {
+            out.write_record(&BrtCommentAuthor::new((*author).into()))?;
+        }
+        out.write_empty(BiffId::BrtEndCommentAuthors)?;
+        out.write_empty(BiffId::BrtBeginCommentList)?;
+        for (&(row, col), comment) in &self.items {
+            let iauthor = authors
+                .iter()
+                .position(|author| *author == comment.author)
+                .unwrap_or_default();
+            let rfx = UncheckedRfX::from_bounds(row, row, col as u32, col as u32);
+            out.write_record(&BrtBeginComment::new(iauthor as u32, rfx, 0, 0))?;
+            out.write_record(&BrtCommentText::new(RichStr::plain(
+                comment.text.as_str().into(),
+            )))?;
+            out.write_empty(BiffId::BrtEndComment)?;
+        }
+        out.write_empty(BiffId::BrtEndCommentList)?;
+        out.write_empty(BiffId::BrtEndComments)?;
+        Ok(())
+    }
+
+    /// Number of blocks of 1024 shape ids, which note boxes take; ids start from the second one
+    /// of the first block
+    pub(super) fn shape_blocks(&self) -> u32 {
+        self.items.len() as u32 / SHAPE_BLOCK + 1
+    }
+
+    /// Write the VML drawing with note boxes, shape ids start from the block `first_block`,
+    /// blocks of other sheets follow, so ids are unique in the workbook
+    pub(super) fn write_vml<W: Write>(&self, out: &mut W, first_block: u32) -> Result<()> {
+        let mut xml = String::from(
+            "<xml xmlns:v=\"urn:schemas-microsoft-com:vml\" \
+             xmlns:o=\"urn:schemas-microsoft-com:office:office\" \
+             xmlns:x=\"urn:schemas-microsoft-com:office:excel\">\
+             <o:shapelayout v:ext=\"edit\">",
+        );
+        let blocks = (first_block..first_block + self.shape_blocks())
+            .map(|block| block.to_string())
+            .collect::<Vec<_>>();
+        let _ = write!(
+            xml,
+            "<o:idmap v:ext=\"edit\" data=\"{}\"/>",
+            blocks.join(",")
+        );
+        xml.push_str(
+            "</o:shapelayout>\
+             <v:shapetype id=\"_x0000_t202\" coordsize=\"21600,21600\" o:spt=\"202\" \
+             path=\"m,l,21600r21600,l21600,xe\">\
+             <v:stroke joinstyle=\"miter\"/>\
+             <v:path gradientshapeok=\"t\" o:connecttype=\"rect\"/>\
+             </v:shapetype>",
+        );
+        for (idx, (&(row, col), comment)) in self.items.iter().enumerate() {
+            let anchor = NoteAnchor::of_cell(row, col);
+            let _ = write!(
+                xml,
+                "<v:shape id=\"_x0000_s{}\" type=\"#_x0000_t202\" \
+                 style=\"position:absolute;margin-left:{}pt;margin-top:{}pt;width:{}pt;\
+                 height:{}pt;z-index:{};visibility:{}\" \
+                 fillcolor=\"#ffffe1\" o:insetmode=\"auto\">\
+                 <v:fill color2=\"#ffffe1\"/>\
+                 <v:shadow on=\"t\" color=\"black\" obscured=\"t\"/>\
+                 <v:path o:connecttype=\"none\"/>\
+                 <v:textbox style=\"mso-direction-alt:auto\">\
+                 <div style=\"text-align:left\"></div>\
+                 </v:textbox>\
+                 <x:ClientData ObjectType=\"Note\">\
+                 <x:MoveWithCells/><x:SizeWithCells/>\
+                 <x:Anchor>{}</x:Anchor>\
+                 <x:AutoFill>False</x:AutoFill>\
+                 <x:Row>{}</x:Row><x:Column>{}</x:Column>{}\
+                 </x:ClientData></v:shape>",
+                first_block * SHAPE_BLOCK + idx as u32 + 1,
+                points(anchor.left()),
+                points(anchor.top()),
+                points(NOTE_WIDTH),
+                points(NOTE_HEIGHT),
+                idx + 1,
+                if comment.visible { "visible" } else { "hidden" },
+                anchor,
+                row,
+                col,
+                if comment.visible { "<x:Visible/>" } else { "" },
+            );
+        }
+        xml.push_str("</xml>");
+        out.write_all(xml.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Position of the note box: it starts right of the cell and a row above it, like in Excel
+struct NoteAnchor {
+    col: u32,
+    x_offset: u32,
+    row: u32,
+    y_offset: u32,
+}
+
+impl NoteAnchor {
+    fn of_cell(row: u32, col: u16) -> Self {
+        let span_cols = NOTE_WIDTH / COL_WIDTH + 1;
+        let span_rows = NOTE_HEIGHT / ROW_HEIGHT + 1;
+        NoteAnchor {
+            col: (col as u32 + 1).min(MAX_COL - span_cols),
+            x_offset: 15,
+            row: row.saturating_sub(1).min(MAX_ROW - span_rows),
+            y_offset: if row == 0 { 2 } else { 10 },
+        }
+    }
+
+    fn left(&self) -> u32 {
+        self.col * COL_WIDTH + self.x_offset
+    }
+
+    fn top(&self) -> u32 {
+        self.row * ROW_HEIGHT + self.y_offset
+    }
+}
+
+/// Columns and rows of corners of the box with offsets in pixels, as `x:Anchor` of VML
+impl std::fmt::Display for NoteAnchor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let right = self.x_offset + NOTE_WIDTH;
+        let bottom = self.y_offset + NOTE_HEIGHT;
+        write!(
+            f,
+            "{}, {}, {}, {}, {}, {}, {}, {}",
+            self.col,
+            self.x_offset,
+            self.row,
+            self.y_offset,
+            self.col + right / COL_WIDTH,
+            right % COL_WIDTH,
+            self.row + bottom / ROW_HEIGHT,
+            bottom % ROW_HEIGHT
+        )
+    }
+}
+
+/// Convert pixels into points of VML styles
+fn points(pixels: u32) -> f64 {
+    pixels as f64 * 0.75
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Add the comment to the cell, which can be empty. Every cell has at most one comment.
+    pub fn add_comment(&mut self, row: u32, col: u16, comment: &CellComment) -> Result<()> {
+        if row > MAX_ROW || col as u32 > MAX_COL {
+            return Err(Error::invalid_input(format!(
+                "cell ({}, {}) is out of the sheet bounds",
+                row, col
+            )));
+        }
+        if comment.text.encode_utf16().count() > MAX_STRING_LEN
+            || comment.author.encode_utf16().count() > MAX_STRING_LEN
+        {
+            return Err(Error::invalid_input(format!(
+                "comment of cell ({}, {}) is too long",
+                row, col
+            )));
+        }
+        if self.comments.items.contains_key(&(row, col)) {
+            return Err(Error::invalid_input(format!(
+                "cell ({}, {}) already has the comment",
+                row, col
+            )));
+        }
+        self.comments.items.insert((row, col), comment.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        core::biff::{records::BrtLegacyDrawing, BiffId},
+        writer::{part_data, part_records},
+        CellComment, XlsbWriter,
+    };
+
+    #[test]
+    fn test_comments() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Checks").unwrap();
+        sheet.write(0, 0, 15).unwrap();
+        let comment = CellComment::new("Suspicious value").with_author("Auditor");
+        sheet.add_comment(0, 0, &comment).unwrap();
+        assert!(sheet.add_comment(0, 0, &comment).is_err());
+        sheet
+            .add_comment(5, 2, &CellComment::new("Missing"))
+            .unwrap();
+        sheet.finish().unwrap();
+        book.add_sheet("Other").unwrap().finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let types = String::from_utf8(part_data(&mut zip, "[Content_Types].xml")).unwrap();
+        assert!(types.contains("<Default Extension=\"vml\""));
+        assert!(types.contains("PartName=\"/xl/comments1.bin\""));
+        let rels =
+            String::from_utf8(part_data(&mut zip, "xl/worksheets/_rels/sheet1.bin.rels")).unwrap();
+        assert!(rels.contains("Target=\"../drawings/vmlDrawing1.vml\""));
+        assert!(zip.by_name("xl/worksheets/_rels/sheet2.bin.rels").is_err());
+
+        let records = part_records(&mut zip, "xl/worksheets/sheet1.bin");
+        let drawing = records
+            .iter()
+            .find(|rec| rec.id == BiffId::BrtLegacyDrawing)
+            .unwrap();
+        assert_eq!(
+            drawing
+                .as_biff::<BrtLegacyDrawing>()
+                .unwrap()
+                .st_rel_id
+                .inner,
+            "rId2"
+        );
+
+        let vml = String::from_utf8(part_data(&mut zip, "xl/drawings/vmlDrawing1.vml")).unwrap();
+        assert_eq!(vml.matches("<x:ClientData ObjectType=\"Note\">").count(), 2);
+        assert!(vml.contains("<x:Anchor>1, 15, 0, 2, 3, 15, 3, 16</x:Anchor>"));
+        assert!(vml.contains("<x:Anchor>3, 15, 4, 10, 5, 15, 8, 4</x:Anchor>"));
+        assert!(vml.contains("data=\"1\""));
+        assert!(vml.contains("id=\"_x0000_s1026\""));
+
+        // blocks of shape ids of sheets don't overlap
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        for count in [1100, 1] {
+            let mut sheet = book.add_sheet(&format!("Notes{}", count)).unwrap();
+            for row in 0..count {
+                sheet
+                    .add_comment(row, 0, &CellComment::new("Note"))
+                    .unwrap();
+            }
+            sheet.finish().unwrap();
+        }
+        let data = book.close().unwrap().into_inner();
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let vml = String::from_utf8(part_data(&mut zip, "xl/drawings/vmlDrawing1.vml")).unwrap();
+        assert!(vml.contains("data=\"1,2\""));
+        assert!(vml.contains("id=\"_x0000_s1025\""));
+        assert!(vml.contains("id=\"_x0000_s2124\""));
+        let vml = String::from_utf8(part_data(&mut zip, "xl/drawings/vmlDrawing2.vml")).unwrap();
+        assert!(vml.contains("data=\"3\""));
+        assert!(vml.contains("id=\"_x0000_s3073\""));
+    }
+}
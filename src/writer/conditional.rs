@@ -0,0 +1,798 @@
+//! Conditional formatting of ranges: highlighting rules, color scales, data bars and icon sets
+use std::io::{Seek, Write};
+
+use super::{styles::brt_color, SheetWriter};
+use crate::{
+    core::biff::{
+        records::{
+            BrtBeginCFRule, BrtBeginCFRule14, BrtBeginConditionalFormatting,
+            BrtBeginConditionalFormatting14, BrtBeginDatabar, BrtBeginDatabar14, BrtBeginIconSet,
+            BrtBeginIconSet14, BrtCFIcon, BrtCFRuleExt, BrtCFVO, BrtCFVO14, BrtColor14,
+            BrtColorRecord, BrtFRTBegin, ParsedFormula,
+        },
+        BiffId, BiffWrite,
+    },
+    formula::FormulaType,
+    CellRange, Color, DifferentialFormat, Error, Result,
+};
+
+/// Maximal rank of top and bottom rules
+const MAX_RANK: u32 = 1000;
+
+/// High part of identifiers of rules, which are linked to the Excel 2010 extension; the low
+/// part is the sheet index and the priority of the rule
+const RULE_GUID_HI: u64 = 0x8E3C_5F0A_D1B2_4C17;
+
+/// Value of the color scale point, the data bar end or the icon threshold
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionValue {
+    /// The lowest value of the range
+    Min,
+    /// The highest value of the range
+    Max,
+    /// The lowest value or zero for positive values, used by data bars
+    AutoMin,
+    /// The highest value or zero for negative values, used by data bars
+    AutoMax,
+    Number(f64),
+    /// Percent of the difference between the lowest and the highest value, from 0 to 100
+    Percent(f64),
+    /// Percentile of values, from 0 to 100
+    Percentile(f64),
+    /// Formula in A1 notation, like `=$B$1`
+    Formula(String),
+}
+
+/// Comparison of the cell value with the result of formulas, like `=10` or `=$B$1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CellCondition {
+    Between(String, String),
+    NotBetween(String, String),
+    Equal(String),
+    NotEqual(String),
+    Greater(String),
+    Less(String),
+    GreaterOrEqual(String),
+    LessOrEqual(String),
+}
+
+impl CellCondition {
+    /// Operator of the rule (`CFOper`) and its formulas
+    pub(super) fn operator(&self) -> (u32, Vec<&str>) {
+        match self {
+            CellCondition::Between(low, high) => (1, vec![low, high]),
+            CellCondition::NotBetween(low, high) => (2, vec![low, high]),
+            CellCondition::Equal(value) => (3, vec![value]),
+            CellCondition::NotEqual(value) => (4, vec![value]),
+            CellCondition::Greater(value) => (5, vec![value]),
+            CellCondition::Less(value) => (6, vec![value]),
+            CellCondition::GreaterOrEqual(value) => (7, vec![value]),
+            CellCondition::LessOrEqual(value) => (8, vec![value]),
+        }
+    }
+}
+
+/// Gradient of colors by values of cells, with two or three points
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorScale {
+    pub points: Vec<(ConditionValue, Color)>,
+}
+
+impl ColorScale {
+    /// Scale from the color of the lowest value to the color of the highest one
+    pub fn two_color(min: Color, max: Color) -> Self {
+        ColorScale {
+            points: vec![(ConditionValue::Min, min), (ConditionValue::Max, max)],
+        }
+    }
+
+    /// Scale with the middle point at the 50th percentile
+    pub fn three_color(min: Color, mid: Color, max: Color) -> Self {
+        ColorScale {
+            points: vec![
+                (ConditionValue::Min, min),
+                (ConditionValue::Percentile(50.0), mid),
+                (ConditionValue::Max, max),
+            ],
+        }
+    }
+}
+
+/// Bar in the cell, its length is proportional to the value
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataBar {
+    pub min: ConditionValue,
+    pub max: ConditionValue,
+    pub color: Color,
+    /// Bar is filled with the solid color instead of the gradient
+    pub solid_fill: bool,
+    pub border_color: Option<Color>,
+    /// Color of bars of negative values
+    pub negative_color: Color,
+    pub show_value: bool,
+}
+
+impl DataBar {
+    /// Gradient bar of the color with the border, like the default data bar of Excel
+    pub fn new(color: Color) -> Self {
+        DataBar {
+            min: ConditionValue::AutoMin,
+            max: ConditionValue::AutoMax,
+            color,
+            solid_fill: false,
+            border_color: Some(color),
+            negative_color: Color::Rgb(0xFF, 0x00, 0x00),
+            show_value: true,
+        }
+    }
+}
+
+/// Built-in set of icons
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum IconSetStyle {
+    ThreeArrows = 0,
+    ThreeArrowsGray = 1,
+    ThreeFlags = 2,
+    ThreeTrafficLights = 3,
+    ThreeTrafficLightsRimmed = 4,
+    ThreeSigns = 5,
+    ThreeSymbolsCircled = 6,
+    ThreeSymbols = 7,
+    FourArrows = 8,
+    FourArrowsGray = 9,
+    FourRedToBlack = 10,
+    FourRatings = 11,
+    FourTrafficLights = 12,
+    FiveArrows = 13,
+    FiveArrowsGray = 14,
+    FiveRatings = 15,
+    FiveQuarters = 16,
+    /// Sets of Excel 2010, older applications show no icons
+    ThreeStars = 17,
+    ThreeTriangles = 18,
+    FiveBoxes = 19,
+}
+
+impl IconSetStyle {
+    /// Count of icons in the set
+    pub fn icon_count(self) -> usize {
+        match self as i32 {
+            0..=7 | 17 | 18 => 3,
+            8..=12 => 4,
+            _ => 5,
+        }
+    }
+
+    fn is_excel_2010(self) -> bool {
+        self as i32 >= IconSetStyle::ThreeStars as i32
+    }
+}
+
+/// Icons by values of cells, the first threshold is the lowest one
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconSet {
+    pub style: IconSetStyle,
+    /// Thresholds of icons, one per icon
+    pub thresholds: Vec<ConditionValue>,
+    /// Icons are in the reverse order, from the highest value to the lowest
+    pub reverse: bool,
+    pub show_value: bool,
+    /// Icons, which replace icons of the style: the set and the index of the icon in it.
+    /// Empty for icons of the style.
+    pub icons: Vec<(IconSetStyle, u32)>,
+}
+
+impl IconSet {
+    /// Icons of the style with evenly spaced thresholds, like `0%`, `33%` and `67%`
+    pub fn new(style: IconSetStyle) -> Self {
+        let count = style.icon_count();
+        IconSet {
+            style,
+            thresholds: (0..count)
+                .map(|i| ConditionValue::Percent((i as f64 * 100.0 / count as f64).round()))
+                .collect(),
+            reverse: false,
+            show_value: true,
+            icons: Vec::new(),
+        }
+    }
+
+    /// Set is written into the Excel 2010 extension only, older applications ignore it
+    fn is_excel_2010(&self) -> bool {
+        self.style.is_excel_2010() || !self.icons.is_empty()
+    }
+
+    fn flags(&self) -> u32 {
+        (!self.show_value as u32 * BrtBeginIconSet::F_ICON_ONLY)
+            | (self.reverse as u32 * BrtBeginIconSet::F_REVERSE)
+    }
+}
+
+/// Condition of the conditional format
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionalRule {
+    CellValue(CellCondition),
+    /// Formula, which is true for formatted cells, like `=$B1>$C1`. Relative references are
+    /// relative to the top left cell of the range.
+    Expression(String),
+    /// The highest values by their rank, or their percent if `percent` is set
+    Top {
+        rank: u32,
+        percent: bool,
+    },
+    Bottom {
+        rank: u32,
+        percent: bool,
+    },
+    AboveAverage,
+    BelowAverage,
+    DuplicateValues,
+    UniqueValues,
+    ColorScale(ColorScale),
+    DataBar(DataBar),
+    IconSet(IconSet),
+}
+
+/// Rule with the format, which is applied to cells matching it. Color scales, data bars and
+/// icon sets ignore the format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionalFormat {
+    pub rule: ConditionalRule,
+    pub format: DifferentialFormat,
+    /// Rules with lower priority (added later) are not applied to matching cells
+    pub stop_if_true: bool,
+}
+
+impl ConditionalFormat {
+    pub fn new(rule: ConditionalRule, format: DifferentialFormat) -> Self {
+        ConditionalFormat {
+            rule,
+            format,
+            stop_if_true: false,
+        }
+    }
+}
+
+/// Records of rules by ranges, from `BrtBeginCFRule` to `BrtEndCFRule`
+type RuleGroups = Vec<(CellRange, Vec<Vec<u8>>)>;
+
+/// Conditional formats of the sheet, rules are kept as records until the sheet is finished
+#[derive(Default)]
+pub(super) struct ConditionalFormats {
+    rules: RuleGroups,
+    /// Rules of the Excel 2010 extension (`x14`)
+    ext_rules: RuleGroups,
+    /// Count of rules, the priority of the last rule
+    count: u32,
+}
+
+impl ConditionalFormats {
+    fn push(groups: &mut RuleGroups, range: CellRange, rule: Vec<u8>) {
+        match groups.iter_mut().find(|(other, _)| *other == range) {
+            Some((_, rules)) => rules.push(rule),
+            None => groups.push((range, vec![rule])),
+        }
+    }
+
+    /// Write rules of the sheet after merged cells
+    pub(super) fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        for (range, rules) in &self.rules {
+            out.write_record(&BrtBeginConditionalFormatting::new(
+                rules.len() as u32,
+                0,
+                vec![range.to_rfx()].into(),
+            ))?;
+            for rule in rules {
+                out.write_all(rule)?;
+            }
+            out.write_empty(BiffId::BrtEndConditionalFormatting)?;
+        }
+        Ok(())
+    }
+
+    /// Write rules of the Excel 2010 extension at the end of the sheet
+    pub(super) fn write_ext<W: Write>(&self, out: &mut W) -> Result<()> {
+        if self.ext_rules.is_empty() {
+            return Ok(());
+        }
+        out.write_record(&BrtFRTBegin::excel_2010())?;
+        out.write_empty(BiffId::BrtBeginConditionalFormattings)?;
+        for (range, rules) in &self.ext_rules {
+            out.write_record(&BrtBeginConditionalFormatting14::new(
+                rules.len() as u32,
+                0,
+                vec![range.to_rfx()].into(),
+            ))?;
+            for rule in rules {
+                out.write_all(rule)?;
+            }
+            out.write_empty(BiffId::BrtEndConditionalFormatting14)?;
+        }
+        out.write_empty(BiffId::BrtEndConditionalFormattings)?;
+        out.write_empty(BiffId::BrtFRTEnd)?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Add the conditional format to the range. Rules of the sheet are evaluated in the order
+    /// of addition, the first added rule has the highest priority.
+    pub fn add_conditional_format(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        format: &ConditionalFormat,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        range.validate()?;
+        let priority = self.conditional.count + 1;
+        let stop = format.stop_if_true as u16 * BrtBeginCFRule::F_STOP_TRUE;
+        let mut rule = Vec::new();
+        let (i_type, i_template, i_param, flags, formulas) = match &format.rule {
+            ConditionalRule::CellValue(condition) => {
+                let (operator, texts) = condition.operator();
+                let formulas = texts
+                    .into_iter()
+                    .map(|text| self.compile_rule_formula(text, range))
+                    .collect::<Result<Vec<_>>>()?;
+                (
+                    BrtBeginCFRule::TYPE_CELL_IS,
+                    BrtBeginCFRule::TEMPLATE_CELL_IS,
+                    operator,
+                    0,
+                    formulas,
+                )
+            }
+            ConditionalRule::Expression(text) => (
+                BrtBeginCFRule::TYPE_EXPRESSION,
+                BrtBeginCFRule::TEMPLATE_EXPRESSION,
+                0,
+                0,
+                vec![self.compile_rule_formula(text, range)?],
+            ),
+            ConditionalRule::Top { rank, percent } | ConditionalRule::Bottom { rank, percent } => {
+                let max_rank = if *percent { 100 } else { MAX_RANK };
+                if !(1..=max_rank).contains(rank) {
+                    return Err(Error::invalid_input(format!(
+                        "rank {} of the conditional format must be from 1 to {}",
+                        rank, max_rank
+                    )));
+                }
+                let bottom = matches!(format.rule, ConditionalRule::Bottom { .. });
+                (
+                    BrtBeginCFRule::TYPE_TOP10,
+                    BrtBeginCFRule::TEMPLATE_TOP10,
+                    *rank,
+                    (bottom as u16 * BrtBeginCFRule::F_BOTTOM)
+                        | (*percent as u16 * BrtBeginCFRule::F_PERCENT),
+                    Vec::new(),
+                )
+            }
+            ConditionalRule::AboveAverage => (
+                BrtBeginCFRule::TYPE_EXPRESSION,
+                BrtBeginCFRule::TEMPLATE_ABOVE_AVERAGE,
+                0,
+                BrtBeginCFRule::F_ABOVE,
+                Vec::new(),
+            ),
+            ConditionalRule::BelowAverage => (
+                BrtBeginCFRule::TYPE_EXPRESSION,
+                BrtBeginCFRule::TEMPLATE_BELOW_AVERAGE,
+                0,
+                0,
+                Vec::new(),
+            ),
+            ConditionalRule::DuplicateValues => (
+                BrtBeginCFRule::TYPE_EXPRESSION,
+                BrtBeginCFRule::TEMPLATE_DUPLICATE,
+                0,
+                0,
+                Vec::new(),
+            ),
+            ConditionalRule::UniqueValues => (
+                BrtBeginCFRule::TYPE_EXPRESSION,
+                BrtBeginCFRule::TEMPLATE_UNIQUE,
+                0,
+                0,
+                Vec::new(),
+            ),
+            ConditionalRule::ColorScale(scale) => {
+                if !(2..=3).contains(&scale.points.len()) {
+                    return Err(Error::invalid_input(
+                        "color scale must have two or three points",
+                    ));
+                }
+                rule.write_record(&BrtBeginCFRule::rule(
+                    BrtBeginCFRule::TYPE_COLOR_SCALE,
+                    BrtBeginCFRule::TEMPLATE_COLOR_SCALE,
+                    BrtBeginCFRule::NO_DXF,
+                    priority,
+                    0,
+                    stop,
+                    Vec::new(),
+                ))?;
+                rule.write_empty(BiffId::BrtBeginColorScale)?;
+                for (value, _) in &scale.points {
+                    rule.write_record(&self.cfvo(value)?)?;
+                }
+                for (_, color) in &scale.points {
+                    rule.write_record(&BrtColorRecord::new(brt_color(*color)))?;
+                }
+                rule.write_empty(BiffId::BrtEndColorScale)?;
+                rule.write_empty(BiffId::BrtEndCFRule)?;
+                return self.push_rule(range, Some(rule), None);
+            }
+            ConditionalRule::DataBar(bar) => return self.add_data_bar(range, bar, stop),
+            ConditionalRule::IconSet(icons) => return self.add_icon_set(range, icons, stop),
+        };
+        let dxf_id = self.add_dxf(&format.format)?;
+        rule.write_record(&BrtBeginCFRule::rule(
+            i_type,
+            i_template,
+            dxf_id,
+            priority,
+            i_param,
+            flags | stop,
+            formulas,
+        ))?;
+        rule.write_empty(BiffId::BrtEndCFRule)?;
+        self.push_rule(range, Some(rule), None)
+    }
+
+    fn add_data_bar(&mut self, range: CellRange, bar: &DataBar, stop: u16) -> Result<()> {
+        let priority = self.conditional.count + 1;
+        let guid = self.rule_guid(priority);
+        let (min, max) = (self.cfvo(&bar.min)?, self.cfvo(&bar.max)?);
+
+        // Excel 2007 has no automatic ends of bars
+        let legacy = |cfvo: &BrtCFVO| {
+            let i_type = match cfvo.i_type {
+                BrtCFVO::TYPE_AUTO_MIN => BrtCFVO::TYPE_MIN,
+                BrtCFVO::TYPE_AUTO_MAX => BrtCFVO::TYPE_MAX,
+                i_type => i_type,
+            };
+            BrtCFVO::new(
+                i_type,
+                cfvo.num_param,
+                cfvo.flags,
+                cfvo.cb_fmla,
+                cfvo.formula.clone(),
+            )
+        };
+        let mut rule = Vec::new();
+        rule.write_record(&BrtBeginCFRule::rule(
+            BrtBeginCFRule::TYPE_DATABAR,
+            BrtBeginCFRule::TEMPLATE_DATABAR,
+            BrtBeginCFRule::NO_DXF,
+            priority,
+            0,
+            stop,
+            Vec::new(),
+        ))?;
+        rule.write_record(&BrtBeginDatabar::new(10, 90, bar.show_value as u8))?;
+        rule.write_record(&legacy(&min))?;
+        rule.write_record(&legacy(&max))?;
+        rule.write_record(&BrtColorRecord::new(brt_color(bar.color)))?;
+        rule.write_empty(BiffId::BrtEndDatabar)?;
+        rule.write_record(&BrtFRTBegin::excel_2010())?;
+        rule.write_record(&BrtCFRuleExt::new(guid.0, guid.1))?;
+        rule.write_empty(BiffId::BrtFRTEnd)?;
+        rule.write_empty(BiffId::BrtEndCFRule)?;
+
+        let mut ext = Vec::new();
+        ext.write_record(&BrtBeginCFRule14::rule(
+            BrtBeginCFRule::TYPE_DATABAR,
+            BrtBeginCFRule::TEMPLATE_DATABAR,
+            guid,
+            0,
+        ))?;
+        let flags = [
+            (bar.show_value, BrtBeginDatabar14::F_SHOW_VALUE),
+            (!bar.solid_fill, BrtBeginDatabar14::F_GRADIENT),
+            (bar.border_color.is_some(), BrtBeginDatabar14::F_BORDER),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, flag)| flags | flag);
+        ext.write_record(&BrtBeginDatabar14::new(
+            0,
+            100,
+            flags,
+            BrtBeginDatabar14::AXIS_AUTO,
+            BrtBeginDatabar14::DIRECTION_CONTEXT,
+        ))?;
+        ext.write_record(&BrtCFVO14::from(min))?;
+        ext.write_record(&BrtCFVO14::from(max))?;
+        let colors = [
+            (BrtColor14::FILL, Some(bar.color)),
+            (BrtColor14::BORDER, bar.border_color),
+            (BrtColor14::NEGATIVE_FILL, Some(bar.negative_color)),
+            (
+                BrtColor14::NEGATIVE_BORDER,
+                bar.border_color.map(|_| bar.negative_color),
+            ),
+            (BrtColor14::AXIS, Some(Color::Rgb(0, 0, 0))),
+        ];
+        for (kind, color) in colors {
+            if let Some(color) = color {
+                ext.write_record(&BrtColor14::new(kind, brt_color(color)))?;
+            }
+        }
+        ext.write_empty(BiffId::BrtEndDatabar14)?;
+        ext.write_empty(BiffId::BrtEndCFRule14)?;
+        self.push_rule(range, Some(rule), Some(ext))
+    }
+
+    fn add_icon_set(&mut self, range: CellRange, icons: &IconSet, stop: u16) -> Result<()> {
+        let count = icons.style.icon_count();
+        if icons.thresholds.len() != count {
+            return Err(Error::invalid_input(format!(
+                "icon set must have {} thresholds",
+                count
+            )));
+        }
+        if let Some(&(style, icon)) = icons
+            .icons
+            .iter()
+            .find(|(style, icon)| *icon as usize >= style.icon_count())
+        {
+            return Err(Error::invalid_input(format!(
+                "icon {} is out of the icon set {:?}",
+                icon, style
+            )));
+        }
+        if !icons.icons.is_empty() && icons.icons.len() != count {
+            return Err(Error::invalid_input(format!(
+                "icon set must have {} custom icons",
+                count
+            )));
+        }
+        let thresholds = icons
+            .thresholds
+            .iter()
+            .map(|value| self.cfvo(value))
+            .collect::<Result<Vec<_>>>()?;
+        let priority = self.conditional.count + 1;
+        if !icons.is_excel_2010() {
+            let mut rule = Vec::new();
+            rule.write_record(&BrtBeginCFRule::rule(
+                BrtBeginCFRule::TYPE_ICON_SET,
+                BrtBeginCFRule::TEMPLATE_ICON_SET,
+                BrtBeginCFRule::NO_DXF,
+                priority,
+                0,
+                stop,
+                Vec::new(),
+            ))?;
+            rule.write_record(&BrtBeginIconSet::new(icons.style as i32, icons.flags()))?;
+            for cfvo in &thresholds {
+                rule.write_record(cfvo)?;
+            }
+            rule.write_empty(BiffId::BrtEndIconSet)?;
+            rule.write_empty(BiffId::BrtEndCFRule)?;
+            return self.push_rule(range, Some(rule), None);
+        }
+
+        let mut ext = Vec::new();
+        let mut record = BrtBeginCFRule14::rule(
+            BrtBeginCFRule::TYPE_ICON_SET,
+            BrtBeginCFRule::TEMPLATE_ICON_SET,
+            self.rule_guid(priority),
+            priority,
+        );
+        record.flags = stop;
+        ext.write_record(&record)?;
+        let i_set = match icons.icons.is_empty() {
+            true => icons.style as i32,
+            false => BrtBeginIconSet::CUSTOM_SET,
+        };
+        ext.write_record(&BrtBeginIconSet14::new(i_set, icons.flags()))?;
+        for cfvo in thresholds {
+            ext.write_record(&BrtCFVO14::from(cfvo))?;
+        }
+        for &(style, icon) in &icons.icons {
+            ext.write_record(&BrtCFIcon::new(style as i32, icon))?;
+        }
+        ext.write_empty(BiffId::BrtEndIconSet14)?;
+        ext.write_empty(BiffId::BrtEndCFRule14)?;
+        self.push_rule(range, None, Some(ext))
+    }
+
+    fn push_rule(
+        &mut self,
+        range: CellRange,
+        rule: Option<Vec<u8>>,
+        ext: Option<Vec<u8>>,
+    ) -> Result<()> {
+        let formats = &mut self.conditional;
+        if let Some(rule) = rule {
+            ConditionalFormats::push(&mut formats.rules, range, rule);
+        }
+        if let Some(ext) = ext {
+            ConditionalFormats::push(&mut formats.ext_rules, range, ext);
+        }
+        formats.count += 1;
+        Ok(())
+    }
+
+    /// Formula of the rule, its relative references are relative to the first cell of the range
+    fn compile_rule_formula(&mut self, text: &str, range: CellRange) -> Result<ParsedFormula> {
+        let kind = FormulaType::Shared {
+            row: range.first_row,
+            col: range.first_col,
+        };
+        self.compile_condition(text, kind)
+    }
+
+    fn cfvo(&mut self, value: &ConditionValue) -> Result<BrtCFVO> {
+        let (i_type, num_param) = match *value {
+            ConditionValue::Min => (BrtCFVO::TYPE_MIN, 0.0),
+            ConditionValue::Max => (BrtCFVO::TYPE_MAX, 0.0),
+            ConditionValue::AutoMin => (BrtCFVO::TYPE_AUTO_MIN, 0.0),
+            ConditionValue::AutoMax => (BrtCFVO::TYPE_AUTO_MAX, 0.0),
+            ConditionValue::Number(value) => (BrtCFVO::TYPE_NUM, value),
+            ConditionValue::Percent(value) => (BrtCFVO::TYPE_PERCENT, value),
+            ConditionValue::Percentile(value) => (BrtCFVO::TYPE_PERCENTILE, value),
+            ConditionValue::Formula(ref text) => {
+                let formula = self.compile_condition(text, FormulaType::Cell)?;
+                return Ok(BrtCFVO::value(BrtCFVO::TYPE_FORMULA, 0.0, Some(formula)));
+            }
+        };
+        let in_percents = matches!(
+            value,
+            ConditionValue::Percent(_) | ConditionValue::Percentile(_)
+        );
+        if !num_param.is_finite() || (in_percents && !(0.0..=100.0).contains(&num_param)) {
+            return Err(Error::invalid_input(format!(
+                "invalid value {:?} of the conditional format",
+                value
+            )));
+        }
+        Ok(BrtCFVO::value(i_type, num_param, None))
+    }
+
+    /// Identifier, which links the rule to its Excel 2010 extension, unique within the workbook
+    fn rule_guid(&self, priority: u32) -> (u64, u64) {
+        ((self.index() as u64) << 32 | priority as u64, RULE_GUID_HI)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{core::biff::records::BrtDXF, writer::part_records, XlsbWriter};
+
+    #[test]
+    fn test_conditional_formats() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Scores").unwrap();
+        for row in 0..10 {
+            sheet.write(row, 0, row as f64 * 10.0).unwrap();
+        }
+        let red = DifferentialFormat {
+            font_color: Some(Color::Rgb(0x9C, 0x00, 0x06)),
+            fill: Some(Color::Rgb(0xFF, 0xC7, 0xCE)),
+            ..DifferentialFormat::default()
+        };
+        let rule = ConditionalRule::CellValue(CellCondition::Between(
+            "=20".to_owned(),
+            "=$B$1".to_owned(),
+        ));
+        sheet
+            .add_conditional_format(0, 0, 9, 0, &ConditionalFormat::new(rule, red.clone()))
+            .unwrap();
+        let rule = ConditionalRule::Expression("=A1>A2".to_owned());
+        sheet
+            .add_conditional_format(0, 0, 9, 0, &ConditionalFormat::new(rule, red))
+            .unwrap();
+        let bar = ConditionalRule::DataBar(DataBar::new(Color::Rgb(0x63, 0x8E, 0xC6)));
+        sheet
+            .add_conditional_format(
+                0,
+                1,
+                9,
+                1,
+                &ConditionalFormat::new(bar, DifferentialFormat::default()),
+            )
+            .unwrap();
+        let mut stars = IconSet::new(IconSetStyle::ThreeStars);
+        stars.thresholds[2] = ConditionValue::Formula("=$B$1".to_owned());
+        let stars = ConditionalFormat::new(
+            ConditionalRule::IconSet(stars),
+            DifferentialFormat::default(),
+        );
+        sheet.add_conditional_format(0, 2, 9, 2, &stars).unwrap();
+        let scale = ConditionalRule::ColorScale(ColorScale {
+            points: vec![(ConditionValue::Min, Color::Auto)],
+        });
+        assert!(sheet
+            .add_conditional_format(
+                0,
+                0,
+                9,
+                0,
+                &ConditionalFormat::new(scale, DifferentialFormat::default()),
+            )
+            .is_err());
+        let top = ConditionalRule::Top {
+            rank: 0,
+            percent: false,
+        };
+        assert!(sheet
+            .add_conditional_format(
+                0,
+                0,
+                9,
+                0,
+                &ConditionalFormat::new(top, DifferentialFormat::default()),
+            )
+            .is_err());
+        sheet.finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let dxfs = part_records(&mut zip, "xl/styles.bin")
+            .into_iter()
+            .filter(|rec| rec.id == BiffId::BrtDXF)
+            .map(|rec| rec.as_biff::<BrtDXF>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(dxfs.len(), 1);
+
+        let records = part_records(&mut zip, "xl/worksheets/sheet1.bin");
+        let ids = records.iter().map(|rec| rec.id).collect::<Vec<_>>();
+        let position = |id: BiffId| ids.iter().position(|other| *other == id).unwrap();
+        assert!(
+            position(BiffId::BrtEndSheetData) < position(BiffId::BrtBeginConditionalFormatting)
+        );
+        assert_eq!(
+            ids.iter()
+                .filter(|id| **id == BiffId::BrtBeginConditionalFormatting)
+                .count(),
+            2
+        );
+        let rules = records
+            .iter()
+            .filter(|rec| rec.id == BiffId::BrtBeginCFRule)
+            .map(|rec| rec.as_biff::<BrtBeginCFRule>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(rules.len(), 3);
+        let between = &rules[0];
+        assert_eq!((between.i_param, between.i_pri, between.dxf_id), (1, 1, 0));
+        assert_eq!(between.formulas.items.len(), 2);
+        // `A1` is the first cell of the range, it is stored as the zero offset
+        assert_eq!(
+            rules[1].formulas.items[0].rgce[..7],
+            [0x4C, 0, 0, 0, 0, 0x00, 0xC0]
+        );
+        assert_eq!(rules[2].dxf_id, BrtBeginCFRule::NO_DXF);
+
+        let ext = &ids[position(BiffId::BrtBeginConditionalFormattings)..];
+        assert_eq!(ext.last(), Some(&BiffId::BrtEndSheet));
+        assert_eq!(
+            ext.iter()
+                .filter(|id| **id == BiffId::BrtBeginConditionalFormatting14)
+                .count(),
+            2
+        );
+        let link = records[position(BiffId::BrtCFRuleExt)]
+            .as_biff::<BrtCFRuleExt>()
+            .unwrap();
+        let rule14 = records[position(BiffId::BrtBeginCFRule14)]
+            .as_biff::<BrtBeginCFRule14>()
+            .unwrap();
+        assert_eq!(
+            (link.guid_lo, link.guid_hi),
+            (rule14.guid_lo, rule14.guid_hi)
+        );
+        assert_eq!(
+            ids.iter().filter(|id| **id == BiffId::BrtCFVO14).count(),
+            2 + 3
+        );
+        assert!(!ids.contains(&BiffId::BrtBeginIconSet));
+    }
+}
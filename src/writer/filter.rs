@@ -0,0 +1,667 @@
+//! AutoFilter of the sheet: dropdown buttons on the header row and criteria of columns
+use std::{
+    collections::BTreeMap,
+    io::{Seek, Write},
+};
+
+use super::SheetWriter;
+use crate::{
+    core::biff::{
+        records::{
+            BrtBeginAFilter, BrtBeginCustomFilters, BrtBeginFilterColumn, BrtBeginFilters,
+            BrtCustomFilter, BrtDynamicFilter, BrtFilter, BrtTop10Filter, FilterCriterion,
+            FilterOperand,
+        },
+        BiffId, BiffWrite,
+    },
+    formula::number_text,
+    CellRange, CellValue, Error, Result,
+};
+
+/// Maximal length of values of filters
+const MAX_VALUE_LEN: usize = 255;
+
+/// Comparison operator of the custom filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl FilterOperator {
+    fn grbit_sgn(self) -> u8 {
+        match self {
+            FilterOperator::Equal => FilterCriterion::EQUAL,
+            FilterOperator::NotEqual => FilterCriterion::NOT_EQUAL,
+            FilterOperator::Less => FilterCriterion::LESS,
+            FilterOperator::LessOrEqual => FilterCriterion::LESS_OR_EQUAL,
+            FilterOperator::Greater => FilterCriterion::GREATER,
+            FilterOperator::GreaterOrEqual => FilterCriterion::GREATER_OR_EQUAL,
+        }
+    }
+}
+
+/// Comparison of cells with the number or the string.
+///
+/// Strings are compared ignoring case, `*` and `?` wildcards are supported by
+/// [`FilterOperator::Equal`] and [`FilterOperator::NotEqual`] (`~` escapes them).
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterComparison {
+    pub operator: FilterOperator,
+    pub value: CellValue,
+}
+
+impl FilterComparison {
+    pub fn new(operator: FilterOperator, value: impl Into<CellValue>) -> Self {
+        FilterComparison {
+            operator,
+            value: value.into(),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        match self.value {
+            CellValue::Number(value) if value.is_finite() => Ok(()),
+            CellValue::String(ref value) if value.chars().count() <= MAX_VALUE_LEN => Ok(()),
+            _ => Err(Error::invalid_input(format!(
+                "filter value {:?} must be a finite number or a string up to 255 characters",
+                self.value
+            ))),
+        }
+    }
+
+    fn criterion(&self) -> FilterCriterion {
+        let value = match self.value {
+            CellValue::Number(value) => FilterOperand::Number(value),
+            CellValue::String(ref value) => FilterOperand::String(value.as_str().into()),
+            _ => FilterOperand::Blanks,
+        };
+        FilterCriterion::new(self.operator.grbit_sgn(), value)
+    }
+
+    fn matches(&self, value: &CellValue) -> bool {
+        use std::cmp::Ordering;
+
+        let ordering = match (value, &self.value) {
+            (CellValue::Blank, _) => None,
+            (CellValue::String(text), _) if text.is_empty() => None,
+            (CellValue::Number(value), CellValue::Number(other)) => value.partial_cmp(other),
+            (CellValue::Number(_), _) | (_, CellValue::Number(_)) => None,
+            (value, CellValue::String(pattern)) => {
+                let text = display_text(value).unwrap_or_default().to_lowercase();
+                let pattern = pattern.to_lowercase();
+                match self.operator {
+                    FilterOperator::Equal | FilterOperator::NotEqual => {
+                        if wildcard_match(&pattern, &text) {
+                            Some(Ordering::Equal)
+                        } else {
+                            Some(Ordering::Less)
+                        }
+                    }
+                    _ => Some(text.cmp(&pattern)),
+                }
+            }
+            _ => None,
+        };
+        match (self.operator, ordering) {
+            // blanks and values of the other type are not equal to anything
+            (FilterOperator::NotEqual, ordering) => ordering != Some(Ordering::Equal),
+            (_, None) => false,
+            (FilterOperator::Equal, Some(ordering)) => ordering.is_eq(),
+            (FilterOperator::Less, Some(ordering)) => ordering.is_lt(),
+            (FilterOperator::LessOrEqual, Some(ordering)) => ordering.is_le(),
+            (FilterOperator::Greater, Some(ordering)) => ordering.is_gt(),
+            (FilterOperator::GreaterOrEqual, Some(ordering)) => ordering.is_ge(),
+        }
+    }
+}
+
+/// Filter, which depends on all values of the column or on the current date
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynamicFilter {
+    AboveAverage,
+    BelowAverage,
+    Tomorrow,
+    Today,
+    Yesterday,
+    NextWeek,
+    ThisWeek,
+    LastWeek,
+    NextMonth,
+    ThisMonth,
+    LastMonth,
+    NextQuarter,
+    ThisQuarter,
+    LastQuarter,
+    NextYear,
+    ThisYear,
+    LastYear,
+    YearToDate,
+    /// Quarter of any year, from 1 to 4
+    Quarter(u8),
+    /// Month of any year, from 1 to 12
+    Month(u8),
+}
+
+impl DynamicFilter {
+    /// Type of the filter (`cft`)
+    fn cft(self) -> u32 {
+        match self {
+            DynamicFilter::AboveAverage => 0x01,
+            DynamicFilter::BelowAverage => 0x02,
+            DynamicFilter::Tomorrow => 0x03,
+            DynamicFilter::Today => 0x04,
+            DynamicFilter::Yesterday => 0x05,
+            DynamicFilter::NextWeek => 0x06,
+            DynamicFilter::ThisWeek => 0x07,
+            DynamicFilter::LastWeek => 0x08,
+            DynamicFilter::NextMonth => 0x09,
+            DynamicFilter::ThisMonth => 0x0A,
+            DynamicFilter::LastMonth => 0x0B,
+            DynamicFilter::NextQuarter => 0x0C,
+            DynamicFilter::ThisQuarter => 0x0D,
+            DynamicFilter::LastQuarter => 0x0E,
+            DynamicFilter::NextYear => 0x0F,
+            DynamicFilter::ThisYear => 0x10,
+            DynamicFilter::LastYear => 0x11,
+            DynamicFilter::YearToDate => 0x12,
+            DynamicFilter::Quarter(quarter) => 0x12 + quarter as u32,
+            DynamicFilter::Month(month) => 0x16 + month as u32,
+        }
+    }
+}
+
+/// Criteria of the AutoFilter column.
+///
+/// Rows, which do not match values or custom comparisons, are hidden as they are written,
+/// so the sheet opens already filtered. Top and dynamic filters depend on all values of the
+/// column, they are only stored and Excel applies them when the filter is reapplied.
+/// Numbers with a number format other than General are not hidden by values, as their
+/// displayed text is not known to the writer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnFilter {
+    /// Show rows with one of the values, which are compared with the displayed text of cells
+    /// ignoring case, and with empty cells if `blanks` is set. Numbers are displayed like
+    /// Excel does with the General format, e.g. `0.1` or `1E+20`.
+    Values {
+        values: Vec<String>,
+        blanks: bool,
+    },
+    /// Show rows, which match the first comparison and (if `and` is set) or the second one
+    Custom {
+        first: FilterComparison,
+        second: Option<FilterComparison>,
+        and: bool,
+    },
+    /// Show rows with the top or bottom `count` values or percent of values (from 1 to 500)
+    Top {
+        count: u16,
+        percent: bool,
+        bottom: bool,
+    },
+    Dynamic(DynamicFilter),
+}
+
+impl ColumnFilter {
+    fn validate(&self) -> Result<()> {
+        match self {
+            ColumnFilter::Values { values, blanks } => {
+                if values.is_empty() && !blanks {
+                    return Err(Error::invalid_input(
+                        "filter must show at least one value or blanks",
+                    ));
+                }
+                if let Some(value) = values
+                    .iter()
+                    .find(|value| value.chars().count() > MAX_VALUE_LEN)
+                {
+                    return Err(Error::invalid_input(format!(
+                        "filter value {:?} is longer than 255 characters",
+                        value
+                    )));
+                }
+                Ok(())
+            }
+            ColumnFilter::Custom { first, second, .. } => {
+                first.validate()?;
+                second.as_ref().map_or(Ok(()), FilterComparison::validate)
+            }
+            ColumnFilter::Top { count, percent, .. } => {
+                let max = if *percent { 100 } else { 500 };
+                if (1..=max).contains(count) {
+                    Ok(())
+                } else {
+                    Err(Error::invalid_input(format!(
+                        "top filter count {} must be from 1 to {}",
+                        count, max
+                    )))
+                }
+            }
+            ColumnFilter::Dynamic(DynamicFilter::Quarter(quarter))
+                if !(1..=4).contains(quarter) =>
+            {
+                Err(Error::invalid_input(format!(
+                    "quarter {} must be from 1 to 4",
+                    quarter
+                )))
+            }
+            ColumnFilter::Dynamic(DynamicFilter::Month(month)) if !(1..=12).contains(month) => Err(
+                Error::invalid_input(format!("month {} must be from 1 to 12", month)),
+            ),
+            ColumnFilter::Dynamic(_) => Ok(()),
+        }
+    }
+
+    /// Filter is evaluated by the writer, when rows are written
+    fn is_evaluated(&self) -> bool {
+        matches!(
+            self,
+            ColumnFilter::Values { .. } | ColumnFilter::Custom { .. }
+        )
+    }
+
+    fn matches(&self, cell: &FilterValue) -> bool {
+        let value = &cell.value;
+        match self {
+            ColumnFilter::Values { .. } if !cell.general => true,
+            ColumnFilter::Values { values, blanks } => match display_text(value) {
+                None => *blanks,
+                Some(text) => values
+                    .iter()
+                    .any(|other| other.to_lowercase() == text.to_lowercase()),
+            },
+            ColumnFilter::Custom { first, second, and } => {
+                let first = first.matches(value);
+                match second {
+                    Some(second) if *and => first && second.matches(value),
+                    Some(second) => first || second.matches(value),
+                    None => first,
+                }
+            }
+            ColumnFilter::Top { .. } | ColumnFilter::Dynamic(_) => true,
+        }
+    }
+
+    fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        match self {
+            ColumnFilter::Values { values, blanks } => {
+                out.write_record(&BrtBeginFilters::new(*blanks as u32, 0))?;
+                for value in values {
+                    out.write_record(&BrtFilter::new(value.as_str().into()))?;
+                }
+                out.write_empty(BiffId::BrtEndFilters)?;
+            }
+            ColumnFilter::Custom { first, second, and } => {
+                out.write_record(&BrtBeginCustomFilters::new(*and as u32))?;
+                for comparison in std::iter::once(first).chain(second) {
+                    out.write_record(&BrtCustomFilter::new(comparison.criterion()))?;
+                }
+                out.write_empty(BiffId::BrtEndCustomFilters)?;
+            }
+            ColumnFilter::Top {
+                count,
+                percent,
+                bottom,
+            } => {
+                let mut flags = 0;
+                if !bottom {
+                    flags |= BrtTop10Filter::F_TOP;
+                }
+                if *percent {
+                    flags |= BrtTop10Filter::F_PERCENT;
+                }
+                out.write_record(&BrtTop10Filter::new(flags, *count as u32, 0.0))?;
+            }
+            ColumnFilter::Dynamic(filter) => {
+                out.write_record(&BrtDynamicFilter::new(filter.cft(), 0.0, 0.0))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value of the cell, which is checked by AutoFilter criteria
+pub(super) struct FilterValue {
+    value: CellValue,
+    /// The value is displayed with the General number format, so its text is known
+    general: bool,
+}
+
+impl FilterValue {
+    /// Value of the cell, which is displayed with the number format
+    pub(super) fn formatted(value: CellValue, code: &str) -> Self {
+        let general =
+            !matches!(value, CellValue::Number(_)) || code.eq_ignore_ascii_case("General");
+        FilterValue { value, general }
+    }
+}
+
+impl From<CellValue> for FilterValue {
+    fn from(value: CellValue) -> Self {
+        FilterValue {
+            value,
+            general: true,
+        }
+    }
+}
+
+/// AutoFilter range and criteria of its columns
+pub(super) struct AutoFilter {
+    range: CellRange,
+    columns: BTreeMap<u16, ColumnFilter>,
+}
+
+impl AutoFilter {
+    pub(super) fn range(&self) -> CellRange {
+        self.range
+    }
+
+    /// Some rows of the range can be hidden by criteria
+    pub(super) fn has_criteria(&self) -> bool {
+        !self.columns.is_empty()
+    }
+
+    fn is_data_row(&self, row: u32) -> bool {
+        row > self.range.first_row && row <= self.range.last_row
+    }
+
+    /// Value of the cell is needed to decide, whether its row is shown
+    pub(super) fn is_evaluated(&self, row: u32, col: u16) -> bool {
+        self.is_data_row(row)
+            && self
+                .columns
+                .get(&col)
+                .is_some_and(ColumnFilter::is_evaluated)
+    }
+
+    /// Row is shown: it is out of the range or its values match criteria of all columns
+    pub(super) fn matches(&self, row: u32, values: &BTreeMap<u16, FilterValue>) -> bool {
+        let blank = FilterValue::from(CellValue::Blank);
+        !self.is_data_row(row)
+            || self
+                .columns
+                .iter()
+                .all(|(col, filter)| filter.matches(values.get(col).unwrap_or(&blank)))
+    }
+
+    pub(super) fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        out.write_record(&BrtBeginAFilter::new(self.range.to_rfx()))?;
+        for (col, filter) in &self.columns {
+            out.write_record(&BrtBeginFilterColumn::new(
+                (col - self.range.first_col) as u32,
+                0,
+            ))?;
+            filter.write(out)?;
+            out.write_empty(BiffId::BrtEndFilterColumn)?;
+        }
+        out.write_empty(BiffId::BrtEndAFilter)?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Place AutoFilter dropdown buttons on the first row of the range, which is the header of
+    /// the filtered data. Criteria of columns within the new range are kept.
+    pub fn autofilter(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        range.validate()?;
+        self.check_table_overlap(range, "AutoFilter range")?;
+        if let Some(col) = self.rows.filter.iter().find_map(|filter| {
+            filter
+                .columns
+                .keys()
+                .find(|&&col| !(first_col..=last_col).contains(&col))
+        }) {
+            return Err(Error::invalid_input(format!(
+                "filtered column {} is out of the AutoFilter range {}",
+                col, range
+            )));
+        }
+        if let Some(ref filter) = self.rows.filter {
+            if filter.has_criteria()
+                && filter.range != range
+                && self.next_row() > filter.range.first_row + 1
+            {
+                return Err(Error::invalid_input(
+                    "AutoFilter range with criteria cannot be changed after data rows are written",
+                ));
+            }
+        }
+        let columns = self
+            .rows
+            .filter
+            .take()
+            .map(|filter| filter.columns)
+            .unwrap_or_default();
+        self.rows.filter = Some(AutoFilter { range, columns });
+        Ok(())
+    }
+
+    /// Set criteria of the column of the AutoFilter range, replacing the previous ones.
+    /// Criteria must be set before data rows of the range are written.
+    pub fn filter_column(&mut self, col: u16, filter: ColumnFilter) -> Result<()> {
+        filter.validate()?;
+        let next_row = self.next_row();
+        let Some(ref mut autofilter) = self.rows.filter else {
+            return Err(Error::invalid_input("sheet has no AutoFilter range"));
+        };
+        let range = autofilter.range;
+        if !(range.first_col..=range.last_col).contains(&col) {
+            return Err(Error::invalid_input(format!(
+                "column {} is out of the AutoFilter range {}",
+                col, range
+            )));
+        }
+        if next_row > range.first_row + 1 {
+            return Err(Error::invalid_input(
+                "filter criteria must be set before data rows are written",
+            ));
+        }
+        autofilter.columns.insert(col, filter);
+        Ok(())
+    }
+}
+
+/// Text of the value, as it is displayed with the General format, `None` for empty cells
+fn display_text(value: &CellValue) -> Option<String> {
+    match value {
+        CellValue::Blank => None,
+        CellValue::String(value) if value.is_empty() => None,
+        CellValue::String(value) => Some(value.clone()),
+        CellValue::Number(value) => Some(number_text(*value)),
+        CellValue::Bool(value) => Some(if *value { "TRUE" } else { "FALSE" }.to_owned()),
+        CellValue::Error(value) => Some(value.as_str().to_owned()),
+    }
+}
+
+/// Token of the wildcard pattern
+#[derive(Clone, Copy, PartialEq)]
+enum Wildcard {
+    /// `*`, any sequence of characters
+    Any,
+    /// `?`, any character
+    One,
+    Char(char),
+}
+
+/// Match the text with the pattern, where `*` is any sequence, `?` is any character and `~`
+/// escapes the next character. On the mismatch, only the last `*` takes one more character, so
+/// the time is linear in the product of lengths.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        tokens.push(match ch {
+            '*' => Wildcard::Any,
+            '?' => Wildcard::One,
+            '~' => Wildcard::Char(chars.next().unwrap_or('~')),
+            ch => Wildcard::Char(ch),
+        });
+    }
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut pos, mut idx) = (0, 0);
+    // token after the last `*` and the position of the text, which it is matched from
+    let mut star = None;
+    while idx < text.len() {
+        match tokens.get(pos) {
+            Some(Wildcard::Any) => {
+                star = Some((pos + 1, idx));
+                pos += 1;
+            }
+            Some(Wildcard::One) => (pos, idx) = (pos + 1, idx + 1),
+            Some(Wildcard::Char(ch)) if *ch == text[idx] => (pos, idx) = (pos + 1, idx + 1),
+            _ => match star {
+                Some((after, from)) => {
+                    star = Some((after, from + 1));
+                    (pos, idx) = (after, from + 1);
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[pos..].iter().all(|token| *token == Wildcard::Any)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{
+        core::biff::records::{BrtName, BrtRowHdr},
+        writer::part_records,
+        XlsbWriter,
+    };
+
+    #[test]
+    fn test_autofilter() {
+        assert!(wildcard_match("a*c?", "abbbcd"));
+        assert!(wildcard_match("~*a", "*a") && !wildcard_match("~*a", "ba"));
+        assert!(wildcard_match("*", "") && !wildcard_match("?", "") && wildcard_match("a~", "a~"));
+        // stars don't backtrack exponentially on long texts
+        let text = "a".repeat(10_000);
+        assert!(!wildcard_match("*a*a*a*a*a*a*b", &text));
+        assert!(wildcard_match("*a*a*a*a*a*a*", &text));
+        let greater = FilterComparison::new(FilterOperator::Greater, 5);
+        assert!(greater.matches(&CellValue::Number(6.0)));
+        assert!(!greater.matches(&CellValue::String("6".to_owned())));
+        let not_x = FilterComparison::new(FilterOperator::NotEqual, "X*");
+        assert!(not_x.matches(&CellValue::Blank) && !not_x.matches(&"xyz".into()));
+        assert_eq!(display_text(&(0.1 + 0.2).into()).unwrap(), "0.3");
+        assert_eq!(display_text(&1e20.into()).unwrap(), "1E+20");
+        let point = ColumnFilter::Values {
+            values: vec!["0.3".to_owned()],
+            blanks: false,
+        };
+        assert!(point.matches(&FilterValue::formatted(0.3.into(), "General")));
+        assert!(!point.matches(&FilterValue::formatted(0.5.into(), "General")));
+        // text of formatted numbers is not known, so they are shown
+        assert!(point.matches(&FilterValue::formatted(0.5.into(), "0.00%")));
+
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Filtered").unwrap();
+        let values = ColumnFilter::Values {
+            values: vec!["a".to_owned(), "1".to_owned()],
+            blanks: false,
+        };
+        assert!(sheet.filter_column(0, values.clone()).is_err());
+        sheet.autofilter(0, 0, 5, 1).unwrap();
+        sheet.filter_column(0, values).unwrap();
+        let custom = ColumnFilter::Custom {
+            first: FilterComparison::new(FilterOperator::GreaterOrEqual, 10),
+            second: Some(FilterComparison::new(FilterOperator::Equal, "n/a")),
+            and: false,
+        };
+        sheet.filter_column(1, custom).unwrap();
+        assert!(sheet
+            .filter_column(
+                2,
+                ColumnFilter::Top {
+                    count: 10,
+                    percent: false,
+                    bottom: false
+                }
+            )
+            .is_err());
+        assert!(sheet.autofilter(0, 1, 5, 1).is_err());
+
+        sheet.write(0, 0, "key").unwrap();
+        sheet.write(0, 1, "value").unwrap();
+        for (row, (key, value)) in [
+            (CellValue::from("A"), CellValue::from(12)),
+            ("b".into(), 20.into()),
+            (1.into(), "N/A".into()),
+            ("a".into(), 3.into()),
+            (CellValue::Blank, 10.into()),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            sheet.write(row as u32 + 1, 0, key).unwrap();
+            sheet.write(row as u32 + 1, 1, value).unwrap();
+        }
+        assert!(sheet
+            .filter_column(1, ColumnFilter::Dynamic(DynamicFilter::Today))
+            .is_err());
+        // written rows were filtered by the current range already
+        assert!(sheet.autofilter(0, 0, 10, 1).is_err());
+        sheet.autofilter(0, 0, 5, 1).unwrap();
+        sheet.finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+
+        let records = part_records(&mut zip, "xl/worksheets/sheet1.bin");
+        let hidden = records
+            .iter()
+            .filter(|rec| rec.id == BiffId::BrtRowHdr)
+            .map(|rec| rec.as_biff::<BrtRowHdr>().unwrap())
+            .filter(|hdr| hdr.flags & BrtRowHdr::F_DY_ZERO != 0)
+            .map(|hdr| hdr.rw.inner)
+            .collect::<Vec<_>>();
+        assert_eq!(hidden, [2, 4, 5]);
+        let ids = records
+            .iter()
+            .map(|rec| rec.id)
+            .skip_while(|id| *id != BiffId::BrtBeginAFilter)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                BiffId::BrtBeginAFilter,
+                BiffId::BrtBeginFilterColumn,
+                BiffId::BrtBeginFilters,
+                BiffId::BrtFilter,
+                BiffId::BrtFilter,
+                BiffId::BrtEndFilters,
+                BiffId::BrtEndFilterColumn,
+                BiffId::BrtBeginFilterColumn,
+                BiffId::BrtBeginCustomFilters,
+                BiffId::BrtCustomFilter,
+                BiffId::BrtCustomFilter,
+                BiffId::BrtEndCustomFilters,
+                BiffId::BrtEndFilterColumn,
+                BiffId::BrtEndAFilter,
+                BiffId::BrtEndSheet,
+            ]
+        );
+
+        let name = part_records(&mut zip, "xl/workbook.bin")
+            .into_iter()
+            .find(|rec| rec.id == BiffId::BrtName)
+            .unwrap()
+            .as_biff::<BrtName>()
+            .unwrap();
+        assert_eq!(name.name.inner, "_xlnm._FilterDatabase");
+        assert_eq!((name.itab, name.flags & BrtName::F_HIDDEN), (0, 1));
+    }
+}
@@ -0,0 +1,297 @@
+//! Shared and array formulas, which are stored once for the range of cells
+use std::io::{Seek, Write};
+
+use super::{metadata::DYNAMIC_ARRAY_BLOCK, SheetWriter};
+use crate::{
+    core::biff::{
+        records::{BrtArrFmla, BrtCellMeta, BrtShrFmla, ParsedFormula},
+        BiffSerializable,
+    },
+    formula::FormulaType,
+    CellRange, CellValue, Error, Result, StyleId,
+};
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Write the formula, which is shared by the range of cells, into its first cell with the
+    /// cached value.
+    ///
+    /// The formula is written for the first cell, its relative references are shifted for
+    /// other cells, like Excel does it on fill. Other cells are written by
+    /// [`SheetWriter::write_formula_result`], cells which are not written stay empty.
+    pub fn write_shared_formula(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+    ) -> Result<()> {
+        self.write_shared_formula_with_style(
+            first_row,
+            first_col,
+            last_row,
+            last_col,
+            formula,
+            value,
+            StyleId::default(),
+        )
+    }
+
+    /// Write the shared formula of the range with the style of its first cell, registered by
+    /// [`XlsbWriter::add_style`](super::XlsbWriter::add_style)
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_shared_formula_with_style(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+        style: StyleId,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        let kind = FormulaType::Shared {
+            row: first_row,
+            col: first_col,
+        };
+        self.put_range_formula(range, formula, kind, &value.into(), style, false)
+    }
+
+    /// Write the array formula of the range, like `=A1:A3*B1:B3`, into its first cell with the
+    /// cached value. Other cells of the range are written by
+    /// [`SheetWriter::write_formula_result`].
+    pub fn write_array_formula(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+    ) -> Result<()> {
+        self.write_array_formula_with_style(
+            first_row,
+            first_col,
+            last_row,
+            last_col,
+            formula,
+            value,
+            StyleId::default(),
+        )
+    }
+
+    /// Write the array formula of the range with the style of its first cell
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_array_formula_with_style(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+        style: StyleId,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        let value = value.into();
+        self.put_range_formula(range, formula, FormulaType::Array, &value, style, false)
+    }
+
+    /// Write the dynamic array formula, like `=SORT(FILTER(A2:B9,B2:B9>0))`, into its first cell
+    /// with the cached value. The result spills into the range, the range is recalculated by
+    /// Excel; cached values of other cells are written as regular cells.
+    pub fn write_dynamic_array_formula(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+    ) -> Result<()> {
+        self.write_dynamic_array_formula_with_style(
+            first_row,
+            first_col,
+            last_row,
+            last_col,
+            formula,
+            value,
+            StyleId::default(),
+        )
+    }
+
+    /// Write the dynamic array formula of the range with the style of its first cell
+    #[allow(clippy::too_many_arguments)]
+    pub fn write_dynamic_array_formula_with_style(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+        style: StyleId,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        let value = value.into();
+        self.put_range_formula(range, formula, FormulaType::Array, &value, style, true)
+    }
+
+    /// Write the cached value of the cell within the range of the shared or the array formula
+    pub fn write_formula_result(
+        &mut self,
+        row: u32,
+        col: u16,
+        value: impl Into<CellValue>,
+    ) -> Result<()> {
+        self.write_formula_result_with_style(row, col, value, StyleId::default())
+    }
+
+    /// Write the cached value of the cell within the range of the shared or the array formula
+    /// with the style
+    pub fn write_formula_result_with_style(
+        &mut self,
+        row: u32,
+        col: u16,
+        value: impl Into<CellValue>,
+        style: StyleId,
+    ) -> Result<()> {
+        self.check_style(style)?;
+        let range = self
+            .formula_ranges
+            .iter()
+            .rev()
+            .find(|range| range.contains(row, col));
+        match range {
+            Some(range) if (range.first_row, range.first_col) != (row, col) => {
+                let formula = ParsedFormula::exp(range.first_row, range.first_col);
+                self.put_formula(row, col, formula, &value.into(), style.index())
+            }
+            Some(_) => Err(Error::invalid_input(format!(
+                "cell ({}, {}) holds the formula of its range",
+                row, col
+            ))),
+            None => Err(Error::invalid_input(format!(
+                "cell ({}, {}) is not within the range of the shared or the array formula",
+                row, col
+            ))),
+        }
+    }
+
+    /// Put the reference to the formula into the first cell of the range, followed by the
+    /// formula itself. Cells of the dynamic array are not referencing its formula.
+    fn put_range_formula(
+        &mut self,
+        range: CellRange,
+        formula: &str,
+        kind: FormulaType,
+        value: &CellValue,
+        style: StyleId,
+        dynamic: bool,
+    ) -> Result<()> {
+        range.validate()?;
+        self.check_style(style)?;
+        if let Some(other) = self
+            .formula_ranges
+            .iter()
+            .chain(&self.spill_ranges)
+            .find(|other| other.intersects(&range))
+        {
+            return Err(Error::invalid_input(format!(
+                "formula range {} overlaps formula range {}",
+                range, other
+            )));
+        }
+        self.check_position(range.first_row, range.first_col)?;
+        let formula = self.compile_formula(formula, kind)?;
+        let record = match kind {
+            FormulaType::Array => BrtArrFmla::new(range.to_rfx(), 0, formula).into_biff()?,
+            _ => BrtShrFmla::new(range.to_rfx(), formula).into_biff()?,
+        };
+        let (row, col) = (range.first_row, range.first_col);
+        let formula_ref = ParsedFormula::exp(row, col);
+        self.put_formula(row, col, formula_ref, value, style.index())?;
+        self.rows.insert_formula(row, col, record);
+        if dynamic {
+            let meta = BrtCellMeta::new(DYNAMIC_ARRAY_BLOCK).into_biff()?;
+            self.rows.insert_metadata(row, col, meta);
+            self.use_dynamic_arrays();
+            self.spill_ranges.push(range);
+        } else {
+            self.formula_ranges.push(range);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{Style, StyleId, XlsbReader, XlsbWriter};
+
+    #[test]
+    fn test_range_formulas() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let percent = book
+            .add_style(&Style {
+                number_format: "0%".to_owned(),
+                ..Style::default()
+            })
+            .unwrap();
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet
+            .write_shared_formula(0, 1, 3, 1, "=A1*2", 2.0)
+            .unwrap();
+        // the first cell holds the formula, cells outside of ranges have no formula to use
+        assert!(sheet.write_formula_result(0, 1, 2.0).is_err());
+        assert!(sheet.write_formula_result(0, 2, 2.0).is_err());
+        assert!(sheet
+            .write_array_formula(2, 0, 2, 1, "=A1:B1", 0.0)
+            .is_err());
+        assert!(sheet.write_array_formula(3, 3, 2, 3, "=A1", 0.0).is_err());
+        sheet.write_formula_result(1, 1, 4.0).unwrap();
+        sheet
+            .write_array_formula(1, 2, 2, 3, "=A1:B2", 0.0)
+            .unwrap();
+        sheet.write_formula_result(2, 3, 0.0).unwrap();
+        sheet
+            .write_dynamic_array_formula(3, 2, 4, 2, "=SORT(A1:A2)", 1.0)
+            .unwrap();
+        // cells of the dynamic array are regular cells
+        assert!(sheet.write_formula_result(4, 2, 2.0).is_err());
+        // other formulas cannot overlap the range of the dynamic array
+        assert!(sheet.write_shared_formula(4, 1, 4, 2, "=A1", 0.0).is_err());
+        assert!(sheet
+            .write_dynamic_array_formula(4, 2, 5, 2, "=SORT(A1:A2)", 1.0)
+            .is_err());
+        // rows are written in order
+        assert!(sheet.write_shared_formula(0, 5, 1, 5, "=A1", 0.0).is_err());
+        sheet
+            .write_shared_formula_with_style(6, 0, 7, 0, "=B1", 0.5, percent)
+            .unwrap();
+        assert!(sheet
+            .write_formula_result_with_style(7, 0, 0.5, StyleId(99))
+            .is_err());
+        sheet
+            .write_formula_result_with_style(7, 0, 0.5, percent)
+            .unwrap();
+        sheet
+            .write_array_formula_with_style(8, 0, 8, 1, "=B1:C1", 0.5, percent)
+            .unwrap();
+        sheet.finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut reader = XlsbReader::new(Cursor::new(data)).unwrap();
+        let styles = reader
+            .cells("Data")
+            .unwrap()
+            .map(|cell| cell.unwrap())
+            .filter(|cell| cell.row >= 6)
+            .map(|cell| cell.style)
+            .collect::<Vec<_>>();
+        assert_eq!(styles, [percent.index(); 3]);
+    }
+}
@@ -0,0 +1,303 @@
+//! Hyperlinks of cells to URLs, files, e-mail addresses and locations in the workbook
+use std::io::{Seek, Write};
+
+use super::{sheet::check_string, SheetWriter};
+use crate::{
+    core::{
+        biff::{records::BrtHLink, BiffWrite},
+        xml::{Relationships, REL_HYPERLINK},
+    },
+    range::RangeIndex,
+    CellRange, Error, Result,
+};
+
+/// Maximal length of the external target
+const MAX_URL_LEN: usize = 2079;
+
+/// Maximal length of the location and of the tooltip
+const MAX_LOCATION_LEN: usize = 255;
+
+/// Maximal count of hyperlinks of the sheet
+const MAX_HYPERLINKS: usize = 65530;
+
+/// Hyperlink of the cell or the range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+    /// External target: URL, like `https://example.com` or `mailto:user@example.com`, or the
+    /// path of the file
+    pub target: Option<String>,
+    /// Location in the target, or in this workbook without the target, like `'Sheet 2'!A1`
+    pub location: Option<String>,
+    /// Tip, which is shown when the cell is hovered
+    pub tooltip: Option<String>,
+    /// Text of the cell, which is written by [`SheetWriter::write_hyperlink`]
+    pub display: Option<String>,
+}
+
+impl Hyperlink {
+    /// Link to the URL, its fragment after `#` becomes the location
+    pub fn url(url: &str) -> Self {
+        match url.split_once('#') {
+            Some((target, location)) => Hyperlink::new(Some(target), Some(location)),
+            None => Hyperlink::new(Some(url), None),
+        }
+    }
+
+    /// Link to the file by its path, which is absolute or relative to the workbook
+    pub fn file(path: &str) -> Self {
+        let bytes = path.as_bytes();
+        let absolute = path.starts_with("\\\\")
+            || (bytes.len() > 2 && bytes[1] == b':' && bytes[0].is_ascii_alphabetic());
+        if absolute {
+            Hyperlink::new(Some(&format!("file:///{}", path)), None)
+        } else {
+            Hyperlink::new(Some(path), None)
+        }
+    }
+
+    /// Link to send e-mail to the address
+    pub fn email(address: &str) -> Self {
+        Hyperlink::new(Some(&format!("mailto:{}", address)), None)
+    }
+
+    /// Link to the location in this workbook: the cell, like `'Sheet 2'!A1`, or the defined
+    /// name
+    pub fn location(location: &str) -> Self {
+        Hyperlink::new(None, Some(location))
+    }
+
+    fn new(target: Option<&str>, location: Option<&str>) -> Self {
+        Hyperlink {
+            target: target.map(str::to_owned),
+            location: location.map(str::to_owned),
+            tooltip: None,
+            display: None,
+        }
+    }
+
+    /// Set the location in the target, like the sheet of the linked workbook
+    pub fn with_location(mut self, location: &str) -> Self {
+        self.location = Some(location.to_owned());
+        self
+    }
+
+    /// Set the tip, which is shown when the cell is hovered
+    pub fn with_tooltip(mut self, tooltip: &str) -> Self {
+        self.tooltip = Some(tooltip.to_owned());
+        self
+    }
+
+    /// Set the text of the cell
+    pub fn with_display(mut self, display: &str) -> Self {
+        self.display = Some(display.to_owned());
+        self
+    }
+
+    /// Text of the cell: the display text, or the target with the location
+    fn text(&self) -> String {
+        if let Some(display) = &self.display {
+            return display.clone();
+        }
+        let target = self
+            .target
+            .as_deref()
+            .map(|target| target.strip_prefix("mailto:").unwrap_or(target));
+        match (target, self.location.as_deref()) {
+            (Some(target), Some(location)) => format!("{}#{}", target, location),
+            (Some(target), None) => target.to_owned(),
+            (None, location) => location.unwrap_or_default().to_owned(),
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let texts = [
+            (&self.target, MAX_URL_LEN, "target"),
+            (&self.location, MAX_LOCATION_LEN, "location"),
+            (&self.tooltip, MAX_LOCATION_LEN, "tooltip"),
+        ];
+        for (text, max_len, kind) in texts {
+            if let Some(text) = text
+                .as_deref()
+                .filter(|text| text.chars().count() > max_len)
+            {
+                return Err(Error::invalid_input(format!(
+                    "{} {:?} of the hyperlink is longer than {} characters",
+                    kind, text, max_len
+                )));
+            }
+        }
+        let is_empty = |text: &Option<String>| text.as_deref().is_none_or(str::is_empty);
+        if is_empty(&self.target) && is_empty(&self.location) {
+            return Err(Error::invalid_input(
+                "hyperlink must have the target or the location",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Hyperlinks of the sheet, in the order of addition
+#[derive(Default)]
+pub(super) struct Hyperlinks {
+    ranges: RangeIndex,
+    /// Hyperlinks of `ranges`, in the same order
+    links: Vec<Hyperlink>,
+}
+
+impl Hyperlinks {
+    /// Write hyperlinks after data validations, external targets are added to relationships
+    /// of the sheet
+    pub(super) fn write<W: Write>(&self, out: &mut W, rels: &mut Relationships) -> Result<()> {
+        for (range, link) in self.ranges.iter().zip(&self.links) {
+            let rel_id = link
+                .target
+                .as_deref()
+                .filter(|target| !target.is_empty())
+                .map(|target| rels.add_external(REL_HYPERLINK, target));
+            out.write_record(&BrtHLink::new(
+                range.to_rfx(),
+                rel_id.as_deref().into(),
+                link.location.as_deref().unwrap_or_default().into(),
+                link.tooltip.as_deref().unwrap_or_default().into(),
+                link.display.as_deref().unwrap_or_default().into(),
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Add the hyperlink to the range, values and styles of cells are not changed
+    pub fn add_hyperlink(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        link: &Hyperlink,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        range.validate()?;
+        link.validate()?;
+        if let Some(other) = self.hyperlinks.ranges.find_overlap(&range) {
+            return Err(Error::invalid_input(format!(
+                "hyperlink range {} overlaps hyperlink range {}",
+                range, other
+            )));
+        }
+        if self.hyperlinks.ranges.len() >= MAX_HYPERLINKS {
+            return Err(Error::invalid_input("too many hyperlinks in the sheet"));
+        }
+        self.hyperlinks.ranges.push(range);
+        self.hyperlinks.links.push(link.clone());
+        Ok(())
+    }
+
+    /// Write the text of the hyperlink into the cell with the built-in `Hyperlink` style and add
+    /// the hyperlink to it
+    pub fn write_hyperlink(&mut self, row: u32, col: u16, link: &Hyperlink) -> Result<()> {
+        self.check_position(row, col)?;
+        // the cell must be written, once the hyperlink is added
+        let text = link.text();
+        check_string(row, col, &text)?;
+        let style = self.hyperlink_style()?;
+        self.add_hyperlink(row, col, row, col, link)?;
+        self.write_with_style(row, col, text, style)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use crate::{
+        core::biff::{
+            records::{BrtHLink, BrtStyle},
+            BiffId,
+        },
+        writer::part_records,
+        Hyperlink, XlsbReader, XlsbWriter,
+    };
+
+    #[test]
+    fn test_hyperlinks() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Index").unwrap();
+        sheet
+            .write_hyperlink(0, 0, &Hyperlink::location("'Sheet 2'!A1"))
+            .unwrap();
+        let url = Hyperlink::url("https://example.com/docs#intro").with_tooltip("Docs");
+        sheet.write_hyperlink(1, 0, &url).unwrap();
+        sheet
+            .write_hyperlink(2, 0, &Hyperlink::email("qa@example.com"))
+            .unwrap();
+        sheet
+            .add_hyperlink(3, 0, 4, 1, &Hyperlink::file("C:\\Reports\\q1.xlsb"))
+            .unwrap();
+        assert!(sheet.add_hyperlink(4, 1, 4, 1, &url).is_err());
+        assert!(sheet
+            .add_hyperlink(5, 0, 5, 0, &Hyperlink::location(""))
+            .is_err());
+        // the hyperlink is not added, when its cell cannot be written
+        let long = Hyperlink::location("A1").with_display(&"x".repeat(32768));
+        assert!(sheet.write_hyperlink(5, 1, &long).is_err());
+        sheet
+            .add_hyperlink(5, 1, 5, 1, &Hyperlink::location("A1"))
+            .unwrap();
+        sheet.finish().unwrap();
+        book.add_sheet("Sheet 2").unwrap().finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(data.clone())).unwrap();
+        let links = part_records(&mut zip, "xl/worksheets/sheet1.bin")
+            .into_iter()
+            .filter(|rec| rec.id == BiffId::BrtHLink)
+            .map(|rec| rec.as_biff::<BrtHLink>().unwrap())
+            .map(|link| (link.rel_id.inner, link.location.inner, link.tooltip.inner))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            links,
+            [
+                (None, "'Sheet 2'!A1".to_owned(), String::new()),
+                (
+                    Some("rId1".to_owned()),
+                    "intro".to_owned(),
+                    "Docs".to_owned()
+                ),
+                (Some("rId2".to_owned()), String::new(), String::new()),
+                (Some("rId3".to_owned()), String::new(), String::new()),
+                (None, "A1".to_owned(), String::new()),
+            ]
+        );
+        let mut rels = String::new();
+        zip.by_name("xl/worksheets/_rels/sheet1.bin.rels")
+            .unwrap()
+            .read_to_string(&mut rels)
+            .unwrap();
+        assert!(rels.contains("Target=\"mailto:qa@example.com\" TargetMode=\"External\""));
+        assert!(rels.contains("Target=\"file:///C:\\Reports\\q1.xlsb\""));
+        let styles = part_records(&mut zip, "xl/styles.bin")
+            .into_iter()
+            .filter(|rec| rec.id == BiffId::BrtStyle)
+            .map(|rec| rec.as_biff::<BrtStyle>().unwrap().st_name.inner)
+            .collect::<Vec<_>>();
+        assert_eq!(styles, ["Normal", "Hyperlink"]);
+
+        let mut reader = XlsbReader::new(Cursor::new(data)).unwrap();
+        let cells = reader
+            .cells("Index")
+            .unwrap()
+            .map(|cell| cell.unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(cells.len(), 3);
+        assert!(cells.iter().all(|cell| cell.style == cells[0].style));
+        assert_eq!(
+            cells[1].value,
+            crate::CellValue::String("https://example.com/docs#intro".to_owned())
+        );
+        assert_eq!(
+            cells[2].value,
+            crate::CellValue::String("qa@example.com".to_owned())
+        );
+    }
+}
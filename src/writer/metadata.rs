@@ -0,0 +1,103 @@
+//! Cell metadata of the workbook (`xl/metadata.bin`), which marks formulas as dynamic arrays
+use std::io::Write;
+
+use crate::{
+    core::biff::{
+        records::{
+            BrtBeginDynamicArrayPr, BrtBeginEsfmd, BrtBeginEsmdb, BrtBeginEsmdtinfo, BrtMdb,
+            BrtMdtinfo, Mdir,
+        },
+        BiffId, BiffWrite,
+    },
+    Result,
+};
+
+/// Index of the cell metadata block of dynamic array formulas (`BrtCellMeta`)
+pub(super) const DYNAMIC_ARRAY_BLOCK: u32 = 0;
+
+/// Write the metadata part with the single block, which is referenced by first cells of dynamic
+/// array formulas
+pub(super) fn write_metadata<W: Write>(out: &mut W) -> Result<()> {
+    out.write_empty(BiffId::BrtBeginMetadata)?;
+    out.write_record(&BrtBeginEsmdtinfo::new(1, 0))?;
+    out.write_record(&BrtMdtinfo::dynamic_array())?;
+    out.write_empty(BiffId::BrtEndEsmdtinfo)?;
+
+    out.write_record(&BrtBeginEsfmd::new(1, BrtMdtinfo::DYNAMIC_ARRAY.into()))?;
+    out.write_empty(BiffId::BrtBeginFmd)?;
+    out.write_record(&BrtBeginDynamicArrayPr::new(
+        BrtBeginDynamicArrayPr::F_DYNAMIC,
+    ))?;
+    out.write_empty(BiffId::BrtEndDynamicArrayPr)?;
+    out.write_empty(BiffId::BrtEndFmd)?;
+    out.write_empty(BiffId::BrtEndEsfmd)?;
+
+    out.write_record(&BrtBeginEsmdb::new(1, BrtBeginEsmdb::F_CELL_META))?;
+    out.write_record(&BrtMdb::new(vec![Mdir::new(1, 0)].into()))?;
+    out.write_empty(BiffId::BrtEndEsmdb)?;
+    out.write_empty(BiffId::BrtEndMetadata)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use crate::{
+        core::biff::{
+            records::{BrtCellMeta, BrtName},
+            BiffId,
+        },
+        writer::part_records,
+        XlsbWriter,
+    };
+
+    #[test]
+    fn test_dynamic_array() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet
+            .write_dynamic_array_formula(0, 1, 1, 1, "=UNIQUE(A1:A3)", "a")
+            .unwrap();
+        sheet.write(1, 1, "b").unwrap();
+        sheet.finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut rels = String::new();
+        zip.by_name("xl/_rels/workbook.bin.rels")
+            .unwrap()
+            .read_to_string(&mut rels)
+            .unwrap();
+        assert!(rels.contains("Target=\"metadata.bin\""));
+        let ids = part_records(&mut zip, "xl/metadata.bin")
+            .into_iter()
+            .map(|rec| rec.id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids.first(), Some(&BiffId::BrtBeginMetadata));
+        assert!(ids.contains(&BiffId::BrtBeginDynamicArrayPr));
+
+        let records = part_records(&mut zip, "xl/worksheets/sheet1.bin");
+        let meta = records
+            .iter()
+            .position(|rec| rec.id == BiffId::BrtCellMeta)
+            .unwrap();
+        assert_eq!(records[meta].as_biff::<BrtCellMeta>().unwrap().icmb, 0);
+        assert_eq!(
+            records[meta + 1..meta + 4]
+                .iter()
+                .map(|rec| rec.id)
+                .collect::<Vec<_>>(),
+            [BiffId::BrtFmlaString, BiffId::BrtArrFmla, BiffId::BrtRowHdr]
+        );
+
+        let name = part_records(&mut zip, "xl/workbook.bin")
+            .into_iter()
+            .find(|rec| rec.id == BiffId::BrtName)
+            .unwrap()
+            .as_biff::<BrtName>()
+            .unwrap();
+        assert_eq!(name.name.inner, "_xlfn.UNIQUE");
+        assert_ne!(name.flags & BrtName::F_FUTURE_FUNCTION, 0);
+    }
+}
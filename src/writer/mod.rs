@@ -0,0 +1,374 @@
+//! Writer of the XLSB package
+mod batch;
+mod columns;
+mod comments;
+mod conditional;
+mod filter;
+mod formulas;
+mod hyperlink;
+mod metadata;
+mod names;
+mod rows;
+mod sheet;
+mod sst;
+mod styles;
+mod table;
+mod validation;
+mod view;
+
+use std::{
+    fs::File,
+    io::{Seek, Write},
+    path::Path,
+};
+
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    core::{
+        biff::{
+            records::{BrtBookView, BrtBundleSh, BrtCalcProp, BrtFileVersion, BrtWbProp},
+            BiffId, BiffWrite,
+        },
+        xml::{
+            ContentTypes, Relationships, CT_COMMENTS, CT_SHARED_STRINGS, CT_SHEET_METADATA,
+            CT_STYLES, CT_TABLE, CT_VML_DRAWING, CT_WORKSHEET, REL_OFFICE_DOCUMENT,
+            REL_SHARED_STRINGS, REL_SHEET_METADATA, REL_STYLES, REL_WORKSHEET,
+        },
+    },
+    DateSystem, Error, Result, Style, StyleId,
+};
+
+pub use columns::ColumnOptions;
+pub use comments::CellComment;
+pub use conditional::{
+    CellCondition, ColorScale, ConditionValue, ConditionalFormat, ConditionalRule, DataBar,
+    IconSet, IconSetStyle,
+};
+pub use filter::{ColumnFilter, DynamicFilter, FilterComparison, FilterOperator};
+pub use hyperlink::Hyperlink;
+pub use names::DefinedName;
+use names::DefinedNames;
+pub use rows::RowOptions;
+pub use sheet::SheetWriter;
+use sst::{SharedStringTable, DEFAULT_SST_MEMORY_LIMIT};
+use styles::StyleSheet;
+pub use table::{TableColumn, TableOptions, TableStyle, TotalFunction};
+pub use validation::{DataValidation, ValidationErrorStyle, ValidationRule};
+pub use view::{Panes, SheetView};
+
+/// Maximal length of the sheet name
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// Cell format of dates (built-in number format `m/d/yyyy`)
+const XF_DATE: u32 = 1;
+/// Cell format of date and time (built-in number format `m/d/yyyy h:mm`)
+const XF_DATETIME: u32 = 2;
+
+/// Sheet, which is already written into the package
+struct SheetEntry {
+    name: String,
+    /// Path of the part, relative to `xl/` directory
+    target: String,
+}
+
+/// Writer of XLSB workbook.
+///
+/// Sheets are written one by one: every sheet is created by [`XlsbWriter::add_sheet`] and
+/// must be completed by [`SheetWriter::finish`] before the next one is started. Workbook-level
+/// parts are written on [`XlsbWriter::close`].
+pub struct XlsbWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    sheets: Vec<SheetEntry>,
+    strings: SharedStringTable,
+    styles: StyleSheet,
+    date_system: DateSystem,
+    names: DefinedNames,
+    /// Names of tables, their positions are ids of table parts
+    tables: Vec<String>,
+    /// Cached values of formulas can be stale, so Excel recalculates them on load
+    full_calc_on_load: bool,
+    /// Some formulas are dynamic arrays, which reference the cell metadata part
+    dynamic_arrays: bool,
+    /// Number of comments parts, each one has its VML drawing with the same number
+    comments: u32,
+    /// Number of blocks of 1024 shape ids, which are taken by VML drawings of sheets
+    shape_blocks: u32,
+}
+
+impl XlsbWriter<File> {
+    /// Create the file by the given `path` and the writer on top of it
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(XlsbWriter::new(File::create(path)?))
+    }
+}
+
+impl<W: Write + Seek> XlsbWriter<W> {
+    pub fn new(inner: W) -> Self {
+        XlsbWriter {
+            zip: ZipWriter::new(inner),
+            sheets: Vec::new(),
+            strings: SharedStringTable::new(DEFAULT_SST_MEMORY_LIMIT),
+            styles: StyleSheet::new(),
+            date_system: DateSystem::default(),
+            names: DefinedNames::default(),
+            tables: Vec::new(),
+            full_calc_on_load: false,
+            dynamic_arrays: false,
+            comments: 0,
+            shape_blocks: 0,
+        }
+    }
+
+    /// Limit the memory of the shared strings table (64 MiB by default).
+    ///
+    /// Strings above the limit are spilled into the temporary file, and new unique strings
+    /// are not deduplicated anymore.
+    pub fn with_shared_strings_limit(mut self, limit: usize) -> Self {
+        self.strings = SharedStringTable::new(limit);
+        self
+    }
+
+    /// Set the date system of the workbook (the 1900 one by default)
+    pub fn with_date_system(mut self, date_system: DateSystem) -> Self {
+        self.date_system = date_system;
+        self
+    }
+
+    /// Date system of the workbook, which is used to convert dates into serial numbers
+    pub fn date_system(&self) -> DateSystem {
+        self.date_system
+    }
+
+    /// Register the cell style, identical styles share the same [`StyleId`]
+    pub fn add_style(&mut self, style: &Style) -> Result<StyleId> {
+        self.styles.add(style)
+    }
+
+    /// Start the new worksheet with the given `name`
+    pub fn add_sheet(&mut self, name: &str) -> Result<SheetWriter<'_, W>> {
+        self.validate_sheet_name(name)?;
+        SheetWriter::new(self, name)
+    }
+
+    /// Write workbook-level parts, finish the package and return the underlying writer
+    pub fn close(mut self) -> Result<W> {
+        if self.sheets.is_empty() {
+            return Err(Error::invalid_input(
+                "workbook must contain at least one sheet",
+            ));
+        }
+
+        let mut content_types = ContentTypes::new();
+        let mut book_rels = Relationships::new();
+        let sheet_rels = self
+            .sheets
+            .iter()
+            .map(|sheet| {
+                content_types.add_override(format!("/xl/{}", sheet.target), CT_WORKSHEET);
+                book_rels.add(REL_WORKSHEET, sheet.target.as_str())
+            })
+            .collect::<Vec<_>>();
+        for id in 1..=self.tables.len() {
+            content_types.add_override(format!("/xl/tables/table{}.bin", id), CT_TABLE);
+        }
+        for id in 1..=self.comments {
+            content_types.add_override(format!("/xl/comments{}.bin", id), CT_COMMENTS);
+        }
+        if self.comments > 0 {
+            content_types.add_default("vml", CT_VML_DRAWING);
+        }
+
+        book_rels.add(REL_STYLES, "styles.bin");
+        content_types.add_override("/xl/styles.bin", CT_STYLES);
+        self.start_part("xl/styles.bin")?;
+        self.styles.write(&mut self.zip)?;
+
+        book_rels.add(REL_SHARED_STRINGS, "sharedStrings.bin");
+        content_types.add_override("/xl/sharedStrings.bin", CT_SHARED_STRINGS);
+        self.start_part("xl/sharedStrings.bin")?;
+        self.strings.write(&mut self.zip)?;
+
+        if self.dynamic_arrays {
+            book_rels.add(REL_SHEET_METADATA, "metadata.bin");
+            content_types.add_override("/xl/metadata.bin", CT_SHEET_METADATA);
+            self.start_part("xl/metadata.bin")?;
+            metadata::write_metadata(&mut self.zip)?;
+        }
+
+        self.start_part("xl/workbook.bin")?;
+        self.write_workbook(&sheet_rels)?;
+
+        self.start_part("xl/_rels/workbook.bin.rels")?;
+        self.zip.write_all(book_rels.to_xml().as_bytes())?;
+
+        let mut package_rels = Relationships::new();
+        package_rels.add(REL_OFFICE_DOCUMENT, "xl/workbook.bin");
+        self.start_part("_rels/.rels")?;
+        self.zip.write_all(package_rels.to_xml().as_bytes())?;
+
+        self.start_part("[Content_Types].xml")?;
+        self.zip.write_all(content_types.to_xml().as_bytes())?;
+
+        Ok(self.zip.finish()?)
+    }
+
+    /// Start the next part of the package, all following writes go into it
+    fn start_part(&mut self, name: &str) -> Result<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        Ok(self.zip.start_file(name, options)?)
+    }
+
+    fn validate_sheet_name(&self, name: &str) -> Result<()> {
+        let invalid = |reason: &str| {
+            Err(Error::invalid_input(format!(
+                "invalid sheet name {:?}: {}",
+                name, reason
+            )))
+        };
+        // Excel counts UTF-16 code units, so emoji take two of them
+        if name.is_empty() || name.encode_utf16().count() > MAX_SHEET_NAME_LEN {
+            return invalid("length must be from 1 to 31 characters");
+        }
+        if name.contains(['[', ']', ':', '*', '?', '/', '\\']) {
+            return invalid("characters []:*?/\\ are not allowed");
+        }
+        if name.starts_with('\'') || name.ends_with('\'') {
+            return invalid("name cannot start or end with an apostrophe");
+        }
+        if self
+            .sheets
+            .iter()
+            .any(|sheet| sheet.name.to_lowercase() == name.to_lowercase())
+        {
+            return invalid("sheet with the same name already exists");
+        }
+        Ok(())
+    }
+
+    fn write_workbook(&mut self, sheet_rels: &[String]) -> Result<()> {
+        let out = &mut self.zip;
+        out.write_empty(BiffId::BrtBeginBook)?;
+        out.write_record(&BrtFileVersion::current())?;
+        out.write_record(&BrtWbProp::with_flags(match self.date_system {
+            DateSystem::V1900 => 0,
+            DateSystem::V1904 => BrtWbProp::F_1904,
+        }))?;
+        out.write_empty(BiffId::BrtBeginBookViews)?;
+        out.write_record(&BrtBookView::with_active_tab(0))?;
+        out.write_empty(BiffId::BrtEndBookViews)?;
+        out.write_empty(BiffId::BrtBeginBundleShs)?;
+        for (idx, (sheet, rel_id)) in self.sheets.iter().zip(sheet_rels).enumerate() {
+            out.write_record(&BrtBundleSh::new(
+                BrtBundleSh::VISIBLE,
+                idx as u32 + 1,
+                Some(rel_id.as_str()).into(),
+                sheet.name.as_str().into(),
+            ))?;
+        }
+        out.write_empty(BiffId::BrtEndBundleShs)?;
+        let sheets = self
+            .sheets
+            .iter()
+            .map(|sheet| sheet.name.as_str())
+            .collect::<Vec<_>>();
+        self.names.write(out, &sheets)?;
+        if self.full_calc_on_load {
+            out.write_record(&BrtCalcProp::with_flags(BrtCalcProp::F_FULL_CALC_ON_LOAD))?;
+        }
+        out.write_empty(BiffId::BrtEndBook)?;
+        Ok(())
+    }
+}
+
+/// Data of the part of the written package
+#[cfg(test)]
+pub(super) fn part_data<R: std::io::Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Vec<u8> {
+    use std::io::Read;
+    let mut data = Vec::new();
+    zip.by_name(name).unwrap().read_to_end(&mut data).unwrap();
+    data
+}
+
+/// All records of the binary part of the written package
+#[cfg(test)]
+pub(super) fn part_records<R: std::io::Read + Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Vec<crate::core::biff::BiffRecord> {
+    let mut cursor = std::io::Cursor::new(part_data(zip, name));
+    std::iter::from_fn(|| crate::core::biff::BiffRecord::read(&mut cursor).unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use zip::ZipArchive;
+
+    use super::*;
+
+    #[test]
+    fn test_package() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet.write(0, 1, "header").unwrap();
+        sheet.write(0, 0, 1.5).unwrap();
+        sheet.write(0, 2, 1.0 / 3.0).unwrap();
+        sheet.write(2, 3, true).unwrap();
+        sheet.merge_range(2, 3, 2, 5).unwrap();
+        assert!(sheet.write(1, 0, 1).is_err());
+        sheet.finish().unwrap();
+        assert!(book.add_sheet("data").is_err());
+        assert!(book.add_sheet("a/b").is_err());
+        assert!(book.add_sheet(&"\u{1F4CA}".repeat(16)).is_err());
+        book.add_sheet("Empty").unwrap().finish().unwrap();
+
+        let mut zip = ZipArchive::new(book.close().unwrap()).unwrap();
+        for name in [
+            "[Content_Types].xml",
+            "_rels/.rels",
+            "xl/workbook.bin",
+            "xl/_rels/workbook.bin.rels",
+            "xl/worksheets/sheet1.bin",
+            "xl/worksheets/sheet2.bin",
+            "xl/styles.bin",
+            "xl/sharedStrings.bin",
+        ] {
+            assert!(zip.by_name(name).is_ok(), "missing part {}", name);
+        }
+
+        let sheets = part_records(&mut zip, "xl/workbook.bin")
+            .into_iter()
+            .filter(|rec| rec.id == BiffId::BrtBundleSh)
+            .map(|rec| rec.as_biff::<BrtBundleSh>().unwrap().str_name.inner)
+            .collect::<Vec<_>>();
+        assert_eq!(sheets, ["Data", "Empty"]);
+
+        let ids = part_records(&mut zip, "xl/worksheets/sheet1.bin")
+            .into_iter()
+            .map(|rec| rec.id)
+            .skip_while(|id| *id != BiffId::BrtBeginSheetData)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                BiffId::BrtBeginSheetData,
+                BiffId::BrtRowHdr,
+                BiffId::BrtCellRk,
+                BiffId::BrtCellIsst,
+                BiffId::BrtCellReal,
+                BiffId::BrtRowHdr,
+                BiffId::BrtCellBool,
+                BiffId::BrtEndSheetData,
+                BiffId::BrtBeginMergeCells,
+                BiffId::BrtMergeCell,
+                BiffId::BrtEndMergeCells,
+                BiffId::BrtEndSheet,
+            ]
+        );
+    }
+}
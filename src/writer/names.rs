@@ -0,0 +1,450 @@
+//! Defined names of the workbook and sheets, which are referenced by their formulas
+use std::io::{Seek, Write};
+
+use super::{table::is_cell_reference, SheetWriter, XlsbWriter};
+use crate::{
+    core::biff::{
+        records::{BrtExternSheet, BrtName, ParsedFormula, Xti},
+        BiffId, BiffWrite, MAX_COL, MAX_ROW,
+    },
+    formula::{compile, FormulaContext, FormulaType},
+    CellRange, Error, Result,
+};
+
+/// Maximal length of the name and of its comment
+const MAX_NAME_LEN: usize = 255;
+
+/// Defined name, which refers to the range, the constant or the formula
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinedName {
+    pub name: String,
+    /// Formula, like `=Data!$A$2:$C$100` or `=0.2`; references must have sheets
+    pub formula: String,
+    /// Comment, which is shown in the Name Manager; it is written into `BrtName` itself, and
+    /// `BrtNameExt` is not written
+    pub comment: Option<String>,
+    /// The name is not shown in the Name Manager
+    pub hidden: bool,
+}
+
+impl DefinedName {
+    pub fn new(name: &str, formula: &str) -> Self {
+        DefinedName {
+            name: name.to_owned(),
+            formula: formula.to_owned(),
+            comment: None,
+            hidden: false,
+        }
+    }
+
+    /// Set the comment, which is shown in the Name Manager
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_owned());
+        self
+    }
+
+    /// Check the name: it starts with a letter, `_` or `\`, and is not a cell reference,
+    /// like `A1` or `R1C1`
+    fn validate(&self) -> Result<()> {
+        let invalid = |reason: &str| {
+            Err(Error::invalid_input(format!(
+                "invalid defined name {:?}: {}",
+                self.name, reason
+            )))
+        };
+        let name = &self.name;
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return invalid("length must be from 1 to 255 characters");
+        }
+        if !name.starts_with(|ch: char| ch.is_alphabetic() || ch == '_' || ch == '\\')
+            || !name
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '.' | '\\' | '?'))
+        {
+            return invalid("only letters, digits, '_', '.', '\\' and '?' are allowed");
+        }
+        if is_cell_reference(name) {
+            return invalid("name cannot be a cell reference");
+        }
+        if name.to_lowercase().starts_with("_xlnm.") {
+            return invalid("names of built-in names are reserved");
+        }
+        if self
+            .comment
+            .as_ref()
+            .is_some_and(|comment| comment.chars().count() > MAX_NAME_LEN)
+        {
+            return invalid("comment is longer than 255 characters");
+        }
+        Ok(())
+    }
+}
+
+/// Names, which are written into the workbook part
+#[derive(Default)]
+pub(super) struct DefinedNames {
+    names: Vec<BrtName>,
+    /// Sheets, referenced by 3D references, their positions are `ixti` of formulas. Sheets are
+    /// resolved on write, so formulas can reference sheets, which are not added yet.
+    sheets: Vec<String>,
+}
+
+impl DefinedNames {
+    /// Index of the sheet in `BrtExternSheet`, it is added on the first use
+    pub(super) fn xti(&mut self, sheet: &str) -> u16 {
+        let sheet = sheet.to_lowercase();
+        match self.sheets.iter().position(|other| *other == sheet) {
+            Some(ixti) => ixti as u16,
+            None => {
+                self.sheets.push(sheet);
+                (self.sheets.len() - 1) as u16
+            }
+        }
+    }
+
+    /// Hidden name of the AutoFilter range, local to the sheet with the index `itab`
+    pub(super) fn add_filter_database(&mut self, itab: u32, sheet: &str, range: CellRange) {
+        let formula = ParsedFormula::area_3d(self.xti(sheet), &range.to_rfx());
+        self.names.push(BrtName::new(
+            BrtName::F_HIDDEN | BrtName::F_BUILTIN,
+            0,
+            itab,
+            "_xlnm._FilterDatabase".into(),
+            formula,
+            None.into(),
+        ));
+    }
+
+    /// Built-in name of the sheet with the index `itab`, like `_xlnm.Print_Area`
+    fn add_builtin(&mut self, itab: u32, name: &str, formula: ParsedFormula) {
+        self.names.push(BrtName::new(
+            BrtName::F_BUILTIN,
+            0,
+            itab,
+            name.into(),
+            formula,
+            None.into(),
+        ));
+    }
+
+    /// Print area and print titles (rows and columns, repeated on every page) of the sheet
+    pub(super) fn add_print_names(
+        &mut self,
+        itab: u32,
+        sheet: &str,
+        area: Option<CellRange>,
+        rows: Option<(u32, u32)>,
+        cols: Option<(u16, u16)>,
+    ) {
+        if let Some(area) = area {
+            let formula = ParsedFormula::area_3d(self.xti(sheet), &area.to_rfx());
+            self.add_builtin(itab, "_xlnm.Print_Area", formula);
+        }
+        // columns go first, like `Data!$A:$B,Data!$1:$2`
+        let ranges = cols
+            .map(|(first, last)| CellRange::new(0, first, MAX_ROW, last))
+            .into_iter()
+            .chain(rows.map(|(first, last)| CellRange::new(first, 0, last, MAX_COL as u16)))
+            .collect::<Vec<_>>();
+        if !ranges.is_empty() {
+            let xti = self.xti(sheet);
+            let mut rgce = Vec::new();
+            for range in &ranges {
+                rgce.extend(ParsedFormula::area_3d(xti, &range.to_rfx()).rgce);
+            }
+            if ranges.len() > 1 {
+                rgce.push(ParsedFormula::PTG_UNION);
+            }
+            self.add_builtin(
+                itab,
+                "_xlnm.Print_Titles",
+                ParsedFormula::new(rgce, Vec::new()),
+            );
+        }
+    }
+
+    /// Define the name of the workbook scope, or of the sheet with the index `itab`. The name
+    /// of the workbook, which is already referenced by formulas, gets the formula.
+    fn define(&mut self, itab: u32, name: &DefinedName, formula: ParsedFormula) -> Result<()> {
+        let lowercase = name.name.to_lowercase();
+        let position = self
+            .names
+            .iter()
+            .position(|other| other.itab == itab && other.name.inner.to_lowercase() == lowercase);
+        let flags = if name.hidden { BrtName::F_HIDDEN } else { 0 };
+        let record = BrtName::new(
+            flags,
+            0,
+            itab,
+            name.name.as_str().into(),
+            formula,
+            name.comment.as_deref().into(),
+        );
+        match position {
+            // names, which are referenced before they are defined, have no formula
+            Some(idx) if self.names[idx].formula.rgce.is_empty() => {
+                self.names[idx] = record;
+                Ok(())
+            }
+            Some(_) => Err(Error::invalid_input(format!(
+                "name {:?} is already defined",
+                name.name
+            ))),
+            None => {
+                self.names.push(record);
+                Ok(())
+            }
+        }
+    }
+
+    /// Name (case-insensitive) of any scope exists, including names, which are only referenced
+    /// by formulas
+    pub(super) fn has_name(&self, name: &str) -> bool {
+        let lowercase = name.to_lowercase();
+        self.names
+            .iter()
+            .any(|other| other.name.inner.to_lowercase() == lowercase)
+    }
+
+    /// One-based index of the name, which is local to the sheet `itab`
+    fn local_name(&self, itab: u32, name: &str) -> Option<u32> {
+        let lowercase = name.to_lowercase();
+        self.names
+            .iter()
+            .position(|other| other.itab == itab && other.name.inner.to_lowercase() == lowercase)
+            .map(|idx| idx as u32 + 1)
+    }
+
+    /// One-based index of the name of the workbook scope, it is added with `flags` on the
+    /// first use
+    fn workbook_name(&mut self, name: &str, flags: u32) -> Result<u32> {
+        let lowercase = name.to_lowercase();
+        let position = self.names.iter().position(|other| {
+            other.itab == BrtName::ITAB_WORKBOOK && other.name.inner.to_lowercase() == lowercase
+        });
+        if let Some(idx) = position {
+            return Ok(idx as u32 + 1);
+        }
+        if name.chars().count() > 255 {
+            return Err(Error::invalid_input(format!("name {:?} is too long", name)));
+        }
+        self.names.push(BrtName::new(
+            flags,
+            0,
+            BrtName::ITAB_WORKBOOK,
+            name.into(),
+            ParsedFormula::default(),
+            None.into(),
+        ));
+        Ok(self.names.len() as u32)
+    }
+
+    /// Write references to sheets and names, they follow sheets of the workbook
+    pub(super) fn write<W: Write>(&self, out: &mut W, sheets: &[&str]) -> Result<()> {
+        if !self.sheets.is_empty() {
+            out.write_empty(BiffId::BrtBeginExternals)?;
+            out.write_empty(BiffId::BrtSupSelf)?;
+            let rg_xti = self
+                .sheets
+                .iter()
+                .map(|sheet| {
+                    match sheets
+                        .iter()
+                        .position(|other| other.to_lowercase() == *sheet)
+                    {
+                        Some(itab) => Ok(Xti::new(0, itab as i32, itab as i32)),
+                        None => Err(Error::invalid_input(format!(
+                            "formula references unknown sheet {:?}",
+                            sheet
+                        ))),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            out.write_record(&BrtExternSheet::new(rg_xti.into()))?;
+            out.write_empty(BiffId::BrtEndExternals)?;
+        }
+        for name in &self.names {
+            out.write_record(name)?;
+        }
+        Ok(())
+    }
+}
+
+impl FormulaContext for DefinedNames {
+    fn sheet(&mut self, name: &str) -> Result<u16> {
+        Ok(self.xti(name))
+    }
+
+    /// Names of the workbook scope; the unknown name is added hidden and without the formula,
+    /// it is evaluated to `#NAME?` until it is defined
+    fn name(&mut self, name: &str) -> Result<u32> {
+        self.workbook_name(name, BrtName::F_HIDDEN)
+    }
+
+    fn function(&mut self, name: &str) -> Result<u32> {
+        self.workbook_name(
+            name,
+            BrtName::F_HIDDEN | BrtName::F_FUNC | BrtName::F_FUTURE_FUNCTION,
+        )
+    }
+}
+
+/// Context of formulas of the sheet `itab`: its local names hide names of the workbook. It also
+/// records whether the formula has 3D references to sheets.
+pub(super) struct SheetNames<'a> {
+    pub(super) names: &'a mut DefinedNames,
+    pub(super) itab: u32,
+    pub(super) found: bool,
+}
+
+impl FormulaContext for SheetNames<'_> {
+    fn sheet(&mut self, name: &str) -> Result<u16> {
+        self.found = true;
+        self.names.sheet(name)
+    }
+
+    fn name(&mut self, name: &str) -> Result<u32> {
+        match self.names.local_name(self.itab, name) {
+            Some(index) => Ok(index),
+            None => self.names.name(name),
+        }
+    }
+
+    fn function(&mut self, name: &str) -> Result<u32> {
+        self.names.function(name)
+    }
+}
+
+impl<W: Write + Seek> XlsbWriter<W> {
+    /// Define the name of the workbook scope. Formulas can reference names before they are
+    /// defined.
+    pub fn define_name(&mut self, name: &DefinedName) -> Result<()> {
+        name.validate()?;
+        let lowercase = name.name.to_lowercase();
+        if self
+            .tables
+            .iter()
+            .any(|table| table.to_lowercase() == lowercase)
+        {
+            return Err(used_by_table(name));
+        }
+        let formula = compile(&name.formula, FormulaType::Name, &mut self.names)?;
+        self.names.define(BrtName::ITAB_WORKBOOK, name, formula)
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Define the name, which is local to this sheet; it hides the workbook name with the same
+    /// name in formulas of the sheet
+    pub fn define_name(&mut self, name: &DefinedName) -> Result<()> {
+        name.validate()?;
+        if self.has_table_name(&name.name) {
+            return Err(used_by_table(name));
+        }
+        let itab = self.index() as u32;
+        let (formula, _) = self.compile_sheet_formula(&name.formula, FormulaType::Name)?;
+        self.names().define(itab, name, formula)
+    }
+
+    /// Set the range, which is printed
+    pub fn set_print_area(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        range.validate()?;
+        self.print.area = Some(range);
+        Ok(())
+    }
+
+    /// Set rows, which are repeated at the top of every printed page
+    pub fn set_repeat_rows(&mut self, first_row: u32, last_row: u32) -> Result<()> {
+        CellRange::new(first_row, 0, last_row, 0).validate()?;
+        self.print.rows = Some((first_row, last_row));
+        Ok(())
+    }
+
+    /// Set columns, which are repeated at the left of every printed page
+    pub fn set_repeat_columns(&mut self, first_col: u16, last_col: u16) -> Result<()> {
+        CellRange::new(0, first_col, 0, last_col).validate()?;
+        self.print.cols = Some((first_col, last_col));
+        Ok(())
+    }
+}
+
+/// Tables and defined names share names, so the table name cannot be defined
+fn used_by_table(name: &DefinedName) -> Error {
+    Error::invalid_input(format!(
+        "defined name {:?} is already used by the table",
+        name.name
+    ))
+}
+
+/// Print area and print titles of the sheet, which are written as built-in names
+#[derive(Default)]
+pub(super) struct PrintNames {
+    pub(super) area: Option<CellRange>,
+    pub(super) rows: Option<(u32, u32)>,
+    pub(super) cols: Option<(u16, u16)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{TableColumn, TableOptions, XlsbWriter};
+
+    #[test]
+    fn test_formula_context() {
+        let mut names = DefinedNames::default();
+        assert_eq!(names.sheet("Other").unwrap(), 0);
+        assert_eq!(names.sheet("Data").unwrap(), 1);
+        assert_eq!(names.sheet("OTHER").unwrap(), 0);
+        assert_eq!(names.name("Rate").unwrap(), 1);
+        assert_eq!(names.name("rate").unwrap(), 1);
+        assert_eq!(names.name("Tax").unwrap(), 2);
+        assert_eq!(names.function("_xlfn.UNIQUE").unwrap(), 3);
+        assert_eq!(names.function("_xlfn.UNIQUE").unwrap(), 3);
+        assert_eq!(names.names[2].flags & BrtName::F_FUNC, BrtName::F_FUNC);
+
+        let mut out = Vec::new();
+        assert!(names.write(&mut out, &["Data"]).is_err());
+        out.clear();
+        names.write(&mut out, &["Data", "other"]).unwrap();
+        assert!(!out.is_empty());
+
+        // sheets, referenced by formulas, must exist when the workbook is closed
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet.write_formula(0, 0, "=Later!A1*2", 0.0).unwrap();
+        sheet.write_formula(1, 0, "='Missing'!A1", 0.0).unwrap();
+        sheet.finish().unwrap();
+        book.add_sheet("Later").unwrap().finish().unwrap();
+        assert!(book.close().is_err());
+        // tables and defined names share names
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        book.define_name(&DefinedName::new("Table1", "=Data!$A$1"))
+            .unwrap();
+        let mut sheet = book.add_sheet("Data").unwrap();
+        let mut options = TableOptions {
+            columns: vec![TableColumn::new("Key")],
+            ..TableOptions::default()
+        };
+        // the defined name is skipped by automatic names of tables
+        sheet.add_table(0, 0, 2, &options).unwrap();
+        assert!(sheet
+            .define_name(&DefinedName::new("table2", "=Data!$A$1"))
+            .is_err());
+        options.name = Some("TABLE1".to_owned());
+        assert!(sheet.add_table(5, 0, 7, &options).is_err());
+        sheet.finish().unwrap();
+        assert!(book
+            .define_name(&DefinedName::new("Table2", "=Data!$A$1"))
+            .is_err());
+        book.close().unwrap();
+    }
+}
@@ -0,0 +1,305 @@
+//! Row headers: heights, styles, outline levels and column spans
+use std::{
+    collections::BTreeMap,
+    io::{Seek, Write},
+};
+
+use super::{
+    columns::MAX_OUTLINE_LEVEL,
+    filter::{AutoFilter, FilterValue},
+    SheetWriter,
+};
+use crate::{
+    core::biff::{
+        records::{BrtRowHdr, ColSpan},
+        BiffRecord, BiffWrite, MAX_ROW,
+    },
+    Error, Result, StyleId,
+};
+
+/// Settings of the row, which are applied by [`SheetWriter::set_row`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RowOptions {
+    /// Height in points, from 0 to 409.5; the default height (15 pt) is used if not set
+    pub height: Option<f64>,
+    /// Style of empty cells in the row
+    pub style: StyleId,
+    pub hidden: bool,
+    /// Outline (grouping) level, from 0 to 7
+    pub outline_level: u8,
+    /// The outline group, which is adjacent to the row, is collapsed
+    pub collapsed: bool,
+}
+
+/// Cells and the header of the single row
+struct RowBuffer {
+    header: BrtRowHdr,
+    cells: BTreeMap<u16, BiffRecord>,
+    /// Shared and array formulas, which follow records of their first cells
+    formulas: BTreeMap<u16, BiffRecord>,
+    /// References to cell metadata blocks, which precede records of cells
+    metadata: BTreeMap<u16, BiffRecord>,
+    /// Values of cells, which are checked by the AutoFilter criteria
+    values: BTreeMap<u16, FilterValue>,
+}
+
+/// Rows of the block of 16 rows, which share column spans.
+///
+/// Column spans of the block are known only when all its rows are written, so rows are
+/// kept in memory until the next block is started.
+#[derive(Default)]
+pub(super) struct RowBlock {
+    rows: Vec<RowBuffer>,
+    /// Maximal outline level of all rows
+    outline_level: u8,
+    /// AutoFilter of the sheet, rows which do not match its criteria are hidden on flush
+    pub(super) filter: Option<AutoFilter>,
+}
+
+impl RowBlock {
+    /// Index of the last row, rows above it cannot be written anymore
+    pub(super) fn current(&self) -> Option<u32> {
+        self.rows.last().map(|row| row.header.rw.inner)
+    }
+
+    pub(super) fn outline_level(&self) -> u8 {
+        self.outline_level
+    }
+
+    /// Row with the given index, it is started if it is after the current one
+    fn row_mut<W: Write>(&mut self, row: u32, out: &mut W) -> Result<&mut RowBuffer> {
+        match self.current() {
+            Some(current) if current == row => {}
+            current => {
+                if current.is_some_and(|current| {
+                    current / BrtRowHdr::BLOCK_ROWS != row / BrtRowHdr::BLOCK_ROWS
+                }) {
+                    self.flush(out)?;
+                }
+                self.rows.push(RowBuffer {
+                    header: BrtRowHdr::at(row),
+                    cells: BTreeMap::new(),
+                    formulas: BTreeMap::new(),
+                    metadata: BTreeMap::new(),
+                    values: BTreeMap::new(),
+                });
+            }
+        }
+        let last = self.rows.len() - 1;
+        Ok(&mut self.rows[last])
+    }
+
+    /// Put the cell record into the row
+    pub(super) fn insert<W: Write>(
+        &mut self,
+        row: u32,
+        col: u16,
+        record: BiffRecord,
+        out: &mut W,
+    ) -> Result<()> {
+        let row = self.row_mut(row, out)?;
+        row.cells.insert(col, record);
+        row.formulas.remove(&col);
+        row.metadata.remove(&col);
+        Ok(())
+    }
+
+    /// Put the shared or the array formula after the cell, which is inserted already
+    pub(super) fn insert_formula(&mut self, row: u32, col: u16, record: BiffRecord) {
+        if let Some(last) = self.last_mut(row) {
+            last.formulas.insert(col, record);
+        }
+    }
+
+    /// Put the reference to the cell metadata before the cell, which is inserted already
+    pub(super) fn insert_metadata(&mut self, row: u32, col: u16, record: BiffRecord) {
+        if let Some(last) = self.last_mut(row) {
+            last.metadata.insert(col, record);
+        }
+    }
+
+    /// The current row, if it has the given index
+    fn last_mut(&mut self, row: u32) -> Option<&mut RowBuffer> {
+        self.rows
+            .last_mut()
+            .filter(|last| last.header.rw.inner == row)
+    }
+
+    /// Keep the value of the inserted cell, if the AutoFilter needs it
+    pub(super) fn insert_value(&mut self, row: u32, col: u16, value: impl FnOnce() -> FilterValue) {
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| filter.is_evaluated(row, col))
+        {
+            if let Some(last) = self.last_mut(row) {
+                last.values.insert(col, value());
+            }
+        }
+    }
+
+    /// Write all rows of the block with their column spans
+    pub(super) fn flush<W: Write>(&mut self, out: &mut W) -> Result<()> {
+        // bounds of columns within every 1024 columns of the sheet
+        let mut spans = BTreeMap::<u32, (u32, u32)>::new();
+        for col in self.rows.iter().flat_map(|row| row.cells.keys()) {
+            let col = *col as u32;
+            let span = spans
+                .entry(col / BrtRowHdr::SPAN_COLS)
+                .or_insert((col, col));
+            *span = (span.0.min(col), span.1.max(col));
+        }
+        for mut row in self.rows.drain(..) {
+            row.header.colspans = spans
+                .values()
+                .map(|&(col_mic, col_last)| ColSpan::from_bounds(col_mic, col_last))
+                .collect::<Vec<_>>()
+                .into();
+            if let Some(ref filter) = self.filter {
+                if !filter.matches(row.header.rw.inner, &row.values) {
+                    row.header.flags |= BrtRowHdr::F_DY_ZERO;
+                }
+            }
+            out.write_record(&row.header)?;
+            for (col, record) in &row.cells {
+                if let Some(metadata) = row.metadata.get(col) {
+                    metadata.push(out)?;
+                }
+                record.push(out)?;
+                if let Some(formula) = row.formulas.get(col) {
+                    formula.push(out)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Apply settings to the row, which must not be above the last written row
+    pub fn set_row(&mut self, row: u32, options: &RowOptions) -> Result<()> {
+        if row > MAX_ROW {
+            return Err(Error::invalid_input(format!(
+                "row {} is out of the sheet bounds",
+                row
+            )));
+        }
+        let height = match options.height {
+            Some(height) if (0.0..=BrtRowHdr::MAX_HEIGHT as f64 / 20.0).contains(&height) => {
+                Some((height * 20.0).round() as u16)
+            }
+            Some(height) => {
+                return Err(Error::invalid_input(format!(
+                    "row height {} must be from 0 to 409.5",
+                    height
+                )))
+            }
+            None => None,
+        };
+        if options.outline_level > MAX_OUTLINE_LEVEL {
+            return Err(Error::invalid_input(format!(
+                "outline level {} must be from 0 to 7",
+                options.outline_level
+            )));
+        }
+        self.check_style(options.style)?;
+        self.check_position(row, 0)?;
+
+        let header = &mut self.rows.row_mut(row, &mut self.data)?.header;
+        header.miy_rw = height.unwrap_or(BrtRowHdr::DEFAULT_HEIGHT);
+        header.ixfe = options.style.index();
+        header.flags = [
+            (height.is_some(), BrtRowHdr::F_UNSYNCED),
+            (options.hidden, BrtRowHdr::F_DY_ZERO),
+            (options.collapsed, BrtRowHdr::F_COLLAPSED),
+            (
+                options.style != StyleId::default(),
+                BrtRowHdr::F_GHOST_DIRTY,
+            ),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .fold(
+            ((options.outline_level as u16) << 8) & BrtRowHdr::OUTLINE_LEVEL,
+            |flags, (_, flag)| flags | flag,
+        );
+        self.rows.outline_level = self.rows.outline_level.max(options.outline_level);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{core::biff::BiffId, writer::part_records, Font, Style, XlsbWriter};
+
+    #[test]
+    fn test_rows() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let bold = book
+            .add_style(&Style {
+                font: Font {
+                    bold: true,
+                    ..Font::default()
+                },
+                ..Style::default()
+            })
+            .unwrap();
+        let mut sheet = book.add_sheet("Rows").unwrap();
+        sheet.write(0, 2, 1).unwrap();
+        let header = RowOptions {
+            height: Some(30.0),
+            style: bold,
+            ..RowOptions::default()
+        };
+        sheet.set_row(0, &header).unwrap();
+        sheet.write(3, 1030, 1).unwrap();
+        let grouped = RowOptions {
+            hidden: true,
+            outline_level: 2,
+            ..RowOptions::default()
+        };
+        sheet.set_row(5, &grouped).unwrap();
+        assert!(sheet.set_row(4, &grouped).is_err());
+        let invalid = RowOptions {
+            height: Some(410.0),
+            ..RowOptions::default()
+        };
+        assert!(sheet.set_row(6, &invalid).is_err());
+        sheet.write(16, 5, 1).unwrap();
+        sheet.finish().unwrap();
+
+        let mut zip = zip::ZipArchive::new(book.close().unwrap()).unwrap();
+        let headers = part_records(&mut zip, "xl/worksheets/sheet1.bin")
+            .into_iter()
+            .filter(|rec| rec.id == BiffId::BrtRowHdr)
+            .map(|rec| rec.as_biff::<BrtRowHdr>().unwrap())
+            .collect::<Vec<_>>();
+        let spans = |hdr: &BrtRowHdr| {
+            hdr.colspans
+                .items
+                .iter()
+                .map(|span| (span.col_mic.inner, span.col_last.inner))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers[0].miy_rw, 600);
+        assert_eq!(
+            headers[0].flags,
+            BrtRowHdr::F_UNSYNCED | BrtRowHdr::F_GHOST_DIRTY
+        );
+        assert_eq!(headers[0].ixfe, bold.index());
+        // rows of the first block share spans, which are split by 1024 columns
+        assert_eq!(spans(&headers[0]), [(2, 2), (1030, 1030)]);
+        assert_eq!(spans(&headers[2]), [(2, 2), (1030, 1030)]);
+        assert_eq!(
+            headers[2].flags,
+            BrtRowHdr::F_DY_ZERO | (2 << 8),
+            "empty row is written for its settings"
+        );
+        assert_eq!(headers[3].rw.inner, 16);
+        assert_eq!(spans(&headers[3]), [(5, 5)]);
+    }
+}
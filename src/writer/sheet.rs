@@ -0,0 +1,634 @@
+use std::{
+    fmt::Display,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone};
+use tempfile::SpooledTempFile;
+
+use super::{
+    columns::{number_width, string_width, Columns},
+    comments::Comments,
+    conditional::ConditionalFormats,
+    filter::{AutoFilter, FilterValue},
+    hyperlink::Hyperlinks,
+    names::{DefinedNames, PrintNames, SheetNames},
+    rows::RowBlock,
+    table::Table,
+    validation::DataValidations,
+    view::SheetView,
+    SheetEntry, XlsbWriter, XF_DATE, XF_DATETIME,
+};
+use crate::{
+    core::biff::{
+        records::{
+            BrtBeginListParts, BrtBeginMergeCells, BrtCellBlank, BrtCellBool, BrtCellError,
+            BrtCellIsst, BrtCellReal, BrtCellRk, BrtFmlaBool, BrtFmlaError, BrtFmlaNum,
+            BrtFmlaString, BrtLegacyDrawing, BrtListPart, BrtMergeCell, BrtWsDim, BrtWsFmtInfo,
+            BrtWsProp, ParsedFormula, MAX_STRING_LEN,
+        },
+        BiffId, BiffSerializable, BiffWrite, Cell, UncheckedRfX, MAX_COL, MAX_ROW,
+    },
+    core::xml::{Relationships, REL_COMMENTS, REL_TABLE, REL_VML_DRAWING},
+    formula::{compile, FormulaType},
+    range::RangeIndex,
+    CellError, CellRange, CellValue, DateSystem, DifferentialFormat, Error, Result, StyleId,
+};
+
+/// Sheet data is kept in memory until this size, and then spilled into the temporary file
+const SPOOL_MEMORY_LIMIT: usize = 16 << 20;
+
+/// Used range of the sheet
+#[derive(Clone, Copy)]
+struct Dimension {
+    rw_first: u32,
+    rw_last: u32,
+    col_first: u16,
+    col_last: u16,
+}
+
+/// Writer of the single worksheet.
+///
+/// Rows must be written in ascending order, while cells within the current row can be written
+/// in any order. Nothing is written into the package until [`SheetWriter::finish`] is called,
+/// so a dropped writer discards the sheet.
+pub struct SheetWriter<'a, W: Write + Seek> {
+    book: &'a mut XlsbWriter<W>,
+    name: String,
+    pub(super) data: BufWriter<SpooledTempFile>,
+    /// Rows of the current block, which are not written into `data` yet
+    pub(super) rows: RowBlock,
+    /// Row after the last written one, where the next batch is started
+    next_row: u32,
+    dimension: Option<Dimension>,
+    pub(super) merges: RangeIndex,
+    pub(super) columns: Columns,
+    pub(super) view: SheetView,
+    pub(super) tables: Vec<Table>,
+    /// Ranges of shared and array formulas, their first cells hold formulas
+    pub(super) formula_ranges: Vec<CellRange>,
+    /// Ranges of dynamic arrays, their results spill into them
+    pub(super) spill_ranges: Vec<CellRange>,
+    pub(super) conditional: ConditionalFormats,
+    pub(super) validations: DataValidations,
+    pub(super) hyperlinks: Hyperlinks,
+    pub(super) comments: Comments,
+    pub(super) print: PrintNames,
+}
+
+impl<'a, W: Write + Seek> SheetWriter<'a, W> {
+    pub(super) fn new(book: &'a mut XlsbWriter<W>, name: &str) -> Result<Self> {
+        Ok(SheetWriter {
+            book,
+            name: name.to_owned(),
+            data: BufWriter::new(SpooledTempFile::new(SPOOL_MEMORY_LIMIT)),
+            rows: RowBlock::default(),
+            next_row: 0,
+            dimension: None,
+            merges: RangeIndex::default(),
+            columns: Columns::default(),
+            view: SheetView::default(),
+            tables: Vec::new(),
+            formula_ranges: Vec::new(),
+            spill_ranges: Vec::new(),
+            conditional: ConditionalFormats::default(),
+            validations: DataValidations::default(),
+            hyperlinks: Hyperlinks::default(),
+            comments: Comments::default(),
+            print: PrintNames::default(),
+        })
+    }
+
+    /// Name of the sheet
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Write the number into the cell
+    pub fn write_number(&mut self, row: u32, col: u16, value: f64) -> Result<()> {
+        self.put_number(row, col, value, 0)
+    }
+
+    /// Write the date into the cell with the default date format
+    pub fn write_date(&mut self, row: u32, col: u16, value: NaiveDate) -> Result<()> {
+        match self.book.date_system.date_to_serial(value) {
+            Some(serial) => self.put_number(row, col, serial, XF_DATE),
+            None => Err(out_of_range(row, col, value)),
+        }
+    }
+
+    /// Write the date and time into the cell with the default date and time format
+    pub fn write_datetime(&mut self, row: u32, col: u16, value: NaiveDateTime) -> Result<()> {
+        match self.book.date_system.datetime_to_serial(value) {
+            Some(serial) => self.put_number(row, col, serial, XF_DATETIME),
+            None => Err(out_of_range(row, col, value)),
+        }
+    }
+
+    /// Write the local date and time of the time zone into the cell (Excel has no time zones)
+    pub fn write_datetime_tz<Tz: TimeZone>(
+        &mut self,
+        row: u32,
+        col: u16,
+        value: &DateTime<Tz>,
+    ) -> Result<()> {
+        self.write_datetime(row, col, value.naive_local())
+    }
+
+    /// Write the string into the cell
+    pub fn write_string(&mut self, row: u32, col: u16, value: &str) -> Result<()> {
+        self.put_string(row, col, value, 0)
+    }
+
+    /// Write the boolean value into the cell
+    pub fn write_bool(&mut self, row: u32, col: u16, value: bool) -> Result<()> {
+        self.put_bool(row, col, value, 0)
+    }
+
+    /// Write the error value into the cell
+    pub fn write_error(&mut self, row: u32, col: u16, value: CellError) -> Result<()> {
+        self.put_error(row, col, value, 0)
+    }
+
+    /// Write the cell without value
+    pub fn write_blank(&mut self, row: u32, col: u16) -> Result<()> {
+        self.put_cell(row, col, BrtCellBlank::new(Cell::at(col as u32, 0)))
+    }
+
+    /// Write any value into the cell
+    pub fn write(&mut self, row: u32, col: u16, value: impl Into<CellValue>) -> Result<()> {
+        self.write_with_style(row, col, value, StyleId::default())
+    }
+
+    /// Write any value into the cell with the style, registered by [`XlsbWriter::add_style`]
+    pub fn write_with_style(
+        &mut self,
+        row: u32,
+        col: u16,
+        value: impl Into<CellValue>,
+        style: StyleId,
+    ) -> Result<()> {
+        self.check_style(style)?;
+        match value.into() {
+            CellValue::Blank => self.put_cell(
+                row,
+                col,
+                BrtCellBlank::new(Cell::at(col as u32, style.index())),
+            ),
+            CellValue::Number(value) => self.put_number(row, col, value, style.index()),
+            CellValue::String(value) => self.put_string(row, col, &value, style.index()),
+            CellValue::Bool(value) => self.put_bool(row, col, value, style.index()),
+            CellValue::Error(value) => self.put_error(row, col, value, style.index()),
+        }
+    }
+
+    /// Write the formula in A1 notation, like `=SUM(A1:A10)*2`, with its cached value.
+    ///
+    /// The value is shown by readers, which do not calculate formulas; Excel recalculates
+    /// formulas of the workbook when it is opened. Sheets, referenced by formulas, can be added
+    /// later, but must exist when the workbook is closed.
+    pub fn write_formula(
+        &mut self,
+        row: u32,
+        col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+    ) -> Result<()> {
+        self.write_formula_with_style(row, col, formula, value, StyleId::default())
+    }
+
+    /// Write the formula with its cached value and the style, registered by
+    /// [`XlsbWriter::add_style`]
+    pub fn write_formula_with_style(
+        &mut self,
+        row: u32,
+        col: u16,
+        formula: &str,
+        value: impl Into<CellValue>,
+        style: StyleId,
+    ) -> Result<()> {
+        self.check_style(style)?;
+        self.check_position(row, col)?;
+        let formula = self.compile_formula(formula, FormulaType::Cell)?;
+        self.put_formula(row, col, formula, &value.into(), style.index())
+    }
+
+    /// Merge the range of cells, it must have more than one cell and must not overlap
+    /// other merged ranges. Only the value of the top left cell is displayed.
+    pub fn merge_range(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        range.validate()?;
+        if range.is_single_cell() {
+            return Err(Error::invalid_input(format!(
+                "merged range {} must have more than one cell",
+                range
+            )));
+        }
+        if let Some(other) = self.merges.find_overlap(&range) {
+            return Err(Error::invalid_input(format!(
+                "merged range {} overlaps merged range {}",
+                range, other
+            )));
+        }
+        self.check_table_overlap(range, "merged range")?;
+        self.merges.push(range);
+        Ok(())
+    }
+
+    /// Write the rest of the sheet into the package
+    pub fn finish(mut self) -> Result<()> {
+        self.rows.flush(&mut self.data)?;
+
+        let index = self.book.sheets.len() + 1;
+        let target = format!("worksheets/sheet{}.bin", index);
+        self.book.start_part(&format!("xl/{}", target))?;
+
+        let out = &mut self.book.zip;
+        out.write_empty(BiffId::BrtBeginSheet)?;
+        let mut ws_prop = BrtWsProp::standard();
+        if self
+            .rows
+            .filter
+            .as_ref()
+            .is_some_and(AutoFilter::has_criteria)
+        {
+            ws_prop.flags2 |= BrtWsProp::F_FILTER_MODE;
+        }
+        out.write_record(&ws_prop)?;
+        out.write_record(&BrtWsDim::new(match self.dimension {
+            Some(dim) => UncheckedRfX::from_bounds(
+                dim.rw_first,
+                dim.rw_last,
+                dim.col_first as u32,
+                dim.col_last as u32,
+            ),
+            None => UncheckedRfX::from_bounds(0, 0, 0, 0),
+        }))?;
+        out.write_empty(BiffId::BrtBeginWsViews)?;
+        self.view.write(out, index == 1)?;
+        out.write_empty(BiffId::BrtEndWsViews)?;
+        let mut fmt_info = BrtWsFmtInfo::standard();
+        fmt_info.i_out_level_rw = self.rows.outline_level();
+        fmt_info.i_out_level_col = self.columns.outline_level();
+        out.write_record(&fmt_info)?;
+        self.columns.write(out)?;
+
+        out.write_empty(BiffId::BrtBeginSheetData)?;
+        let mut data = self.data.into_inner().map_err(|e| e.into_error())?;
+        data.seek(SeekFrom::Start(0))?;
+        io::copy(&mut data, out)?;
+        out.write_empty(BiffId::BrtEndSheetData)?;
+        if let Some(ref filter) = self.rows.filter {
+            filter.write(out)?;
+            self.book
+                .names
+                .add_filter_database(index as u32 - 1, &self.name, filter.range());
+        }
+        self.book.names.add_print_names(
+            index as u32 - 1,
+            &self.name,
+            self.print.area,
+            self.print.rows,
+            self.print.cols,
+        );
+        let out = &mut self.book.zip;
+        if !self.merges.is_empty() {
+            out.write_record(&BrtBeginMergeCells::new(self.merges.len() as u32))?;
+            for range in self.merges.iter() {
+                out.write_record(&BrtMergeCell::new(range.to_rfx()))?;
+            }
+            out.write_empty(BiffId::BrtEndMergeCells)?;
+        }
+        self.conditional.write(out)?;
+        self.validations.write(out)?;
+        let mut rels = Relationships::new();
+        self.hyperlinks.write(out, &mut rels)?;
+        if !self.comments.is_empty() {
+            let id = self.book.comments + 1;
+            rels.add(REL_COMMENTS, format!("../comments{}.bin", id));
+            let rel_id = rels.add(REL_VML_DRAWING, format!("../drawings/vmlDrawing{}.vml", id));
+            out.write_record(&BrtLegacyDrawing::new(rel_id.as_str().into()))?;
+        }
+        if !self.tables.is_empty() {
+            out.write_record(&BrtBeginListParts::new(self.tables.len() as u32))?;
+            for idx in 0..self.tables.len() {
+                let id = self.book.tables.len() + idx + 1;
+                let rel_id = rels.add(REL_TABLE, format!("../tables/table{}.bin", id));
+                out.write_record(&BrtListPart::new(rel_id.as_str().into()))?;
+            }
+            out.write_empty(BiffId::BrtEndListParts)?;
+        }
+        self.conditional.write_ext(out)?;
+        self.validations.write_ext(out)?;
+        out.write_empty(BiffId::BrtEndSheet)?;
+
+        for table in &self.tables {
+            let id = self.book.tables.len() as u32 + 1;
+            self.book
+                .start_part(&format!("xl/tables/table{}.bin", id))?;
+            table.write(&mut self.book.zip, id)?;
+            self.book.tables.push(table.name().to_owned());
+        }
+        if !self.comments.is_empty() {
+            self.book.comments += 1;
+            let id = self.book.comments;
+            self.book.start_part(&format!("xl/comments{}.bin", id))?;
+            self.comments.write(&mut self.book.zip)?;
+            self.book
+                .start_part(&format!("xl/drawings/vmlDrawing{}.vml", id))?;
+            let first_block = self.book.shape_blocks + 1;
+            self.comments.write_vml(&mut self.book.zip, first_block)?;
+            self.book.shape_blocks += self.comments.shape_blocks();
+        }
+        if !rels.is_empty() {
+            self.book
+                .start_part(&format!("xl/worksheets/_rels/sheet{}.bin.rels", index))?;
+            self.book.zip.write_all(rels.to_xml().as_bytes())?;
+        }
+
+        self.book.sheets.push(SheetEntry {
+            name: self.name,
+            target,
+        });
+        Ok(())
+    }
+
+    /// Check, that the cell is within the sheet and is not above the current row
+    pub(super) fn check_position(&self, row: u32, col: u16) -> Result<()> {
+        if row > MAX_ROW || col as u32 > MAX_COL {
+            return Err(Error::invalid_input(format!(
+                "cell ({}, {}) is out of the sheet bounds",
+                row, col
+            )));
+        }
+        match self.rows.current() {
+            Some(current) if current > row => Err(Error::invalid_input(format!(
+                "rows must be written in ascending order: row {} after row {}",
+                row, current
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check, that the style is registered in the workbook
+    pub(super) fn check_style(&self, style: StyleId) -> Result<()> {
+        if self.book.styles.contains(style) {
+            Ok(())
+        } else {
+            Err(Error::invalid_input(format!(
+                "style {} is not registered in the workbook",
+                style.index()
+            )))
+        }
+    }
+
+    /// Zero-based index of the sheet in the workbook
+    pub(super) fn index(&self) -> usize {
+        self.book.sheets.len()
+    }
+
+    /// Index of the row after the last written one
+    pub(super) fn next_row(&self) -> u32 {
+        self.next_row
+    }
+
+    /// Date system of the workbook
+    pub(super) fn date_system(&self) -> DateSystem {
+        self.book.date_system
+    }
+
+    /// Table with the name (case-insensitive) exists in the workbook or in this sheet
+    pub(super) fn has_table_name(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.book
+            .tables
+            .iter()
+            .map(String::as_str)
+            .chain(self.tables.iter().map(Table::name))
+            .any(|other| other.to_lowercase() == name)
+    }
+
+    /// Move the next row forward, rows in between are left empty
+    pub(super) fn skip_rows_to(&mut self, row: u32) {
+        self.next_row = self.next_row.max(row);
+    }
+
+    /// Put the string into the shared strings table and reference it from the cell
+    fn put_string(&mut self, row: u32, col: u16, value: &str, style: u32) -> Result<()> {
+        check_string(row, col, value)?;
+        self.check_position(row, col)?;
+        let isst = self.book.strings.intern(value)?;
+        self.put_cell(
+            row,
+            col,
+            BrtCellIsst::new(Cell::at(col as u32, style), isst),
+        )?;
+        self.columns.measure(col, || string_width(value));
+        self.rows
+            .insert_value(row, col, || CellValue::String(value.to_owned()).into());
+        Ok(())
+    }
+
+    /// Put the number as `BrtCellRk`, if it is packed without loss, or as `BrtCellReal`
+    pub(super) fn put_number(&mut self, row: u32, col: u16, value: f64, style: u32) -> Result<()> {
+        let cell = Cell::at(col as u32, style);
+        match BrtCellRk::encode(value) {
+            Some(rk) => self.put_cell(row, col, BrtCellRk::new(cell, rk))?,
+            None => self.put_cell(row, col, BrtCellReal::new(cell, value))?,
+        }
+        let code = self.book.styles.number_format_code(style);
+        self.columns.measure(col, || number_width(value, code));
+        self.rows.insert_value(row, col, || {
+            FilterValue::formatted(CellValue::Number(value), code)
+        });
+        Ok(())
+    }
+
+    pub(super) fn put_bool(&mut self, row: u32, col: u16, value: bool, style: u32) -> Result<()> {
+        self.put_cell(
+            row,
+            col,
+            BrtCellBool::new(Cell::at(col as u32, style), value as u8),
+        )?;
+        self.rows
+            .insert_value(row, col, || CellValue::Bool(value).into());
+        Ok(())
+    }
+
+    pub(super) fn put_error(
+        &mut self,
+        row: u32,
+        col: u16,
+        value: CellError,
+        style: u32,
+    ) -> Result<()> {
+        self.put_cell(
+            row,
+            col,
+            BrtCellError::new(Cell::at(col as u32, style), value.code()),
+        )?;
+        self.rows
+            .insert_value(row, col, || CellValue::Error(value).into());
+        Ok(())
+    }
+
+    /// Compile the formula text, the workbook is recalculated on opening, as cached values of
+    /// formulas can be stale
+    pub(super) fn compile_formula(
+        &mut self,
+        text: &str,
+        kind: FormulaType,
+    ) -> Result<ParsedFormula> {
+        let (formula, _) = self.compile_sheet_formula(text, kind)?;
+        self.book.full_calc_on_load = true;
+        Ok(formula)
+    }
+
+    /// Compile the formula of conditional formatting, it has no cached value
+    pub(super) fn compile_condition(
+        &mut self,
+        text: &str,
+        kind: FormulaType,
+    ) -> Result<ParsedFormula> {
+        self.compile_sheet_formula(text, kind)
+            .map(|(formula, _)| formula)
+    }
+
+    /// Compile the formula of data validation, also returns whether it references sheets,
+    /// which is supported since Excel 2010
+    pub(super) fn compile_validation(
+        &mut self,
+        text: &str,
+        kind: FormulaType,
+    ) -> Result<(ParsedFormula, bool)> {
+        self.compile_sheet_formula(text, kind)
+    }
+
+    /// Compile the formula, which resolves local names of this sheet first, also returns
+    /// whether it references sheets
+    pub(super) fn compile_sheet_formula(
+        &mut self,
+        text: &str,
+        kind: FormulaType,
+    ) -> Result<(ParsedFormula, bool)> {
+        let mut context = SheetNames {
+            itab: self.index() as u32,
+            names: &mut self.book.names,
+            found: false,
+        };
+        let formula = compile(text, kind, &mut context)?;
+        Ok((formula, context.found))
+    }
+
+    /// Defined names of the workbook
+    pub(super) fn names(&mut self) -> &mut DefinedNames {
+        &mut self.book.names
+    }
+
+    /// Cell format of hyperlinks with the built-in `Hyperlink` cell style
+    pub(super) fn hyperlink_style(&mut self) -> Result<StyleId> {
+        self.book.styles.hyperlink()
+    }
+
+    /// Register the differential format of conditional formatting in the workbook
+    pub(super) fn add_dxf(&mut self, format: &DifferentialFormat) -> Result<u32> {
+        self.book.styles.add_dxf(format)
+    }
+
+    /// Write the cell metadata part, which is referenced by dynamic array formulas
+    pub(super) fn use_dynamic_arrays(&mut self) {
+        self.book.dynamic_arrays = true;
+    }
+
+    /// Put the formula with its cached value, which is shown until the workbook is recalculated
+    pub(super) fn put_formula(
+        &mut self,
+        row: u32,
+        col: u16,
+        formula: ParsedFormula,
+        value: &CellValue,
+        style: u32,
+    ) -> Result<()> {
+        let cell = Cell::at(col as u32, style);
+        match value {
+            CellValue::Number(value) => {
+                self.put_cell(row, col, BrtFmlaNum::new(cell, *value, 0, formula))?
+            }
+            CellValue::String(value) => {
+                check_string(row, col, value)?;
+                self.put_cell(
+                    row,
+                    col,
+                    BrtFmlaString::new(cell, value.as_str().into(), 0, formula),
+                )?
+            }
+            CellValue::Bool(value) => {
+                self.put_cell(row, col, BrtFmlaBool::new(cell, *value as u8, 0, formula))?
+            }
+            CellValue::Error(value) => {
+                self.put_cell(row, col, BrtFmlaError::new(cell, value.code(), 0, formula))?
+            }
+            CellValue::Blank => {
+                self.put_cell(row, col, BrtFmlaString::new(cell, "".into(), 0, formula))?
+            }
+        }
+        let code = self.book.styles.number_format_code(style);
+        self.rows
+            .insert_value(row, col, || FilterValue::formatted(value.clone(), code));
+        Ok(())
+    }
+
+    /// Validate the cell record and put it into the current row
+    pub(super) fn put_cell<T: BiffSerializable>(
+        &mut self,
+        row: u32,
+        col: u16,
+        cell: T,
+    ) -> Result<()> {
+        self.check_position(row, col)?;
+        let record = cell.into_biff()?;
+
+        self.rows.insert(row, col, record, &mut self.data)?;
+        self.next_row = self.next_row.max(row + 1);
+
+        self.dimension = Some(match self.dimension {
+            None => Dimension {
+                rw_first: row,
+                rw_last: row,
+                col_first: col,
+                col_last: col,
+            },
+            Some(dim) => Dimension {
+                rw_first: dim.rw_first.min(row),
+                rw_last: dim.rw_last.max(row),
+                col_first: dim.col_first.min(col),
+                col_last: dim.col_last.max(col),
+            },
+        });
+        Ok(())
+    }
+}
+
+fn out_of_range(row: u32, col: u16, value: impl Display) -> Error {
+    Error::invalid_input(format!(
+        "date {} in cell ({}, {}) is out of the range of the date system",
+        value, row, col
+    ))
+}
+
+/// Strings of cells are limited to 32767 UTF-16 code units
+pub(super) fn check_string(row: u32, col: u16, value: &str) -> Result<()> {
+    if value.encode_utf16().count() > MAX_STRING_LEN {
+        return Err(Error::invalid_input(format!(
+            "string in cell ({}, {}) is too long",
+            row, col
+        )));
+    }
+    Ok(())
+}
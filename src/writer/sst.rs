@@ -0,0 +1,127 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufWriter, Seek, SeekFrom, Write},
+};
+
+use tempfile::SpooledTempFile;
+
+use crate::{
+    core::biff::{
+        records::{BrtBeginSst, BrtSSTItem},
+        BiffId, BiffWrite, RichStr,
+    },
+    Result,
+};
+
+/// Default memory limit of the shared strings table
+pub(super) const DEFAULT_SST_MEMORY_LIMIT: usize = 64 << 20;
+
+/// Estimated memory, which is used by the index entry besides the string itself
+const INDEX_ENTRY_OVERHEAD: usize = 32;
+
+/// Shared strings table of the workbook, every unique string is stored once.
+///
+/// Items are serialized as soon as the string is interned and kept in memory until
+/// `memory_limit` bytes, then spilled into the temporary file. The index of unique strings
+/// is limited by the same amount of memory: after the limit, known strings are still
+/// deduplicated, while new ones are added without indexing (so they can be repeated in the
+/// table, which is valid for the format).
+pub(super) struct SharedStringTable {
+    index: HashMap<Box<str>, u32>,
+    /// Estimated memory used by the index
+    index_size: usize,
+    memory_limit: usize,
+    items: BufWriter<SpooledTempFile>,
+    /// Count of items in the table
+    unique: u32,
+    /// Count of references to the table from all cells
+    total: u32,
+}
+
+impl SharedStringTable {
+    pub(super) fn new(memory_limit: usize) -> Self {
+        SharedStringTable {
+            index: HashMap::new(),
+            index_size: 0,
+            memory_limit,
+            items: BufWriter::new(SpooledTempFile::new(memory_limit)),
+            unique: 0,
+            total: 0,
+        }
+    }
+
+    /// Add the reference to the string and return its index in the table (`isst`)
+    pub(super) fn intern(&mut self, value: &str) -> Result<u32> {
+        if let Some(&isst) = self.index.get(value) {
+            self.total += 1;
+            return Ok(isst);
+        }
+        let isst = self.unique;
+        self.items
+            .write_record(&BrtSSTItem::new(RichStr::plain(value.into())))?;
+        let entry_size = value.len() + INDEX_ENTRY_OVERHEAD;
+        if self.index_size + entry_size <= self.memory_limit {
+            self.index.insert(value.into(), isst);
+            self.index_size += entry_size;
+        }
+        self.unique += 1;
+        self.total += 1;
+        Ok(isst)
+    }
+
+    /// Write the shared strings part
+    pub(super) fn write<W: Write>(&mut self, out: &mut W) -> Result<()> {
+        out.write_record(&BrtBeginSst::new(self.total, self.unique))?;
+        self.items.flush()?;
+        let items = self.items.get_mut();
+        items.seek(SeekFrom::Start(0))?;
+        io::copy(items, out)?;
+        out.write_empty(BiffId::BrtEndSst)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::biff::{records::read_sst_item, BiffRecord};
+
+    fn read_table(mut sst: SharedStringTable) -> (BrtBeginSst, Vec<String>) {
+        let mut data = Vec::new();
+        sst.write(&mut data).unwrap();
+        let mut cursor = io::Cursor::new(data);
+        let begin = BiffRecord::read(&mut cursor).unwrap().unwrap();
+        let mut items = Vec::new();
+        while let Some(rec) = BiffRecord::read(&mut cursor).unwrap() {
+            match rec.id {
+                BiffId::BrtSSTItem => items.push(read_sst_item(&rec).unwrap()),
+                _ => assert!(rec.id == BiffId::BrtEndSst),
+            }
+        }
+        (begin.as_biff::<BrtBeginSst>().unwrap(), items)
+    }
+
+    #[test]
+    fn test_shared_strings() {
+        let mut sst = SharedStringTable::new(DEFAULT_SST_MEMORY_LIMIT);
+        assert_eq!(sst.intern("a").unwrap(), 0);
+        assert_eq!(sst.intern("b").unwrap(), 1);
+        assert_eq!(sst.intern("a").unwrap(), 0);
+
+        let (begin, items) = read_table(sst);
+        assert_eq!(begin, BrtBeginSst::new(3, 2));
+        assert_eq!(items, ["a", "b"]);
+
+        // the index is full after the first string, items are spilled into the file
+        let mut sst = SharedStringTable::new(40);
+        let long = "x".repeat(8);
+        assert_eq!(sst.intern(&long).unwrap(), 0);
+        assert_eq!(sst.intern("y").unwrap(), 1);
+        assert_eq!(sst.intern("y").unwrap(), 2);
+        assert_eq!(sst.intern(&long).unwrap(), 0);
+
+        let (begin, items) = read_table(sst);
+        assert_eq!(begin, BrtBeginSst::new(4, 3));
+        assert_eq!(items, [long.as_str(), "y", "y"]);
+    }
+}
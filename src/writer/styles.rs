@@ -0,0 +1,582 @@
+use std::{collections::HashMap, hash::Hash, io::Write};
+
+use crate::{
+    core::biff::{
+        records::{
+            Blxf, BrtBeginBorders, BrtBeginCellStyleXFs, BrtBeginCellXFs, BrtBeginDXFs,
+            BrtBeginFills, BrtBeginFmts, BrtBeginFonts, BrtBeginStyles, BrtBeginTableStyles,
+            BrtBorder, BrtDXF, BrtFill, BrtFmt, BrtFont, BrtStyle, BrtXF, XfProp,
+        },
+        BiffId, BiffWrite, BrtColor,
+    },
+    Color, DifferentialFormat, Error, Font, Result, Style, StyleId,
+};
+
+/// Built-in number formats, which are not localized
+const BUILTIN_FORMATS: [(u16, &str); 30] = [
+    (0, "General"),
+    (1, "0"),
+    (2, "0.00"),
+    (3, "#,##0"),
+    (4, "#,##0.00"),
+    (9, "0%"),
+    (10, "0.00%"),
+    (11, "0.00E+00"),
+    (12, "# ?/?"),
+    (13, "# ??/??"),
+    (14, "m/d/yyyy"),
+    (15, "d-mmm-yy"),
+    (16, "d-mmm"),
+    (17, "mmm-yy"),
+    (18, "h:mm AM/PM"),
+    (19, "h:mm:ss AM/PM"),
+    (20, "h:mm"),
+    (21, "h:mm:ss"),
+    (22, "m/d/yyyy h:mm"),
+    (37, "#,##0 ;(#,##0)"),
+    (38, "#,##0 ;[Red](#,##0)"),
+    (39, "#,##0.00;(#,##0.00)"),
+    (40, "#,##0.00;[Red](#,##0.00)"),
+    (45, "mm:ss"),
+    (46, "[h]:mm:ss"),
+    (47, "mmss.0"),
+    (48, "##0.0E+0"),
+    (49, "@"),
+    (BrtXF::IFMT_DATE, "mm-dd-yy"),
+    (BrtXF::IFMT_DATETIME, "m/d/yy h:mm"),
+];
+
+/// Index of the first custom number format
+const FIRST_CUSTOM_FORMAT: u16 = 164;
+
+/// Maximal count of cell formats in the workbook
+const MAX_CELL_XFS: usize = 65430;
+
+/// Cell style format of the built-in `Hyperlink` cell style, after the `Normal` one
+const HYPERLINK_STYLE_XF: u16 = 1;
+
+/// Theme color of hyperlinks
+const THEME_HYPERLINK: u8 = 10;
+
+/// Maximal length of the font name
+const MAX_FONT_NAME_LEN: usize = 31;
+
+/// Maximal length of the number format code
+const MAX_FORMAT_LEN: usize = 255;
+
+/// Unique items, in the order of addition
+struct Pool<K> {
+    items: Vec<K>,
+    index: HashMap<K, u16>,
+}
+
+impl<K: Hash + Eq + Clone> Pool<K> {
+    fn new() -> Self {
+        Pool {
+            items: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+
+    /// Index of the item, it is added if it is new
+    fn intern(&mut self, item: K) -> u16 {
+        if let Some(&idx) = self.index.get(&item) {
+            return idx;
+        }
+        let idx = self.items.len() as u16;
+        self.items.push(item.clone());
+        self.index.insert(item, idx);
+        idx
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct FontKey {
+    name: String,
+    /// Height in twips
+    dy_height: u16,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strikeout: bool,
+    color: BrtColor,
+}
+
+impl FontKey {
+    fn record(&self) -> BrtFont {
+        BrtFont {
+            dy_height: self.dy_height,
+            grbit: (self.italic as u16 * BrtFont::F_ITALIC)
+                | (self.strikeout as u16 * BrtFont::F_STRIKEOUT),
+            bls: if self.bold {
+                BrtFont::BLS_BOLD
+            } else {
+                BrtFont::BLS_NORMAL
+            },
+            uls: self.underline as u8,
+            brt_color: self.color,
+            name: self.name.as_str().into(),
+            ..BrtFont::standard()
+        }
+    }
+}
+
+/// Lines of the border: top, bottom, left, right
+type BorderKey = [(u8, BrtColor); 4];
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct XfKey {
+    /// Cell style format, which the cell format is based on
+    ixfe_parent: u16,
+    i_fmt: u16,
+    i_font: u16,
+    i_fill: u16,
+    ix_border: u16,
+    alc: u8,
+    alcv: u8,
+    wrap: bool,
+}
+
+impl XfKey {
+    /// Cell format, `parent` is the format of its cell style
+    fn record(&self, parent: &XfKey) -> BrtXF {
+        let mut xf = BrtXF::with_number_format(self.ixfe_parent, self.i_fmt);
+        xf.i_font = self.i_font;
+        xf.i_fill = self.i_fill;
+        xf.ix_border = self.ix_border;
+        xf.flags = (xf.flags & !(BrtXF::ALC | BrtXF::ALCV))
+            | self.alc as u16
+            | ((self.alcv as u16) << 3)
+            | (self.wrap as u16 * BrtXF::F_WRAP);
+        // attributes, which differ from the cell style
+        xf.xf_grbit_atr = [
+            (self.i_fmt != parent.i_fmt, BrtXF::F_ATR_NUM),
+            (self.i_font != parent.i_font, BrtXF::F_ATR_FNT),
+            (
+                (self.alc, self.alcv, self.wrap) != (parent.alc, parent.alcv, parent.wrap),
+                BrtXF::F_ATR_ALC,
+            ),
+            (self.ix_border != parent.ix_border, BrtXF::F_ATR_BDR),
+            (self.i_fill != parent.i_fill, BrtXF::F_ATR_PAT),
+        ]
+        .into_iter()
+        .filter(|(differs, _)| *differs)
+        .fold(0, |atr, (_, flag)| atr | flag);
+        xf
+    }
+}
+
+impl Default for XfKey {
+    fn default() -> Self {
+        XfKey {
+            ixfe_parent: 0,
+            i_fmt: 0,
+            i_font: 0,
+            i_fill: 0,
+            ix_border: 0,
+            alc: 0,
+            alcv: 2,
+            wrap: false,
+        }
+    }
+}
+
+/// Styles part of the workbook.
+///
+/// Fonts, fills, borders, number formats and cell formats are deduplicated, so identical
+/// styles share the same cell format. First cell formats are the default one and formats
+/// of dates (`XF_DATE` and `XF_DATETIME`).
+pub(super) struct StyleSheet {
+    /// Custom number formats, their indexes are shifted by `FIRST_CUSTOM_FORMAT`
+    formats: Pool<String>,
+    fonts: Pool<FontKey>,
+    /// Solid fills, after two mandatory ones
+    fills: Pool<BrtColor>,
+    borders: Pool<BorderKey>,
+    xfs: Pool<XfKey>,
+    /// Differential formats of conditional formatting
+    dxfs: Pool<Vec<XfProp>>,
+    /// Font of the `Hyperlink` cell style, which is written on the first use
+    hyperlink_font: Option<u16>,
+}
+
+impl StyleSheet {
+    pub(super) fn new() -> Self {
+        let mut styles = StyleSheet {
+            formats: Pool::new(),
+            fonts: Pool::new(),
+            fills: Pool::new(),
+            borders: Pool::new(),
+            xfs: Pool::new(),
+            dxfs: Pool::new(),
+            hyperlink_font: None,
+        };
+        let standard = Style::default();
+        for i_fmt in [0, BrtXF::IFMT_DATE, BrtXF::IFMT_DATETIME] {
+            let mut key = styles.xf_key(&standard);
+            key.i_fmt = i_fmt;
+            styles.xfs.intern(key);
+        }
+        styles
+    }
+
+    /// Register the style and return its cell format
+    pub(super) fn add(&mut self, style: &Style) -> Result<StyleId> {
+        let font = &style.font;
+        if font.name.is_empty() || font.name.chars().count() > MAX_FONT_NAME_LEN {
+            return Err(Error::invalid_input(format!(
+                "font name {:?} must be from 1 to 31 characters",
+                font.name
+            )));
+        }
+        if !(1.0..=409.0).contains(&font.size) {
+            return Err(Error::invalid_input(format!(
+                "font size {} must be from 1 to 409 points",
+                font.size
+            )));
+        }
+        if style.number_format.is_empty()
+            || style.number_format.encode_utf16().count() > MAX_FORMAT_LEN
+        {
+            return Err(Error::invalid_input(format!(
+                "number format {:?} must be from 1 to 255 characters",
+                style.number_format
+            )));
+        }
+        let key = self.xf_key(style);
+        if !self.xfs.index.contains_key(&key) && self.xfs.items.len() >= MAX_CELL_XFS {
+            return Err(Error::invalid_input("too many distinct cell styles"));
+        }
+        Ok(StyleId(self.xfs.intern(key) as u32))
+    }
+
+    /// Cell format of hyperlinks, which is based on the built-in `Hyperlink` cell style
+    pub(super) fn hyperlink(&mut self) -> Result<StyleId> {
+        let i_font = self.font(&Font {
+            underline: true,
+            color: Color::Theme(THEME_HYPERLINK),
+            ..Font::default()
+        });
+        self.hyperlink_font = Some(i_font);
+        let key = XfKey {
+            ixfe_parent: HYPERLINK_STYLE_XF,
+            i_font,
+            ..XfKey::default()
+        };
+        if !self.xfs.index.contains_key(&key) && self.xfs.items.len() >= MAX_CELL_XFS {
+            return Err(Error::invalid_input("too many distinct cell styles"));
+        }
+        Ok(StyleId(self.xfs.intern(key) as u32))
+    }
+
+    /// Register the differential format and return its index
+    pub(super) fn add_dxf(&mut self, format: &DifferentialFormat) -> Result<u32> {
+        let mut props = Vec::new();
+        if let Some(color) = format.fill {
+            let color = brt_color(color);
+            props.push(XfProp::new(
+                XfProp::FILL_PATTERN,
+                vec![BrtFill::FLS_SOLID as u8],
+            ));
+            props.push(XfProp::color(XfProp::FILL_FORE_COLOR, color));
+            props.push(XfProp::color(XfProp::FILL_BACK_COLOR, color));
+        }
+        if let Some(color) = format.font_color {
+            props.push(XfProp::color(XfProp::FONT_COLOR, brt_color(color)));
+        }
+        if let Some(line) = format.border {
+            for side in [
+                XfProp::BORDER_TOP,
+                XfProp::BORDER_BOTTOM,
+                XfProp::BORDER_LEFT,
+                XfProp::BORDER_RIGHT,
+            ] {
+                props.push(XfProp::border(
+                    side,
+                    brt_color(line.color),
+                    line.style as u16,
+                ));
+            }
+        }
+        if let Some(bold) = format.bold {
+            let bls = if bold {
+                BrtFont::BLS_BOLD
+            } else {
+                BrtFont::BLS_NORMAL
+            };
+            props.push(XfProp::new(XfProp::FONT_WEIGHT, bls.to_le_bytes().to_vec()));
+        }
+        if let Some(underline) = format.underline {
+            props.push(XfProp::new(
+                XfProp::FONT_UNDERLINE,
+                (underline as u16).to_le_bytes().to_vec(),
+            ));
+        }
+        if let Some(italic) = format.italic {
+            props.push(XfProp::new(XfProp::FONT_ITALIC, vec![italic as u8]));
+        }
+        if let Some(strikeout) = format.strikeout {
+            props.push(XfProp::new(XfProp::FONT_STRIKEOUT, vec![strikeout as u8]));
+        }
+        if let Some(code) = &format.number_format {
+            if code.is_empty() || code.encode_utf16().count() > MAX_FORMAT_LEN {
+                return Err(Error::invalid_input(format!(
+                    "number format {:?} must be from 1 to 255 characters",
+                    code
+                )));
+            }
+            props.push(XfProp::string(XfProp::NUMBER_FORMAT, code));
+            let ifmt = self.number_format(code);
+            props.push(XfProp::new(
+                XfProp::NUMBER_FORMAT_ID,
+                ifmt.to_le_bytes().to_vec(),
+            ));
+        }
+        Ok(self.dxfs.intern(props) as u32)
+    }
+
+    /// Check, that the style is registered in this workbook
+    pub(super) fn contains(&self, style: StyleId) -> bool {
+        (style.0 as usize) < self.xfs.items.len()
+    }
+
+    /// Number format code of the cell format
+    pub(super) fn number_format_code(&self, style: u32) -> &str {
+        let i_fmt = match self.xfs.items.get(style as usize) {
+            Some(xf) => xf.i_fmt,
+            None => return "General",
+        };
+        match i_fmt.checked_sub(FIRST_CUSTOM_FORMAT) {
+            Some(idx) => self.formats.items[idx as usize].as_str(),
+            None => BUILTIN_FORMATS
+                .iter()
+                .find(|(ifmt, _)| *ifmt == i_fmt)
+                .map_or("General", |(_, code)| code),
+        }
+    }
+
+    fn xf_key(&mut self, style: &Style) -> XfKey {
+        let border = &style.border;
+        let line = |line: &crate::BorderLine| (line.style as u8, brt_color(line.color));
+        XfKey {
+            ixfe_parent: 0,
+            i_fmt: self.number_format(&style.number_format),
+            i_font: self.font(&style.font),
+            i_fill: match style.fill {
+                // two first fills are reserved
+                Some(color) => self.fills.intern(brt_color(color)) + 2,
+                None => 0,
+            },
+            ix_border: self.borders.intern([
+                line(&border.top),
+                line(&border.bottom),
+                line(&border.left),
+                line(&border.right),
+            ]),
+            alc: style.horizontal_align as u8,
+            alcv: style.vertical_align as u8,
+            wrap: style.wrap_text,
+        }
+    }
+
+    fn font(&mut self, font: &Font) -> u16 {
+        self.fonts.intern(FontKey {
+            name: font.name.clone(),
+            dy_height: (font.size * 20.0).round() as u16,
+            bold: font.bold,
+            italic: font.italic,
+            underline: font.underline,
+            strikeout: font.strikeout,
+            color: brt_color(font.color),
+        })
+    }
+
+    fn number_format(&mut self, code: &str) -> u16 {
+        match BUILTIN_FORMATS.iter().find(|(_, builtin)| *builtin == code) {
+            Some(&(ifmt, _)) => ifmt,
+            None => self.formats.intern(code.to_owned()) + FIRST_CUSTOM_FORMAT,
+        }
+    }
+
+    /// Write the styles part
+    pub(super) fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        out.write_empty(BiffId::BrtBeginStyleSheet)?;
+        if !self.formats.items.is_empty() {
+            out.write_record(&BrtBeginFmts::new(self.formats.items.len() as u32))?;
+            for (idx, code) in self.formats.items.iter().enumerate() {
+                out.write_record(&BrtFmt::new(
+                    idx as u16 + FIRST_CUSTOM_FORMAT,
+                    code.as_str().into(),
+                ))?;
+            }
+            out.write_empty(BiffId::BrtEndFmts)?;
+        }
+        out.write_record(&BrtBeginFonts::new(self.fonts.items.len() as u32))?;
+        for font in &self.fonts.items {
+            out.write_record(&font.record())?;
+        }
+        out.write_empty(BiffId::BrtEndFonts)?;
+        out.write_record(&BrtBeginFills::new(self.fills.items.len() as u32 + 2))?;
+        out.write_record(&BrtFill::pattern(BrtFill::FLS_NONE))?;
+        out.write_record(&BrtFill::pattern(BrtFill::FLS_GRAY_125))?;
+        for color in &self.fills.items {
+            out.write_record(&BrtFill::solid(*color))?;
+        }
+        out.write_empty(BiffId::BrtEndFills)?;
+        out.write_record(&BrtBeginBorders::new(self.borders.items.len() as u32))?;
+        for lines in &self.borders.items {
+            let [top, bottom, left, right] = lines.map(|(dg, color)| Blxf::new(dg, 0, color));
+            out.write_record(&BrtBorder::new(
+                0,
+                top,
+                bottom,
+                left,
+                right,
+                Blxf::new(0, 0, BrtColor::auto()),
+            ))?;
+        }
+        out.write_empty(BiffId::BrtEndBorders)?;
+        let styles = 1 + self.hyperlink_font.is_some() as u32;
+        out.write_record(&BrtBeginCellStyleXFs::new(styles))?;
+        out.write_record(&BrtXF::standard(BrtXF::NO_PARENT))?;
+        let standard = XfKey::default();
+        let mut hyperlink = standard.clone();
+        if let Some(i_font) = self.hyperlink_font {
+            out.write_record(&BrtXF::hyperlink_style(i_font))?;
+            hyperlink.i_font = i_font;
+        }
+        out.write_empty(BiffId::BrtEndCellStyleXFs)?;
+        out.write_record(&BrtBeginCellXFs::new(self.xfs.items.len() as u32))?;
+        for xf in &self.xfs.items {
+            let parent = match xf.ixfe_parent {
+                HYPERLINK_STYLE_XF => &hyperlink,
+                _ => &standard,
+            };
+            out.write_record(&xf.record(parent))?;
+        }
+        out.write_empty(BiffId::BrtEndCellXFs)?;
+        out.write_record(&BrtBeginStyles::new(styles))?;
+        out.write_record(&BrtStyle::normal())?;
+        if self.hyperlink_font.is_some() {
+            out.write_record(&BrtStyle::hyperlink(HYPERLINK_STYLE_XF as u32))?;
+        }
+        out.write_empty(BiffId::BrtEndStyles)?;
+        out.write_record(&BrtBeginDXFs::new(self.dxfs.items.len() as u32))?;
+        for props in &self.dxfs.items {
+            out.write_record(&BrtDXF::new(0, 0, props.clone().into()))?;
+        }
+        out.write_empty(BiffId::BrtEndDXFs)?;
+        out.write_record(&BrtBeginTableStyles::standard())?;
+        out.write_empty(BiffId::BrtEndTableStyles)?;
+        out.write_empty(BiffId::BrtEndStyleSheet)?;
+        Ok(())
+    }
+}
+
+pub(super) fn brt_color(color: Color) -> BrtColor {
+    match color {
+        Color::Auto => BrtColor::auto(),
+        Color::Rgb(red, green, blue) => BrtColor::rgb(red, green, blue),
+        Color::Theme(theme) => BrtColor::themed(theme),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{core::biff::BiffRecord, Border, BorderStyle, Font, HorizontalAlign};
+
+    #[test]
+    fn test_style_sheet() {
+        let mut styles = StyleSheet::new();
+        let header = Style {
+            font: Font {
+                bold: true,
+                ..Font::default()
+            },
+            border: Border::all(BorderStyle::Thin),
+            horizontal_align: HorizontalAlign::Center,
+            ..Style::default()
+        };
+        let currency = Style {
+            number_format: "\"$\"#,##0.00".to_owned(),
+            fill: Some(Color::Rgb(0xFF, 0xFF, 0x00)),
+            ..Style::default()
+        };
+        assert_eq!(styles.add(&Style::default()).unwrap(), StyleId(0));
+        assert_eq!(styles.add(&header).unwrap(), StyleId(3));
+        assert_eq!(styles.add(&currency).unwrap(), StyleId(4));
+        assert_eq!(styles.add(&header.clone()).unwrap(), StyleId(3));
+        let percent = Style {
+            number_format: "0%".to_owned(),
+            ..Style::default()
+        };
+        assert_eq!(styles.add(&percent).unwrap(), StyleId(5));
+        assert!(styles.contains(StyleId(5)) && !styles.contains(StyleId(6)));
+        let bad = Style {
+            font: Font {
+                size: 0.5,
+                ..Font::default()
+            },
+            ..Style::default()
+        };
+        assert!(styles.add(&bad).is_err());
+
+        let mut data = Vec::new();
+        styles.write(&mut data).unwrap();
+        let mut cursor = std::io::Cursor::new(data);
+        let records =
+            std::iter::from_fn(|| BiffRecord::read(&mut cursor).unwrap()).collect::<Vec<_>>();
+        let all = |id: BiffId| records.iter().filter(move |rec| rec.id == id);
+        let fmts = all(BiffId::BrtFmt)
+            .map(|rec| rec.as_biff::<BrtFmt>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(fmts.len(), 1);
+        assert_eq!(fmts[0].ifmt, FIRST_CUSTOM_FORMAT);
+        assert_eq!(all(BiffId::BrtFont).count(), 2);
+        assert_eq!(all(BiffId::BrtFill).count(), 3);
+        assert_eq!(all(BiffId::BrtBorder).count(), 2);
+        let xfs = all(BiffId::BrtXF)
+            .map(|rec| rec.as_biff::<BrtXF>().unwrap())
+            .collect::<Vec<_>>();
+        // the cell style format goes first
+        assert_eq!(xfs.len(), 1 + 6);
+        assert_eq!(xfs[1], BrtXF::standard(0));
+        let header = &xfs[4];
+        assert_eq!((header.i_font, header.ix_border), (1, 1));
+        assert_eq!(header.flags & BrtXF::ALC, 2);
+        let currency = &xfs[5];
+        assert_eq!((currency.i_fmt, currency.i_fill), (FIRST_CUSTOM_FORMAT, 2));
+        assert_eq!(xfs[6].i_fmt, 9);
+        assert!(all(BiffId::BrtDXF).next().is_none());
+
+        let highlight = DifferentialFormat {
+            bold: Some(true),
+            fill: Some(Color::Rgb(0xFF, 0xC7, 0xCE)),
+            number_format: Some("0.0%".to_owned()),
+            ..DifferentialFormat::default()
+        };
+        assert_eq!(styles.add_dxf(&highlight).unwrap(), 0);
+        assert_eq!(styles.add_dxf(&DifferentialFormat::default()).unwrap(), 1);
+        assert_eq!(styles.add_dxf(&highlight.clone()).unwrap(), 0);
+        let mut data = Vec::new();
+        styles.write(&mut data).unwrap();
+        let mut cursor = std::io::Cursor::new(data);
+        let dxfs = std::iter::from_fn(|| BiffRecord::read(&mut cursor).unwrap())
+            .filter(|rec| rec.id == BiffId::BrtDXF)
+            .map(|rec| rec.as_biff::<BrtDXF>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(dxfs.len(), 2);
+        let types = dxfs[0]
+            .props
+            .items
+            .iter()
+            .map(|prop| prop.xf_prop_type)
+            .collect::<Vec<_>>();
+        assert_eq!(types, [0x00, 0x01, 0x02, 0x19, 0x26, 0x29]);
+        assert_eq!(
+            dxfs[0].props.items[5].data,
+            (FIRST_CUSTOM_FORMAT + 1).to_le_bytes()
+        );
+    }
+}
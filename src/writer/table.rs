@@ -0,0 +1,638 @@
+//! Tables (list objects): ranges with the header row, the built-in style and the totals row
+use std::io::{Seek, Write};
+
+use super::SheetWriter;
+use crate::{
+    core::biff::{
+        records::{
+            BrtBeginAFilter, BrtBeginList, BrtBeginListCol, BrtBeginListCols, BrtTableStyleClient,
+            ParsedFormula,
+        },
+        BiffId, BiffWrite, MAX_COL,
+    },
+    CellError, CellRange, CellValue, Error, Result,
+};
+
+/// Maximal length of names of tables and their columns
+const MAX_NAME_LEN: usize = 255;
+
+/// Built-in style of the table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableStyle {
+    /// Table without formatting
+    None,
+    /// `TableStyleLight1` to `TableStyleLight21`
+    Light(u8),
+    /// `TableStyleMedium1` to `TableStyleMedium28`
+    Medium(u8),
+    /// `TableStyleDark1` to `TableStyleDark11`
+    Dark(u8),
+}
+
+impl Default for TableStyle {
+    fn default() -> Self {
+        TableStyle::Medium(2)
+    }
+}
+
+impl TableStyle {
+    fn validate(self) -> Result<()> {
+        let (kind, index, max) = match self {
+            TableStyle::None => return Ok(()),
+            TableStyle::Light(index) => ("light", index, 21),
+            TableStyle::Medium(index) => ("medium", index, 28),
+            TableStyle::Dark(index) => ("dark", index, 11),
+        };
+        if (1..=max).contains(&index) {
+            Ok(())
+        } else {
+            Err(Error::invalid_input(format!(
+                "{} table style {} must be from 1 to {}",
+                kind, index, max
+            )))
+        }
+    }
+
+    fn name(self) -> Option<String> {
+        match self {
+            TableStyle::None => None,
+            TableStyle::Light(index) => Some(format!("TableStyleLight{}", index)),
+            TableStyle::Medium(index) => Some(format!("TableStyleMedium{}", index)),
+            TableStyle::Dark(index) => Some(format!("TableStyleDark{}", index)),
+        }
+    }
+}
+
+/// Content of the totals row cell of the column
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum TotalFunction {
+    #[default]
+    None,
+    /// Text, usually in the first column
+    Label(String),
+    Average,
+    /// Count of non-empty cells
+    Count,
+    /// Count of numbers
+    CountNums,
+    Max,
+    Min,
+    StdDev,
+    Sum,
+    Var,
+}
+
+impl TotalFunction {
+    fn ilta(&self) -> u32 {
+        match self {
+            TotalFunction::None | TotalFunction::Label(_) => BrtBeginListCol::ILTA_NONE,
+            TotalFunction::Average => BrtBeginListCol::ILTA_AVERAGE,
+            TotalFunction::Count => BrtBeginListCol::ILTA_COUNT,
+            TotalFunction::CountNums => BrtBeginListCol::ILTA_COUNT_NUMS,
+            TotalFunction::Max => BrtBeginListCol::ILTA_MAX,
+            TotalFunction::Min => BrtBeginListCol::ILTA_MIN,
+            TotalFunction::Sum => BrtBeginListCol::ILTA_SUM,
+            TotalFunction::StdDev => BrtBeginListCol::ILTA_STD_DEV,
+            TotalFunction::Var => BrtBeginListCol::ILTA_VAR,
+        }
+    }
+
+    /// Number of the `SUBTOTAL` function, which ignores hidden rows
+    pub(super) fn subtotal(&self) -> Option<u16> {
+        match self {
+            TotalFunction::None | TotalFunction::Label(_) => None,
+            TotalFunction::Average => Some(101),
+            TotalFunction::CountNums => Some(102),
+            TotalFunction::Count => Some(103),
+            TotalFunction::Max => Some(104),
+            TotalFunction::Min => Some(105),
+            TotalFunction::StdDev => Some(107),
+            TotalFunction::Sum => Some(109),
+            TotalFunction::Var => Some(110),
+        }
+    }
+}
+
+/// Column of the table
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TableColumn {
+    /// Name, which is displayed in the header cell, unique within the table
+    pub name: String,
+    pub total: TotalFunction,
+}
+
+impl TableColumn {
+    pub fn new(name: impl Into<String>) -> Self {
+        TableColumn {
+            name: name.into(),
+            total: TotalFunction::None,
+        }
+    }
+}
+
+/// Settings of the table, which is added by [`SheetWriter::add_table`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableOptions {
+    /// Name, which is unique in the workbook, like `Sales_2024`; `TableN` is used if not set
+    pub name: Option<String>,
+    /// Columns of the table; the totals row is shown, if any of them has the total function
+    pub columns: Vec<TableColumn>,
+    pub style: TableStyle,
+    /// Highlight the first column
+    pub first_column: bool,
+    /// Highlight the last column
+    pub last_column: bool,
+    pub row_stripes: bool,
+    pub column_stripes: bool,
+    /// Show AutoFilter buttons on the header row
+    pub autofilter: bool,
+}
+
+impl Default for TableOptions {
+    fn default() -> Self {
+        TableOptions {
+            name: None,
+            columns: Vec::new(),
+            style: TableStyle::default(),
+            first_column: false,
+            last_column: false,
+            row_stripes: true,
+            column_stripes: false,
+            autofilter: true,
+        }
+    }
+}
+
+impl TableOptions {
+    pub(super) fn has_totals(&self) -> bool {
+        self.columns
+            .iter()
+            .any(|column| column.total != TotalFunction::None)
+    }
+}
+
+/// Table of the sheet, which is written into its own part
+pub(super) struct Table {
+    name: String,
+    range: CellRange,
+    options: TableOptions,
+}
+
+impl Table {
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Write the table part, `id` is unique in the workbook
+    pub(super) fn write<W: Write>(&self, out: &mut W, id: u32) -> Result<()> {
+        let totals = self.options.has_totals();
+        let mut flags = 0;
+        if totals {
+            flags |= BrtBeginList::F_SHOWN_TOTAL_ROW;
+        }
+        out.write_record(&BrtBeginList::new(
+            self.range.to_rfx(),
+            BrtBeginList::LT_RANGE,
+            id,
+            1,
+            totals as u32,
+            flags,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            BrtBeginList::NO_DXF,
+            0,
+            0,
+            Some(self.name.as_str()).into(),
+            self.name.as_str().into(),
+            None.into(),
+            None.into(),
+            None.into(),
+            None.into(),
+        ))?;
+        if self.options.autofilter {
+            let mut range = self.range;
+            range.last_row -= totals as u32;
+            out.write_record(&BrtBeginAFilter::new(range.to_rfx()))?;
+            out.write_empty(BiffId::BrtEndAFilter)?;
+        }
+
+        out.write_record(&BrtBeginListCols::new(self.options.columns.len() as u32))?;
+        for (idx, column) in self.options.columns.iter().enumerate() {
+            let label = match column.total {
+                TotalFunction::Label(ref label) => Some(label.as_str()),
+                _ => None,
+            };
+            out.write_record(&BrtBeginListCol::new(
+                idx as u32 + 1,
+                column.total.ilta(),
+                BrtBeginList::NO_DXF,
+                BrtBeginList::NO_DXF,
+                BrtBeginList::NO_DXF,
+                0,
+                Some(column.name.as_str()).into(),
+                Some(column.name.as_str()).into(),
+                label.into(),
+                None.into(),
+                None.into(),
+                None.into(),
+            ))?;
+            out.write_empty(BiffId::BrtEndListCol)?;
+        }
+        out.write_empty(BiffId::BrtEndListCols)?;
+
+        let style_flags = [
+            (
+                self.options.first_column,
+                BrtTableStyleClient::F_FIRST_COLUMN,
+            ),
+            (self.options.last_column, BrtTableStyleClient::F_LAST_COLUMN),
+            (self.options.row_stripes, BrtTableStyleClient::F_ROW_STRIPES),
+            (
+                self.options.column_stripes,
+                BrtTableStyleClient::F_COLUMN_STRIPES,
+            ),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |flags, (_, flag)| flags | flag);
+        let style = self.options.style.name();
+        out.write_record(&BrtTableStyleClient::new(
+            style_flags,
+            style.as_deref().into(),
+        ))?;
+        out.write_empty(BiffId::BrtEndList)?;
+        Ok(())
+    }
+}
+
+/// Aggregates of the column, which are cached values of the totals row
+#[derive(Default)]
+pub(super) struct ColumnTotals {
+    /// Count of non-empty cells
+    count: usize,
+    numbers: usize,
+    sum: f64,
+    min: f64,
+    max: f64,
+    /// Running mean and sum of squared deviations (Welford's algorithm)
+    mean: f64,
+    m2: f64,
+    error: Option<CellError>,
+}
+
+impl ColumnTotals {
+    pub(super) fn add_number(&mut self, value: f64) {
+        self.count += 1;
+        self.numbers += 1;
+        self.sum += value;
+        if self.numbers == 1 {
+            (self.min, self.max) = (value, value);
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        let delta = value - self.mean;
+        self.mean += delta / self.numbers as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Add the cell, which is not a number
+    pub(super) fn add_other(&mut self) {
+        self.count += 1;
+    }
+
+    pub(super) fn add_error(&mut self, error: CellError) {
+        self.count += 1;
+        self.error.get_or_insert(error);
+    }
+
+    /// Value of the function, as Excel calculates it
+    pub(super) fn value(&self, function: &TotalFunction) -> CellValue {
+        let n = self.numbers as f64;
+        match function {
+            TotalFunction::None | TotalFunction::Label(_) => return CellValue::Blank,
+            TotalFunction::Count => return CellValue::Number(self.count as f64),
+            TotalFunction::CountNums => return CellValue::Number(n),
+            _ => {}
+        }
+        if let Some(error) = self.error {
+            return CellValue::Error(error);
+        }
+        let value = match function {
+            TotalFunction::Sum => Some(self.sum),
+            TotalFunction::Min => Some(self.min),
+            TotalFunction::Max => Some(self.max),
+            TotalFunction::Average if self.numbers > 0 => Some(self.sum / n),
+            TotalFunction::Var if self.numbers > 1 => Some(self.m2 / (n - 1.0)),
+            TotalFunction::StdDev if self.numbers > 1 => Some((self.m2 / (n - 1.0)).sqrt()),
+            _ => None,
+        };
+        value.map_or(CellValue::Error(CellError::Div0), CellValue::Number)
+    }
+}
+
+/// Formula `SUBTOTAL(function, range)` with the absolute reference to the range
+pub(super) fn subtotal_formula(function: u16, range: CellRange) -> ParsedFormula {
+    const PTG_INT: u8 = 0x1E;
+    const PTG_AREA: u8 = 0x25;
+    const PTG_FUNC_VAR: u8 = 0x42;
+    const SUBTOTAL: u16 = 344;
+
+    let mut rgce = vec![PTG_INT];
+    rgce.extend_from_slice(&function.to_le_bytes());
+    rgce.push(PTG_AREA);
+    rgce.extend_from_slice(&range.first_row.to_le_bytes());
+    rgce.extend_from_slice(&range.last_row.to_le_bytes());
+    rgce.extend_from_slice(&range.first_col.to_le_bytes());
+    rgce.extend_from_slice(&range.last_col.to_le_bytes());
+    rgce.extend_from_slice(&[PTG_FUNC_VAR, 2]);
+    rgce.extend_from_slice(&SUBTOTAL.to_le_bytes());
+    ParsedFormula::new(rgce, Vec::new())
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Add the table over the range from `first_row` (the header row) to `last_row` (the
+    /// totals row, if it is shown), its columns start at `first_col`.
+    ///
+    /// Cells are written separately: header cells must contain names of columns, and totals
+    /// cells contain labels and formulas. [`SheetWriter::write_table`] writes all of them.
+    pub fn add_table(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        options: &TableOptions,
+    ) -> Result<()> {
+        options.style.validate()?;
+        if options.columns.is_empty()
+            || first_col as usize + options.columns.len() > MAX_COL as usize + 1
+        {
+            return Err(Error::invalid_input(
+                "table must have columns within the sheet bounds",
+            ));
+        }
+        let last_col = first_col + (options.columns.len() - 1) as u16;
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        range.validate()?;
+        // the header row, at least one data row and the totals row
+        if last_row - first_row < 1 + options.has_totals() as u32 {
+            return Err(Error::invalid_input(format!(
+                "table range {} has no data rows",
+                range
+            )));
+        }
+        for (idx, column) in options.columns.iter().enumerate() {
+            let len = column.name.chars().count();
+            if len == 0 || len > MAX_NAME_LEN {
+                return Err(Error::invalid_input(format!(
+                    "name of table column {} must have from 1 to 255 characters",
+                    idx
+                )));
+            }
+            if options.columns[..idx]
+                .iter()
+                .any(|other| other.name.to_lowercase() == column.name.to_lowercase())
+            {
+                return Err(Error::invalid_input(format!(
+                    "table has duplicate column {:?}",
+                    column.name
+                )));
+            }
+        }
+        self.check_table_overlap(range, "table")?;
+        if let Some(other) = self.merges.find_overlap(&range) {
+            return Err(Error::invalid_input(format!(
+                "table {} overlaps merged range {}",
+                range, other
+            )));
+        }
+        if let Some(ref filter) = self.rows.filter {
+            if filter.range().intersects(&range) {
+                return Err(Error::invalid_input(format!(
+                    "table {} overlaps the AutoFilter range {}",
+                    range,
+                    filter.range()
+                )));
+            }
+        }
+
+        let name = match options.name {
+            Some(ref name) => {
+                validate_table_name(name)?;
+                if self.has_table_name(name) {
+                    return Err(Error::invalid_input(format!(
+                        "table {:?} already exists",
+                        name
+                    )));
+                }
+                // tables and defined names share names
+                if self.names().has_name(name) {
+                    return Err(Error::invalid_input(format!(
+                        "table name {:?} is already used by the defined name",
+                        name
+                    )));
+                }
+                name.clone()
+            }
+            None => (1..)
+                .map(|index| format!("Table{}", index))
+                .find(|name| !self.has_table_name(name) && !self.names().has_name(name))
+                .unwrap_or_default(),
+        };
+        self.tables.push(Table {
+            name,
+            range,
+            options: options.clone(),
+        });
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Check, that the range (the table, the merged or the AutoFilter one) has no common
+    /// cells with tables of the sheet
+    pub(super) fn check_table_overlap(&self, range: CellRange, kind: &str) -> Result<()> {
+        match self
+            .tables
+            .iter()
+            .find(|table| table.range.intersects(&range))
+        {
+            Some(table) => Err(Error::invalid_input(format!(
+                "{} {} overlaps table {:?} at {}",
+                kind, range, table.name, table.range
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Check, that the name can be used for the table: it starts with a letter, `_` or `\`,
+/// contains letters, digits, `_` and `.`, and is not a cell reference
+fn validate_table_name(name: &str) -> Result<()> {
+    let invalid = |reason: &str| {
+        Err(Error::invalid_input(format!(
+            "invalid table name {:?}: {}",
+            name, reason
+        )))
+    };
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(ch) if ch.is_alphabetic() || ch == '_' || ch == '\\' => {}
+        _ => return invalid("name must start with a letter, '_' or '\\'"),
+    }
+    if !chars.all(|ch| ch.is_alphanumeric() || ch == '_' || ch == '.') {
+        return invalid("only letters, digits, '_' and '.' are allowed");
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return invalid("name is longer than 255 characters");
+    }
+    if is_cell_reference(name) {
+        return invalid("name cannot be a cell reference");
+    }
+    Ok(())
+}
+
+/// Name looks like the reference in A1 or R1C1 notation, like `AB12`, `R`, `C2` or `R1C1`
+pub(super) fn is_cell_reference(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    let letters = upper.bytes().take_while(u8::is_ascii_uppercase).count();
+    let digits = &upper[letters..];
+    let col = upper[..letters].bytes().try_fold(0u32, |col, ch| {
+        col.checked_mul(26)?.checked_add((ch - b'A' + 1) as u32)
+    });
+    let a1 = letters > 0
+        && !digits.is_empty()
+        && digits.bytes().all(|ch| ch.is_ascii_digit())
+        && col.is_some_and(|col| col <= MAX_COL + 1);
+
+    let skip_digits = |rest: &str| {
+        rest.trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .to_owned()
+    };
+    let rest = upper.strip_prefix('R').map_or(upper.clone(), skip_digits);
+    let rest = rest.strip_prefix('C').map_or(rest.clone(), skip_digits);
+    a1 || rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::Arc};
+
+    use arrow::{
+        array::{ArrayRef, Float64Array, StringArray},
+        record_batch::RecordBatch,
+    };
+    use zip::ZipArchive;
+
+    use super::*;
+    use crate::{
+        writer::{part_data, part_records},
+        XlsbReader, XlsbWriter,
+    };
+
+    #[test]
+    fn test_table() {
+        let batch = RecordBatch::try_from_iter([
+            (
+                "Region",
+                Arc::new(StringArray::from(vec!["North", "South", "East"])) as ArrayRef,
+            ),
+            (
+                "Amount",
+                Arc::new(Float64Array::from(vec![Some(10.0), None, Some(4.0)])) as ArrayRef,
+            ),
+        ])
+        .unwrap();
+        let mut options = TableOptions {
+            name: Some("Sales".to_owned()),
+            columns: vec![TableColumn::new("Region"), TableColumn::new("Amount")],
+            ..Default::default()
+        };
+        options.columns[0].total = TotalFunction::Label("Total".to_owned());
+        options.columns[1].total = TotalFunction::Average;
+
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut sheet = book.add_sheet("Data").unwrap();
+        sheet.write_table(&batch, &options).unwrap();
+        assert!(sheet.write_table(&batch, &options).is_err());
+        assert!(sheet.merge_range(4, 1, 5, 1).is_err());
+        let second = TableOptions {
+            columns: vec![TableColumn::new("x"), TableColumn::new("X")],
+            ..Default::default()
+        };
+        assert!(sheet.add_table(10, 0, 12, &second).is_err());
+        for name in ["A1", "rc", "R2C3", "1st", "a b"] {
+            let invalid = TableOptions {
+                name: Some(name.to_owned()),
+                columns: vec![TableColumn::new("x")],
+                ..Default::default()
+            };
+            assert!(sheet.add_table(10, 0, 12, &invalid).is_err(), "{}", name);
+        }
+        let second = TableOptions {
+            columns: vec![TableColumn::new("x")],
+            style: TableStyle::Light(1),
+            ..Default::default()
+        };
+        sheet.add_table(10, 3, 12, &second).unwrap();
+        sheet.finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut reader = XlsbReader::new(Cursor::new(data.clone())).unwrap();
+        let totals = reader
+            .cells("Data")
+            .unwrap()
+            .map(Result::unwrap)
+            .filter(|cell| cell.row == 4)
+            .map(|cell| cell.value)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            totals,
+            [
+                CellValue::String("Total".to_owned()),
+                CellValue::Number(7.0)
+            ]
+        );
+
+        let mut zip = ZipArchive::new(Cursor::new(data)).unwrap();
+        let rels =
+            String::from_utf8(part_data(&mut zip, "xl/worksheets/_rels/sheet1.bin.rels")).unwrap();
+        assert!(rels.contains("../tables/table2.bin"));
+        let content_types = String::from_utf8(part_data(&mut zip, "[Content_Types].xml")).unwrap();
+        assert!(content_types.contains("/xl/tables/table2.bin"));
+
+        let records = part_records(&mut zip, "xl/tables/table1.bin");
+        assert_eq!(
+            records.iter().map(|rec| rec.id).collect::<Vec<_>>(),
+            [
+                BiffId::BrtBeginList,
+                BiffId::BrtBeginAFilter,
+                BiffId::BrtEndAFilter,
+                BiffId::BrtBeginListCols,
+                BiffId::BrtBeginListCol,
+                BiffId::BrtEndListCol,
+                BiffId::BrtBeginListCol,
+                BiffId::BrtEndListCol,
+                BiffId::BrtEndListCols,
+                BiffId::BrtTableStyleClient,
+                BiffId::BrtEndList,
+            ]
+        );
+        let list = records[0].as_biff::<BrtBeginList>().unwrap();
+        assert_eq!(
+            CellRange::from_rfx(&list.rfx_list),
+            CellRange::new(0, 0, 4, 1)
+        );
+        assert_eq!(list.crw_totals, 1);
+        assert_eq!(list.st_display_name.inner, "Sales");
+        let filter = records[1].as_biff::<BrtBeginAFilter>().unwrap();
+        assert_eq!(CellRange::from_rfx(&filter.rfx), CellRange::new(0, 0, 3, 1));
+        let column = records[6].as_biff::<BrtBeginListCol>().unwrap();
+        assert_eq!(column.ilta, BrtBeginListCol::ILTA_AVERAGE);
+        let style = records[9].as_biff::<BrtTableStyleClient>().unwrap();
+        assert_eq!(style.flags, BrtTableStyleClient::F_ROW_STRIPES);
+    }
+}
@@ -0,0 +1,367 @@
+//! Data validation of ranges: dropdown lists, constraints of values, input and error messages
+use std::io::{Seek, Write};
+
+use super::{CellCondition, SheetWriter};
+use crate::{
+    core::biff::{
+        records::{
+            BrtBeginDVals, BrtBeginDVals14, BrtDVal, BrtDVal14, BrtDValList, BrtFRTBegin,
+            ParsedFormula, MAX_STRING_LEN,
+        },
+        BiffId, BiffWrite,
+    },
+    formula::FormulaType,
+    CellRange, Error, Result,
+};
+
+/// Maximal length of titles of messages
+const MAX_TITLE_LEN: usize = 32;
+
+/// Maximal length of messages
+const MAX_MESSAGE_LEN: usize = 255;
+
+/// Maximal length of the list, which is stored in the formula
+const MAX_LIST_FORMULA_LEN: usize = 255;
+
+/// Values, which are allowed in cells. Conditions compare values with formulas, like `=10`,
+/// `=$B$1` or `=DATE(2025,1,31)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationRule {
+    /// Any value, the validation only shows the input message
+    Any,
+    WholeNumber(CellCondition),
+    Decimal(CellCondition),
+    /// Dropdown list of values, they must not contain commas
+    List(Vec<String>),
+    /// Dropdown list of values of the range, like `=$A$1:$A$5` or `=Lists!$A$1:$A$5`
+    ListRange(String),
+    Date(CellCondition),
+    Time(CellCondition),
+    TextLength(CellCondition),
+    /// Formula, which is true for valid values, like `=ISNUMBER(A1)`. Relative references are
+    /// relative to the top left cell of the range.
+    Custom(String),
+}
+
+/// Alert on the invalid value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u32)]
+pub enum ValidationErrorStyle {
+    /// The value is rejected
+    #[default]
+    Stop = 0,
+    /// The value can be accepted after the confirmation
+    Warning = 1,
+    /// The value is accepted, the message is shown
+    Information = 2,
+}
+
+/// Data validation with its messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataValidation {
+    pub rule: ValidationRule,
+    /// Empty cells are valid
+    pub allow_blank: bool,
+    /// Dropdown button of lists is shown
+    pub show_dropdown: bool,
+    /// Message, which is shown when the cell is selected, with its title
+    pub input_title: Option<String>,
+    pub input_message: Option<String>,
+    /// Message of the alert on the invalid value, with its title
+    pub error_title: Option<String>,
+    pub error_message: Option<String>,
+    pub error_style: ValidationErrorStyle,
+    /// Invalid values are reported by the alert
+    pub show_error: bool,
+}
+
+impl DataValidation {
+    pub fn new(rule: ValidationRule) -> Self {
+        DataValidation {
+            rule,
+            allow_blank: true,
+            show_dropdown: true,
+            input_title: None,
+            input_message: None,
+            error_title: None,
+            error_message: None,
+            error_style: ValidationErrorStyle::default(),
+            show_error: true,
+        }
+    }
+
+    /// Set the input message with its title
+    pub fn with_input_message(mut self, title: &str, message: &str) -> Self {
+        self.input_title = Some(title.to_owned());
+        self.input_message = Some(message.to_owned());
+        self
+    }
+
+    /// Set the error message with its title
+    pub fn with_error_message(mut self, title: &str, message: &str) -> Self {
+        self.error_title = Some(title.to_owned());
+        self.error_message = Some(message.to_owned());
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        let texts = [
+            (&self.input_title, MAX_TITLE_LEN, "title"),
+            (&self.error_title, MAX_TITLE_LEN, "title"),
+            (&self.input_message, MAX_MESSAGE_LEN, "message"),
+            (&self.error_message, MAX_MESSAGE_LEN, "message"),
+        ];
+        for (text, max_len, kind) in texts {
+            if let Some(text) = text
+                .as_deref()
+                .filter(|text| text.chars().count() > max_len)
+            {
+                return Err(Error::invalid_input(format!(
+                    "{} {:?} of the data validation is longer than {} characters",
+                    kind, text, max_len
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Data validations of the sheet, as records `BrtDVal` and `BrtDValList`
+#[derive(Default)]
+pub(super) struct DataValidations {
+    records: Vec<u8>,
+    count: u32,
+    /// Validations of the Excel 2010 extension, which reference other sheets
+    ext_records: Vec<u8>,
+    ext_count: u32,
+}
+
+impl DataValidations {
+    /// Write validations of the sheet after conditional formatting
+    pub(super) fn write<W: Write>(&self, out: &mut W) -> Result<()> {
+        if self.count > 0 {
+            out.write_record(&BrtBeginDVals::with_count(self.count))?;
+            out.write_all(&self.records)?;
+            out.write_empty(BiffId::BrtEndDVals)?;
+        }
+        Ok(())
+    }
+
+    /// Write validations of the Excel 2010 extension at the end of the sheet
+    pub(super) fn write_ext<W: Write>(&self, out: &mut W) -> Result<()> {
+        if self.ext_count > 0 {
+            out.write_record(&BrtFRTBegin::excel_2010())?;
+            out.write_record(&BrtBeginDVals14::with_count(self.ext_count))?;
+            out.write_all(&self.ext_records)?;
+            out.write_empty(BiffId::BrtEndDVals14)?;
+            out.write_empty(BiffId::BrtFRTEnd)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Add the data validation to the range. Validations are checked by Excel on input, values
+    /// of written cells are not checked.
+    pub fn add_data_validation(
+        &mut self,
+        first_row: u32,
+        first_col: u16,
+        last_row: u32,
+        last_col: u16,
+        validation: &DataValidation,
+    ) -> Result<()> {
+        let range = CellRange::new(first_row, first_col, last_row, last_col);
+        range.validate()?;
+        validation.validate()?;
+
+        let kind = FormulaType::Shared {
+            row: first_row,
+            col: first_col,
+        };
+        let mut references_sheets = false;
+        let mut compile = |writer: &mut Self, text: &str| {
+            let (formula, found) = writer.compile_validation(text, kind)?;
+            references_sheets |= found;
+            Ok::<_, Error>(formula)
+        };
+        let mut list = None;
+        let (val_type, operator, formulas) = match &validation.rule {
+            ValidationRule::Any => (BrtDVal::TYPE_ANY, 0, Vec::new()),
+            ValidationRule::WholeNumber(condition)
+            | ValidationRule::Decimal(condition)
+            | ValidationRule::Date(condition)
+            | ValidationRule::Time(condition)
+            | ValidationRule::TextLength(condition) => {
+                let val_type = match validation.rule {
+                    ValidationRule::WholeNumber(_) => BrtDVal::TYPE_WHOLE,
+                    ValidationRule::Decimal(_) => BrtDVal::TYPE_DECIMAL,
+                    ValidationRule::Date(_) => BrtDVal::TYPE_DATE,
+                    ValidationRule::Time(_) => BrtDVal::TYPE_TIME,
+                    _ => BrtDVal::TYPE_TEXT_LENGTH,
+                };
+                let (operator, texts) = condition.operator();
+                let formulas = texts
+                    .into_iter()
+                    .map(|text| compile(self, text))
+                    .collect::<Result<Vec<_>>>()?;
+                // operators of conditional formatting are one-based
+                (val_type, operator - 1, formulas)
+            }
+            ValidationRule::List(values) => {
+                if values.is_empty() || values.iter().any(|value| value.contains(',')) {
+                    return Err(Error::invalid_input(
+                        "list of the data validation must have values without commas",
+                    ));
+                }
+                let text = values.join(",");
+                let len = text.encode_utf16().count();
+                if len > MAX_STRING_LEN {
+                    return Err(Error::invalid_input(
+                        "list of the data validation is too long",
+                    ));
+                }
+                let formula = if len <= MAX_LIST_FORMULA_LEN {
+                    compile(self, &format!("\"{}\"", text.replace('"', "\"\"")))?
+                } else {
+                    // the long list follows the validation in its own record
+                    list = Some(BrtDValList::new(text.as_str().into()));
+                    ParsedFormula::default()
+                };
+                (BrtDVal::TYPE_LIST, 0, vec![formula])
+            }
+            ValidationRule::ListRange(text) => (BrtDVal::TYPE_LIST, 0, vec![compile(self, text)?]),
+            ValidationRule::Custom(text) => (BrtDVal::TYPE_CUSTOM, 0, vec![compile(self, text)?]),
+        };
+
+        let flags = [
+            (
+                matches!(validation.rule, ValidationRule::List(_)),
+                BrtDVal::F_STR_LOOKUP,
+            ),
+            (validation.allow_blank, BrtDVal::F_ALLOW_BLANK),
+            (!validation.show_dropdown, BrtDVal::F_SUPPRESS_COMBO),
+            (
+                validation.input_title.is_some() || validation.input_message.is_some(),
+                BrtDVal::F_SHOW_INPUT_MSG,
+            ),
+            (validation.show_error, BrtDVal::F_SHOW_ERROR_MSG),
+        ]
+        .into_iter()
+        .filter(|(set, _)| *set)
+        .fold(
+            val_type
+                | (validation.error_style as u32) << BrtDVal::ERR_STYLE_SHIFT
+                | operator << BrtDVal::OPERATOR_SHIFT,
+            |flags, (_, flag)| flags | flag,
+        );
+        let mut formulas = formulas.into_iter();
+        let dval = BrtDVal::new(
+            flags,
+            vec![range.to_rfx()].into(),
+            validation.error_title.as_deref().into(),
+            validation.error_message.as_deref().into(),
+            validation.input_title.as_deref().into(),
+            validation.input_message.as_deref().into(),
+            formulas.next().unwrap_or_default(),
+            formulas.next().unwrap_or_default(),
+        );
+        let validations = &mut self.validations;
+        if references_sheets {
+            validations
+                .ext_records
+                .write_record(&BrtDVal14::from(dval))?;
+            validations.ext_count += 1;
+        } else {
+            validations.records.write_record(&dval)?;
+            if let Some(list) = list {
+                validations.records.write_record(&list)?;
+            }
+            validations.count += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{writer::part_records, XlsbWriter};
+
+    #[test]
+    fn test_data_validation() {
+        let mut book = XlsbWriter::new(Cursor::new(Vec::new()));
+        let mut lists = book.add_sheet("Lists").unwrap();
+        lists.write(0, 0, "North").unwrap();
+        lists.finish().unwrap();
+        let mut sheet = book.add_sheet("Orders").unwrap();
+        sheet.write(0, 0, "Region").unwrap();
+        let region = DataValidation::new(ValidationRule::List(vec![
+            "North".to_owned(),
+            "South".to_owned(),
+        ]))
+        .with_input_message("Region", "Pick the sales region");
+        sheet.add_data_validation(1, 0, 100, 0, &region).unwrap();
+        let quantity = DataValidation {
+            error_style: ValidationErrorStyle::Warning,
+            ..DataValidation::new(ValidationRule::WholeNumber(CellCondition::Between(
+                "=1".to_owned(),
+                "=1000".to_owned(),
+            )))
+        }
+        .with_error_message("Quantity", "Quantity must be from 1 to 1000");
+        sheet.add_data_validation(1, 1, 100, 1, &quantity).unwrap();
+        let long = (0..100).map(|i| format!("Item {}", i)).collect::<Vec<_>>();
+        let long = DataValidation::new(ValidationRule::List(long));
+        sheet.add_data_validation(1, 2, 100, 2, &long).unwrap();
+        let other = DataValidation::new(ValidationRule::ListRange("=Lists!$A$1:$A$5".to_owned()));
+        sheet.add_data_validation(1, 3, 100, 3, &other).unwrap();
+
+        let bad = DataValidation::new(ValidationRule::List(vec!["a,b".to_owned()]));
+        assert!(sheet.add_data_validation(1, 4, 1, 4, &bad).is_err());
+        let bad = DataValidation::new(ValidationRule::Any).with_input_message(&"x".repeat(33), "");
+        assert!(sheet.add_data_validation(1, 4, 1, 4, &bad).is_err());
+        sheet.finish().unwrap();
+        let data = book.close().unwrap().into_inner();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        let records = part_records(&mut zip, "xl/worksheets/sheet2.bin");
+        let ids = records.iter().map(|rec| rec.id).collect::<Vec<_>>();
+        let position = |id: BiffId| ids.iter().position(|other| *other == id).unwrap();
+        let begin = records[position(BiffId::BrtBeginDVals)]
+            .as_biff::<BrtBeginDVals>()
+            .unwrap();
+        assert_eq!(begin.idv_mac, 3);
+        let dvals = records
+            .iter()
+            .filter(|rec| rec.id == BiffId::BrtDVal)
+            .map(|rec| rec.as_biff::<BrtDVal>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(dvals[0].flags & 0x0F, BrtDVal::TYPE_LIST);
+        assert_ne!(dvals[0].flags & BrtDVal::F_SHOW_INPUT_MSG, 0);
+        assert_eq!(
+            dvals[0].str_prompt.inner.as_deref(),
+            Some("Pick the sales region")
+        );
+        // `PtgStr` with comma-separated values
+        assert_eq!(dvals[0].formula1.rgce[..3], [0x17, 11, 0]);
+        assert_eq!(dvals[1].flags >> BrtDVal::ERR_STYLE_SHIFT & 0x07, 1);
+        assert_eq!(dvals[1].formula2.rgce, [0x1E, 0xE8, 0x03]);
+        assert!(dvals[2].formula1.rgce.is_empty());
+        assert_eq!(ids[position(BiffId::BrtDVal) + 3], BiffId::BrtDValList);
+
+        let ext = &ids[position(BiffId::BrtBeginDVals14)..];
+        assert_eq!(
+            ext,
+            [
+                BiffId::BrtBeginDVals14,
+                BiffId::BrtDVal14,
+                BiffId::BrtEndDVals14,
+                BiffId::BrtFRTEnd,
+                BiffId::BrtEndSheet
+            ]
+        );
+    }
+}
@@ -0,0 +1,276 @@
+//! Window of the sheet: panes, zoom, visibility of gridlines and the selection
+use std::io::{Seek, Write};
+
+use super::SheetWriter;
+use crate::{
+    core::biff::{
+        records::{BrtBeginWsView, BrtPane, BrtSel},
+        BiffId, BiffWrite, UncheckedRfX, MAX_COL, MAX_ROW,
+    },
+    CellRange, Error, Result,
+};
+
+/// Panes of the sheet window
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Panes {
+    #[default]
+    None,
+    /// Top rows and left columns are always visible
+    Frozen { rows: u32, cols: u16 },
+    /// Window is split into scrollable panes at the position in points from the top left
+    /// corner of the sheet area
+    Split { x: f64, y: f64 },
+}
+
+/// Settings of the sheet window, which are applied by [`SheetWriter::set_view`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetView {
+    /// Zoom in percent, from 10 to 400
+    pub zoom: u16,
+    pub show_gridlines: bool,
+    /// Show row numbers and column letters
+    pub show_headers: bool,
+    /// Column A is on the right side
+    pub right_to_left: bool,
+    pub panes: Panes,
+    /// Top left visible cell of the scrollable area: of the bottom right pane, if the window
+    /// has panes. By default the first cell after frozen rows and columns.
+    pub top_left: Option<(u32, u16)>,
+    /// Selected range, its top left cell is active
+    pub selection: Option<CellRange>,
+}
+
+impl Default for SheetView {
+    fn default() -> Self {
+        SheetView {
+            zoom: 100,
+            show_gridlines: true,
+            show_headers: true,
+            right_to_left: false,
+            panes: Panes::None,
+            top_left: None,
+            selection: None,
+        }
+    }
+}
+
+impl SheetView {
+    fn validate(&self) -> Result<()> {
+        if !(10..=400).contains(&self.zoom) {
+            return Err(Error::invalid_input(format!(
+                "zoom {} must be from 10 to 400",
+                self.zoom
+            )));
+        }
+        match self.panes {
+            Panes::Frozen { rows, cols } if rows > MAX_ROW || cols as u32 > MAX_COL => {
+                return Err(Error::invalid_input(
+                    "frozen panes are out of the sheet bounds",
+                ))
+            }
+            Panes::Split { x, y } if !(x >= 0.0 && y >= 0.0 && x.is_finite() && y.is_finite()) => {
+                return Err(Error::invalid_input("split position must be non-negative"))
+            }
+            _ => {}
+        }
+        if let Some((row, col)) = self.top_left {
+            let frozen = match self.panes {
+                Panes::Frozen { rows, cols } => row < rows || col < cols,
+                _ => false,
+            };
+            if row > MAX_ROW || col as u32 > MAX_COL || frozen {
+                return Err(Error::invalid_input(format!(
+                    "top left cell ({}, {}) is out of the scrollable area",
+                    row, col
+                )));
+            }
+        }
+        if let Some(selection) = self.selection {
+            selection.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Write the window of the sheet, `selected` is set for the active sheet
+    pub(super) fn write<W: Write>(&self, out: &mut W, selected: bool) -> Result<()> {
+        let mut view = BrtBeginWsView::standard();
+        view.w_scale = self.zoom;
+        for (set, flag) in [
+            (selected, BrtBeginWsView::F_SELECTED),
+            (self.show_gridlines, BrtBeginWsView::F_DSP_GRID),
+            (self.show_headers, BrtBeginWsView::F_DSP_RW_COL),
+            (self.right_to_left, BrtBeginWsView::F_RIGHT_TO_LEFT),
+        ] {
+            if set {
+                view.flags |= flag;
+            } else {
+                view.flags &= !flag;
+            }
+        }
+
+        let (pane, pnn) = match self.panes {
+            Panes::Frozen { rows, cols } if rows > 0 || cols > 0 => {
+                let (row, col) = self.top_left.unwrap_or((rows, cols));
+                let pnn = match (rows > 0, cols > 0) {
+                    (true, true) => BrtPane::PNN_BOTTOM_RIGHT,
+                    (true, false) => BrtPane::PNN_BOTTOM_LEFT,
+                    _ => BrtPane::PNN_TOP_RIGHT,
+                };
+                let pane = BrtPane::new(
+                    cols as f64,
+                    rows as f64,
+                    row.into(),
+                    (col as i32).into(),
+                    pnn,
+                    BrtPane::F_FROZEN | BrtPane::F_FROZEN_NO_SPLIT,
+                );
+                (Some(pane), pnn)
+            }
+            Panes::Split { x, y } if x > 0.0 || y > 0.0 => {
+                let (row, col) = self.top_left.unwrap_or((0, 0));
+                let pnn = match (y > 0.0, x > 0.0) {
+                    (true, true) => BrtPane::PNN_BOTTOM_RIGHT,
+                    (true, false) => BrtPane::PNN_BOTTOM_LEFT,
+                    _ => BrtPane::PNN_TOP_RIGHT,
+                };
+                // positions are stored in twips
+                let pane =
+                    BrtPane::new(x * 20.0, y * 20.0, row.into(), (col as i32).into(), pnn, 0);
+                (Some(pane), pnn)
+            }
+            _ => {
+                if let Some((row, col)) = self.top_left {
+                    view.rw_top = row.into();
+                    view.col_left = (col as i32).into();
+                }
+                (None, BrtPane::PNN_TOP_LEFT)
+            }
+        };
+
+        out.write_record(&view)?;
+        if let Some(ref pane) = pane {
+            out.write_record(pane)?;
+        }
+        if let Some(selection) = self.selection {
+            out.write_record(&BrtSel::new(
+                pnn,
+                selection.first_row.into(),
+                (selection.first_col as i32).into(),
+                0,
+                vec![selection.to_rfx()].into(),
+            ))?;
+        } else if pane.is_some() {
+            // the active pane must have the selection
+            out.write_record(&BrtSel::new(
+                pnn,
+                0.into(),
+                0.into(),
+                0,
+                vec![UncheckedRfX::from_bounds(0, 0, 0, 0)].into(),
+            ))?;
+        }
+        out.write_empty(BiffId::BrtEndWsView)?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> SheetWriter<'_, W> {
+    /// Replace settings of the sheet window
+    pub fn set_view(&mut self, view: &SheetView) -> Result<()> {
+        view.validate()?;
+        self.view = view.clone();
+        Ok(())
+    }
+
+    /// Freeze top `rows` and left `cols`, keeping other settings of the window
+    pub fn freeze_panes(&mut self, rows: u32, cols: u16) -> Result<()> {
+        let view = SheetView {
+            panes: Panes::Frozen { rows, cols },
+            top_left: None,
+            ..self.view.clone()
+        };
+        self.set_view(&view)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::biff::BiffRecord;
+
+    fn records(view: &SheetView) -> Vec<BiffRecord> {
+        let mut data = Vec::new();
+        view.write(&mut data, true).unwrap();
+        let mut cursor = std::io::Cursor::new(data);
+        std::iter::from_fn(|| BiffRecord::read(&mut cursor).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_sheet_view() {
+        let recs = records(&SheetView::default());
+        assert_eq!(recs.len(), 2);
+        let view = recs[0].as_biff::<BrtBeginWsView>().unwrap();
+        let mut standard = BrtBeginWsView::standard();
+        standard.flags |= BrtBeginWsView::F_SELECTED;
+        assert_eq!(view, standard);
+
+        let frozen = SheetView {
+            zoom: 85,
+            show_gridlines: false,
+            panes: Panes::Frozen { rows: 1, cols: 0 },
+            ..SheetView::default()
+        };
+        let recs = records(&frozen);
+        let ids = recs.iter().map(|rec| rec.id).collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            [
+                BiffId::BrtBeginWsView,
+                BiffId::BrtPane,
+                BiffId::BrtSel,
+                BiffId::BrtEndWsView
+            ]
+        );
+        let view = recs[0].as_biff::<BrtBeginWsView>().unwrap();
+        assert_eq!(view.w_scale, 85);
+        assert_eq!(view.flags & BrtBeginWsView::F_DSP_GRID, 0);
+        let pane = recs[1].as_biff::<BrtPane>().unwrap();
+        assert_eq!((pane.xnum_x_split, pane.xnum_y_split), (0.0, 1.0));
+        assert_eq!((pane.rw_top.inner, pane.col_left.inner), (1, 0));
+        assert_eq!(pane.pnn_acct, BrtPane::PNN_BOTTOM_LEFT);
+        assert_eq!(
+            recs[2].as_biff::<BrtSel>().unwrap().pnn,
+            BrtPane::PNN_BOTTOM_LEFT
+        );
+
+        let selected = SheetView {
+            top_left: Some((10, 2)),
+            selection: Some(CellRange::new(12, 3, 14, 5)),
+            ..SheetView::default()
+        };
+        let recs = records(&selected);
+        let view = recs[0].as_biff::<BrtBeginWsView>().unwrap();
+        assert_eq!((view.rw_top.inner, view.col_left.inner), (10, 2));
+        let sel = recs[1].as_biff::<BrtSel>().unwrap();
+        assert_eq!((sel.rw_act.inner, sel.col_act.inner), (12, 3));
+        assert_eq!(sel.pnn, BrtPane::PNN_TOP_LEFT);
+
+        for invalid in [
+            SheetView {
+                zoom: 5,
+                ..SheetView::default()
+            },
+            SheetView {
+                panes: Panes::Frozen { rows: 2, cols: 0 },
+                top_left: Some((1, 0)),
+                ..SheetView::default()
+            },
+            SheetView {
+                panes: Panes::Split { x: -1.0, y: 0.0 },
+                ..SheetView::default()
+            },
+        ] {
+            assert!(invalid.validate().is_err());
+        }
+    }
+}